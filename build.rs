@@ -0,0 +1,6 @@
+fn main() {
+    println!("cargo:rerun-if-changed=src/runtime/jni/varargs.c");
+    cc::Build::new()
+        .file("src/runtime/jni/varargs.c")
+        .compile("hippo_jni_varargs");
+}
@@ -38,10 +38,10 @@ pub fn parse_constant_value_attribute(buf: &[u8]) -> IResult<&[u8], ConstantValu
 }
 #[derive(Debug)]
 pub struct ExceptionHandler {
-    start_pc: u16,
-    end_pc: u16,
-    handler_pc: u16,
-    catch_type: u16,
+    pub start_pc: u16,
+    pub end_pc: u16,
+    pub handler_pc: u16,
+    pub catch_type: u16,
 }
 
 pub fn parse_exception_handler(buf: &[u8]) -> IResult<&[u8], ExceptionHandler> {
@@ -240,7 +240,7 @@ pub fn parse_stack_map_frame(buf: &[u8]) -> IResult<&[u8], StackMapFrame> {
 }
 #[derive(Debug)]
 pub struct StackMapTableAttribute {
-    entries: Vec<StackMapFrame>,
+    pub entries: Vec<StackMapFrame>,
 }
 
 pub fn parse_stack_map_table_attribute(buf: &[u8]) -> IResult<&[u8], StackMapTableAttribute> {
@@ -250,7 +250,7 @@ pub fn parse_stack_map_table_attribute(buf: &[u8]) -> IResult<&[u8], StackMapTab
 }
 #[derive(Debug)]
 pub struct ExceptionsAttribute {
-    exception_index_table: Vec<u16>,
+    pub exception_index_table: Vec<u16>,
 }
 
 pub fn parse_exceptions_attribute(buf: &[u8]) -> IResult<&[u8], ExceptionsAttribute> {
@@ -329,7 +329,7 @@ pub fn parse_signature_attribute(buf: &[u8]) -> IResult<&[u8], SignatureAttribut
 }
 #[derive(Debug)]
 pub struct SourceFileAttribute {
-    sourcefile_index: u16,
+    pub sourcefile_index: u16,
 }
 
 pub fn parse_source_file_attribute(buf: &[u8]) -> IResult<&[u8], SourceFileAttribute> {
@@ -356,8 +356,8 @@ pub fn parse_source_debug_extension_attribute(
 }
 #[derive(Debug)]
 pub struct LineNumberTable {
-    start_pc: u16,
-    line_number: u16,
+    pub start_pc: u16,
+    pub line_number: u16,
 }
 
 fn parse_line_number_table(buf: &[u8]) -> IResult<&[u8], LineNumberTable> {
@@ -374,7 +374,7 @@ fn parse_line_number_table(buf: &[u8]) -> IResult<&[u8], LineNumberTable> {
 }
 #[derive(Debug)]
 pub struct LineNumberTableAttribute {
-    line_number_table: Vec<LineNumberTable>,
+    pub line_number_table: Vec<LineNumberTable>,
 }
 
 pub fn parse_line_number_table_attribute(buf: &[u8]) -> IResult<&[u8], LineNumberTableAttribute> {
@@ -887,8 +887,8 @@ pub fn parse_annotation_default_attribute(
 }
 #[derive(Debug)]
 pub struct BootstrapMethod {
-    bootstrap_method_ref: u16,
-    bootstrap_arguments: Vec<u16>,
+    pub bootstrap_method_ref: u16,
+    pub bootstrap_arguments: Vec<u16>,
 }
 
 fn parse_bootstrap_method(buf: &[u8]) -> IResult<&[u8], BootstrapMethod> {
@@ -905,7 +905,7 @@ fn parse_bootstrap_method(buf: &[u8]) -> IResult<&[u8], BootstrapMethod> {
 }
 #[derive(Debug)]
 pub struct BootstrapMethodsAttribute {
-    bootstrap_methods: Vec<BootstrapMethod>,
+    pub bootstrap_methods: Vec<BootstrapMethod>,
 }
 
 pub fn parse_bootstrap_methods_attribute(buf: &[u8]) -> IResult<&[u8], BootstrapMethodsAttribute> {
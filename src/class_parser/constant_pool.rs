@@ -38,6 +38,23 @@ pub struct MethodRef<'a> {
     pub descriptor: &'a str,
 }
 
+#[derive(Debug)]
+pub struct MethodHandleRef<'a> {
+    pub reference_kind: u8,
+    pub class_name: &'a str,
+    pub name: &'a str,
+    pub descriptor: &'a str,
+    /// The handle refers to a `CONSTANT_InterfaceMethodref`.
+    pub is_interface: bool,
+}
+
+#[derive(Debug)]
+pub struct InvokeDynamicRef<'a> {
+    pub bootstrap_method_attr_index: u16,
+    pub name: &'a str,
+    pub descriptor: &'a str,
+}
+
 impl ConstPool {
     pub fn new(const_pool_infos: Vec<ConstPoolInfo>) -> Self {
         ConstPool {
@@ -49,6 +66,14 @@ impl ConstPool {
         &self.infos[index as usize - 1]
     }
 
+    /// The entry at `index`, None if there is none, like at 0 or after a long.
+    pub fn get(&self, index: u16) -> Option<&ConstPoolInfo> {
+        match self.infos.get((index as usize).checked_sub(1)?)? {
+            ConstPoolInfo::Placeholder => None,
+            info => Some(info),
+        }
+    }
+
     pub fn get_utf8_string_at(&self, index: u16) -> &String {
         self.get_const_pool_info_at(index).as_constant_utf8_info()
     }
@@ -153,6 +178,13 @@ impl ConstPool {
         }
     }
 
+    pub fn is_interface_method_ref_at(&self, index: u16) -> bool {
+        matches!(
+            self.get_const_pool_info_at(index),
+            ConstPoolInfo::ConstantInterfaceMethodRefInfo { .. }
+        )
+    }
+
     pub fn get_constant_long_at(&self, index: u16) -> i64 {
         match self.get_const_pool_info_at(index) {
             ConstPoolInfo::ConstantLongInfo(num) => (*num),
@@ -181,6 +213,67 @@ impl ConstPool {
         }
     }
 
+    pub fn get_method_type_at(&self, index: u16) -> &String {
+        match self.get_const_pool_info_at(index) {
+            ConstPoolInfo::ConstantMethodTypeInfo { descriptor_index } => {
+                self.get_utf8_string_at(*descriptor_index)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn get_method_handle_at(&self, index: u16) -> MethodHandleRef<'_> {
+        match self.get_const_pool_info_at(index) {
+            ConstPoolInfo::ConstantMethodHandleInfo {
+                reference_kind,
+                reference_index,
+            } => {
+                let (class_index, name_and_type_index, is_interface) =
+                    match self.get_const_pool_info_at(*reference_index) {
+                        ConstPoolInfo::ConstantFieldRefInfo {
+                            class_index,
+                            name_and_type_index,
+                        }
+                        | ConstPoolInfo::ConstantMethodRefInfo {
+                            class_index,
+                            name_and_type_index,
+                        } => (class_index, name_and_type_index, false),
+                        ConstPoolInfo::ConstantInterfaceMethodRefInfo {
+                            class_index,
+                            name_and_type_index,
+                        } => (class_index, name_and_type_index, true),
+                        _ => unreachable!(),
+                    };
+                let (name, descriptor) = self.get_name_and_type_at(*name_and_type_index);
+                MethodHandleRef {
+                    reference_kind: *reference_kind,
+                    class_name: self.get_class_name_at(*class_index),
+                    name,
+                    descriptor,
+                    is_interface,
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn get_invoke_dynamic_at(&self, index: u16) -> InvokeDynamicRef<'_> {
+        match self.get_const_pool_info_at(index) {
+            ConstPoolInfo::ConstantInvokeDynamicInfo {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            } => {
+                let (name, descriptor) = self.get_name_and_type_at(*name_and_type_index);
+                InvokeDynamicRef {
+                    bootstrap_method_attr_index: *bootstrap_method_attr_index,
+                    name,
+                    descriptor,
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
     pub fn get_constant_string_at(&self, index: u16) -> u16 {
         match self.get_const_pool_info_at(index) {
             ConstPoolInfo::ConstantStringInfo { string_index } => *string_index,
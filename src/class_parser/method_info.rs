@@ -45,6 +45,15 @@ impl MethodInfo {
             })
     }
 
+    pub fn code(&self) -> Option<&CodeAttribute> {
+        self.attributes
+            .iter()
+            .find_map(|attr| match &attr.attribute {
+                PredefinedAttribute::CodeAttribute(code_attr) => Some(code_attr),
+                _ => None,
+            })
+    }
+
     pub fn parameters(&self) -> Option<&[Parameter]> {
         self.attributes
             .iter()
@@ -55,4 +64,18 @@ impl MethodInfo {
                 _ => None,
             })
     }
+
+    /// Constant pool indexes of the classes of the checked exceptions the
+    /// method declares.
+    pub fn exceptions(&self) -> &[u16] {
+        self.attributes
+            .iter()
+            .find_map(|attr| match &attr.attribute {
+                PredefinedAttribute::ExceptionsAttribute(attr) => {
+                    Some(attr.exception_index_table.as_slice())
+                }
+                _ => None,
+            })
+            .unwrap_or(&[])
+    }
 }
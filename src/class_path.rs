@@ -1,151 +1,586 @@
-use anyhow::Context;
+use crate::class_source::{package_of, ClassSource, DirSource, JarSource};
+use crate::jimage::JImage;
+use std::collections::{HashMap, HashSet};
 use std::env;
-use std::fs::read_dir;
-use std::fs::File;
+use std::fs;
 use std::io;
 use std::io::Error;
 use std::io::ErrorKind;
-use std::io::Read;
 use std::path::Path;
 use std::path::PathBuf;
-use tracing::trace;
+use tracing::{trace, warn};
 
-#[derive(Debug)]
-enum Entry {
-    Dir { path: PathBuf },
-    Wildcard { path_vec: Vec<PathBuf> },
-    Zip { path: PathBuf },
-}
-
-impl Entry {
-    fn new(path: &str) -> Entry {
-        if path.ends_with('*') {
-            trace!(path, "Entry::new Wildcard");
-            let len = path.len();
-            let base_path = &path[..len - 1];
-            trace!(base_path);
-            let path_vec: Vec<PathBuf> = read_dir(base_path)
-                .with_context(|| format!("read_dir: {}", base_path))
-                .unwrap()
-                .map(|x| x.unwrap())
-                .map(|x| x.path())
-                .filter(|x| {
-                    x.extension()
-                        .map(|x| x.to_str().unwrap() == "jar")
-                        .unwrap_or(false)
-                })
-                .collect();
-            trace!("path_vec {:?}", path_vec);
-
-            Entry::Wildcard { path_vec }
-        } else if path.ends_with(".jar") {
-            trace!("Entry::new Zip {}", path);
-            Entry::Zip {
-                path: Path::new(path).to_owned(),
-            }
-        } else {
-            trace!("Entry::new Dir {}", path);
-            Entry::Dir {
-                path: Path::new(path).to_owned(),
+/// Separates the entries of a class path.
+pub const PATH_SEPARATOR: char = if cfg!(windows) { ';' } else { ':' };
+
+/// The runtime image of a JDK 9+, relative to its home.
+const MODULES: &str = "lib/modules";
+
+/// Which sources of a class path have classes in each package, shared by all
+/// of them so a lookup only asks the sources that may have the class. Each
+/// source finds the file in its own index then, e.g. a jar by its name.
+#[derive(Debug, Default)]
+struct PackageIndex {
+    /// The indexes of the sources with classes in a package, in class path
+    /// order.
+    sources: HashMap<String, Vec<usize>>,
+    /// The sources that can't tell their packages, asked for every class.
+    unindexed: Vec<usize>,
+}
+
+impl PackageIndex {
+    /// Adds the source at `index`, after all the sources added so far.
+    fn add(&mut self, index: usize, source: &dyn ClassSource) {
+        match source.packages() {
+            Some(packages) => {
+                for package in packages {
+                    self.sources.entry(package).or_default().push(index);
+                }
             }
+            None => self.unindexed.push(index),
         }
     }
 
-    fn read_class(&self, class_file_name: &str) -> Result<Vec<u8>, io::Error> {
-        match self {
-            Entry::Dir { path } => {
-                trace!("read class {} using Dir", class_file_name);
-                let filepath = Path::new(path).join(class_file_name);
-                tracing::debug!(?filepath, "read_class");
-                let mut file = File::open(filepath)?;
-                let meta = file.metadata()?;
-                let mut buf = Vec::<u8>::with_capacity(meta.len() as usize);
-                file.read_to_end(&mut buf)?;
-                Ok(buf)
-            }
-            Entry::Wildcard { path_vec } => {
-                trace!("read class {} using Wildcard", class_file_name);
-                path_vec
-                    .iter()
-                    .map(|x| Entry::new(x.to_str().unwrap()))
-                    .map(|x| x.read_class(class_file_name))
-                    .find(|x| x.is_ok())
-                    .unwrap_or_else(|| Err(Error::new(ErrorKind::Other, "Class not found")))
-            }
-            Entry::Zip { path } => {
-                trace!("read class {} using Zip", class_file_name);
-                let file = File::open(path)?;
-                let mut zip = zip::ZipArchive::new(file)?;
-                let mut file = zip.by_name(&class_file_name)?;
-                let mut buf = Vec::<u8>::with_capacity(file.size() as usize);
-                file.read_to_end(&mut buf).expect("read file content");
-                Ok(buf)
-            }
+    /// The indexes of the sources that may have classes in `package`, in
+    /// class path order.
+    fn sources(&self, package: &str) -> Vec<usize> {
+        let indexed = self.sources.get(package).map_or(&[][..], Vec::as_slice);
+        let mut sources: Vec<usize> = indexed.iter().chain(&self.unindexed).copied().collect();
+        if !indexed.is_empty() && !self.unindexed.is_empty() {
+            sources.sort_unstable();
         }
+        sources
     }
 }
 
+/// The entries of a class path besides the JRE, usually from the command
+/// line. Each is a list of directories, jars and `dir/*` for all the jars in
+/// a directory, separated by `PATH_SEPARATOR`.
+#[derive(Debug, Default)]
+pub struct ClassPathOptions {
+    /// `-cp`, the current directory if not given.
+    pub class_path: Option<String>,
+    /// `-Xbootclasspath/p:`, searched before the classes of the JRE.
+    pub boot_prepend: Option<String>,
+    /// `-Xbootclasspath/a:`, searched after the classes of the JRE.
+    pub boot_append: Option<String>,
+    /// `java.ext.dirs`, directories of jars searched after the boot class
+    /// path. `lib/ext` of the JRE if not given.
+    pub ext_dirs: Option<String>,
+}
+
+/// Where classes are found: the boot class path, the jars of the extension
+/// directories, then the user class path, each a list of sources.
 #[derive(Debug)]
 pub struct ClassPath {
-    boot: Entry,
-    user: Entry,
+    /// The sources of all three, in the order they are searched.
+    sources: Vec<Box<dyn ClassSource>>,
+    /// How many of `sources` are on the boot class path.
+    boot_sources: usize,
+    packages: PackageIndex,
 }
 
 impl ClassPath {
+    fn with_sources(
+        boot: Vec<Box<dyn ClassSource>>,
+        ext: Vec<Box<dyn ClassSource>>,
+        user: Vec<Box<dyn ClassSource>>,
+    ) -> ClassPath {
+        let mut class_path = ClassPath {
+            boot_sources: boot.len(),
+            sources: Vec::new(),
+            packages: PackageIndex::default(),
+        };
+        for source in boot.into_iter().chain(ext).chain(user) {
+            class_path.push_source(source);
+        }
+        class_path
+    }
+
     pub fn read_class(&self, name: &str) -> Result<Vec<u8>, io::Error> {
-        let class_file_name = name.to_owned() + ".class";
-        tracing::debug!(%class_file_name, "read_class");
+        self.read_class_with_source(name).map(|(data, _)| data)
+    }
+
+    /// Like `read_class`, also returning the source the class was found in.
+    /// If none has it the error lists every one searched.
+    pub fn read_class_with_source(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<u8>, &dyn ClassSource), io::Error> {
+        tracing::debug!(%name, "read_class");
+        for index in self.packages.sources(package_of(name)) {
+            let source = self.sources[index].as_ref();
+            trace!(?source, "read class {}", name);
+            if let Some(data) = source.find_class(name) {
+                return Ok((data?, source));
+            }
+        }
+        let searched: Vec<String> = self
+            .sources
+            .iter()
+            .map(|source| source.location())
+            .collect();
+        let message = format!("{} (searched {})", name, searched.join(", "));
+        Err(Error::new(ErrorKind::NotFound, message))
+    }
+
+    /// Reads the resource `name`, e.g. `META-INF/services/java.sql.Driver`,
+    /// from the first source that has it.
+    pub fn read_resource(&self, name: &str) -> Option<Result<Vec<u8>, io::Error>> {
+        self.sources
+            .iter()
+            .find_map(|source| source.find_resource(name))
+    }
+
+    /// Whether `source`, as returned by `read_class_with_source`, is on the
+    /// boot class path.
+    pub fn is_boot_source(&self, source: &dyn ClassSource) -> bool {
+        let source = source as *const dyn ClassSource as *const ();
+        (self.sources[..self.boot_sources].iter())
+            .any(|boot| boot.as_ref() as *const dyn ClassSource as *const () == source)
+    }
+
+    /// The sources of the boot class path, like `sun.boot.class.path`.
+    pub fn boot_class_path(&self) -> String {
+        let locations: Vec<String> = self.sources[..self.boot_sources]
+            .iter()
+            .map(|source| source.location())
+            .collect();
+        locations.join(&PATH_SEPARATOR.to_string())
+    }
+
+    /// Adds a source to search after the user class path.
+    pub fn push_source(&mut self, source: Box<dyn ClassSource>) {
+        self.packages.add(self.sources.len(), source.as_ref());
+        self.sources.push(source);
+    }
+
+    pub fn new(jre: &str, options: &ClassPathOptions) -> ClassPath {
+        let mut jars = HashSet::new();
+        let mut boot = Vec::new();
+        push_entries(&mut boot, &mut jars, options.boot_prepend.as_deref());
+        if is_runtime_image(Path::new(jre)) {
+            push_image(&mut boot, &Path::new(jre).join(MODULES));
+        } else {
+            let jre_lib = Path::new(jre).join("lib").join("*");
+            push_entry(&mut boot, &mut jars, &jre_lib);
+        }
+        push_entries(&mut boot, &mut jars, options.boot_append.as_deref());
+        let mut ext = Vec::new();
+        let ext_dirs = (options.ext_dirs.clone()).unwrap_or_else(|| default_ext_dirs(jre));
+        for dir in ext_dirs.split(PATH_SEPARATOR).filter(|dir| !dir.is_empty()) {
+            push_entry(&mut ext, &mut jars, &Path::new(dir).join("*"));
+        }
+        let mut user = Vec::new();
+        let class_path = options.class_path.as_deref().unwrap_or(".");
+        push_entries(&mut user, &mut jars, Some(class_path));
+        ClassPath::with_sources(boot, ext, user)
+    }
 
-        self.boot
-            .read_class(&class_file_name)
-            .or_else(|_x| self.user.read_class(&class_file_name))
-        //            .or_else(|| self.ext.read_class())
+    /// Builds a class path from explicit entries, without looking for a JRE.
+    pub fn from_entries(boot: &str, user: &str) -> ClassPath {
+        let mut jars = HashSet::new();
+        let mut boot_sources = Vec::new();
+        push_entries(&mut boot_sources, &mut jars, Some(boot));
+        let mut user_sources = Vec::new();
+        push_entries(&mut user_sources, &mut jars, Some(user));
+        ClassPath::with_sources(boot_sources, Vec::new(), user_sources)
     }
 
-    pub fn new(jre_opt: Option<String>, cp_opt: Option<String>) -> ClassPath {
-        let jre = get_jre(jre_opt);
-        let boot = parse_boot_classpath(&jre);
-        let user = parse_user_classpath(cp_opt);
-        ClassPath { user, boot }
+    /// Builds a class path from sources, e.g. of classes in memory, which are
+    /// searched in order.
+    pub fn from_sources(
+        boot: Vec<Box<dyn ClassSource>>,
+        user: Vec<Box<dyn ClassSource>>,
+    ) -> ClassPath {
+        ClassPath::with_sources(boot, Vec::new(), user)
     }
 }
 
-fn parse_boot_classpath(jre: &str) -> Entry {
-    let jre_lib_path = Path::new(jre)
+/// `lib/ext` of `jre`, the default `java.ext.dirs`. Runtime images have no
+/// extension directories.
+pub fn default_ext_dirs(jre: &str) -> String {
+    if is_runtime_image(Path::new(jre)) {
+        return String::new();
+    }
+    Path::new(jre)
         .join("lib")
-        .join("*")
+        .join("ext")
         .to_str()
         .unwrap()
-        .to_owned();
-    Entry::new(&jre_lib_path)
+        .to_owned()
+}
+
+/// The paths of a class path string, an empty one stands for the current
+/// directory.
+fn split_class_path(class_path: &str) -> impl Iterator<Item = PathBuf> + '_ {
+    class_path.split(PATH_SEPARATOR).map(|path| {
+        if path.is_empty() {
+            PathBuf::from(".")
+        } else {
+            PathBuf::from(path)
+        }
+    })
+}
+
+/// `jars` has the canonical paths of the jars added so far, each is added
+/// once.
+fn push_entries(
+    entries: &mut Vec<Box<dyn ClassSource>>,
+    jars: &mut HashSet<PathBuf>,
+    class_path: Option<&str>,
+) {
+    for path in class_path.into_iter().flat_map(split_class_path) {
+        push_entry(entries, jars, &path);
+    }
+}
+
+/// Adds a directory, a jar with the jars its manifest refers to, or for
+/// `dir/*` all the jars in `dir`.
+fn push_entry(entries: &mut Vec<Box<dyn ClassSource>>, jars: &mut HashSet<PathBuf>, path: &Path) {
+    if path.file_name() == Some("*".as_ref()) {
+        let dir = path.parent().filter(|dir| *dir != Path::new(""));
+        let dir = dir.unwrap_or_else(|| Path::new("."));
+        trace!(?dir, "push_entry Wildcard");
+        let mut paths: Vec<PathBuf> = match fs::read_dir(dir) {
+            Ok(read_dir) => read_dir
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .filter(|path| is_jar(path))
+                .collect(),
+            Err(e) => {
+                warn!(?dir, %e, "can't read class path directory");
+                return;
+            }
+        };
+        paths.sort();
+        for jar in paths {
+            push_jar(entries, jars, &jar);
+        }
+    } else if is_jar(path) {
+        push_jar(entries, jars, path);
+    } else {
+        trace!(?path, "push_entry Dir");
+        entries.push(Box::new(DirSource::new(path)));
+    }
+}
+
+/// Adds a jar and then, transitively, the entries of the `Class-Path` of its
+/// manifest. Jars that can't be read or are already added are left out, like
+/// the JVM ignores such entries.
+fn push_jar(entries: &mut Vec<Box<dyn ClassSource>>, jars: &mut HashSet<PathBuf>, path: &Path) {
+    trace!(?path, "push_entry Jar");
+    let canonical_path = fs::canonicalize(path).unwrap_or_else(|_| path.to_owned());
+    if !jars.insert(canonical_path) {
+        return;
+    }
+    let jar = match JarSource::open(path) {
+        Ok(jar) => jar,
+        Err(e) => {
+            warn!(?path, %e, "can't read jar");
+            return;
+        }
+    };
+    let manifest_class_path = jar.manifest_class_path().to_vec();
+    entries.push(Box::new(jar));
+    for path in manifest_class_path {
+        // the directories of `Class-Path` end with a slash
+        if path.to_str().is_some_and(|path| path.ends_with('/')) {
+            entries.push(Box::new(DirSource::new(&path)));
+        } else {
+            push_jar(entries, jars, &path);
+        }
+    }
+}
+
+/// Adds the runtime image at `path`, left out if it can't be read like jars.
+fn push_image(entries: &mut Vec<Box<dyn ClassSource>>, path: &Path) {
+    trace!(?path, "push_entry Image");
+    match JImage::open(path) {
+        Ok(image) => entries.push(Box::new(image)),
+        Err(e) => warn!(?path, %e, "can't read runtime image"),
+    }
+}
+
+/// Whether `jre` is a JDK 9+ with its classes in a runtime image instead of
+/// `lib/*.jar`.
+fn is_runtime_image(jre: &Path) -> bool {
+    jre.join(MODULES).is_file()
+}
+
+fn is_jar(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            extension.eq_ignore_ascii_case("jar") || extension.eq_ignore_ascii_case("zip")
+        })
 }
 
-fn parse_user_classpath(cp_opt: Option<String>) -> Entry {
-    let cp = cp_opt.unwrap_or_else(|| ".".to_owned());
-    Entry::new(&cp)
+/// Finds the attribute `name` in the main section of a manifest, joining
+/// continuation lines (those starting with a space).
+pub fn manifest_attribute(manifest: &str, name: &str) -> Option<String> {
+    let mut attributes: Vec<String> = Vec::new();
+    for line in manifest.lines() {
+        if line.is_empty() {
+            // the main section ends at the first blank line
+            break;
+        }
+        match (line.strip_prefix(' '), attributes.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => attributes.push(line.to_string()),
+        }
+    }
+    attributes.into_iter().find_map(|attribute| {
+        let (key, value) = attribute.split_at(attribute.find(':')?);
+        if key.eq_ignore_ascii_case(name) {
+            Some(value[1..].trim().to_string())
+        } else {
+            None
+        }
+    })
 }
 
 fn exists(path: &str) -> bool {
     Path::new(path).exists()
 }
 
-fn get_jre(jre_opt: Option<String>) -> String {
+/// The JRE to boot from: `jre_opt`, `./jre`, the `jre` of `$JAVA_HOME` or
+/// `$JAVA_HOME` itself if it's a runtime image.
+pub fn get_jre(jre_opt: Option<String>) -> String {
     match jre_opt {
         Some(ref jre) if exists(jre) => jre.to_string(),
         _ => {
             if exists("./jre") {
-                println!("use jre in current dir");
+                trace!("use jre in current dir");
                 "./jre".to_string()
             } else {
                 match env::var_os("JAVA_HOME") {
-                    Some(java_home) => Path::new(&java_home)
-                        .join("jre")
-                        .to_str()
-                        .unwrap()
-                        .to_string(),
+                    Some(java_home) => {
+                        let java_home = Path::new(&java_home);
+                        let jre = java_home.join("jre");
+                        if !jre.exists() && is_runtime_image(java_home) {
+                            trace!("use runtime image of JAVA_HOME");
+                            java_home.to_str().unwrap().to_string()
+                        } else {
+                            jre.to_str().unwrap().to_string()
+                        }
+                    }
                     None => panic!("Can not find JRE folder"),
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ClassPath, ClassPathOptions};
+    use crate::class_source::{ClassSource, DirSource, FnSource, MemorySource};
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::{env, fs, process};
+
+    fn write_jar(path: &Path, class_path: Option<&str>, files: &[(&str, &str)]) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut writer = zip::ZipWriter::new(fs::File::create(path).unwrap());
+        let options = zip::write::FileOptions::default();
+        if let Some(class_path) = class_path {
+            writer.start_file("META-INF/MANIFEST.MF", options).unwrap();
+            write!(
+                writer,
+                "Manifest-Version: 1.0\r\nClass-Path: {}\r\n",
+                class_path
+            )
+            .unwrap();
+        }
+        for (name, data) in files {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(data.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    fn write_class(dir: &Path, name: &str, data: &str) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join(name), data).unwrap();
+    }
+
+    fn read(class_path: &ClassPath, name: &str) -> String {
+        String::from_utf8(class_path.read_class(name).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_class_path() {
+        let dir = env::temp_dir().join(format!("hippo-class-path-{}", process::id()));
+        let jre = dir.join("jre");
+        write_jar(
+            &jre.join("lib/rt.jar"),
+            None,
+            &[("A.class", "rt"), ("B.class", "rt")],
+        );
+        write_class(&dir.join("prepend"), "A.class", "prepend");
+        write_class(&dir.join("append"), "C.class", "append");
+        write_jar(&dir.join("ext/e.jar"), None, &[("D.class", "ext")]);
+        // app.jar refers to lib/dep.jar, which refers to lib/more.jar and a
+        // directory next to it, and back to app.jar
+        write_jar(
+            &dir.join("app.jar"),
+            Some("lib/dep.jar"),
+            &[("E.class", "app")],
+        );
+        let files = [("E.class", "dep"), ("F.class", "dep")];
+        write_jar(
+            &dir.join("lib/dep.jar"),
+            Some("more.jar res/ ../app.jar"),
+            &files,
+        );
+        write_jar(&dir.join("lib/more.jar"), None, &[("G.class", "more")]);
+        write_class(&dir.join("lib/res"), "H.class", "res");
+        write_jar(&dir.join("wild/x.jar"), None, &[("I.class", "wild")]);
+        write_class(&dir.join("classes"), "I.class", "classes");
+
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        let options = ClassPathOptions {
+            class_path: Some(format!(
+                "{}:{}:{}",
+                path("app.jar"),
+                path("wild/*"),
+                path("classes")
+            )),
+            boot_prepend: Some(path("prepend")),
+            boot_append: Some(path("append")),
+            ext_dirs: Some(path("ext")),
+        };
+        let class_path = ClassPath::new(jre.to_str().unwrap(), &options);
+        assert_eq!(read(&class_path, "A"), "prepend");
+        assert_eq!(read(&class_path, "B"), "rt");
+        assert_eq!(read(&class_path, "C"), "append");
+        assert_eq!(read(&class_path, "D"), "ext");
+        assert_eq!(read(&class_path, "E"), "app");
+        assert_eq!(read(&class_path, "F"), "dep");
+        assert_eq!(read(&class_path, "G"), "more");
+        assert_eq!(read(&class_path, "H"), "res");
+        assert_eq!(read(&class_path, "I"), "wild");
+
+        let (_, source) = class_path.read_class_with_source("C").unwrap();
+        assert!(class_path.is_boot_source(source));
+        let (_, source) = class_path.read_class_with_source("D").unwrap();
+        assert!(!class_path.is_boot_source(source));
+        let boot_class_path = [path("prepend"), path("jre/lib/rt.jar"), path("append")];
+        assert_eq!(class_path.boot_class_path(), boot_class_path.join(":"));
+
+        let searched = [
+            "prepend",
+            "jre/lib/rt.jar",
+            "append",
+            "ext/e.jar",
+            "app.jar",
+            "lib/dep.jar",
+            "lib/more.jar",
+            "lib/res/",
+            "wild/x.jar",
+            "classes",
+        ];
+        let searched: Vec<String> = searched.iter().map(|name| path(name)).collect();
+        let e = class_path.read_class("Missing").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            format!("Missing (searched {})", searched.join(", "))
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_class_sources() {
+        let memory = |classes: &[(&str, &str)]| {
+            let mut source = MemorySource::default();
+            for (name, data) in classes {
+                source.insert(name, data.as_bytes().to_vec());
+            }
+            Box::new(source)
+        };
+        let calls = Arc::new(AtomicUsize::new(0));
+        let generator = {
+            let calls = calls.clone();
+            FnSource::new("generator", move |name| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Some(Ok(format!("generated {}", name).into_bytes())).filter(|_| name == "b/B")
+            })
+        };
+        let mut class_path = ClassPath::from_sources(
+            vec![memory(&[("a/A", "boot")])],
+            vec![
+                Box::new(generator),
+                memory(&[("a/A", "user"), ("c/C", "user")]),
+            ],
+        );
+        class_path.push_source(memory(&[("d/D", "pushed")]));
+
+        assert_eq!(read(&class_path, "a/A"), "boot");
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(read(&class_path, "b/B"), "generated b/B");
+        assert_eq!(read(&class_path, "c/C"), "user");
+        assert_eq!(read(&class_path, "d/D"), "pushed");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let (_, source) = class_path.read_class_with_source("a/A").unwrap();
+        assert!(class_path.is_boot_source(source));
+        let (_, source) = class_path.read_class_with_source("c/C").unwrap();
+        assert!(!class_path.is_boot_source(source));
+        let e = class_path.read_class("Missing").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Missing (searched memory, generator, memory, memory)"
+        );
+
+        // only a missing file is left out, other errors are reported
+        let dir = DirSource::new(Path::new("test_data"));
+        assert!(dir.find_resource("missing").is_none());
+        assert!(matches!(dir.find_resource("java"), Some(Err(_))));
+    }
+
+    /// The class files in `dir`, relative to it.
+    fn class_files(dir: &Path, files: &mut Vec<PathBuf>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                class_files(&path, files);
+            } else if path.extension() == Some("class".as_ref()) {
+                files.push(path);
+            }
+        }
+    }
+
+    #[test]
+    fn test_runtime_image() {
+        let mut files = Vec::new();
+        class_files(Path::new("test_data/java"), &mut files);
+        class_files(Path::new("test_data/sun"), &mut files);
+        // zip has java/lang compressed with zip and the rest stored,
+        // compact-cp has all the classes with their strings shared
+        for jre in &["test_data/jimage/zip", "test_data/jimage/compact-cp"] {
+            let options = ClassPathOptions {
+                class_path: Some("test_data/io".to_string()),
+                ..ClassPathOptions::default()
+            };
+            let class_path = ClassPath::new(jre, &options);
+            let modules = Path::new(jre).join("lib/modules");
+            for file in &files {
+                let name = file.strip_prefix("test_data").unwrap().with_extension("");
+                let (data, source) = class_path
+                    .read_class_with_source(name.to_str().unwrap())
+                    .unwrap();
+                assert_eq!(data, fs::read(file).unwrap(), "{}", file.display());
+                assert_eq!(Path::new(&source.location()), modules);
+                assert!(class_path.is_boot_source(source));
+            }
+            assert_eq!(class_path.boot_class_path(), modules.to_str().unwrap());
+            for missing in &["java/lang/Missing", "missing/Class", "Missing"] {
+                let e = class_path.read_class(missing).unwrap_err();
+                let message = format!("{} (searched {}/lib/modules, test_data/io)", missing, jre);
+                assert_eq!(e.to_string(), message);
+            }
+        }
+    }
+}
@@ -0,0 +1,256 @@
+//! Where the bootstrap class loader reads class files from. The class path is
+//! a list of sources asked in order, the first that has a class wins. Besides
+//! directories, jars and runtime images, embedders can add sources of their
+//! own, e.g. classes they generated in memory.
+use crate::class_path::manifest_attribute;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use zip::ZipArchive;
+
+const MANIFEST: &str = "META-INF/MANIFEST.MF";
+
+/// Finds class files by the binary name of their class.
+pub trait ClassSource: fmt::Debug + Send + Sync {
+    /// Reads the class `name`, e.g. `java/lang/Object`, `None` if this source
+    /// doesn't have it.
+    fn find_class(&self, name: &str) -> Option<io::Result<Vec<u8>>>;
+
+    /// The packages this source has classes in, e.g. `java/lang`, with `""`
+    /// for the unnamed package. `None` if it can't tell without searching,
+    /// it's asked for classes of any package then.
+    fn packages(&self) -> Option<Vec<String>>;
+
+    /// Where the classes come from, for `-verbose:class` and errors.
+    fn location(&self) -> String;
+
+    /// Reads the resource `name`, e.g. `META-INF/services/java.sql.Driver`,
+    /// `None` if this source doesn't have it. The resources of a source of
+    /// just classes are their class files.
+    fn find_resource(&self, name: &str) -> Option<io::Result<Vec<u8>>> {
+        self.find_class(name.strip_suffix(".class")?)
+    }
+}
+
+/// The package of the class or file `name`, `""` for the unnamed package.
+pub fn package_of(name: &str) -> &str {
+    name.rfind('/').map_or("", |i| &name[..i])
+}
+
+/// A directory with a class file for each class, e.g. `java/lang/Object.class`.
+#[derive(Debug)]
+pub struct DirSource {
+    path: PathBuf,
+}
+
+impl DirSource {
+    pub fn new(path: &Path) -> Self {
+        DirSource {
+            path: path.to_owned(),
+        }
+    }
+}
+
+impl ClassSource for DirSource {
+    fn find_class(&self, name: &str) -> Option<io::Result<Vec<u8>>> {
+        self.find_resource(&(name.to_owned() + ".class"))
+    }
+
+    fn packages(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    fn location(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn find_resource(&self, name: &str) -> Option<io::Result<Vec<u8>>> {
+        let path = self.path.join(name);
+        tracing::debug!(?path, "read_file");
+        let mut buf = Vec::new();
+        match File::open(&path).and_then(|mut file| file.read_to_end(&mut buf)) {
+            Ok(_) => Some(Ok(buf)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+#[cfg(feature = "mmap")]
+type ArchiveReader = io::Cursor<memmap2::Mmap>;
+#[cfg(not(feature = "mmap"))]
+type ArchiveReader = io::BufReader<File>;
+
+/// A jar, opened once with the names of its files indexed.
+#[derive(Debug)]
+pub struct JarSource {
+    path: PathBuf,
+    zip: Mutex<ZipArchive<ArchiveReader>>,
+    /// The index in the jar of each file, by name.
+    files: HashMap<String, usize>,
+    /// The `Class-Path` of its manifest, relative to the jar.
+    manifest_class_path: Vec<PathBuf>,
+}
+
+impl JarSource {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: jars on the class path must not change while the VM runs
+        #[cfg(feature = "mmap")]
+        let reader = io::Cursor::new(unsafe { memmap2::Mmap::map(&file)? });
+        #[cfg(not(feature = "mmap"))]
+        let reader = io::BufReader::new(file);
+        let mut zip = ZipArchive::new(reader)?;
+        let mut files = HashMap::with_capacity(zip.len());
+        for i in 0..zip.len() {
+            // the first of duplicate names wins, like for the class path
+            files
+                .entry(zip.by_index(i)?.name().to_string())
+                .or_insert(i);
+        }
+        let mut manifest = String::new();
+        if let Ok(mut file) = zip.by_name(MANIFEST) {
+            file.read_to_string(&mut manifest)?;
+        }
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let manifest_class_path = manifest_attribute(&manifest, "Class-Path")
+            .map(|class_path| {
+                class_path
+                    .split_whitespace()
+                    .map(|url| dir.join(url.trim_start_matches("file:").replace("%20", " ")))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(JarSource {
+            path: path.to_owned(),
+            zip: Mutex::new(zip),
+            files,
+            manifest_class_path,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The jars and directories the `Class-Path` of its manifest refers to.
+    pub fn manifest_class_path(&self) -> &[PathBuf] {
+        &self.manifest_class_path
+    }
+}
+
+impl ClassSource for JarSource {
+    fn find_class(&self, name: &str) -> Option<io::Result<Vec<u8>>> {
+        self.find_resource(&(name.to_owned() + ".class"))
+    }
+
+    fn packages(&self) -> Option<Vec<String>> {
+        let mut packages: Vec<String> = (self.files.keys())
+            .filter(|name| name.ends_with(".class"))
+            .map(|name| package_of(name).to_string())
+            .collect();
+        packages.sort();
+        packages.dedup();
+        Some(packages)
+    }
+
+    fn location(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn find_resource(&self, name: &str) -> Option<io::Result<Vec<u8>>> {
+        let index = *self.files.get(name)?;
+        let mut zip = self.zip.lock().unwrap();
+        let read = |zip: &mut ZipArchive<ArchiveReader>| {
+            let mut file = zip.by_index(index)?;
+            let mut buf = Vec::<u8>::with_capacity(file.size() as usize);
+            file.read_to_end(&mut buf)?;
+            Ok(buf)
+        };
+        Some(read(&mut zip))
+    }
+}
+
+/// Class files in memory by the binary name of their class, e.g. generated
+/// ones that never get written to disk.
+#[derive(Debug, Default)]
+pub struct MemorySource {
+    classes: HashMap<String, Vec<u8>>,
+}
+
+impl MemorySource {
+    pub fn new(classes: HashMap<String, Vec<u8>>) -> Self {
+        MemorySource { classes }
+    }
+
+    pub fn insert(&mut self, name: &str, data: Vec<u8>) {
+        self.classes.insert(name.to_string(), data);
+    }
+}
+
+impl ClassSource for MemorySource {
+    fn find_class(&self, name: &str) -> Option<io::Result<Vec<u8>>> {
+        self.classes.get(name).map(|data| Ok(data.clone()))
+    }
+
+    fn packages(&self) -> Option<Vec<String>> {
+        let mut packages: Vec<String> = (self.classes.keys())
+            .map(|name| package_of(name).to_string())
+            .collect();
+        packages.sort();
+        packages.dedup();
+        Some(packages)
+    }
+
+    fn location(&self) -> String {
+        "memory".to_string()
+    }
+}
+
+/// Adapts a closure that finds class files, e.g. by generating them when
+/// they're first asked for.
+pub struct FnSource<F> {
+    location: String,
+    find_class: F,
+}
+
+impl<F> FnSource<F>
+where
+    F: Fn(&str) -> Option<io::Result<Vec<u8>>> + Send + Sync,
+{
+    pub fn new(location: &str, find_class: F) -> Self {
+        FnSource {
+            location: location.to_string(),
+            find_class,
+        }
+    }
+}
+
+impl<F> fmt::Debug for FnSource<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FnSource")
+            .field("location", &self.location)
+            .finish()
+    }
+}
+
+impl<F> ClassSource for FnSource<F>
+where
+    F: Fn(&str) -> Option<io::Result<Vec<u8>>> + Send + Sync,
+{
+    fn find_class(&self, name: &str) -> Option<io::Result<Vec<u8>>> {
+        (self.find_class)(name)
+    }
+
+    fn packages(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    fn location(&self) -> String {
+        self.location.clone()
+    }
+}
@@ -0,0 +1,508 @@
+//! Reads `lib/modules`, the runtime image of a JDK 9+ with the classes of all
+//! its modules. The file starts with an index: a header, the redirect and
+//! offset tables of a perfect hash of the resource names, the attributes of
+//! each resource's location and the strings they refer to. The resources
+//! follow, some of them compressed by jlink.
+use crate::class_source::ClassSource;
+use std::fs::File;
+use std::io;
+use std::io::{Error, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+#[cfg(not(feature = "mmap"))]
+use std::{
+    io::{Seek, SeekFrom},
+    sync::Mutex,
+};
+
+const IMAGE_MAGIC: u32 = 0xCAFE_DADA;
+const MAJOR_VERSION: u32 = 1;
+/// magic, version, flags, resource count, table length, locations size and
+/// strings size.
+const HEADER_SIZE: usize = 7 * 4;
+const HASH_MULTIPLIER: i32 = 0x0100_0193;
+
+const ATTRIBUTE_END: usize = 0;
+const ATTRIBUTE_MODULE: usize = 1;
+const ATTRIBUTE_PARENT: usize = 2;
+const ATTRIBUTE_BASE: usize = 3;
+const ATTRIBUTE_EXTENSION: usize = 4;
+const ATTRIBUTE_OFFSET: usize = 5;
+const ATTRIBUTE_COMPRESSED: usize = 6;
+const ATTRIBUTE_UNCOMPRESSED: usize = 7;
+const ATTRIBUTE_COUNT: usize = 8;
+
+/// Compressed resources start with a header, the content after it may be
+/// compressed again.
+const COMPRESSED_MAGIC: u32 = 0xCAFE_FAFA;
+/// magic, compressed size, uncompressed size, decompressor name, decompressor
+/// config and whether the content is compressed again.
+const COMPRESSED_HEADER_SIZE: usize = 4 + 8 + 8 + 4 + 4 + 1;
+
+const CONSTANT_UTF8: u8 = 1;
+const CONSTANT_LONG: u8 = 5;
+const CONSTANT_DOUBLE: u8 = 6;
+/// A `CONSTANT_Utf8` moved to the string table by `compact-cp`.
+const EXTERNALIZED_STRING: u8 = 23;
+/// A descriptor whose class names were moved to the string table.
+const EXTERNALIZED_STRING_DESCRIPTOR: u8 = 25;
+
+#[cfg(feature = "mmap")]
+type ImageData = memmap2::Mmap;
+#[cfg(not(feature = "mmap"))]
+type ImageData = Mutex<File>;
+
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/// `ImageStringsReader.hashCode`, FNV-1a of the name with `seed`.
+fn hash_code(name: &[u8], seed: i32) -> usize {
+    let hash = name.iter().fold(seed, |hash, byte| {
+        hash.wrapping_mul(HASH_MULTIPLIER) ^ i32::from(*byte)
+    });
+    (hash & 0x7FFF_FFFF) as usize
+}
+
+/// The size of a constant pool entry after its tag, besides `CONSTANT_Utf8`.
+fn constant_size(tag: u8) -> Option<usize> {
+    match tag {
+        7 | 8 | 16 | 19 | 20 => Some(2),
+        15 => Some(3),
+        3 | 4 | 9 | 10 | 11 | 12 | 17 | 18 => Some(4),
+        5 | 6 => Some(8),
+        _ => None,
+    }
+}
+
+/// Reads a resource from its start, big-endian like class files.
+struct Input<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Input<'a> {
+    fn take(&mut self, size: usize) -> io::Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + size)
+            .ok_or_else(|| invalid_data("truncated compressed class".to_string()))?;
+        self.pos += size;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// `CompressIndexes.readInt`: if the high bit of the first byte is set,
+    /// the next two are the length of the value, 1 to 3 bytes, and the rest
+    /// its high bits. Otherwise it's 4 bytes.
+    fn compressed_int(&mut self) -> io::Result<u32> {
+        let header = self.take(1)?[0];
+        if header & 0x80 == 0 {
+            let rest = self.take(3)?;
+            return Ok(u32::from_be_bytes([header, rest[0], rest[1], rest[2]]));
+        }
+        let length = usize::from((header >> 5) & 3);
+        if length == 0 {
+            return Err(invalid_data("invalid compressed index".to_string()));
+        }
+        let rest = self.take(length - 1)?;
+        Ok((rest.iter()).fold(u32::from(header & 0x1F), |value, byte| {
+            value << 8 | u32::from(*byte)
+        }))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.data[self.pos.min(self.data.len())..]
+    }
+}
+
+/// A runtime image, opened once. The index is read into memory, resources
+/// are read when looked up.
+pub struct JImage {
+    path: PathBuf,
+    data: ImageData,
+    big_endian: bool,
+    index: Vec<u8>,
+    table_length: usize,
+    locations_size: usize,
+}
+
+impl std::fmt::Debug for JImage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JImage").field("path", &self.path).finish()
+    }
+}
+
+impl JImage {
+    pub fn open(path: &Path) -> io::Result<JImage> {
+        let mut file = File::open(path)?;
+        let mut index = vec![0; HEADER_SIZE];
+        file.read_exact(&mut index)?;
+        // the image is in the byte order of the platform it was made for
+        let big_endian = match u32::from_be_bytes([index[0], index[1], index[2], index[3]]) {
+            IMAGE_MAGIC => true,
+            magic if magic.swap_bytes() == IMAGE_MAGIC => false,
+            _ => return Err(invalid_data(format!("{} is not a jimage", path.display()))),
+        };
+        let header = |i: usize| read_u32(&index, i * 4, big_endian) as usize;
+        let version = header(1);
+        if version >> 16 != MAJOR_VERSION as usize {
+            let message = format!(
+                "unsupported jimage version {}.{}",
+                version >> 16,
+                version & 0xFFFF
+            );
+            return Err(invalid_data(message));
+        }
+        let (table_length, locations_size, strings_size) = (header(4), header(5), header(6));
+        let index_size = HEADER_SIZE + table_length * 8 + locations_size + strings_size;
+        index.resize(index_size, 0);
+        file.read_exact(&mut index[HEADER_SIZE..])?;
+        // SAFETY: the runtime image must not change while the VM runs
+        #[cfg(feature = "mmap")]
+        let data = unsafe { memmap2::Mmap::map(&file)? };
+        #[cfg(not(feature = "mmap"))]
+        let data = Mutex::new(file);
+        Ok(JImage {
+            path: path.to_owned(),
+            data,
+            big_endian,
+            index,
+            table_length,
+            locations_size,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads the file `name`, e.g. `java/lang/Object.class`, from the module
+    /// of its package.
+    pub fn read_module_file(&self, name: &str) -> Option<io::Result<Vec<u8>>> {
+        let module = match self.file_module(name) {
+            Ok(module) => module?,
+            Err(e) => return Some(Err(e)),
+        };
+        self.read(&format!("/{}/{}", module, name))
+    }
+
+    /// The module with the package of the file `name`, files outside packages
+    /// aren't in any.
+    fn file_module(&self, name: &str) -> io::Result<Option<String>> {
+        match name.rfind('/') {
+            Some(end) => self.package_module(&name[..end].replace('/', ".")),
+            None => Ok(None),
+        }
+    }
+
+    /// The module with the classes of `package`, e.g. `java.lang`. The
+    /// resource `/packages/<package>` lists the modules with the package and
+    /// whether theirs is empty.
+    fn package_module(&self, package: &str) -> io::Result<Option<String>> {
+        let data = match self.read(&format!("/packages/{}", package)) {
+            Some(data) => data?,
+            None => return Ok(None),
+        };
+        for entry in data.chunks_exact(8) {
+            let is_empty = read_u32(entry, 0, self.big_endian);
+            if is_empty == 0 {
+                let module = self.string(read_u32(entry, 4, self.big_endian))?;
+                return Ok(Some(String::from_utf8_lossy(module).into_owned()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The packages of the modules in the image, each has a location
+    /// `/packages/<package>`.
+    fn package_names(&self) -> io::Result<Vec<String>> {
+        let mut packages = Vec::new();
+        for index in 0..self.table_length {
+            let attributes = self.location(self.table_entry(1, index) as usize)?;
+            if self.string(attributes[ATTRIBUTE_MODULE] as u32)? != b"packages" {
+                continue;
+            }
+            let name = self.location_name(&attributes)?;
+            if let Some(package) = name.strip_prefix(b"/packages/") {
+                packages.push(String::from_utf8_lossy(package).replace('.', "/"));
+            }
+        }
+        Ok(packages)
+    }
+
+    /// Reads the resource `name`, e.g. `/java.base/java/lang/Object.class`.
+    pub fn read(&self, name: &str) -> Option<io::Result<Vec<u8>>> {
+        let attributes = match self.find_location(name) {
+            Ok(attributes) => attributes?,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(self.read_resource(&attributes))
+    }
+
+    /// The attributes of the location of `name`, at the index the perfect
+    /// hash of the name gives.
+    fn find_location(&self, name: &str) -> io::Result<Option<[u64; ATTRIBUTE_COUNT]>> {
+        if self.table_length == 0 {
+            return Ok(None);
+        }
+        let name = name.as_bytes();
+        let slot = hash_code(name, HASH_MULTIPLIER) % self.table_length;
+        let index = match self.table_entry(0, slot) as i32 {
+            0 => return Ok(None),
+            // the only name with this hash
+            redirect if redirect < 0 => (-1 - redirect) as usize,
+            // a seed that maps the names with this hash to different indexes
+            seed => hash_code(name, seed) % self.table_length,
+        };
+        if index >= self.table_length {
+            return Err(invalid_data(format!("invalid location index {}", index)));
+        }
+        let attributes = self.location(self.table_entry(1, index) as usize)?;
+        // the hash of other names may lead here too
+        if self.location_name(&attributes)? == name {
+            Ok(Some(attributes))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// The entry at `index` of the redirect table if `table` is 0, or of the
+    /// location offsets if it's 1.
+    fn table_entry(&self, table: usize, index: usize) -> u32 {
+        let offset = HEADER_SIZE + (table * self.table_length + index) * 4;
+        read_u32(&self.index, offset, self.big_endian)
+    }
+
+    /// Decodes the attributes at `offset` in the locations: each starts with
+    /// a byte of its kind and length, then that many bytes of its value.
+    fn location(&self, offset: usize) -> io::Result<[u64; ATTRIBUTE_COUNT]> {
+        let start = HEADER_SIZE + self.table_length * 8;
+        let locations = &self.index[start..start + self.locations_size];
+        let truncated = || invalid_data(format!("invalid location offset {}", offset));
+        let mut attributes = [0; ATTRIBUTE_COUNT];
+        let mut pos = offset;
+        loop {
+            let byte = *locations.get(pos).ok_or_else(truncated)?;
+            let kind = usize::from(byte >> 3);
+            if kind == ATTRIBUTE_END {
+                return Ok(attributes);
+            }
+            let length = usize::from(byte & 7) + 1;
+            let value = locations
+                .get(pos + 1..pos + 1 + length)
+                .ok_or_else(truncated)?;
+            let attribute = attributes.get_mut(kind).ok_or_else(truncated)?;
+            *attribute = (value.iter()).fold(0, |value, byte| value << 8 | u64::from(*byte));
+            pos += 1 + length;
+        }
+    }
+
+    /// The name of a location, `/module/parent/base.extension` without the
+    /// parts it doesn't have.
+    fn location_name(&self, attributes: &[u64; ATTRIBUTE_COUNT]) -> io::Result<Vec<u8>> {
+        let part = |kind: usize| self.string(attributes[kind] as u32);
+        let mut name = Vec::new();
+        let module = part(ATTRIBUTE_MODULE)?;
+        if !module.is_empty() {
+            name.push(b'/');
+            name.extend_from_slice(module);
+            name.push(b'/');
+        }
+        let parent = part(ATTRIBUTE_PARENT)?;
+        if !parent.is_empty() {
+            name.extend_from_slice(parent);
+            name.push(b'/');
+        }
+        name.extend_from_slice(part(ATTRIBUTE_BASE)?);
+        let extension = part(ATTRIBUTE_EXTENSION)?;
+        if !extension.is_empty() {
+            name.push(b'.');
+            name.extend_from_slice(extension);
+        }
+        Ok(name)
+    }
+
+    /// The NUL-terminated modified UTF-8 string at `offset` in the strings.
+    fn string(&self, offset: u32) -> io::Result<&[u8]> {
+        let start = HEADER_SIZE + self.table_length * 8 + self.locations_size;
+        let strings = &self.index[start..];
+        let string = strings
+            .get(offset as usize..)
+            .and_then(|rest| Some(&rest[..rest.iter().position(|byte| *byte == 0)?]));
+        string.ok_or_else(|| invalid_data(format!("invalid string offset {}", offset)))
+    }
+
+    fn read_resource(&self, attributes: &[u64; ATTRIBUTE_COUNT]) -> io::Result<Vec<u8>> {
+        let offset = self.index.len() as u64 + attributes[ATTRIBUTE_OFFSET];
+        let compressed_size = attributes[ATTRIBUTE_COMPRESSED] as usize;
+        let uncompressed_size = attributes[ATTRIBUTE_UNCOMPRESSED] as usize;
+        if compressed_size == 0 {
+            return self.read_at(offset, uncompressed_size);
+        }
+        let mut data = self.read_at(offset, compressed_size)?;
+        while data.len() >= COMPRESSED_HEADER_SIZE
+            && read_u32(&data, 0, self.big_endian) == COMPRESSED_MAGIC
+        {
+            let size = read_u64(&data, 12, self.big_endian) as usize;
+            let decompressor = self.string(read_u32(&data, 20, self.big_endian))?;
+            let content = &data[COMPRESSED_HEADER_SIZE..];
+            data = match decompressor {
+                b"zip" => {
+                    let mut inflated = Vec::with_capacity(size);
+                    flate2::read::ZlibDecoder::new(content).read_to_end(&mut inflated)?;
+                    inflated
+                }
+                b"compact-cp" => self.expand_shared_strings(content)?,
+                _ => {
+                    let decompressor = String::from_utf8_lossy(decompressor);
+                    return Err(invalid_data(format!(
+                        "unknown decompressor {}",
+                        decompressor
+                    )));
+                }
+            };
+        }
+        Ok(data)
+    }
+
+    /// Undoes `compact-cp`, which moves the `CONSTANT_Utf8` entries of class
+    /// files into the strings of the image to share them between classes.
+    fn expand_shared_strings(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut input = Input { data, pos: 0 };
+        let mut class = Vec::with_capacity(data.len() * 2);
+        // magic, minor and major version
+        class.extend_from_slice(input.take(8)?);
+        let count = input.u16()?;
+        class.extend_from_slice(&count.to_be_bytes());
+        let mut i = 1;
+        while i < count {
+            let tag = input.take(1)?[0];
+            let string = match tag {
+                CONSTANT_UTF8 => {
+                    let length = input.u16()?;
+                    input.take(usize::from(length))?.to_vec()
+                }
+                EXTERNALIZED_STRING => self.string(input.compressed_int()?)?.to_vec(),
+                EXTERNALIZED_STRING_DESCRIPTOR => self.shared_descriptor(&mut input)?,
+                _ => {
+                    let size = constant_size(tag)
+                        .ok_or_else(|| invalid_data(format!("invalid constant tag {}", tag)))?;
+                    class.push(tag);
+                    class.extend_from_slice(input.take(size)?);
+                    // they take two entries
+                    if tag == CONSTANT_LONG || tag == CONSTANT_DOUBLE {
+                        i += 1;
+                    }
+                    i += 1;
+                    continue;
+                }
+            };
+            if string.len() > usize::from(u16::MAX) {
+                return Err(invalid_data("constant string too long".to_string()));
+            }
+            class.push(CONSTANT_UTF8);
+            class.extend_from_slice(&(string.len() as u16).to_be_bytes());
+            class.extend_from_slice(&string);
+            i += 1;
+        }
+        class.extend_from_slice(input.rest());
+        Ok(class)
+    }
+
+    /// A descriptor with the package and simple name of each class it refers
+    /// to replaced by indexes of strings, e.g. `(L)V` and the strings of
+    /// `java/lang` and `Object` for `(Ljava/lang/Object;)V`.
+    fn shared_descriptor(&self, input: &mut Input) -> io::Result<Vec<u8>> {
+        let descriptor = self.string(input.compressed_int()?)?;
+        let length = input.compressed_int()? as usize;
+        let mut indexes = Input {
+            data: input.take(length)?,
+            pos: 0,
+        };
+        let mut next_string = || {
+            if indexes.is_empty() {
+                return Err(invalid_data("missing descriptor class".to_string()));
+            }
+            self.string(indexes.compressed_int()?)
+        };
+        let mut expanded = Vec::with_capacity(descriptor.len() * 2);
+        for byte in descriptor {
+            expanded.push(*byte);
+            if *byte == b'L' {
+                let package = next_string()?;
+                if !package.is_empty() {
+                    expanded.extend_from_slice(package);
+                    expanded.push(b'/');
+                }
+                expanded.extend_from_slice(next_string()?);
+            }
+        }
+        Ok(expanded)
+    }
+
+    #[cfg(feature = "mmap")]
+    fn read_at(&self, offset: u64, size: usize) -> io::Result<Vec<u8>> {
+        let start = offset as usize;
+        let data = self.data.get(start..start + size);
+        let data = data.ok_or_else(|| invalid_data(format!("invalid resource offset {}", offset)));
+        Ok(data?.to_vec())
+    }
+
+    #[cfg(not(feature = "mmap"))]
+    fn read_at(&self, offset: u64, size: usize) -> io::Result<Vec<u8>> {
+        let mut file = self.data.lock().unwrap();
+        file.seek(SeekFrom::Start(offset))?;
+        let mut data = vec![0; size];
+        file.read_exact(&mut data)?;
+        Ok(data)
+    }
+}
+
+impl ClassSource for JImage {
+    fn find_class(&self, name: &str) -> Option<io::Result<Vec<u8>>> {
+        self.read_module_file(&(name.to_owned() + ".class"))
+    }
+
+    fn packages(&self) -> Option<Vec<String>> {
+        self.package_names().ok()
+    }
+
+    fn location(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn find_resource(&self, name: &str) -> Option<io::Result<Vec<u8>>> {
+        self.read_module_file(name)
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize, big_endian: bool) -> u32 {
+    let bytes = [
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ];
+    if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    }
+}
+
+fn read_u64(bytes: &[u8], offset: usize, big_endian: bool) -> u64 {
+    let (high, low) = if big_endian {
+        (offset, offset + 4)
+    } else {
+        (offset + 4, offset)
+    };
+    u64::from(read_u32(bytes, high, big_endian)) << 32 | u64::from(read_u32(bytes, low, big_endian))
+}
@@ -0,0 +1,240 @@
+use crate::class_path::{manifest_attribute, PATH_SEPARATOR};
+use crate::runtime::VerifyMode;
+use anyhow::{anyhow, bail, Context, Result};
+use std::fs::File;
+use std::io::Read;
+
+pub const USAGE: &str = "Usage: hippo [options] <mainclass> [args...]
+           (to execute a class)
+   or  hippo [options] -jar <jarfile> [args...]
+           (to execute a jar file)
+where options include:
+    -cp <class search path of directories and zip/jar files>
+    -classpath <class search path of directories and zip/jar files>
+                  A : separated list of directories, JAR archives,
+                  and ZIP archives to search for class files.
+    -D<name>=<value>
+                  set a system property
+    -verbose:class
+                  enable verbose output for class loading
+    -Xbootclasspath/a:<directories and zip/jar files separated by :>
+                  append to end of bootstrap class path
+    -Xbootclasspath/p:<directories and zip/jar files separated by :>
+                  prepend in front of bootstrap class path
+    -Xmx<size>    set maximum Java heap size
+    -Xss<size>    set java thread stack size
+    -Xverify:<none|remote|all>
+                  verify no classes, classes not on the boot class path
+                  (the default) or all classes
+    -? -help      print this help message";
+
+/// What the `java`-style command line asks for.
+#[derive(Debug, Default, PartialEq)]
+pub struct LaunchOptions {
+    pub class_path: Option<String>,
+    /// `-Xbootclasspath/p:`, the last one given first.
+    pub boot_class_path_prepend: Option<String>,
+    /// `-Xbootclasspath/a:`, the first one given first.
+    pub boot_class_path_append: Option<String>,
+    /// Binary name of the main class, e.g. `com/example/Main`.
+    pub main_class: Option<String>,
+    pub jar: Option<String>,
+    pub args: Vec<String>,
+    pub system_properties: Vec<(String, String)>,
+    pub max_heap_size: Option<usize>,
+    pub stack_size: Option<usize>,
+    pub verbose_class: bool,
+    pub verify_mode: VerifyMode,
+    pub help: bool,
+}
+
+/// Parses the arguments after the program name. Options must come before the
+/// main class or `-jar <jarfile>`, everything after it is passed to `main`.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<LaunchOptions> {
+    let mut options = LaunchOptions::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-cp" | "-classpath" | "--class-path" => {
+                let class_path = args
+                    .next()
+                    .ok_or_else(|| anyhow!("{} requires class path specification", arg))?;
+                options.class_path = Some(class_path);
+            }
+            "-jar" => {
+                let jar = args
+                    .next()
+                    .ok_or_else(|| anyhow!("-jar requires jar file specification"))?;
+                options.jar = Some(jar);
+                break;
+            }
+            "-verbose:class" => options.verbose_class = true,
+            "-?" | "-h" | "-help" | "--help" => options.help = true,
+            _ if arg.starts_with("-D") => {
+                let property = &arg[2..];
+                let (key, value) = match property.find('=') {
+                    Some(i) => (&property[..i], &property[i + 1..]),
+                    None => (property, ""),
+                };
+                options
+                    .system_properties
+                    .push((key.to_string(), value.to_string()));
+            }
+            _ if arg.starts_with("-Xbootclasspath/p:") => {
+                let path = arg["-Xbootclasspath/p:".len()..].to_string();
+                let prepend = options.boot_class_path_prepend.take();
+                options.boot_class_path_prepend = Some(join_class_paths(Some(path), prepend));
+            }
+            _ if arg.starts_with("-Xbootclasspath/a:") => {
+                let path = arg["-Xbootclasspath/a:".len()..].to_string();
+                let append = options.boot_class_path_append.take();
+                options.boot_class_path_append = Some(join_class_paths(append, Some(path)));
+            }
+            _ if arg.starts_with("-Xmx") => {
+                let size = parse_size(&arg[4..])
+                    .ok_or_else(|| anyhow!("Invalid maximum heap size: {}", arg))?;
+                options.max_heap_size = Some(size);
+            }
+            _ if arg.starts_with("-Xss") => {
+                let size = parse_size(&arg[4..])
+                    .ok_or_else(|| anyhow!("Invalid thread stack size: {}", arg))?;
+                options.stack_size = Some(size);
+            }
+            _ if arg.starts_with("-Xverify:") => {
+                options.verify_mode = match &arg["-Xverify:".len()..] {
+                    "none" => VerifyMode::None,
+                    "remote" => VerifyMode::Remote,
+                    "all" => VerifyMode::All,
+                    _ => bail!("Invalid verification option: {}", arg),
+                };
+            }
+            _ if arg.starts_with('-') => bail!("Unrecognized option: {}", arg),
+            _ => {
+                options.main_class = Some(arg.replace('.', "/"));
+                break;
+            }
+        }
+    }
+    options.args = args.collect();
+    Ok(options)
+}
+
+/// Joins two class paths, either may be missing but not both.
+fn join_class_paths(first: Option<String>, second: Option<String>) -> String {
+    match (first, second) {
+        (Some(first), Some(second)) => format!("{}{}{}", first, PATH_SEPARATOR, second),
+        (first, second) => first.or(second).unwrap_or_default(),
+    }
+}
+
+/// Parses a memory size like `64m`, the suffix is one of k, m or g in either
+/// case, without one the size is in bytes.
+fn parse_size(size: &str) -> Option<usize> {
+    let (digits, unit) = match size.chars().last()? {
+        'k' | 'K' => (&size[..size.len() - 1], 1 << 10),
+        'm' | 'M' => (&size[..size.len() - 1], 1 << 20),
+        'g' | 'G' => (&size[..size.len() - 1], 1 << 30),
+        _ => (size, 1),
+    };
+    let n: usize = digits.parse().ok()?;
+    n.checked_mul(unit).filter(|n| *n > 0)
+}
+
+/// Reads the `Main-Class` attribute of the jar's manifest.
+pub fn read_jar_main_class(jar: &str) -> Result<String> {
+    let file = File::open(jar).with_context(|| format!("Unable to access jarfile {}", jar))?;
+    let mut zip = zip::ZipArchive::new(file).with_context(|| format!("Invalid jarfile {}", jar))?;
+    let mut manifest = String::new();
+    zip.by_name("META-INF/MANIFEST.MF")
+        .with_context(|| format!("no main manifest attribute, in {}", jar))?
+        .read_to_string(&mut manifest)?;
+    manifest_main_class(&manifest)
+        .map(|main_class| main_class.replace('.', "/"))
+        .ok_or_else(|| anyhow!("no main manifest attribute, in {}", jar))
+}
+
+/// Finds `Main-Class` in the main section of a manifest.
+fn manifest_main_class(manifest: &str) -> Option<String> {
+    manifest_attribute(manifest, "Main-Class")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{manifest_main_class, parse_args, parse_size, LaunchOptions};
+    use crate::runtime::VerifyMode;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(args(&[
+            "-cp",
+            "a.jar:classes",
+            "-Dfoo=bar=baz",
+            "-Dempty",
+            "-Xmx64m",
+            "-Xss512k",
+            "-Xbootclasspath/p:p1",
+            "-Xbootclasspath/p:p2",
+            "-Xbootclasspath/a:a1",
+            "-Xbootclasspath/a:a2",
+            "-verbose:class",
+            "-Xverify:all",
+            "com.example.Main",
+            "-cp",
+            "x",
+        ]))
+        .unwrap();
+        assert_eq!(
+            options,
+            LaunchOptions {
+                class_path: Some("a.jar:classes".to_string()),
+                boot_class_path_prepend: Some("p2:p1".to_string()),
+                boot_class_path_append: Some("a1:a2".to_string()),
+                main_class: Some("com/example/Main".to_string()),
+                args: args(&["-cp", "x"]),
+                system_properties: vec![
+                    ("foo".to_string(), "bar=baz".to_string()),
+                    ("empty".to_string(), "".to_string()),
+                ],
+                max_heap_size: Some(64 << 20),
+                stack_size: Some(512 << 10),
+                verbose_class: true,
+                verify_mode: VerifyMode::All,
+                ..Default::default()
+            }
+        );
+
+        let options = parse_args(args(&["-jar", "app.jar", "a"])).unwrap();
+        assert_eq!(options.jar.as_deref(), Some("app.jar"));
+        assert_eq!(options.main_class, None);
+        assert_eq!(options.args, args(&["a"]));
+
+        assert!(parse_args(args(&["-cp"])).is_err());
+        assert!(parse_args(args(&["-Xmx12q", "Main"])).is_err());
+        assert!(parse_args(args(&["-Xss", "Main"])).is_err());
+        assert!(parse_args(args(&["-server", "Main"])).is_err());
+        assert!(parse_args(args(&["-Xverify:some", "Main"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024"), Some(1024));
+        assert_eq!(parse_size("2k"), Some(2048));
+        assert_eq!(parse_size("1G"), Some(1 << 30));
+        assert_eq!(parse_size("m"), None);
+        assert_eq!(parse_size("0"), None);
+    }
+
+    #[test]
+    fn test_manifest_main_class() {
+        let manifest = "Manifest-Version: 1.0\r\nMain-Class: com.example.VeryLong\r\n MainClass\r\n\r\nName: x\r\nMain-Class: Other\r\n";
+        assert_eq!(
+            manifest_main_class(manifest).as_deref(),
+            Some("com.example.VeryLongMainClass")
+        );
+        assert_eq!(manifest_main_class("Manifest-Version: 1.0\n"), None);
+    }
+}
@@ -0,0 +1,19 @@
+//! A JVM running Java 8 class files. `Jvm` runs a main class, and an
+//! embedder can bind Rust functions to the `native` methods of its classes
+//! with `Jvm::register_native` and add `ClassSource`s, e.g. of classes
+//! generated in memory, with `JvmOptions::class_sources`.
+#![allow(dead_code)]
+
+#[macro_use]
+mod macros;
+mod class_parser;
+mod class_path;
+mod class_source;
+mod jimage;
+#[doc(hidden)]
+pub mod launcher;
+mod nom_utils;
+mod runtime;
+
+pub use crate::class_source::{ClassSource, DirSource, FnSource, JarSource, MemorySource};
+pub use crate::runtime::{Class, Jvm, JvmEnv, JvmOptions, NativeMethod, Operand, VerifyMode};
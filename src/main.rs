@@ -1,38 +1,69 @@
-#![allow(dead_code)]
-
-use crate::runtime::Jvm;
+use hippo::launcher::{parse_args, read_jar_main_class, USAGE};
+use hippo::{Jvm, JvmOptions};
 use std::env;
 use std::fs::OpenOptions;
 use std::io::BufWriter;
+use std::process::exit;
 use tracing_subscriber::EnvFilter;
 
-#[macro_use]
-mod macros;
-mod class_parser;
-mod class_path;
-mod nom_utils;
-mod runtime;
-
 fn main() {
-    env::set_var("RUST_LOG", "debug");
-    let file = OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .create(true)
-        .open("hippo.log")
-        .unwrap();
-    let writer = BufWriter::new(file);
-    let (non_blocking, _guard) = tracing_appender::non_blocking(writer);
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .with_writer(non_blocking)
-        .json()
-        .init();
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("Error: {}\n{}", e, USAGE);
+            exit(1);
+        }
+    };
+    if options.help {
+        println!("{}", USAGE);
+        exit(0);
+    }
+    let (main_class, class_path) = match (options.jar, options.main_class) {
+        (Some(jar), _) => match read_jar_main_class(&jar) {
+            Ok(main_class) => (main_class, Some(jar)),
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                exit(1);
+            }
+        },
+        (None, Some(main_class)) => (main_class, options.class_path),
+        (None, None) => {
+            eprintln!("{}", USAGE);
+            exit(1);
+        }
+    };
+
+    // logs are only written when asked for with RUST_LOG
+    let guard = env::var_os("RUST_LOG").map(|_| {
+        let file = OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .create(true)
+            .open("hippo.log")
+            .unwrap();
+        let writer = BufWriter::new(file);
+        let (non_blocking, guard) = tracing_appender::non_blocking(writer);
+        tracing_subscriber::fmt()
+            .with_env_filter(EnvFilter::from_default_env())
+            .with_writer(non_blocking)
+            .json()
+            .init();
+        guard
+    });
 
-    let mut jvm = Jvm::new(
-        "main/Main",
-        Some("./jre".to_string()),
-        Some("./jre/lib/rt".to_string()),
-    );
-    jvm.run();
+    let mut jvm = Jvm::new(JvmOptions {
+        jre: None,
+        class_path,
+        boot_class_path_prepend: options.boot_class_path_prepend,
+        boot_class_path_append: options.boot_class_path_append,
+        class_sources: Vec::new(),
+        system_properties: options.system_properties,
+        max_heap_size: options.max_heap_size,
+        stack_size: options.stack_size,
+        verbose_class: options.verbose_class,
+        verify_mode: options.verify_mode,
+    });
+    let status = jvm.run(&main_class, &options.args);
+    drop(guard);
+    exit(status);
 }
@@ -1,19 +1,35 @@
+use crate::class_parser::attribute_info::predefined_attribute::{
+    BootstrapMethod, PredefinedAttribute,
+};
 use crate::class_parser::constant_pool::ConstPool;
 use crate::class_parser::field_info::FieldInfo;
 use crate::class_parser::{
     ClassFile, ACC_FINAL, ACC_INTERFACE, ACC_PRIVATE, ACC_PROTECTED, ACC_PUBLIC, ACC_STATIC,
     ACC_SUPER,
 };
+use crate::runtime::class::vtable::{self, ItableEntry, Selection};
 use crate::runtime::field::Field;
 use crate::runtime::frame::operand_stack::Operand;
 use crate::runtime::method::Method;
 use nom::lib::std::collections::HashMap;
 use nom::lib::std::fmt::{Debug, Formatter};
-use std::cell::{Cell, RefCell};
 use std::fmt;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Condvar, Mutex};
 use tracing::trace;
 
+/// How far a class got in linking and initialization (JVMS §5.5).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InitState {
+    Loaded,
+    /// Verified and prepared, static fields hold their initial values.
+    Linked,
+    /// `<clinit>` runs in the thread with this id.
+    BeingInitialized(u64),
+    Initialized,
+    /// Initialization failed, the class can't be used.
+    Erroneous,
+}
+
 #[derive(Clone)]
 pub struct InstanceClass {
     inner: Arc<InnerClass>,
@@ -28,9 +44,24 @@ struct InnerClass {
     static_fields: HashMap<String, Field>,
     instance_fields: HashMap<String, Field>,
     static_field_values: Mutex<Vec<Operand>>,
+    /// The indexes of the static fields with a `String` constant value, and
+    /// the values, which are interned when the class is initialized.
+    constant_strings: Vec<(usize, String)>,
     methods: Vec<Method>,
-    mirror_class_name: RefCell<Option<String>>,
-    inited: Cell<bool>,
+    /// The superinterfaces, direct or not, of the class and of its superclasses.
+    all_interfaces: Vec<InstanceClass>,
+    /// The methods `invokevirtual` selects from, empty for interfaces.
+    vtable: Vec<Selection>,
+    /// The methods `invokeinterface` selects from, empty for interfaces.
+    itable: Vec<ItableEntry>,
+    /// The methods of an interface its itable entries have, in that order.
+    interface_methods: Vec<Method>,
+    bootstrap_methods: Vec<BootstrapMethod>,
+    /// The name of the source file from the `SourceFile` attribute.
+    source_file: Option<String>,
+    init_state: Mutex<InitState>,
+    /// Notified when initialization completes or fails.
+    init_done: Condvar,
 }
 
 impl InstanceClass {
@@ -45,8 +76,26 @@ impl InstanceClass {
             access_flags,
             fields: field_infos,
             methods: method_infos,
+            attributes,
             ..
         } = class_file;
+        let source_file = attributes.iter().find_map(|attr| match &attr.attribute {
+            PredefinedAttribute::SourceFileAttribute(attr) => Some(
+                constant_pool
+                    .get_utf8_string_at(attr.sourcefile_index)
+                    .to_string(),
+            ),
+            _ => None,
+        });
+        let bootstrap_methods = attributes
+            .into_iter()
+            .find_map(|attr| match attr.attribute {
+                PredefinedAttribute::BootstrapMethodsAttribute(attr) => {
+                    Some(attr.bootstrap_methods)
+                }
+                _ => None,
+            })
+            .unwrap_or_default();
         let base_index = super_class
             .as_ref()
             .map(|c| c.total_instance_fields())
@@ -56,6 +105,7 @@ impl InstanceClass {
         let mut static_fields = HashMap::new();
         let mut instance_fields = HashMap::new();
         let mut static_field_values = Vec::new();
+        let mut constant_strings = Vec::new();
         for filed_info in &field_infos {
             if filed_info.is_static() {
                 let f = Field::new(&constant_pool, filed_info, static_index);
@@ -65,6 +115,9 @@ impl InstanceClass {
                     .unwrap_or(f.default_value());
 
                 static_field_values.push(v);
+                if let Some(s) = get_constant_string_from_field_info(filed_info, &constant_pool) {
+                    constant_strings.push((static_index, s));
+                }
                 static_index += 1;
             } else {
                 let f = Field::new(&constant_pool, filed_info, instance_index);
@@ -72,10 +125,18 @@ impl InstanceClass {
                 instance_index += 1;
             }
         }
-        let methods = method_infos
+        let methods: Vec<_> = method_infos
             .into_iter()
             .map(|method| Method::new(&constant_pool, method, name.clone()))
             .collect();
+        let all_interfaces = vtable::all_interfaces(super_class.as_ref(), &interfaces);
+        let (vtable, itable, interface_methods) = if access_flags & ACC_INTERFACE != 0 {
+            (vec![], vec![], vtable::interface_methods(&methods))
+        } else {
+            let vtable = vtable::build_vtable(super_class.as_ref(), &methods, &all_interfaces);
+            let itable = vtable::build_itable(&vtable, &all_interfaces);
+            (vtable, itable, vec![])
+        };
         let inner_class = InnerClass {
             name,
             constant_pool,
@@ -84,26 +145,40 @@ impl InstanceClass {
             instance_fields,
             static_fields,
             static_field_values: Mutex::new(static_field_values),
+            constant_strings,
             methods,
+            all_interfaces,
+            vtable,
+            itable,
+            interface_methods,
+            bootstrap_methods,
+            source_file,
             interfaces,
-            mirror_class_name: RefCell::new(None),
-            inited: Cell::new(false),
+            init_state: Mutex::new(InitState::Loaded),
+            init_done: Condvar::new(),
         };
         InstanceClass {
             inner: Arc::new(inner_class),
         }
     }
 
-    pub fn set_mirror_class_name(&self, name: String) {
-        self.inner.mirror_class_name.borrow_mut().replace(name);
+    pub fn set_init_state(&self, state: InitState) {
+        *self.inner.init_state.lock().unwrap() = state;
+        if let InitState::Initialized | InitState::Erroneous = state {
+            self.inner.init_done.notify_all();
+        }
     }
 
-    pub fn set_inited(&self) {
-        self.inner.inited.replace(true);
+    pub fn init_state(&self) -> InitState {
+        *self.inner.init_state.lock().unwrap()
     }
 
-    pub fn is_inited(&self) -> bool {
-        self.inner.inited.get()
+    /// Blocks while another thread than `thread_id` initializes the class.
+    pub fn wait_for_init(&self, thread_id: u64) {
+        let mut state = self.inner.init_state.lock().unwrap();
+        while matches!(*state, InitState::BeingInitialized(id) if id != thread_id) {
+            state = self.inner.init_done.wait(state).unwrap();
+        }
     }
 
     pub fn is_interface(&self) -> bool {
@@ -192,10 +267,80 @@ impl InstanceClass {
         &self.inner.methods
     }
 
+    pub fn all_interfaces(&self) -> &[InstanceClass] {
+        &self.inner.all_interfaces
+    }
+
+    pub fn vtable(&self) -> &[Selection] {
+        &self.inner.vtable
+    }
+
+    pub fn interface_methods(&self) -> &[Method] {
+        &self.inner.interface_methods
+    }
+
+    /// The vtable slot of `method`, which this class declares or inherits.
+    pub fn vtable_index(&self, method: &Method) -> Option<usize> {
+        let vtable = self.vtable();
+        vtable
+            .iter()
+            .position(|slot| slot.method() == method)
+            .or_else(|| {
+                // a method of an interface, the slot has the method selected for it
+                vtable.iter().map(Selection::method).position(|m| {
+                    m.name() == method.name() && m.descriptor() == method.descriptor()
+                })
+            })
+    }
+
+    /// The index of `method` of this interface in the itable entries for it.
+    pub fn interface_method_index(&self, method: &Method) -> Option<usize> {
+        self.interface_methods().iter().position(|m| m == method)
+    }
+
+    /// The method selected for the method at `index` of `interface`, if the
+    /// class implements it.
+    pub fn itable_method(&self, interface: &InstanceClass, index: usize) -> Option<&Selection> {
+        let entry = self
+            .inner
+            .itable
+            .iter()
+            .find(|entry| entry.interface == *interface)?;
+        entry.methods.get(index)
+    }
+
+    pub fn bootstrap_method(&self, index: u16) -> &BootstrapMethod {
+        &self.inner.bootstrap_methods[index as usize]
+    }
+
+    pub fn source_file(&self) -> Option<&str> {
+        self.inner.source_file.as_deref()
+    }
+
     pub fn interfaces(&self) -> &[InstanceClass] {
         &self.inner.interfaces
     }
 
+    /// The direct and indirect super interfaces declaring a non-abstract,
+    /// non-static method, in the order they are initialized before this class.
+    pub fn default_method_interfaces(&self) -> Vec<InstanceClass> {
+        fn collect(interfaces: &[InstanceClass], found: &mut Vec<InstanceClass>) {
+            for interface in interfaces {
+                let declares_default_method = interface
+                    .methods()
+                    .iter()
+                    .any(|m| !m.is_abstract() && !m.is_static());
+                if declares_default_method && !found.contains(interface) {
+                    found.push(interface.clone());
+                }
+                collect(interface.interfaces(), found);
+            }
+        }
+        let mut found = Vec::new();
+        collect(self.interfaces(), &mut found);
+        found
+    }
+
     pub fn did_implement_interface(&self, interface: InstanceClass) -> bool {
         self.inner.interfaces.contains(&interface)
             || self
@@ -208,10 +353,6 @@ impl InstanceClass {
         &self.inner.name
     }
 
-    pub fn mirror_class_name(&self) -> String {
-        self.inner.mirror_class_name.borrow().clone().unwrap()
-    }
-
     pub fn main_method(&self) -> Option<Method> {
         self.get_self_method("main", "([Ljava/lang/String;)V", true)
     }
@@ -247,36 +388,69 @@ impl InstanceClass {
             .and_then(|super_class| super_class.get_class_method(name, descriptor, is_static))
     }
 
-    fn get_interface_method_inner(&self, name: &str, descriptor: &str) -> Option<Method> {
-        if let Some(method) = self
-            .interfaces()
+    /// The method of this class or interface with `name` and `descriptor`,
+    /// static or not.
+    fn get_declared_method(&self, name: &str, descriptor: &str) -> Option<Method> {
+        self.methods()
             .iter()
-            .filter_map(|interface| interface.get_interface_method(name, descriptor))
-            .filter(|method| !method.is_abstract() && !method.is_private() && !method.is_static())
-            .take(1)
-            .collect::<Vec<_>>()
-            .first()
+            .find(|m| m.name() == name && m.descriptor() == descriptor)
             .cloned()
-        {
-            return Some(method);
-        }
+    }
 
-        self.super_class()
-            .and_then(|c| c.get_interface_method(name, descriptor))
+    /// Resolves a method of this class (JVMS §5.4.3.3): the method this class
+    /// or a superclass declares, otherwise a superinterface method.
+    pub fn resolve_method(&self, name: &str, descriptor: &str) -> Option<Method> {
+        // todo: polymorphic method
+        std::iter::once(self.clone())
+            .chain(self.iter_super_classes())
+            .find_map(|class| class.get_declared_method(name, descriptor))
+            .or_else(|| {
+                vtable::resolve_superinterface_method(self.all_interfaces(), name, descriptor)
+            })
     }
 
-    pub fn get_interface_method(&self, name: &str, descriptor: &str) -> Option<Method> {
-        if let Some(method) = self.get_self_method(name, descriptor, false) {
-            return Some(method);
+    /// Resolves a method of this interface (JVMS §5.4.3.4): the method the
+    /// interface declares, a public instance method of `Object`, otherwise a
+    /// superinterface method.
+    pub fn resolve_interface_method(&self, name: &str, descriptor: &str) -> Option<Method> {
+        self.get_declared_method(name, descriptor)
+            .or_else(|| {
+                self.super_class()?
+                    .get_declared_method(name, descriptor)
+                    .filter(|m| m.is_public() && !m.is_static())
+            })
+            .or_else(|| {
+                vtable::resolve_superinterface_method(self.all_interfaces(), name, descriptor)
+            })
+    }
+
+    /// Selects the method `invokespecial` calls when this class or interface
+    /// is the one to search (JVMS §6.5.invokespecial), `None` if there is none.
+    pub fn select_special_method(&self, name: &str, descriptor: &str) -> Option<Selection> {
+        let method = if self.is_interface() {
+            self.get_self_method(name, descriptor, false).or_else(|| {
+                self.super_class()?
+                    .get_self_method(name, descriptor, false)
+                    .filter(|m| m.is_public())
+            })
+        } else {
+            std::iter::once(self.clone())
+                .chain(self.iter_super_classes())
+                .find_map(|class| class.get_self_method(name, descriptor, false))
+        };
+        match method {
+            Some(method) => Some(Selection::Method(method)),
+            None => vtable::select_superinterface_method(self.all_interfaces(), name, descriptor),
         }
-        self.get_interface_method_inner(name, descriptor)
+    }
+
+    pub fn get_interface_method(&self, name: &str, descriptor: &str) -> Option<Method> {
+        self.resolve_interface_method(name, descriptor)
     }
 
     pub fn get_method(&self, name: &str, descriptor: &str, is_static: bool) -> Option<Method> {
-        if let Some(method) = self.get_class_method(name, descriptor, is_static) {
-            return Some(method);
-        }
-        self.get_interface_method_inner(name, descriptor)
+        self.resolve_method(name, descriptor)
+            .filter(|m| m.is_static() == is_static)
     }
 
     fn get_self_field(&self, name: &str, descriptor: &str) -> Option<Field> {
@@ -314,6 +488,14 @@ impl InstanceClass {
         self.inner.static_field_values.lock().unwrap()[index].clone()
     }
 
+    pub fn static_field_values(&self) -> Vec<Operand> {
+        self.inner.static_field_values.lock().unwrap().clone()
+    }
+
+    pub fn constant_strings(&self) -> &[(usize, String)] {
+        &self.inner.constant_strings
+    }
+
     pub fn set_static_field_value(&self, index: usize, value: Operand) {
         self.inner.static_field_values.lock().unwrap()[index] = value;
     }
@@ -395,9 +577,8 @@ fn get_default_value_from_field_info(
             "D" => Operand::Double(const_pool.get_constant_double_at(constant_value_index)),
             "F" => Operand::Float(const_pool.get_constant_float_at(constant_value_index)),
             "J" => Operand::Long(const_pool.get_constant_long_at(constant_value_index)),
-            "Ljava/lang/String;" => {
-                Operand::Str(const_pool.get_constant_string_at(constant_value_index))
-            }
+            // interned on initialization, see `get_constant_string_from_field_info`
+            "Ljava/lang/String;" => return None,
             _ => unreachable!(),
         })
     } else {
@@ -405,6 +586,20 @@ fn get_default_value_from_field_info(
     }
 }
 
+fn get_constant_string_from_field_info(
+    field_info: &FieldInfo,
+    const_pool: &ConstPool,
+) -> Option<String> {
+    let constant_value_index = field_info.constant_value_attribute()?.constant_value_index;
+    let descriptor = const_pool.get_utf8_string_at(field_info.descriptor_index);
+    if field_info.is_static() && field_info.is_final() && descriptor == "Ljava/lang/String;" {
+        let string_index = const_pool.get_constant_string_at(constant_value_index);
+        Some(const_pool.get_utf8_string_at(string_index).to_string())
+    } else {
+        None
+    }
+}
+
 impl Debug for InstanceClass {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "InstanceClass{{ name: {}}}", self.name())
@@ -1,18 +1,21 @@
 mod instance_class;
 mod obj_array_class;
 mod type_array_class;
+mod vtable;
 
+use crate::class_parser::attribute_info::predefined_attribute::BootstrapMethod;
 use crate::class_parser::constant_pool::ConstPool;
 use crate::runtime::class::instance_class::SuperClassesIter;
 use crate::runtime::field::Field;
 use crate::runtime::frame::operand_stack::Operand;
 use crate::runtime::method::Method;
-pub use instance_class::InstanceClass;
+pub use instance_class::{InitState, InstanceClass};
 pub use obj_array_class::ObjArrayClass;
 use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Display;
 pub use type_array_class::TypeArrayClass;
+pub use vtable::Selection;
 
 #[derive(Clone, Debug)]
 pub enum Class {
@@ -59,9 +62,6 @@ impl Class {
         }
     }
 
-    pub fn set_mirror_class_name(&self, name: String) {
-        self.instance_class_ref().set_mirror_class_name(name)
-    }
     pub fn is_interface(&self) -> bool {
         self.instance_class_ref().is_interface()
     }
@@ -136,6 +136,10 @@ impl Class {
         self.instance_class_ref().methods()
     }
 
+    pub fn bootstrap_method(&self, index: u16) -> &BootstrapMethod {
+        self.instance_class_ref().bootstrap_method(index)
+    }
+
     pub fn interfaces(&self) -> &[InstanceClass] {
         self.instance_class_ref().interfaces()
     }
@@ -147,16 +151,11 @@ impl Class {
     pub fn name(&self) -> &str {
         match self {
             Class::InstanceClass(i) => i.name(),
-            _ => unreachable!(),
+            Class::ObjArrayClass(i) => &i.name,
+            Class::TypeArrayClass(i) => &i.name,
         }
     }
 
-    pub fn mirror_class_name(&self) -> String {
-        match self {
-            Class::InstanceClass(i) => i.mirror_class_name(),
-            _ => unreachable!(),
-        }
-    }
     pub fn main_method(&self) -> Option<Method> {
         self.instance_class_ref().main_method()
     }
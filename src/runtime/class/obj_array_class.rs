@@ -1,4 +1,7 @@
 #[derive(Clone, Debug)]
 pub struct ObjArrayClass {
+    /// Like `[Ljava/lang/String;`.
+    pub name: String,
+    /// The component class, an array class keeps its descriptor form.
     pub class: String,
 }
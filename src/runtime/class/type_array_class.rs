@@ -1,4 +1,6 @@
 #[derive(Clone, Debug)]
 pub struct TypeArrayClass {
+    /// Like `[I`.
+    pub name: String,
     pub ty: u8,
 }
@@ -0,0 +1,237 @@
+//! The method tables of classes, built when a class is linked. An invoke
+//! instruction resolves its method to an index into them once, invoking it
+//! then takes the method at that index in the tables of the receiver's class.
+//!
+//! The vtable of a class has a slot for each method `invokevirtual` selects
+//! from: those of its superclass first, in the same slots, then the methods it
+//! adds. The itable has a table for each interface the class implements, with
+//! the method selected for each method the interface declares, in the order
+//! `InstanceClass::interface_methods` has them.
+use crate::runtime::class::InstanceClass;
+use crate::runtime::method::Method;
+
+/// The method selected for a call (JVMS §5.4.6). Calling an abstract method
+/// throws `AbstractMethodError`.
+#[derive(Debug, Clone)]
+pub enum Selection {
+    Method(Method),
+    /// One of several maximally-specific default methods, calling it throws
+    /// `IncompatibleClassChangeError`.
+    Conflict(Method),
+}
+
+impl Selection {
+    pub fn method(&self) -> &Method {
+        match self {
+            Selection::Method(method) | Selection::Conflict(method) => method,
+        }
+    }
+}
+
+/// The methods of a class selected for the methods of `interface`.
+#[derive(Debug)]
+pub struct ItableEntry {
+    pub interface: InstanceClass,
+    pub methods: Vec<Selection>,
+}
+
+/// The runtime package of a class, there is only the bootstrap class loader.
+fn package(class_name: &str) -> &str {
+    match class_name.rfind('/') {
+        Some(i) => &class_name[..i],
+        None => "",
+    }
+}
+
+/// Whether `method` overrides `other` (JVMS §5.4.5), an instance method of a
+/// superclass with the same name and descriptor.
+fn can_override(method: &Method, other: &Method) -> bool {
+    if other.is_private() {
+        return false;
+    }
+    other.is_public()
+        || other.is_protected()
+        || package(method.class_name()) == package(other.class_name())
+}
+
+/// Whether a vtable or itable has a slot for `method`.
+fn is_virtual(method: &Method) -> bool {
+    !method.is_static() && !method.is_private() && !method.name().starts_with('<')
+}
+
+/// The methods an interface declares that classes implement, in the order of
+/// the interface's itable entry.
+pub fn interface_methods(methods: &[Method]) -> Vec<Method> {
+    methods.iter().filter(|m| is_virtual(m)).cloned().collect()
+}
+
+/// All the superinterfaces of a class or interface with `super_class` and the
+/// direct superinterfaces `interfaces`, each once.
+pub fn all_interfaces(
+    super_class: Option<&InstanceClass>,
+    interfaces: &[InstanceClass],
+) -> Vec<InstanceClass> {
+    let mut all: Vec<InstanceClass> = super_class
+        .map(|class| class.all_interfaces().to_vec())
+        .unwrap_or_default();
+    for interface in interfaces {
+        let inherited = std::iter::once(interface).chain(interface.all_interfaces());
+        for interface in inherited {
+            if !all.contains(interface) {
+                all.push(interface.clone());
+            }
+        }
+    }
+    all
+}
+
+/// The maximally-specific superinterface methods of `name` and `descriptor`
+/// among `interfaces` (JVMS §5.4.3.3), those no other candidate overrides.
+fn maximally_specific_methods(
+    interfaces: &[InstanceClass],
+    name: &str,
+    descriptor: &str,
+) -> Vec<Method> {
+    let candidates: Vec<(&InstanceClass, Method)> = interfaces
+        .iter()
+        .filter_map(|interface| {
+            let method = interface.get_self_method(name, descriptor, false)?;
+            Some((interface, method)).filter(|(_, method)| !method.is_private())
+        })
+        .collect();
+    candidates
+        .iter()
+        .filter(|(interface, _)| {
+            !candidates
+                .iter()
+                .any(|(other, _)| other.all_interfaces().contains(interface))
+        })
+        .map(|(_, method)| method.clone())
+        .collect()
+}
+
+/// The superinterface method resolution finds (JVMS §5.4.3.3): the only
+/// maximally-specific default method, otherwise any of the candidates.
+pub fn resolve_superinterface_method(
+    interfaces: &[InstanceClass],
+    name: &str,
+    descriptor: &str,
+) -> Option<Method> {
+    let methods = maximally_specific_methods(interfaces, name, descriptor);
+    let mut defaults = methods.iter().filter(|m| !m.is_abstract());
+    match (defaults.next(), defaults.next()) {
+        (Some(method), None) => Some(method.clone()),
+        _ => methods.into_iter().next(),
+    }
+}
+
+/// The superinterface method selected when no class method overrides it: the
+/// only maximally-specific default method, a conflict if there are several
+/// or an abstract method if there are none.
+pub fn select_superinterface_method(
+    interfaces: &[InstanceClass],
+    name: &str,
+    descriptor: &str,
+) -> Option<Selection> {
+    let methods = maximally_specific_methods(interfaces, name, descriptor);
+    let defaults: Vec<&Method> = methods.iter().filter(|m| !m.is_abstract()).collect();
+    match defaults.as_slice() {
+        [] => methods.first().cloned().map(Selection::Method),
+        [method] => Some(Selection::Method((*method).clone())),
+        [method, ..] => Some(Selection::Conflict((*method).clone())),
+    }
+}
+
+/// The vtable of a class declaring `methods`, which implements
+/// `all_interfaces`.
+pub fn build_vtable(
+    super_class: Option<&InstanceClass>,
+    methods: &[Method],
+    all_interfaces: &[InstanceClass],
+) -> Vec<Selection> {
+    let mut vtable = super_class
+        .map(|class| class.vtable().to_vec())
+        .unwrap_or_default();
+    for method in methods.iter().filter(|m| is_virtual(m)) {
+        let mut overrides = false;
+        for slot in vtable.iter_mut() {
+            let other = slot.method();
+            if other.name() == method.name()
+                && other.descriptor() == method.descriptor()
+                && can_override(method, other)
+            {
+                *slot = Selection::Method(method.clone());
+                overrides = true;
+            }
+        }
+        if !overrides {
+            vtable.push(Selection::Method(method.clone()));
+        }
+    }
+
+    // interface methods no class method implements get the default method,
+    // or the abstract method itself for abstract classes
+    let is_declared_by_interface = |method: &Method| {
+        all_interfaces
+            .iter()
+            .any(|i| i.name() == method.class_name())
+    };
+    for interface in all_interfaces {
+        for method in interface.interface_methods() {
+            let (name, descriptor) = (method.name(), method.descriptor());
+            let slots: Vec<usize> = (0..vtable.len())
+                .filter(|i| {
+                    let other = vtable[*i].method();
+                    other.name() == name && other.descriptor() == descriptor
+                })
+                .collect();
+            if slots
+                .iter()
+                .any(|i| !is_declared_by_interface(vtable[*i].method()))
+            {
+                continue;
+            }
+            let selected = select_superinterface_method(all_interfaces, name, descriptor)
+                .unwrap_or_else(|| Selection::Method(method.clone()));
+            if slots.is_empty() {
+                vtable.push(selected);
+            } else {
+                for i in slots {
+                    vtable[i] = selected.clone();
+                }
+            }
+        }
+    }
+    vtable
+}
+
+/// The itable of a class with `vtable`, which implements `all_interfaces`.
+pub fn build_itable(vtable: &[Selection], all_interfaces: &[InstanceClass]) -> Vec<ItableEntry> {
+    all_interfaces
+        .iter()
+        .map(|interface| {
+            let methods = interface
+                .interface_methods()
+                .iter()
+                .map(|method| {
+                    // the vtable has the class method overriding it, or the
+                    // default method selected for it
+                    vtable
+                        .iter()
+                        .find(|slot| {
+                            let other = slot.method();
+                            other.name() == method.name()
+                                && other.descriptor() == method.descriptor()
+                                && other.is_public()
+                        })
+                        .cloned()
+                        .unwrap_or_else(|| Selection::Method(method.clone()))
+                })
+                .collect();
+            ItableEntry {
+                interface: interface.clone(),
+                methods,
+            }
+        })
+        .collect()
+}
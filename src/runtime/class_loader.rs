@@ -1,13 +1,36 @@
-use crate::class_parser::parse_class_file;
+use crate::class_parser::{is_bit_set, parse_class_file, ACC_INTERFACE};
 use crate::class_path::ClassPath;
-use crate::runtime::class::{Class, InstanceClass, ObjArrayClass, TypeArrayClass};
+use crate::runtime::class::{Class, InitState, InstanceClass, ObjArrayClass, TypeArrayClass};
+use crate::runtime::verifier::{verify_class, ClassHierarchy, ClassInfo, VerifyError, VerifyMode};
 use std::collections::HashMap;
 use tracing::debug;
 
+/// Why loading a class failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    /// No entry of the class path has it, thrown as `NoClassDefFoundError`
+    /// with the entries searched.
+    NotFound(String),
+    Verify(VerifyError),
+}
+
+impl From<VerifyError> for LoadError {
+    fn from(e: VerifyError) -> Self {
+        LoadError::Verify(e)
+    }
+}
+
 #[derive(Debug)]
 pub struct BootstrapClassLoader {
     class_path: ClassPath,
     classes: HashMap<String, Class>,
+    /// Print every loaded class like `-verbose:class`.
+    verbose_class: bool,
+    verify_mode: VerifyMode,
+    /// Classes that failed to load, loading them again fails the same way.
+    load_errors: HashMap<String, LoadError>,
+    /// Super classes of classes the verifier asked about without loading them.
+    class_infos: HashMap<String, Option<ClassInfo>>,
 }
 
 impl BootstrapClassLoader {
@@ -15,46 +38,123 @@ impl BootstrapClassLoader {
         BootstrapClassLoader {
             class_path,
             classes: Default::default(),
+            verbose_class: false,
+            verify_mode: VerifyMode::default(),
+            load_errors: Default::default(),
+            class_infos: Default::default(),
         }
     }
 
+    pub fn set_verbose_class(&mut self, verbose_class: bool) {
+        self.verbose_class = verbose_class;
+    }
+
+    pub fn set_verify_mode(&mut self, verify_mode: VerifyMode) {
+        self.verify_mode = verify_mode;
+    }
+
     pub fn contains_class(&self, class: &Class) -> bool {
         self.classes.contains_key(class.name())
     }
 
-    pub(super) fn load_class(&mut self, name: &str) -> Class {
+    /// Whether `name` is loaded or can be read from the class path.
+    pub fn can_load_class(&self, name: &str) -> bool {
+        self.classes.contains_key(name) || self.class_path.read_class(name).is_ok()
+    }
+
+    pub fn classes(&self) -> impl Iterator<Item = &Class> {
+        self.classes.values()
+    }
+
+    /// Loads and links a class, failing if it or one of its super classes
+    /// can't be found or doesn't pass verification.
+    pub(super) fn load_class(&mut self, name: &str) -> Result<Class, LoadError> {
         if self.classes.contains_key(name) {
-            self.classes
+            Ok(self
+                .classes
                 .get(name)
                 .unwrap_or_else(|| panic!("get class: {}", name))
-                .clone()
+                .clone())
+        } else if let Some(e) = self.load_errors.get(name) {
+            Err(e.clone())
         } else {
             debug!(%name, "load_class");
             let name_bytes = name.as_bytes();
             let class = match name_bytes {
-                [b'[', .., b'L'] => {
+                [b'[', b'L', .., b';'] => {
                     let class_name =
                         std::str::from_utf8(&name_bytes[2..name_bytes.len() - 1]).unwrap();
                     Class::ObjArrayClass(ObjArrayClass {
+                        name: name.to_string(),
                         class: class_name.to_string(),
                     })
                 }
-                [b'[', ty, ..] => Class::TypeArrayClass(TypeArrayClass { ty: *ty }),
+                // array of arrays, the component keeps its descriptor form
+                [b'[', b'[', ..] => Class::ObjArrayClass(ObjArrayClass {
+                    name: name.to_string(),
+                    class: name[1..].to_string(),
+                }),
+                [b'[', ty] => Class::TypeArrayClass(TypeArrayClass {
+                    name: name.to_string(),
+                    ty: *ty,
+                }),
                 [b'L', name_slice @ .., b';'] | name_slice => {
                     let name = std::str::from_utf8(name_slice).unwrap();
-                    let data = self
-                        .class_path
-                        .read_class(name)
-                        .unwrap_or_else(|_| panic!("read class file: {}", name));
-                    self.define_class(name.to_string(), data).into()
+                    let (data, source) = match self.class_path.read_class_with_source(name) {
+                        Ok(class_file) => class_file,
+                        Err(e) => {
+                            let e = LoadError::NotFound(e.to_string());
+                            self.load_errors.insert(name.to_string(), e.clone());
+                            return Err(e);
+                        }
+                    };
+                    let verify = match self.verify_mode {
+                        VerifyMode::None => false,
+                        VerifyMode::Remote => !self.class_path.is_boot_source(source),
+                        VerifyMode::All => true,
+                    };
+                    let location = source.location();
+                    let class = match self.define_class(name.to_string(), data, verify) {
+                        Ok(class) => class.into(),
+                        Err(e) => {
+                            self.load_errors.insert(name.to_string(), e.clone());
+                            return Err(e);
+                        }
+                    };
+                    if self.verbose_class {
+                        println!("[Loaded {} from {}]", name.replace('/', "."), location);
+                    }
+                    class
                 }
             };
             self.classes.insert(name.to_string(), class.clone());
-            class
+            Ok(class)
+        }
+    }
+
+    /// Defines a class the VM generated itself, e.g. a lambda proxy. It's
+    /// trusted and not verified.
+    pub(super) fn define_generated_class(&mut self, name: &str, data: Vec<u8>) -> Class {
+        let class: Class = self
+            .define_class(name.to_string(), data, false)
+            .expect("define generated class")
+            .into();
+        if self.verbose_class {
+            println!(
+                "[Loaded {} from __JVM_DefineClass__]",
+                name.replace('/', ".")
+            );
         }
+        self.classes.insert(name.to_string(), class.clone());
+        class
     }
 
-    fn define_class(&mut self, name: String, data: Vec<u8>) -> InstanceClass {
+    fn define_class(
+        &mut self,
+        name: String,
+        data: Vec<u8>,
+        verify: bool,
+    ) -> Result<InstanceClass, LoadError> {
         debug!(%name, data_len = data.len(), "define_class");
         let (_, class_file) = parse_class_file(&data).expect("parse class");
         let super_class_index = class_file.super_class;
@@ -64,15 +164,49 @@ impl BootstrapClassLoader {
             let super_class_name = class_file
                 .constant_pool
                 .get_class_name_at(super_class_index);
-            Some(self.load_class(super_class_name).instance_class())
+            Some(self.load_class(super_class_name)?.instance_class())
         };
 
         let mut interfaces = Vec::with_capacity(class_file.interfaces.len());
         for interface_index in &class_file.interfaces {
             let interface_name = class_file.constant_pool.get_class_name_at(*interface_index);
-            interfaces.push(self.load_class(interface_name).instance_class());
+            interfaces.push(self.load_class(interface_name)?.instance_class());
         }
 
-        InstanceClass::new(name, class_file, super_class, interfaces)
+        if verify {
+            verify_class(&class_file, self)?;
+        }
+        let class = InstanceClass::new(name, class_file, super_class, interfaces);
+        class.set_init_state(InitState::Linked);
+        Ok(class)
+    }
+}
+
+impl ClassHierarchy for BootstrapClassLoader {
+    /// Answers from the loaded classes, otherwise reads just the class file so
+    /// verifying doesn't load other classes.
+    fn class_info(&mut self, name: &str) -> Option<ClassInfo> {
+        if let Some(Class::InstanceClass(class)) = self.classes.get(name) {
+            return Some(ClassInfo {
+                super_class: class.super_class().map(|c| c.name().to_string()),
+                is_interface: class.is_interface(),
+            });
+        }
+        if let Some(info) = self.class_infos.get(name) {
+            return info.clone();
+        }
+        let info = self.class_path.read_class(name).ok().and_then(|data| {
+            let (_, class_file) = parse_class_file(&data).ok()?;
+            let constant_pool = &class_file.constant_pool;
+            let super_class = Some(class_file.super_class)
+                .filter(|index| *index != 0)
+                .map(|index| constant_pool.get_class_name_at(index).to_string());
+            Some(ClassInfo {
+                super_class,
+                is_interface: is_bit_set(class_file.access_flags, ACC_INTERFACE),
+            })
+        });
+        self.class_infos.insert(name.to_string(), info.clone());
+        info
     }
 }
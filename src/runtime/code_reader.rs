@@ -6,6 +6,7 @@ pub struct CodeReader {
     code: Arc<Vec<u8>>,
     method: Method,
     pc: usize,
+    opcode_pc: usize,
 }
 
 impl CodeReader {
@@ -13,10 +14,16 @@ impl CodeReader {
         CodeReader {
             code: method.code(),
             pc: 0,
+            opcode_pc: 0,
             method,
         }
     }
 
+    pub fn read_opcode(&mut self) -> Option<u8> {
+        self.opcode_pc = self.pc;
+        self.read_u8()
+    }
+
     pub fn read_u8(&mut self) -> Option<u8> {
         let code = self.code.get(self.pc as usize).cloned();
         self.pc += 1;
@@ -39,10 +46,22 @@ impl CodeReader {
         Some(byte1 << 8 | byte2)
     }
 
+    pub fn read_i32(&mut self) -> Option<i32> {
+        let bytes = self.code.get(self.pc..self.pc + 4)?;
+        let n = i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        self.pc += 4;
+        Some(n)
+    }
+
     pub fn pc(&self) -> usize {
         self.pc
     }
 
+    /// pc of the instruction currently being executed.
+    pub fn opcode_pc(&self) -> usize {
+        self.opcode_pc
+    }
+
     pub fn set_pc(&mut self, pc: usize) {
         self.pc = pc;
     }
@@ -1,4 +1,5 @@
-use crate::runtime::class::Class;
+use crate::runtime::class::{Class, InstanceClass};
+use crate::runtime::invoke::CallSite;
 use crate::runtime::jvm_env::JvmPC;
 use crate::runtime::method::Method;
 
@@ -7,11 +8,34 @@ pub struct CpCache {
     cache: Vec<CpCacheEntry>,
 }
 
+/// How an invoke instruction calls the method it resolved to.
+#[derive(Debug, Clone)]
+pub enum MethodCall {
+    /// Calls the method itself, e.g. for `invokestatic` or a final method.
+    Direct(Method),
+    /// Calls the method in the vtable slot of the class of the receiver.
+    Virtual(Method, usize),
+    /// Calls the method at the index in the itable entry of the interface.
+    Interface(Method, InstanceClass, usize),
+}
+
+impl MethodCall {
+    /// The resolved method, its descriptor is that of the method called.
+    pub fn method(&self) -> &Method {
+        match self {
+            MethodCall::Direct(method)
+            | MethodCall::Virtual(method, _)
+            | MethodCall::Interface(method, ..) => method,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 enum CpCacheEntry {
     StaticField(Class, usize),
     Field(usize),
-    Method(Method),
+    Method(MethodCall),
+    CallSite(CallSite),
     Empty,
 }
 
@@ -41,15 +65,36 @@ impl CpCache {
     }
 
     pub fn set_field(&mut self, pc: JvmPC, field_index: usize) {
-        self.cache.insert(pc, CpCacheEntry::Field(field_index));
+        self.cache[pc] = CpCacheEntry::Field(field_index);
     }
 
     pub fn set_static_field(&mut self, pc: JvmPC, class: Class, field_index: usize) {
-        self.cache
-            .insert(pc, CpCacheEntry::StaticField(class, field_index));
+        self.cache[pc] = CpCacheEntry::StaticField(class, field_index);
+    }
+
+    pub fn resolve_call_site(&self, pc: JvmPC) -> Option<CallSite> {
+        match self.cache.get(pc) {
+            Some(CpCacheEntry::CallSite(call_site)) => Some(call_site.clone()),
+            Some(CpCacheEntry::Empty) => None,
+            Some(_) => unreachable!(),
+            None => None,
+        }
+    }
+
+    pub fn set_call_site(&mut self, pc: JvmPC, call_site: CallSite) {
+        self.cache[pc] = CpCacheEntry::CallSite(call_site);
+    }
+
+    pub fn resolve_method(&self, pc: JvmPC) -> Option<MethodCall> {
+        match self.cache.get(pc) {
+            Some(CpCacheEntry::Method(call)) => Some(call.clone()),
+            Some(CpCacheEntry::Empty) => None,
+            Some(_) => unreachable!(),
+            None => None,
+        }
     }
 
-    pub fn resolve_method(&mut self, _pc: JvmPC) -> CpCache {
-        unimplemented!()
+    pub fn set_method(&mut self, pc: JvmPC, call: MethodCall) {
+        self.cache[pc] = CpCacheEntry::Method(call);
     }
 }
@@ -66,14 +66,11 @@ impl Field {
     pub fn default_value(&self) -> Operand {
         let descriptor = self.descriptor();
         match descriptor.as_bytes()[0] {
-            b'B' => Operand::Byte(0),
-            b'C' => Operand::Char(0),
+            // byte, char, short and boolean are all ints on the operand stack
+            b'B' | b'C' | b'I' | b'S' | b'Z' => Operand::Int(0),
             b'D' => Operand::Double(0.0),
             b'F' => Operand::Float(0.0),
-            b'I' => Operand::Int(0),
             b'J' => Operand::Long(0),
-            b'S' => Operand::Short(0),
-            b'Z' => Operand::Int(0),
             b'L' | b'[' => Operand::Null,
             _ => unreachable!("{}", descriptor),
         }
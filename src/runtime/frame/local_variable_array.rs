@@ -30,6 +30,10 @@ impl LocalVariableArray {
         LocalVariableArray { local_variables }
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = &Operand> {
+        self.local_variables.iter()
+    }
+
     pub fn set_integer(&mut self, index: u16, value: i32) {
         self.local_variables[index as usize] = Operand::Int(value);
     }
@@ -48,6 +52,21 @@ impl LocalVariableArray {
         }
     }
 
+    pub fn set_long(&mut self, index: u16, value: i64) {
+        self.local_variables[index as usize] = Operand::Long(value);
+    }
+
+    pub fn set_double(&mut self, index: u16, value: f64) {
+        self.local_variables[index as usize] = Operand::Double(value);
+    }
+
+    pub fn get_double(&mut self, index: u16) -> f64 {
+        match self.local_variables[index as usize] {
+            Operand::Double(num) => num,
+            _ => unreachable!(),
+        }
+    }
+
     pub fn set_float(&mut self, index: u16, value: f32) {
         self.local_variables[index as usize] = Operand::Float(value);
     }
@@ -67,6 +86,13 @@ impl LocalVariableArray {
         self.local_variables[index as usize] = value;
     }
 
+    pub fn get_return_address(&mut self, index: u16) -> usize {
+        match self.local_variables[index as usize] {
+            Operand::ReturnAddress(pc) => pc,
+            ref v => unreachable!("{:?}", v),
+        }
+    }
+
     pub fn get_object(&mut self, index: u16) -> Operand {
         match &self.local_variables[index as usize] {
             v @ Operand::ObjectRef(_) | v @ Operand::Null | v @ Operand::ArrayRef(_) => v.clone(),
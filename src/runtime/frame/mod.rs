@@ -15,21 +15,34 @@ pub struct JvmFrame {
     pub operand_stack: OperandStack,
     pub method: Method,
     pub class: Class,
+    /// The monitor entered by the synchronized method of the frame, released
+    /// when the frame is popped.
+    pub lock: Option<Operand>,
     #[derivative(Debug = "ignore")]
     pub code_reader: CodeReader,
 }
 
 impl JvmFrame {
-    pub fn new_with_args(class: Class, method: Method, args: Vec<Operand>) -> Self {
+    pub fn new_with_args(
+        class: Class,
+        method: Method,
+        args: Vec<Operand>,
+        lock: Option<Operand>,
+    ) -> Self {
         JvmFrame {
             local_variable_array: LocalVariableArray::new_with_args(method.max_locals(), args),
             operand_stack: OperandStack::with_capacity(method.max_stack()),
             method: method.clone(),
             class,
+            lock,
             code_reader: CodeReader::new(method),
         }
     }
 
+    pub fn read_opcode(&mut self) -> Option<u8> {
+        self.code_reader.read_opcode()
+    }
+
     pub fn read_u8(&mut self) -> Option<u8> {
         self.code_reader.read_u8()
     }
@@ -46,10 +59,18 @@ impl JvmFrame {
         self.code_reader.read_i16()
     }
 
+    pub fn read_i32(&mut self) -> Option<i32> {
+        self.code_reader.read_i32()
+    }
+
     pub fn pc(&self) -> usize {
         self.code_reader.pc()
     }
 
+    pub fn opcode_pc(&self) -> usize {
+        self.code_reader.opcode_pc()
+    }
+
     pub fn set_pc(&mut self, pc: usize) {
         self.code_reader.set_pc(pc)
     }
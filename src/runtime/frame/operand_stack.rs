@@ -7,10 +7,11 @@ pub enum Operand {
     Float(f32),
     Double(f64),
     Long(i64),
-    Str(u16),
     ObjectRef(u32),
     ArrayRef(u32),
     Null,
+    /// Pushed by `jsr`/`jsr_w` and consumed by `ret`.
+    ReturnAddress(usize),
 }
 
 impl Operand {
@@ -40,6 +41,12 @@ impl Operand {
         }
     }
 
+    /// long and double take two slots in the local variables and count as two
+    /// values for the `dup2`/`pop2` family.
+    pub fn is_category2(&self) -> bool {
+        matches!(self, Operand::Long(_) | Operand::Double(_))
+    }
+
     pub fn hash_code(&self) -> i32 {
         match self {
             Operand::ObjectRef(i) | Operand::ArrayRef(i) => *i as i32,
@@ -64,6 +71,15 @@ impl OperandStack {
         }
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = &Operand> {
+        self.stack.iter()
+    }
+
+    /// The `n` topmost values, the top of the stack last.
+    pub fn last_n(&self, n: usize) -> &[Operand] {
+        &self.stack[self.stack.len() - n..]
+    }
+
     pub fn push(&mut self, val: Operand) {
         self.stack.push(val)
     }
@@ -0,0 +1,117 @@
+use std::cell::UnsafeCell;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::{Arc, Condvar, Mutex};
+
+/// The lock a Java thread holds while it runs bytecode or touches the heap,
+/// classes or other threads. Threads give it up at safepoints and while they
+/// block in native code, so only one of them interprets at a time.
+#[derive(Debug, Default)]
+pub struct GlobalLock {
+    state: Mutex<LockState>,
+    released: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct LockState {
+    held: bool,
+    waiting: usize,
+}
+
+impl GlobalLock {
+    /// A lock already held by the creating thread.
+    pub fn new_held() -> Self {
+        GlobalLock {
+            state: Mutex::new(LockState {
+                held: true,
+                waiting: 0,
+            }),
+            released: Condvar::new(),
+        }
+    }
+
+    pub fn acquire(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.waiting += 1;
+        while state.held {
+            state = self.released.wait(state).unwrap();
+        }
+        state.waiting -= 1;
+        state.held = true;
+    }
+
+    pub fn release(&self) {
+        let mut state = self.state.lock().unwrap();
+        debug_assert!(state.held, "release a lock that isn't held");
+        state.held = false;
+        self.released.notify_one();
+    }
+
+    /// Whether another thread is blocked in `acquire`.
+    pub fn has_waiters(&self) -> bool {
+        self.state.lock().unwrap().waiting > 0
+    }
+}
+
+/// A value shared by all threads of a VM, only accessed by the thread holding
+/// its `GlobalLock`.
+///
+/// Every handle refers to the same value, and nothing keeps a thread without
+/// the lock from reaching it, so both `get` and `get_mut` are unsafe.
+pub struct Shared<T> {
+    inner: Arc<UnsafeCell<T>>,
+}
+
+// SAFETY: the value is only dereferenced while the global lock is held, which
+// serializes all accesses and orders them across threads, so like with a
+// `Mutex` it is enough that the value can be sent to another thread.
+unsafe impl<T: Send> Send for Shared<T> {}
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    pub fn new(value: T) -> Self {
+        Shared {
+            inner: Arc::new(UnsafeCell::new(value)),
+        }
+    }
+
+    /// The value, for reading it.
+    ///
+    /// # Safety
+    ///
+    /// The calling thread holds the global lock, and no mutable reference to
+    /// the value, through this handle or another one, is alive while the
+    /// returned one is.
+    pub unsafe fn get(&self) -> &T {
+        &*self.inner.get()
+    }
+
+    /// The value, for changing it.
+    ///
+    /// # Safety
+    ///
+    /// The calling thread holds the global lock, and no other reference to
+    /// the value, through this handle or another one, is alive while the
+    /// returned one is.
+    pub unsafe fn get_mut(&mut self) -> &mut T {
+        &mut *self.inner.get()
+    }
+
+    pub fn ptr_eq(&self, other: &Shared<T>) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Shared {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+// the value itself isn't printed, formatting may happen without the lock
+impl<T> Debug for Shared<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Shared").finish_non_exhaustive()
+    }
+}
@@ -1,21 +1,39 @@
 use crate::runtime::class::Class;
 use crate::runtime::frame::operand_stack::Operand;
 use crate::runtime::method::Method;
+use crate::runtime::monitor::Monitor;
 use std::fmt;
 use std::fmt::Debug;
 
+/// Default for `-Xmx`.
+pub const DEFAULT_MAX_HEAP_SIZE: usize = 512 * 1024 * 1024;
+/// The heap isn't collected before it holds this many bytes.
+const MIN_GC_THRESHOLD: usize = 1024 * 1024;
+/// Estimated size of an object header, every field or array element takes
+/// its own size on top of it.
+const HEADER_SIZE: usize = 16;
+
+/// A non-moving heap collected by mark-sweep, the slots of freed objects are
+/// reused by later allocations so addresses stay small.
 pub struct JvmHeap {
     mem: Vec<Memory>,
+    /// The header of the object in the slot of the same index.
+    headers: Vec<ObjectHeader>,
+    free_slots: Vec<u32>,
+    /// Estimated bytes of all live and not yet collected memory.
+    used: usize,
+    max_size: usize,
+    next_gc: usize,
 }
 
-const T_BOOLEAN: u8 = 4;
-const T_CHAR: u8 = 5;
-const T_FLOAT: u8 = 6;
-const T_DOUBLE: u8 = 7;
-const T_BYTE: u8 = 8;
-const T_SHORT: u8 = 9;
-const T_INT: u8 = 10;
-const T_LONG: u8 = 11;
+pub const T_BOOLEAN: u8 = 4;
+pub const T_CHAR: u8 = 5;
+pub const T_FLOAT: u8 = 6;
+pub const T_DOUBLE: u8 = 7;
+pub const T_BYTE: u8 = 8;
+pub const T_SHORT: u8 = 9;
+pub const T_INT: u8 = 10;
+pub const T_LONG: u8 = 11;
 
 pub const JAVA_LANG_CLASS_DESCRIPTOR: &str = "Ljava/lang/Class;";
 pub const JAVA_LANG_STRING_DESCRIPTOR: &str = "Ljava/lang/String;";
@@ -28,6 +46,56 @@ pub const JAVA_LANG_STRING: &str = "java/lang/String";
 pub const JAVA_LANG_OBJECT: &str = "java/lang/Object";
 pub const JAVA_LANG_THREAD: &str = "java/lang/Thread";
 pub const JAVA_LANG_THREAD_GROUP: &str = "java/lang/ThreadGroup";
+pub const JAVA_LANG_STACK_TRACE_ELEMENT: &str = "java/lang/StackTraceElement";
+pub const JAVA_LANG_CLONEABLE: &str = "java/lang/Cloneable";
+pub const JAVA_IO_SERIALIZABLE: &str = "java/io/Serializable";
+pub const JAVA_LANG_REFLECT_FIELD: &str = "java/lang/reflect/Field";
+pub const JAVA_LANG_REFLECT_METHOD: &str = "java/lang/reflect/Method";
+pub const JAVA_LANG_REFLECT_CONSTRUCTOR: &str = "java/lang/reflect/Constructor";
+pub const JAVA_LANG_INVOKE_METHOD_TYPE: &str = "java/lang/invoke/MethodType";
+pub const JAVA_LANG_INVOKE_METHOD_HANDLE_NATIVES: &str = "java/lang/invoke/MethodHandleNatives";
+
+pub const JAVA_LANG_NULL_POINTER_EXCEPTION: &str = "java/lang/NullPointerException";
+pub const JAVA_LANG_ARITHMETIC_EXCEPTION: &str = "java/lang/ArithmeticException";
+pub const JAVA_LANG_CLASS_CAST_EXCEPTION: &str = "java/lang/ClassCastException";
+pub const JAVA_LANG_ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION: &str =
+    "java/lang/ArrayIndexOutOfBoundsException";
+pub const JAVA_LANG_STRING_INDEX_OUT_OF_BOUNDS_EXCEPTION: &str =
+    "java/lang/StringIndexOutOfBoundsException";
+pub const JAVA_LANG_ARRAY_STORE_EXCEPTION: &str = "java/lang/ArrayStoreException";
+pub const JAVA_LANG_NEGATIVE_ARRAY_SIZE_EXCEPTION: &str = "java/lang/NegativeArraySizeException";
+pub const JAVA_LANG_OUT_OF_MEMORY_ERROR: &str = "java/lang/OutOfMemoryError";
+pub const JAVA_LANG_STACK_OVERFLOW_ERROR: &str = "java/lang/StackOverflowError";
+pub const JAVA_LANG_BOOTSTRAP_METHOD_ERROR: &str = "java/lang/BootstrapMethodError";
+pub const JAVA_LANG_ILLEGAL_ARGUMENT_EXCEPTION: &str = "java/lang/IllegalArgumentException";
+pub const JAVA_LANG_ILLEGAL_MONITOR_STATE_EXCEPTION: &str =
+    "java/lang/IllegalMonitorStateException";
+pub const JAVA_LANG_INTERRUPTED_EXCEPTION: &str = "java/lang/InterruptedException";
+pub const JAVA_LANG_VERIFY_ERROR: &str = "java/lang/VerifyError";
+pub const JAVA_LANG_ERROR: &str = "java/lang/Error";
+pub const JAVA_LANG_EXCEPTION_IN_INITIALIZER_ERROR: &str = "java/lang/ExceptionInInitializerError";
+pub const JAVA_LANG_NO_CLASS_DEF_FOUND_ERROR: &str = "java/lang/NoClassDefFoundError";
+pub const JAVA_LANG_UNSATISFIED_LINK_ERROR: &str = "java/lang/UnsatisfiedLinkError";
+pub const JAVA_LANG_NO_SUCH_METHOD_ERROR: &str = "java/lang/NoSuchMethodError";
+pub const JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR: &str =
+    "java/lang/IncompatibleClassChangeError";
+pub const JAVA_LANG_ABSTRACT_METHOD_ERROR: &str = "java/lang/AbstractMethodError";
+pub const JAVA_LANG_NO_SUCH_FIELD_ERROR: &str = "java/lang/NoSuchFieldError";
+pub const JAVA_LANG_INSTANTIATION_EXCEPTION: &str = "java/lang/InstantiationException";
+pub const JAVA_LANG_REFLECT_INVOCATION_TARGET_EXCEPTION: &str =
+    "java/lang/reflect/InvocationTargetException";
+pub const JAVA_LANG_INDEX_OUT_OF_BOUNDS_EXCEPTION: &str = "java/lang/IndexOutOfBoundsException";
+pub const JAVA_IO_IO_EXCEPTION: &str = "java/io/IOException";
+pub const JAVA_IO_FILE_NOT_FOUND_EXCEPTION: &str = "java/io/FileNotFoundException";
+pub const JAVA_IO_SYNC_FAILED_EXCEPTION: &str = "java/io/SyncFailedException";
+pub const JAVA_UTIL_ZIP_ZIP_EXCEPTION: &str = "java/util/zip/ZipException";
+pub const JAVA_UTIL_ZIP_DATA_FORMAT_EXCEPTION: &str = "java/util/zip/DataFormatException";
+
+#[derive(Debug, Default)]
+struct ObjectHeader {
+    /// Created when the object is first locked.
+    monitor: Option<Box<Monitor>>,
+}
 
 #[derive(Debug)]
 enum Memory {
@@ -44,6 +112,26 @@ enum Memory {
         class_name: String,
         array: Vec<Operand>,
     },
+    /// A slot freed by the collector, waiting to be reused.
+    Free,
+}
+
+impl Memory {
+    fn size(&self) -> usize {
+        HEADER_SIZE
+            + match self {
+                Memory::Object(object) => object.fields.len() * 8,
+                Memory::BooleanArray(array) | Memory::ByteArray(array) => array.len(),
+                Memory::CharArray(array) => array.len() * 2,
+                Memory::ShortArray(array) => array.len() * 2,
+                Memory::FloatArray(array) => array.len() * 4,
+                Memory::IntArray(array) => array.len() * 4,
+                Memory::DoubleArray(array) => array.len() * 8,
+                Memory::LongArray(array) => array.len() * 8,
+                Memory::ReferenceArray { array, .. } => array.len() * 8,
+                Memory::Free => 0,
+            }
+    }
 }
 
 pub struct Object {
@@ -68,12 +156,12 @@ impl Object {
         Object { class, fields }
     }
 
-    pub fn class_name(&self) -> &str {
-        self.class.name()
+    pub fn class(&self) -> &Class {
+        &self.class
     }
 
-    pub fn mirror_class_name(&self) -> String {
-        self.class.mirror_class_name()
+    pub fn class_name(&self) -> &str {
+        self.class.name()
     }
 
     pub fn set_field(&mut self, idx: usize, value: Operand) {
@@ -118,15 +206,134 @@ impl JvmHeap {
     pub fn new() -> Self {
         JvmHeap {
             mem: Vec::with_capacity(100),
+            headers: Vec::with_capacity(100),
+            free_slots: Vec::new(),
+            used: 0,
+            max_size: DEFAULT_MAX_HEAP_SIZE,
+            next_gc: MIN_GC_THRESHOLD,
         }
     }
 
+    pub fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = max_size;
+        self.next_gc = self.next_gc.min(max_size);
+    }
+
+    /// Estimated size of an instance of `class`.
+    pub fn object_size(class: &Class) -> usize {
+        HEADER_SIZE + class.total_instance_fields() * 8
+    }
+
+    /// Estimated size of an array of `count` elements of `element_size` bytes.
+    pub fn array_size(element_size: usize, count: i32) -> usize {
+        HEADER_SIZE + element_size * count.max(0) as usize
+    }
+
+    /// Estimated size of an array of references.
+    pub fn reference_array_size(count: i32) -> usize {
+        Self::array_size(8, count)
+    }
+
+    /// Size of an element of a primitive array of type `atype`.
+    pub fn element_size(atype: u8) -> usize {
+        match atype {
+            T_BOOLEAN | T_BYTE => 1,
+            T_CHAR | T_SHORT => 2,
+            T_FLOAT | T_INT => 4,
+            T_DOUBLE | T_LONG => 8,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Bytes allocated and not freed by a collection yet.
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    /// Number of allocated slots, live or free.
+    pub fn capacity(&self) -> usize {
+        self.mem.len()
+    }
+
+    /// Whether the slot at `addr` was freed by the collector and not reused.
+    pub fn is_free(&self, addr: u32) -> bool {
+        matches!(self.mem[addr as usize], Memory::Free)
+    }
+
+    /// Whether allocating `size` more bytes should trigger a collection first.
+    pub fn should_collect(&self, size: usize) -> bool {
+        self.used + size > self.next_gc
+    }
+
+    /// Whether allocating `size` more bytes exceeds the heap limit.
+    pub fn would_exceed_max_size(&self, size: usize) -> bool {
+        self.used + size > self.max_size
+    }
+
     fn alloc(&mut self, mem: Memory) -> u32 {
+        self.used += mem.size();
+        if let Some(obj_ref) = self.free_slots.pop() {
+            self.mem[obj_ref as usize] = mem;
+            self.headers[obj_ref as usize] = ObjectHeader::default();
+            return obj_ref;
+        }
         let obj_ref = self.mem.len();
         self.mem.push(mem);
+        self.headers.push(ObjectHeader::default());
         obj_ref as u32
     }
 
+    /// Frees everything not reachable from `roots` and returns the number of
+    /// freed slots.
+    pub fn collect(&mut self, roots: impl IntoIterator<Item = Operand>) -> usize {
+        let mut marked = vec![false; self.mem.len()];
+        let mut gray: Vec<u32> = roots.into_iter().filter_map(|r| reference(&r)).collect();
+        // objects locked or waited on are alive, whether referenced or not
+        gray.extend(
+            self.headers
+                .iter()
+                .enumerate()
+                .filter(|(_, header)| header.monitor.as_ref().is_some_and(|m| m.is_in_use()))
+                .map(|(index, _)| index as u32),
+        );
+        while let Some(obj_ref) = gray.pop() {
+            let index = obj_ref as usize;
+            if marked[index] {
+                continue;
+            }
+            marked[index] = true;
+            match &self.mem[index] {
+                Memory::Object(object) => gray.extend(object.fields.iter().filter_map(reference)),
+                Memory::ReferenceArray { array, .. } => {
+                    gray.extend(array.iter().filter_map(reference))
+                }
+                _ => {}
+            }
+        }
+
+        let mut freed = 0;
+        for (index, is_marked) in marked.into_iter().enumerate() {
+            if is_marked || matches!(self.mem[index], Memory::Free) {
+                continue;
+            }
+            self.used -= self.mem[index].size();
+            self.mem[index] = Memory::Free;
+            self.headers[index] = ObjectHeader::default();
+            self.free_slots.push(index as u32);
+            freed += 1;
+        }
+        self.next_gc = (self.used * 2).max(MIN_GC_THRESHOLD).min(self.max_size);
+        freed
+    }
+
+    /// The monitor of an object or array, created on first use.
+    pub fn monitor_mut(&mut self, obj_ref: &Operand) -> &mut Monitor {
+        let index = reference(obj_ref).unwrap_or_else(|| unreachable!("{:?}", obj_ref));
+        self.headers[index as usize]
+            .monitor
+            .get_or_insert_with(Default::default)
+    }
+
     pub fn new_object(&mut self, class: Class) -> (&mut Object, u32) {
         let addr = self.alloc(Memory::Object(Object::new_object(class)));
         (self.get_object_mut(&Operand::ObjectRef(addr)), addr)
@@ -215,10 +422,11 @@ impl JvmHeap {
             _ => unreachable!(),
         }
     }
+    /// `baload`/`bastore` share byte and boolean arrays.
     pub fn get_byte_array_mut(&mut self, array_ref: Operand) -> &mut Vec<i8> {
         match array_ref {
             Operand::ArrayRef(ref_i) => match &mut self.mem[ref_i as usize] {
-                Memory::BooleanArray(array) => array,
+                Memory::ByteArray(array) | Memory::BooleanArray(array) => array,
                 _ => unreachable!(),
             },
             _ => unreachable!(),
@@ -291,6 +499,66 @@ impl JvmHeap {
         }) as i32
     }
 
+    /// Where the elements of a primitive array are stored, for native code.
+    /// They stay there as the heap never moves anything, but not longer than
+    /// the array is reachable.
+    pub fn primitive_array_data(&mut self, array_ref: &Operand) -> *mut u8 {
+        match array_ref {
+            Operand::ArrayRef(ref_i) => match &mut self.mem[*ref_i as usize] {
+                Memory::BooleanArray(array) | Memory::ByteArray(array) => array.as_mut_ptr() as _,
+                Memory::CharArray(array) => array.as_mut_ptr() as _,
+                Memory::ShortArray(array) => array.as_mut_ptr() as _,
+                Memory::IntArray(array) => array.as_mut_ptr() as _,
+                Memory::LongArray(array) => array.as_mut_ptr() as _,
+                Memory::FloatArray(array) => array.as_mut_ptr() as _,
+                Memory::DoubleArray(array) => array.as_mut_ptr() as _,
+                v => unreachable!("{:?}", v),
+            },
+            v => unreachable!("{:?}", v),
+        }
+    }
+
+    /// The element at `index` of any array, the types smaller than int are
+    /// widened to it as on the operand stack.
+    pub fn get_array_element(&self, array_ref: &Operand, index: usize) -> Operand {
+        match array_ref {
+            Operand::ArrayRef(ref_i) => match &self.mem[*ref_i as usize] {
+                Memory::BooleanArray(array) | Memory::ByteArray(array) => {
+                    Operand::Int(array[index] as i32)
+                }
+                Memory::CharArray(array) => Operand::Int(array[index] as i32),
+                Memory::ShortArray(array) => Operand::Int(array[index] as i32),
+                Memory::IntArray(array) => Operand::Int(array[index]),
+                Memory::LongArray(array) => Operand::Long(array[index]),
+                Memory::FloatArray(array) => Operand::Float(array[index]),
+                Memory::DoubleArray(array) => Operand::Double(array[index]),
+                Memory::ReferenceArray { array, .. } => array[index].clone(),
+                _ => unreachable!(),
+            },
+            v => unreachable!("{:?}", v),
+        }
+    }
+
+    /// Stores `value` at `index` of any array, narrowing ints to the type of
+    /// the elements.
+    pub fn set_array_element(&mut self, array_ref: &Operand, index: usize, value: Operand) {
+        match (array_ref, value) {
+            (Operand::ArrayRef(ref_i), value) => match (&mut self.mem[*ref_i as usize], value) {
+                (Memory::BooleanArray(array), Operand::Int(n))
+                | (Memory::ByteArray(array), Operand::Int(n)) => array[index] = n as i8,
+                (Memory::CharArray(array), Operand::Int(n)) => array[index] = n as u16,
+                (Memory::ShortArray(array), Operand::Int(n)) => array[index] = n as i16,
+                (Memory::IntArray(array), Operand::Int(n)) => array[index] = n,
+                (Memory::LongArray(array), Operand::Long(n)) => array[index] = n,
+                (Memory::FloatArray(array), Operand::Float(n)) => array[index] = n,
+                (Memory::DoubleArray(array), Operand::Double(n)) => array[index] = n,
+                (Memory::ReferenceArray { array, .. }, value) => array[index] = value,
+                (_, value) => unreachable!("{:?}", value),
+            },
+            (v, _) => unreachable!("{:?}", v),
+        }
+    }
+
     pub fn get_object_mut(&mut self, obj_ref: &Operand) -> &mut Object {
         match obj_ref {
             Operand::ObjectRef(ref_i) => match &mut self.mem[*ref_i as usize] {
@@ -301,6 +569,19 @@ impl JvmHeap {
         }
     }
 
+    /// The class of the object `obj_ref` refers to, `None` for arrays.
+    pub fn get_object_class(&self, obj_ref: &Operand) -> Option<&Class> {
+        match obj_ref {
+            Operand::ObjectRef(ref_i) | Operand::ArrayRef(ref_i) => {
+                match &self.mem[*ref_i as usize] {
+                    Memory::Object(obj) => Some(obj.class()),
+                    _ => None,
+                }
+            }
+            v => unreachable!("{:?}", v),
+        }
+    }
+
     pub fn get_class_name(&self, obj_ref: &Operand) -> String {
         match obj_ref {
             Operand::ObjectRef(ref_i) | Operand::ArrayRef(ref_i) => {
@@ -314,7 +595,11 @@ impl JvmHeap {
                     Memory::ShortArray(_) => "[S".to_string(),
                     Memory::IntArray(_) => "[I".to_string(),
                     Memory::LongArray(_) => "[J".to_string(),
+                    Memory::ReferenceArray { class_name, .. } if class_name.starts_with('[') => {
+                        format!("[{}", class_name)
+                    }
                     Memory::ReferenceArray { class_name, .. } => format!("[L{};", class_name),
+                    Memory::Free => unreachable!("freed object: {}", ref_i),
                 }
             }
             v => unreachable!("{:?}", v),
@@ -336,6 +621,13 @@ impl JvmHeap {
     }
 }
 
+fn reference(operand: &Operand) -> Option<u32> {
+    match operand {
+        Operand::ObjectRef(obj_ref) | Operand::ArrayRef(obj_ref) => Some(*obj_ref),
+        _ => None,
+    }
+}
+
 impl Debug for JvmHeap {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for (i, item) in self.mem.iter().enumerate() {
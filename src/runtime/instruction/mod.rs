@@ -1,66 +1,213 @@
 #![allow(unused_variables)]
 use crate::class_parser::constant_pool::ConstPoolInfo;
-use crate::runtime::class::Class;
-use crate::runtime::execute_method;
+use crate::runtime::class::{Class, InitState, InstanceClass, Selection};
+use crate::runtime::cp_cache::MethodCall;
 use crate::runtime::frame::operand_stack::Operand;
-use crate::runtime::heap::JAVA_LANG_OBJECT;
+use crate::runtime::frame::JvmFrame;
+use crate::runtime::heap::{
+    JvmHeap, JAVA_LANG_ABSTRACT_METHOD_ERROR, JAVA_LANG_ARITHMETIC_EXCEPTION,
+    JAVA_LANG_ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION, JAVA_LANG_ARRAY_STORE_EXCEPTION,
+    JAVA_LANG_CLASS_CAST_EXCEPTION, JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR,
+    JAVA_LANG_INVOKE_METHOD_HANDLE_NATIVES, JAVA_LANG_INVOKE_METHOD_TYPE,
+    JAVA_LANG_NEGATIVE_ARRAY_SIZE_EXCEPTION, JAVA_LANG_NO_SUCH_METHOD_ERROR,
+    JAVA_LANG_NULL_POINTER_EXCEPTION, JAVA_LANG_OBJECT, T_BOOLEAN, T_BYTE, T_CHAR, T_DOUBLE,
+    T_FLOAT, T_INT, T_LONG, T_SHORT,
+};
+use crate::runtime::invoke::{invoke_call_site, link_call_site};
 use crate::runtime::jvm_env::JvmEnv;
+use crate::runtime::method::Method;
+use crate::runtime::monitor::{monitor_enter, monitor_exit};
+use crate::runtime::opcode;
+use crate::runtime::{execute_method, invoke_method, pop_frame};
 use tracing::debug;
 
+/// Throws `NullPointerException` if `obj_ref` is null.
+fn check_not_null(jenv: &mut JvmEnv, obj_ref: &Operand) -> bool {
+    if *obj_ref == Operand::Null {
+        jenv.throw_exception(JAVA_LANG_NULL_POINTER_EXCEPTION, None);
+        return false;
+    }
+    true
+}
+
+/// Throws `NullPointerException` or `ArrayIndexOutOfBoundsException` if
+/// `array_ref[index]` can't be accessed.
+fn check_array_index(jenv: &mut JvmEnv, array_ref: &Operand, index: i32) -> bool {
+    if !check_not_null(jenv, array_ref) {
+        return false;
+    }
+    let len = jenv.heap_mut().get_array_length(array_ref);
+    if index < 0 || index >= len {
+        jenv.throw_exception(
+            JAVA_LANG_ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION,
+            Some(&index.to_string()),
+        );
+        return false;
+    }
+    true
+}
+
+/// Throws `NegativeArraySizeException` if `count` is negative.
+fn check_array_size(jenv: &mut JvmEnv, count: i32) -> bool {
+    if count < 0 {
+        jenv.throw_exception(
+            JAVA_LANG_NEGATIVE_ARRAY_SIZE_EXCEPTION,
+            Some(&count.to_string()),
+        );
+        return false;
+    }
+    true
+}
+
 pub fn iconst_n(jenv: &mut JvmEnv, class: &Class, n: i32) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     frame.operand_stack.push_integer(n);
 }
 
 pub fn lconst_n(jenv: &mut JvmEnv, class: &Class, n: i64) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     frame.operand_stack.push_long(n);
 }
 
 pub fn fconst_n(jenv: &mut JvmEnv, class: &Class, n: f32) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     frame.operand_stack.push_float(n);
 }
 
 pub fn ldc(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let index = frame.read_u8().unwrap();
-    let const_pool_info = class.constant_pool().get_const_pool_info_at(index as u16);
+    load_constant(jenv, class, index as u16);
+}
+
+pub fn ldc_w(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let index = frame.read_u16().unwrap();
+    load_constant(jenv, class, index);
+}
+
+fn load_constant(jenv: &mut JvmEnv, class: &Class, index: u16) {
+    let const_pool_info = class.constant_pool().get_const_pool_info_at(index);
     match const_pool_info {
         ConstPoolInfo::ConstantIntegerInfo(num) => {
-            let frame = jenv.thread.stack.frames.back_mut().unwrap();
+            let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
             frame.operand_stack.push_integer(*num);
         }
         ConstPoolInfo::ConstantFloatInfo(num) => {
-            let frame = jenv.thread.stack.frames.back_mut().unwrap();
+            let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
             frame.operand_stack.push_float(*num);
         }
         ConstPoolInfo::ConstantStringInfo { string_index } => {
             let s = class.constant_pool().get_utf8_string_at(*string_index);
-            let str_ref = jenv.new_java_lang_string(s);
-            let frame = jenv.thread.stack.frames.back_mut().unwrap();
+            let str_ref = match jenv.intern_string(s) {
+                Some(addr) => addr,
+                None => return,
+            };
+            let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
             frame.operand_stack.push_object_ref(str_ref)
         }
         ConstPoolInfo::ConstantClassInfo { name_index } => {
             let name = class.constant_pool().get_utf8_string_at(*name_index);
-            let addr = jenv.new_java_lang_class(name);
-            let frame = jenv.thread.stack.frames.back_mut().unwrap();
+            let addr = match jenv.resolve_class_mirror(name) {
+                Some(addr) => addr,
+                None => return,
+            };
+            let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
             frame.operand_stack.push_object_ref(addr);
         }
-        ConstPoolInfo::ConstantMethodHandleInfo { .. } => unimplemented!(),
-        ConstPoolInfo::ConstantMethodTypeInfo { .. } => unimplemented!(),
+        ConstPoolInfo::ConstantMethodHandleInfo { .. } => {
+            let method_handle = class.constant_pool().get_method_handle_at(index);
+            let caller = Operand::ObjectRef(jenv.class_mirror(class));
+            let defc = match jenv.resolve_class_mirror(method_handle.class_name) {
+                Some(addr) => Operand::ObjectRef(addr),
+                None => return,
+            };
+            let name = match jenv.new_java_lang_string(method_handle.name) {
+                Some(addr) => Operand::ObjectRef(addr),
+                None => return,
+            };
+            // creating the type runs Java, which may collect garbage
+            jenv.thread_mut().native_args.push(vec![name.clone()]);
+            let ty = member_type(jenv, method_handle.descriptor);
+            jenv.thread_mut().native_args.pop();
+            let ty = match ty {
+                Some(ty) => ty,
+                None => return,
+            };
+            let kind = Operand::Int(method_handle.reference_kind as i32);
+            invoke_static_method(
+                jenv,
+                JAVA_LANG_INVOKE_METHOD_HANDLE_NATIVES,
+                "linkMethodHandleConstant",
+                "(Ljava/lang/Class;ILjava/lang/Class;Ljava/lang/String;Ljava/lang/Object;)Ljava/lang/invoke/MethodHandle;",
+                vec![caller, kind, defc, name, ty],
+            );
+        }
+        ConstPoolInfo::ConstantMethodTypeInfo { .. } => {
+            let descriptor = class.constant_pool().get_method_type_at(index);
+            push_method_type(jenv, descriptor);
+        }
         _ => unreachable!(),
     }
 }
 
+/// Pushes the `java.lang.invoke.MethodType` of `descriptor`, returns false if
+/// creating it threw.
+fn push_method_type(jenv: &mut JvmEnv, descriptor: &str) -> bool {
+    let descriptor = match jenv.new_java_lang_string(descriptor) {
+        Some(addr) => Operand::ObjectRef(addr),
+        None => return false,
+    };
+    invoke_static_method(
+        jenv,
+        JAVA_LANG_INVOKE_METHOD_TYPE,
+        "fromMethodDescriptorString",
+        "(Ljava/lang/String;Ljava/lang/ClassLoader;)Ljava/lang/invoke/MethodType;",
+        vec![descriptor, Operand::Null],
+    );
+    !jenv.thread().has_pending_exception()
+}
+
+/// The `MethodType` of a method `descriptor`, or the mirror of the type of a
+/// field `descriptor`. `None` if creating it threw.
+fn member_type(jenv: &mut JvmEnv, descriptor: &str) -> Option<Operand> {
+    if descriptor.starts_with('(') {
+        if !push_method_type(jenv, descriptor) {
+            return None;
+        }
+        let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+        Some(frame.operand_stack.pop())
+    } else {
+        jenv.descriptor_mirror(descriptor).map(Operand::ObjectRef)
+    }
+}
+
+/// Calls a static method, its result is pushed to the current frame.
+fn invoke_static_method(
+    jenv: &mut JvmEnv,
+    class_name: &str,
+    name: &str,
+    descriptor: &str,
+    args: Vec<Operand>,
+) {
+    // the arguments must survive a collection while the class initializes
+    jenv.thread_mut().native_args.push(args.clone());
+    let class = jenv.load_and_init_class(class_name);
+    jenv.thread_mut().native_args.pop();
+    let method = class
+        .get_method(name, descriptor, true)
+        .unwrap_or_else(|| panic!("get method: {}.{}{}", class_name, name, descriptor));
+    execute_method(jenv, method, args);
+}
+
 pub fn istore_n(jenv: &mut JvmEnv, class: &Class, n: i32) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val = frame.operand_stack.pop_integer();
     frame.local_variable_array.set(n as u16, Operand::Int(val));
 }
 
 pub fn istore(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let index = frame.read_u8().unwrap();
     let val = frame.operand_stack.pop_integer();
     frame
@@ -69,185 +216,322 @@ pub fn istore(jenv: &mut JvmEnv, class: &Class) {
 }
 
 pub fn astore_n(jenv: &mut JvmEnv, class: &Class, n: i32) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val = frame.operand_stack.pop();
     frame.local_variable_array.set(n as u16, val);
 }
 
 pub fn astore(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let index = frame.read_u8().unwrap();
     let val = frame.operand_stack.pop();
     frame.local_variable_array.set(index as u16, val);
 }
 
 pub fn aastore(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val = frame.operand_stack.pop();
     let index = frame.operand_stack.pop_integer();
     let array_ref = frame.operand_stack.pop();
-    let array = jenv.heap.get_object_array_mut(&array_ref);
+    if !check_array_index(jenv, &array_ref, index) {
+        return;
+    }
+    if val != Operand::Null {
+        let array_class = jenv.load_class(&jenv.heap().get_class_name(&array_ref));
+        let component_class = jenv.load_class(&array_class.obj_array_class().class);
+        let val_class_name = jenv.heap().get_class_name(&val);
+        let val_class = jenv.load_class(&val_class_name);
+        if !can_cast_to(jenv, val_class, component_class) {
+            let message = val_class_name.replace('/', ".");
+            jenv.throw_exception(JAVA_LANG_ARRAY_STORE_EXCEPTION, Some(&message));
+            return;
+        }
+    }
+    let array = jenv.heap_mut().get_object_array_mut(&array_ref);
     array[index as usize] = val;
 }
 
 pub fn iload_n(jenv: &mut JvmEnv, class: &Class, n: i32) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val = frame.local_variable_array.get_integer(n as u16);
     frame.operand_stack.push_integer(val);
 }
 
 pub fn lload_n(jenv: &mut JvmEnv, class: &Class, n: i32) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val = frame.local_variable_array.get_long(n as u16);
     frame.operand_stack.push_long(val);
 }
 
 pub fn lload(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let index = frame.read_u8().unwrap();
     let val = frame.local_variable_array.get_long(index as u16);
     frame.operand_stack.push_long(val);
 }
 
 pub fn iload(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let index = frame.read_u8().unwrap();
     let val = frame.local_variable_array.get_integer(index as u16);
     frame.operand_stack.push_integer(val);
 }
 
 pub fn iinc(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let index = frame.read_u8().unwrap();
     // amount is signed
     let amount = frame.read_u8().unwrap() as i8 as i32;
     let val = frame.local_variable_array.get_integer(index as u16);
     frame
         .local_variable_array
-        .set_integer(index as u16, val + amount);
+        .set_integer(index as u16, val.wrapping_add(amount));
 }
 
 pub fn aload_n(jenv: &mut JvmEnv, class: &Class, n: i32) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val = frame.local_variable_array.get_object(n as u16);
     frame.operand_stack.push(val);
 }
 
 pub fn aload(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let index = frame.read_u8().unwrap();
     let val = frame.local_variable_array.get_object(index as u16);
     frame.operand_stack.push(val);
 }
 
 pub fn aaload(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let index = frame.operand_stack.pop_integer();
     let array_ref = frame.operand_stack.pop();
-    let array = jenv.heap.get_object_array(&array_ref);
+    if !check_array_index(jenv, &array_ref, index) {
+        return;
+    }
+    let array = jenv.heap_mut().get_object_array(&array_ref);
     debug!(index, ?array_ref, ?array, "aaload");
-    frame.operand_stack.push(array[index as usize].clone());
+    let value = array[index as usize].clone();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(value);
 }
 
 pub fn caload(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let index = frame.operand_stack.pop_integer();
     let array_ref = frame.operand_stack.pop();
-    let array = jenv.heap.get_char_array(&array_ref);
+    if !check_array_index(jenv, &array_ref, index) {
+        return;
+    }
+    let array = jenv.heap().get_char_array(&array_ref);
     debug!(index, ?array_ref, ?array, "caload");
-    frame
-        .operand_stack
-        .push_integer(array[index as usize] as i32);
+    let value = array[index as usize] as i32;
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push_integer(value);
 }
 
 pub fn fload_n(jenv: &mut JvmEnv, class: &Class, n: i32) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val = frame.local_variable_array.get_float(n as u16);
     frame.operand_stack.push_float(val);
 }
 
 pub fn irem(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val2 = frame.operand_stack.pop_integer();
     let val1 = frame.operand_stack.pop_integer();
-    frame.operand_stack.push_integer(val1 % val2);
+    if val2 == 0 {
+        jenv.throw_exception(JAVA_LANG_ARITHMETIC_EXCEPTION, Some("/ by zero"));
+        return;
+    }
+    frame.operand_stack.push_integer(val1.wrapping_rem(val2));
 }
 
 pub fn iadd(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val2 = frame.operand_stack.pop_integer();
     let val1 = frame.operand_stack.pop_integer();
-    frame.operand_stack.push_integer(val1 + val2);
+    frame.operand_stack.push_integer(val1.wrapping_add(val2));
 }
 
 pub fn ladd(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val2 = frame.operand_stack.pop_long();
     let val1 = frame.operand_stack.pop_long();
-    frame.operand_stack.push_long(val1 + val2);
+    frame.operand_stack.push_long(val1.wrapping_add(val2));
 }
 
 pub fn invokestatic(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let opcode_pc = frame.pc() - 1;
     let index = frame.read_u16().unwrap();
-    let method_ref = class
-        .constant_pool()
-        .get_class_method_or_interface_method_at(index);
+    let method = frame.method();
+    let call = match method.resolve_method(opcode_pc) {
+        Some(call) => call,
+        None => {
+            let resolved_method = match resolve_method_ref(jenv, class, index) {
+                Some(method) => method,
+                None => return,
+            };
+            if !resolved_method.is_static() {
+                let message = format!("Expected static method {}", describe(&resolved_method));
+                jenv.throw_exception(JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR, Some(&message));
+                return;
+            }
+            let declaring_class = jenv.load_class(resolved_method.class_name());
+            if !jenv.initialize_class(&declaring_class) {
+                return;
+            }
+            let call = MethodCall::Direct(resolved_method);
+            // while the class is being initialized, other threads must wait
+            if declaring_class.instance_class_ref().init_state() == InitState::Initialized {
+                method.set_method(opcode_pc, call.clone());
+            }
+            call
+        }
+    };
+    let args = pop_args(jenv, call.method());
+    invoke_method(jenv, call.method().clone(), args);
+}
+
+/// `method` the way linkage errors name it, e.g. `pkg.Main.run()V`.
+fn describe(method: &Method) -> String {
+    format!(
+        "{}.{}{}",
+        method.class_name().replace('/', "."),
+        method.name(),
+        method.descriptor()
+    )
+}
+
+/// Resolves the `Methodref` or `InterfaceMethodref` at `index` of the constant
+/// pool of `class` (JVMS §5.4.3.3, §5.4.3.4), `None` if resolution threw.
+fn resolve_method_ref(jenv: &mut JvmEnv, class: &Class, index: u16) -> Option<Method> {
+    let constant_pool = class.constant_pool();
+    let is_interface_method_ref = constant_pool.is_interface_method_ref_at(index);
+    let method_ref = constant_pool.get_class_method_or_interface_method_at(index);
+    let resolved_class = match jenv.resolve_class(method_ref.class_name)? {
+        Class::InstanceClass(class) => class,
+        // the methods of arrays are those of `Object`
+        _ => jenv.load_class(JAVA_LANG_OBJECT).instance_class(),
+    };
+    if resolved_class.is_interface() != is_interface_method_ref {
+        let (found, expected) = if is_interface_method_ref {
+            ("class", "interface")
+        } else {
+            ("interface", "class")
+        };
+        let message = format!(
+            "Found {} {}, but {} was expected",
+            found,
+            resolved_class.name().replace('/', "."),
+            expected
+        );
+        jenv.throw_exception(JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR, Some(&message));
+        return None;
+    }
+    let (name, descriptor) = (method_ref.method_name, method_ref.descriptor);
+    let resolved_method = if is_interface_method_ref {
+        resolved_class.resolve_interface_method(name, descriptor)
+    } else {
+        resolved_class.resolve_method(name, descriptor)
+    };
+    if resolved_method.is_none() {
+        let message = format!(
+            "{}.{}{}",
+            resolved_class.name().replace('/', "."),
+            name,
+            descriptor
+        );
+        jenv.throw_exception(JAVA_LANG_NO_SUCH_METHOD_ERROR, Some(&message));
+    }
+    resolved_method
+}
 
-    let class_name = method_ref.class_name;
-    let class = jenv.load_and_init_class(class_name);
-    let method = class
-        .get_method(method_ref.method_name, method_ref.descriptor, true)
-        .expect("get method");
+/// Resolves the method of an `invokevirtual` or `invokeinterface` at `index`,
+/// which must be an instance method.
+fn resolve_virtual_method_ref(jenv: &mut JvmEnv, class: &Class, index: u16) -> Option<Method> {
+    let resolved_method = resolve_method_ref(jenv, class, index)?;
+    assert!(
+        !resolved_method.name().starts_with('<'),
+        "<init> and <clinit> are not allowed here"
+    );
+    if resolved_method.is_static() {
+        let message = format!("Expecting non-static method {}", describe(&resolved_method));
+        jenv.throw_exception(JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR, Some(&message));
+        return None;
+    }
+    Some(resolved_method)
+}
+
+/// The method of `selection`, `None` with `AbstractMethodError` or
+/// `IncompatibleClassChangeError` thrown if it can't be called.
+fn selected_method(jenv: &mut JvmEnv, selection: &Selection) -> Option<Method> {
+    match selection {
+        Selection::Method(method) if method.is_abstract() => {
+            jenv.throw_exception(JAVA_LANG_ABSTRACT_METHOD_ERROR, Some(&describe(method)));
+            None
+        }
+        Selection::Method(method) => Some(method.clone()),
+        Selection::Conflict(method) => {
+            let message = format!("Conflicting default methods: {}", describe(method));
+            jenv.throw_exception(JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR, Some(&message));
+            None
+        }
+    }
+}
 
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
-    let n_args = method.n_args();
+/// Pops the arguments of `method` from the current frame, the receiver first
+/// unless it is static.
+fn pop_args(jenv: &mut JvmEnv, method: &Method) -> Vec<Operand> {
+    let n_args = method.n_args() + if method.is_static() { 0 } else { 1 };
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let mut args = Vec::with_capacity(n_args);
     for _ in 0..n_args {
         args.push(frame.operand_stack.pop());
     }
     args.reverse();
-    execute_method(jenv, method, args);
+    args
 }
-
 pub fn ireturn(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val = frame.operand_stack.pop_integer();
-    let _ = jenv.thread.stack.frames.pop_back();
-    let last_frame = jenv.thread.stack.frames.back_mut().unwrap();
-    last_frame.operand_stack.push_integer(val);
+    return_value(jenv, Operand::Int(val));
 }
 
 pub fn dreturn(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val = frame.operand_stack.pop_double();
-    let _ = jenv.thread.stack.frames.pop_back();
-    let last_frame = jenv.thread.stack.frames.back_mut().unwrap();
-    last_frame.operand_stack.push_double(val);
+    return_value(jenv, Operand::Double(val));
 }
 
 pub fn freturn(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val = frame.operand_stack.pop_float();
-    let _ = jenv.thread.stack.frames.pop_back();
-    let last_frame = jenv.thread.stack.frames.back_mut().unwrap();
-    last_frame.operand_stack.push_float(val);
+    return_value(jenv, Operand::Float(val));
 }
 
 pub fn areturn(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val = frame.operand_stack.pop();
-    let _ = jenv.thread.stack.frames.pop_back();
-    let last_frame = jenv.thread.stack.frames.back_mut().unwrap();
-    last_frame.operand_stack.push(val);
+    return_value(jenv, val);
 }
 
 pub fn return_(jenv: &mut JvmEnv, class: &Class) {
-    let _ = jenv.thread.stack.frames.pop_back();
+    pop_frame(jenv);
+}
+
+/// Pops the frame of the returning method and pushes `value` to the frame of
+/// its caller, unless releasing the monitor of the method threw.
+fn return_value(jenv: &mut JvmEnv, value: Operand) {
+    pop_frame(jenv);
+    if !jenv.thread().has_pending_exception() {
+        let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+        frame.operand_stack.push(value);
+    }
 }
 
 pub fn getstatic(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let opcode_pc = frame.pc() - 1;
     let index = frame.read_u16().unwrap();
     let method = frame.method();
@@ -255,7 +539,10 @@ pub fn getstatic(jenv: &mut JvmEnv, class: &Class) {
         v
     } else {
         let field_ref = class.constant_pool().get_field_ref_at(index);
-        let field_class = jenv.load_and_init_class(field_ref.class_name);
+        let field_class = match jenv.resolve_class(field_ref.class_name) {
+            Some(class) => class,
+            None => return,
+        };
         let field = field_class
             .get_static_field(field_ref.field_name, field_ref.descriptor)
             .unwrap_or_else(|| panic!("resolve field: {:?}", field_ref));
@@ -265,200 +552,253 @@ pub fn getstatic(jenv: &mut JvmEnv, class: &Class) {
 
         (field_class, field_index)
     };
+    // the cached field may belong to a class another thread still initializes
+    if !jenv.initialize_class(&field_class) {
+        return;
+    }
 
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     frame
         .operand_stack
         .push(field_class.get_static_field_value(field_index))
 }
 
 pub fn putstatic(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let opcode_pc = frame.pc() - 1;
     let index = frame.read_u16().unwrap();
-    let value = frame.operand_stack.pop();
     let method = frame.method();
     let (field_class, field_index) = if let Some(v) = method.resolve_static_field(opcode_pc) {
         v
     } else {
         let field_ref = class.constant_pool().get_field_ref_at(index);
-        let field_class = jenv.load_and_init_class(field_ref.class_name);
+        let field_class = match jenv.resolve_class(field_ref.class_name) {
+            Some(class) => class,
+            None => return,
+        };
         let field = field_class
             .get_static_field(field_ref.field_name, field_ref.descriptor)
             .unwrap_or_else(|| panic!("resolve field: {:?}", field_ref));
         let field_index = field.index();
-        debug!(?field_ref, %field_index, ?class, field=?value, "putstatic");
+        debug!(?field_ref, %field_index, ?class, "putstatic");
         method.set_static_field(opcode_pc, field_class.clone(), field_index);
         (field_class, field_index)
     };
+    if !jenv.initialize_class(&field_class) {
+        return;
+    }
 
+    // popped only now as the value must stay reachable while <clinit> runs
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value = frame.operand_stack.pop();
     field_class.set_static_field_value(field_index, value);
 }
 
 pub fn aconst_null(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     frame.operand_stack.push(Operand::Null)
 }
 
 pub fn invokevirtual(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let opcode_pc = frame.pc() - 1;
     let index = frame.read_u16().unwrap();
-    let method_ref = class.constant_pool().get_method_ref_at(index);
-    debug!(?method_ref, "invokevirtual");
-    let resolved_class = jenv.load_and_init_class(method_ref.class_name);
-    let resolved_method = resolved_class
-        .get_method(method_ref.method_name, method_ref.descriptor, false)
-        .unwrap_or_else(|| panic!("get method: {}", &method_ref.method_name));
-    assert!(
-        resolved_method.name() != "<init>" && resolved_method.name() != "<clinit>",
-        "<init> and <clinit> are not allowed here"
-    );
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
-    let n_args = resolved_method.n_args();
-    let mut args = Vec::with_capacity(n_args + 1);
-    for i in 0..n_args {
-        args.push(frame.operand_stack.pop());
-    }
-    let object_ref = frame.operand_stack.pop();
-    args.push(object_ref.clone());
-    args.reverse();
+    let method = frame.method();
+    let call = match method.resolve_method(opcode_pc) {
+        Some(call) => call,
+        None => {
+            let resolved_method = match resolve_virtual_method_ref(jenv, class, index) {
+                Some(method) => method,
+                None => return,
+            };
+            debug!(%resolved_method, "invokevirtual");
+            if resolved_method.is_signature_polymorphic() {
+                unimplemented!("is_signature_polymorphic")
+            }
+            let call = virtual_call(jenv, resolved_method);
+            method.set_method(opcode_pc, call.clone());
+            call
+        }
+    };
+    invoke_virtual_call(jenv, &call);
+}
 
-    if resolved_method.is_native() {
-        execute_method(jenv, resolved_method, args);
+/// Pops the arguments of `call` and calls the method it selects for the
+/// receiver.
+fn invoke_virtual_call(jenv: &mut JvmEnv, call: &MethodCall) {
+    let args = pop_args(jenv, call.method());
+    if !check_not_null(jenv, &args[0]) {
         return;
     }
+    if let Some(actual_method) = select_method(jenv, call, &args[0]) {
+        invoke_method(jenv, actual_method, args);
+    }
+}
 
-    let class_name = jenv.heap.get_class_name(&object_ref);
-    let object_class = jenv.load_and_init_class(&class_name);
+/// How a virtual call of `resolved_method` selects the method it calls: by
+/// the vtable slot of a class method, by the itable entry of an interface
+/// method, or not at all for private and final methods.
+pub fn virtual_call(jenv: &mut JvmEnv, resolved_method: Method) -> MethodCall {
+    if resolved_method.is_private() || resolved_method.is_final() {
+        return MethodCall::Direct(resolved_method);
+    }
+    let declaring_class = jenv
+        .load_class(resolved_method.class_name())
+        .instance_class();
+    if declaring_class.is_interface() {
+        let index = declaring_class
+            .interface_method_index(&resolved_method)
+            .unwrap_or_else(|| panic!("interface method: {}", resolved_method));
+        MethodCall::Interface(resolved_method, declaring_class, index)
+    } else {
+        let index = declaring_class
+            .vtable_index(&resolved_method)
+            .unwrap_or_else(|| panic!("vtable slot: {}", resolved_method));
+        MethodCall::Virtual(resolved_method, index)
+    }
+}
 
-    let acutal_method = if !resolved_method.is_signature_polymorphic() {
-        if let Some(actual_method) = object_class
-            .get_self_method(resolved_method.name(), resolved_method.descriptor(), false)
-            .filter(|m| jenv.did_override_method(m, &resolved_method))
-        {
-            actual_method
-        } else if let Some(actual_method) = object_class
-            .iter_super_classes()
-            .filter_map(|klass| {
-                klass.get_self_method(resolved_method.name(), resolved_method.descriptor(), false)
-            })
-            .find(|m| jenv.did_override_method(m, &resolved_method))
-        {
-            actual_method
-        } else if let Some(actual_method) =
-            object_class.get_interface_method(resolved_method.name(), resolved_method.descriptor())
-        {
-            actual_method
-        } else {
-            unreachable!("no method found")
+/// The method `call` selects for `receiver` (JVMS §5.4.6), `None` if
+/// selecting it threw.
+pub fn select_method(jenv: &mut JvmEnv, call: &MethodCall, receiver: &Operand) -> Option<Method> {
+    let receiver_class = match jenv.heap().get_object_class(receiver) {
+        Some(class) => class.instance_class(),
+        // the tables of arrays are those of `Object`
+        None => jenv.load_class(JAVA_LANG_OBJECT).instance_class(),
+    };
+    let selection = match call {
+        MethodCall::Direct(method) => return Some(method.clone()),
+        MethodCall::Virtual(_, index) => receiver_class.vtable()[*index].clone(),
+        MethodCall::Interface(_, interface, index) => {
+            match receiver_class.itable_method(interface, *index) {
+                Some(selection) => selection.clone(),
+                None => {
+                    let message = format!(
+                        "Class {} does not implement the requested interface {}",
+                        receiver_class.name().replace('/', "."),
+                        interface.name().replace('/', ".")
+                    );
+                    jenv.throw_exception(JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR, Some(&message));
+                    return None;
+                }
+            }
         }
-    } else {
-        unimplemented!("is_signature_polymorphic")
     };
-
-    let actual_class = jenv.load_and_init_class(acutal_method.class_name());
-
-    execute_method(jenv, acutal_method, args);
+    selected_method(jenv, &selection)
 }
 
 pub fn invokeinterface(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let opcode_pc = frame.pc() - 1;
     let index = frame.read_u16().unwrap();
     let count = frame.read_u8().unwrap();
     assert_ne!(count, 0);
     let forth = frame.read_u8().unwrap();
     assert_eq!(forth, 0);
-    let method_ref = class.constant_pool().get_interface_method_ref_at(index);
-    debug!(?method_ref, "invokeinterface");
-    let resolved_class = jenv.load_and_init_class(method_ref.class_name);
-    let resolved_method = resolved_class
-        .get_interface_method(method_ref.method_name, method_ref.descriptor)
-        .unwrap_or_else(|| panic!("get interface method: {}", &method_ref.method_name));
-    assert!(
-        resolved_method.name() != "<init>" && resolved_method.name() != "<clinit>",
-        "<init> and <clinit> are not allowed here"
-    );
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
-    let n_args = resolved_method.n_args();
-    let mut args = Vec::with_capacity(n_args + 1);
-    for i in 0..n_args {
-        args.push(frame.operand_stack.pop());
-    }
-    let object_ref = frame.operand_stack.pop();
-    args.push(object_ref.clone());
-    args.reverse();
-
-    if resolved_method.is_native() {
-        execute_method(jenv, resolved_method, args);
-        return;
-    }
-
-    let class_name = jenv.heap.get_class_name(&object_ref);
-    let object_class = jenv.load_and_init_class(&class_name);
-
-    let acutal_method = if let Some(actual_method) =
-        object_class.get_self_method(resolved_method.name(), resolved_method.descriptor(), false)
-    {
-        actual_method
-    } else if let Some(actual_method) = object_class.iter_super_classes().find_map(|klass| {
-        klass.get_self_method(resolved_method.name(), resolved_method.descriptor(), false)
-    }) {
-        actual_method
-    } else if let Some(actual_method) =
-        object_class.get_interface_method(resolved_method.name(), resolved_method.descriptor())
-    {
-        actual_method
-    } else {
-        unreachable!("no method found")
+    let method = frame.method();
+    let call = match method.resolve_method(opcode_pc) {
+        Some(call) => call,
+        None => {
+            let resolved_method = match resolve_virtual_method_ref(jenv, class, index) {
+                Some(method) => method,
+                None => return,
+            };
+            debug!(%resolved_method, "invokeinterface");
+            // the methods of `Object` are called through the vtable
+            let call = virtual_call(jenv, resolved_method);
+            method.set_method(opcode_pc, call.clone());
+            call
+        }
     };
-
-    let actual_class = jenv.load_and_init_class(acutal_method.class_name());
-
-    execute_method(jenv, acutal_method, args);
+    invoke_virtual_call(jenv, &call);
+}
+pub fn invokedynamic(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let opcode_pc = frame.pc() - 1;
+    let index = frame.read_u16().unwrap();
+    let third = frame.read_u8().unwrap();
+    let forth = frame.read_u8().unwrap();
+    assert_eq!((third, forth), (0, 0));
+    let method = frame.method();
+    let call_site = match method.resolve_call_site(opcode_pc) {
+        Some(call_site) => call_site,
+        None => match link_call_site(jenv, class, index) {
+            Some(call_site) => {
+                method.set_call_site(opcode_pc, call_site.clone());
+                call_site
+            }
+            None => return,
+        },
+    };
+    invoke_call_site(jenv, &call_site);
 }
 
 pub fn new(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let index = frame.read_u16().unwrap();
     let class_name = class.constant_pool().get_class_name_at(index);
-    let class = jenv.load_and_init_class(class_name);
-    let (object, addr) = jenv.heap.new_object(class);
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let class = match jenv.resolve_and_init_class(class_name) {
+        Some(class) => class,
+        None => return,
+    };
+    if !jenv.reserve_heap(JvmHeap::object_size(&class)) {
+        return;
+    }
+    let (object, addr) = jenv.heap_mut().new_object(class);
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     frame.operand_stack.push(Operand::ObjectRef(addr))
 }
 
 pub fn newarray(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let count = frame.operand_stack.pop_integer();
     let atype = frame.read_u8().unwrap();
-    let array_ref = jenv.heap.new_empty_array(atype, count);
+    if !check_array_size(jenv, count) {
+        return;
+    }
+    if !jenv.reserve_heap(JvmHeap::array_size(JvmHeap::element_size(atype), count)) {
+        return;
+    }
+    let array_ref = jenv.heap_mut().new_empty_array(atype, count);
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     frame.operand_stack.push(Operand::ArrayRef(array_ref))
 }
 
 pub fn anewarray(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let count = frame.operand_stack.pop_integer();
     let index = frame.read_u16().unwrap();
     let resolved_class_name = class.constant_pool().get_class_name_at(index);
+    if !check_array_size(jenv, count) {
+        return;
+    }
+    if !jenv.reserve_heap(JvmHeap::reference_array_size(count)) {
+        return;
+    }
     let array_ref = jenv
-        .heap
+        .heap_mut()
         .new_reference_array(resolved_class_name.clone(), count);
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     frame.operand_stack.push(Operand::ArrayRef(array_ref))
 }
 
 pub fn arraylength(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let array_ref = frame.operand_stack.pop();
-    let len = jenv.heap.get_array_length(&array_ref);
+    if !check_not_null(jenv, &array_ref) {
+        return;
+    }
+    let len = jenv.heap_mut().get_array_length(&array_ref);
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     frame.operand_stack.push_integer(len);
 }
 
 pub fn pop(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let _ = frame.operand_stack.pop();
 }
 
-fn can_cast_to(jenv: &mut JvmEnv, s: Class, t: Class) -> bool {
+pub fn can_cast_to(jenv: &mut JvmEnv, s: Class, t: Class) -> bool {
     match (s, t) {
         (Class::InstanceClass(s), Class::InstanceClass(t))
             if (s.is_class() && t.is_class()) || (s.is_interface() && t.is_interface()) =>
@@ -472,7 +812,10 @@ fn can_cast_to(jenv: &mut JvmEnv, s: Class, t: Class) -> bool {
             t.name() == JAVA_LANG_OBJECT
         }
         (s, Class::InstanceClass(t)) if t.is_class() => t.name() == JAVA_LANG_OBJECT,
-        (s, Class::InstanceClass(t)) if t.is_interface() => unimplemented!(),
+        // arrays implement exactly these two interfaces
+        (s, Class::InstanceClass(t)) if t.is_interface() => {
+            t.name() == "java/lang/Cloneable" || t.name() == "java/io/Serializable"
+        }
         (Class::TypeArrayClass(s), Class::TypeArrayClass(t)) => s.ty == t.ty,
         (Class::ObjArrayClass(s), Class::ObjArrayClass(t)) => {
             let sc = jenv.load_and_init_class(&s.class);
@@ -484,34 +827,45 @@ fn can_cast_to(jenv: &mut JvmEnv, s: Class, t: Class) -> bool {
 }
 
 pub fn checkcast(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let index = frame.read_u16().unwrap();
     let class_name = class.constant_pool().get_class_name_at(index);
-    let class = jenv.load_and_init_class(class_name);
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let class = match jenv.resolve_class(class_name) {
+        Some(class) => class,
+        None => return,
+    };
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let obj_ref = frame.operand_stack.pop();
     if obj_ref == Operand::Null {
         frame.operand_stack.push(obj_ref);
         return;
     }
-    let obj_class_name = jenv.heap.get_class_name(&obj_ref);
+    let obj_class_name = jenv.heap().get_class_name(&obj_ref);
     let obj_class = jenv.load_and_init_class(&obj_class_name);
 
-    assert!(can_cast_to(jenv, obj_class, class));
+    if !can_cast_to(jenv, obj_class, class) {
+        let message = format!(
+            "{} cannot be cast to {}",
+            obj_class_name.replace('/', "."),
+            class_name.replace('/', ".")
+        );
+        jenv.throw_exception(JAVA_LANG_CLASS_CAST_EXCEPTION, Some(&message));
+        return;
+    }
 
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     frame.operand_stack.push(obj_ref);
 }
 
 pub fn dup(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val = frame.operand_stack.pop();
     frame.operand_stack.push(val.clone());
     frame.operand_stack.push(val);
 }
 
 pub fn dup_x1(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val1 = frame.operand_stack.pop();
     let val2 = frame.operand_stack.pop();
     frame.operand_stack.push(val1.clone());
@@ -520,86 +874,102 @@ pub fn dup_x1(jenv: &mut JvmEnv, class: &Class) {
 }
 
 pub fn castore(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val = frame.operand_stack.pop_integer();
     let index = frame.operand_stack.pop_integer();
     let array_ref = frame.operand_stack.pop();
-    let array = jenv.heap.get_mut_char_array(array_ref);
+    if !check_array_index(jenv, &array_ref, index) {
+        return;
+    }
+    let array = jenv.heap_mut().get_mut_char_array(array_ref);
     array[index as usize] = val as u16;
 }
 
 pub fn invokespecial(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let opcode_pc = frame.pc() - 1;
     let index = frame.read_u16().unwrap();
+    let method = frame.method();
+    let call = match method.resolve_method(opcode_pc) {
+        Some(call) => call,
+        None => match resolve_special_method(jenv, class, index) {
+            Some(actual_method) => {
+                let call = MethodCall::Direct(actual_method);
+                method.set_method(opcode_pc, call.clone());
+                call
+            }
+            None => return,
+        },
+    };
+    let args = pop_args(jenv, call.method());
+    if !check_not_null(jenv, &args[0]) {
+        return;
+    }
+    invoke_method(jenv, call.method().clone(), args);
+}
+
+/// The method `invokespecial` in `class` calls, JVMS §6.5.invokespecial.
+fn resolve_special_method(jenv: &mut JvmEnv, class: &Class, index: u16) -> Option<Method> {
+    let resolved_method = resolve_method_ref(jenv, class, index)?;
     let method_ref = class
         .constant_pool()
         .get_class_method_or_interface_method_at(index);
+    if resolved_method.is_initialization_method()
+        && resolved_method.class_name() != method_ref.class_name
+    {
+        jenv.throw_exception(
+            JAVA_LANG_NO_SUCH_METHOD_ERROR,
+            Some(&describe(&resolved_method)),
+        );
+        return None;
+    }
+    if resolved_method.is_static() {
+        let message = format!("Expecting non-static method {}", describe(&resolved_method));
+        jenv.throw_exception(JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR, Some(&message));
+        return None;
+    }
 
-    let resolved_class = jenv.load_and_init_class(method_ref.class_name);
-
-    let resolved_method = resolved_class
-        .get_method(method_ref.method_name, method_ref.descriptor, false)
-        .unwrap_or_else(|| {
-            panic!(
-                "get method: {}, descriptor: {}, class: {}",
-                method_ref.method_name,
-                method_ref.descriptor,
-                resolved_class.name()
-            )
-        });
-
-    let actual_class = if !resolved_method.is_initialization_method()
-        && (resolved_class.is_interface()
-            || (resolved_class.is_class() && class.is_subclass_of(resolved_class.clone())))
-        && class.is_super()
+    let resolved_class: InstanceClass = jenv.load_class(method_ref.class_name).instance_class();
+    let current_class = class.instance_class_ref();
+    // with ACC_SUPER, `super.m()` searches from the direct superclass even if
+    // the method was resolved in a class further up
+    let search_class = if !resolved_method.is_initialization_method()
+        && resolved_class.is_class()
+        && current_class.is_subclass_of(resolved_class.clone())
+        && current_class.is_super()
     {
-        class.super_class().unwrap()
+        current_class.super_class().unwrap()
     } else {
         resolved_class
     };
 
-    let actual_method = actual_class
-        .get_method(
-            resolved_method.name(),
-            resolved_method.descriptor(),
-            resolved_method.is_static(),
-        )
-        .unwrap_or_else(|| {
-            panic!(
-                "class: {}, method: {}, descriptor: {}",
-                actual_class.name(),
-                resolved_method.name(),
-                resolved_method.descriptor()
-            )
-        });
-
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
-    let n_args = actual_method.n_args();
-    let mut args = Vec::with_capacity(n_args + 1);
-    for i in 0..n_args {
-        args.push(frame.operand_stack.pop());
+    match search_class.select_special_method(resolved_method.name(), resolved_method.descriptor()) {
+        Some(selection) => selected_method(jenv, &selection),
+        None => {
+            let message = describe(&resolved_method);
+            jenv.throw_exception(JAVA_LANG_ABSTRACT_METHOD_ERROR, Some(&message));
+            None
+        }
     }
-    let object_ref = frame.operand_stack.pop();
-    args.push(object_ref);
-    args.reverse();
-
-    execute_method(jenv, actual_method, args);
 }
 
 pub fn putfield(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let opcode_pc = frame.pc() - 1;
     let index = frame.read_u16().unwrap();
     let value = frame.operand_stack.pop();
     let object_ref = frame.operand_stack.pop();
+    if !check_not_null(jenv, &object_ref) {
+        return;
+    }
 
-    let method = frame.method();
+    let method = jenv.thread().current_frame().unwrap().method();
     let field_index = if let Some(index) = method.resolve_field(opcode_pc) {
         index
     } else {
         let field_ref = class.constant_pool().get_field_ref_at(index);
         debug!(?object_ref, ?field_ref, "putfield");
-        let object_class_name = jenv.heap.get_object(&object_ref).class_name().to_string();
+        let object_class_name = jenv.heap().get_object(&object_ref).class_name().to_string();
         let field_class = jenv.load_and_init_class(&object_class_name);
         let class_field = field_class
             .get_field(field_ref.field_name, field_ref.descriptor)
@@ -608,22 +978,25 @@ pub fn putfield(jenv: &mut JvmEnv, class: &Class) {
         method.set_field(opcode_pc, index);
         index
     };
-    let obj = jenv.heap.get_object_mut(&object_ref);
+    let obj = jenv.heap_mut().get_object_mut(&object_ref);
     obj.set_field(field_index, value);
 }
 
 pub fn getfield(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let opcode_pc = frame.pc() - 1;
     let index = frame.read_u16().unwrap();
     let object_ref = frame.operand_stack.pop();
+    if !check_not_null(jenv, &object_ref) {
+        return;
+    }
 
-    let method = frame.method();
+    let method = jenv.thread().current_frame().unwrap().method();
     let field_index = if let Some(index) = method.resolve_field(opcode_pc) {
         index
     } else {
         let field_ref = class.constant_pool().get_field_ref_at(index);
-        let object_class_name = jenv.heap.get_object(&object_ref).class_name().to_string();
+        let object_class_name = jenv.heap().get_object(&object_ref).class_name().to_string();
         let obj_class = jenv.load_and_init_class(&object_class_name);
         let class_field = obj_class
             .get_field(field_ref.field_name, field_ref.descriptor)
@@ -633,15 +1006,15 @@ pub fn getfield(jenv: &mut JvmEnv, class: &Class) {
         method.set_field(opcode_pc, index);
         index
     };
-    let obj = jenv.heap.get_object(&object_ref);
+    let obj = jenv.heap().get_object(&object_ref);
     let value = obj.get_field(field_index).clone();
     debug!(?value, "getfield");
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     frame.operand_stack.push(value);
 }
 
 pub fn ifge(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let pc = frame.pc();
     let offset = frame.read_i16().unwrap() as i32;
     let value = frame.operand_stack.pop_integer();
@@ -651,7 +1024,7 @@ pub fn ifge(jenv: &mut JvmEnv, class: &Class) {
 }
 
 pub fn ifgt(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let pc = frame.pc();
     let offset = frame.read_i16().unwrap() as i32;
     let value = frame.operand_stack.pop_integer();
@@ -661,7 +1034,7 @@ pub fn ifgt(jenv: &mut JvmEnv, class: &Class) {
 }
 
 pub fn iflt(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let pc = frame.pc();
     let offset = frame.read_i16().unwrap() as i32;
     let value = frame.operand_stack.pop_integer();
@@ -671,7 +1044,7 @@ pub fn iflt(jenv: &mut JvmEnv, class: &Class) {
 }
 
 pub fn ifle(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let pc = frame.pc();
     let offset = frame.read_i16().unwrap() as i32;
     let value = frame.operand_stack.pop_integer();
@@ -681,7 +1054,7 @@ pub fn ifle(jenv: &mut JvmEnv, class: &Class) {
 }
 
 pub fn if_icmpeq(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let pc = frame.pc();
     let offset = frame.read_i16().unwrap() as i32;
     let value2 = frame.operand_stack.pop_integer();
@@ -692,7 +1065,7 @@ pub fn if_icmpeq(jenv: &mut JvmEnv, class: &Class) {
 }
 
 pub fn if_icmpne(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let pc = frame.pc();
     let offset = frame.read_i16().unwrap() as i32;
     let value2 = frame.operand_stack.pop_integer();
@@ -703,7 +1076,7 @@ pub fn if_icmpne(jenv: &mut JvmEnv, class: &Class) {
 }
 
 pub fn if_acmpne(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let pc = frame.pc();
     let offset = frame.read_i16().unwrap() as i32;
     let value2 = frame.operand_stack.pop();
@@ -714,7 +1087,7 @@ pub fn if_acmpne(jenv: &mut JvmEnv, class: &Class) {
 }
 
 pub fn if_acmpeq(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let pc = frame.pc();
     let offset = frame.read_i16().unwrap() as i32;
     let value2 = frame.operand_stack.pop();
@@ -725,7 +1098,7 @@ pub fn if_acmpeq(jenv: &mut JvmEnv, class: &Class) {
 }
 
 pub fn if_icmplt(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let pc = frame.pc();
     let offset = frame.read_i16().unwrap() as i32;
     let value2 = frame.operand_stack.pop_integer();
@@ -736,7 +1109,7 @@ pub fn if_icmplt(jenv: &mut JvmEnv, class: &Class) {
 }
 
 pub fn if_icmple(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let pc = frame.pc();
     let offset = frame.read_i16().unwrap() as i32;
     let value2 = frame.operand_stack.pop_integer();
@@ -747,7 +1120,7 @@ pub fn if_icmple(jenv: &mut JvmEnv, class: &Class) {
 }
 
 pub fn if_icmpgt(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let pc = frame.pc();
     let offset = frame.read_i16().unwrap() as i32;
     let value2 = frame.operand_stack.pop_integer();
@@ -758,7 +1131,7 @@ pub fn if_icmpgt(jenv: &mut JvmEnv, class: &Class) {
 }
 
 pub fn if_icmpge(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let pc = frame.pc();
     let offset = frame.read_i16().unwrap() as i32;
     let value2 = frame.operand_stack.pop_integer();
@@ -769,7 +1142,7 @@ pub fn if_icmpge(jenv: &mut JvmEnv, class: &Class) {
 }
 
 pub fn ifeq(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let pc = frame.pc();
     let offset = frame.read_i16().unwrap() as i32;
     let value = frame.operand_stack.pop_integer();
@@ -779,7 +1152,7 @@ pub fn ifeq(jenv: &mut JvmEnv, class: &Class) {
 }
 
 pub fn ifne(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let pc = frame.pc();
     let offset = frame.read_i16().unwrap() as i32;
     let value = frame.operand_stack.pop_integer();
@@ -789,7 +1162,7 @@ pub fn ifne(jenv: &mut JvmEnv, class: &Class) {
 }
 
 pub fn ifnonnull(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let pc = frame.pc();
     let offset = frame.read_i16().unwrap() as i32;
     let value = frame.operand_stack.pop();
@@ -799,7 +1172,7 @@ pub fn ifnonnull(jenv: &mut JvmEnv, class: &Class) {
 }
 
 pub fn ifnull(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let pc = frame.pc();
     let offset = frame.read_i16().unwrap() as i32;
     let value = frame.operand_stack.pop();
@@ -808,52 +1181,70 @@ pub fn ifnull(jenv: &mut JvmEnv, class: &Class) {
     }
 }
 pub fn goto(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let pc = frame.pc();
     let offset = frame.read_i16().unwrap() as i32;
     frame.set_pc((pc as i32 - 1 + offset) as usize);
 }
 
 pub fn i2f(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let value = frame.operand_stack.pop_integer();
     frame.operand_stack.push_float(value as f32);
 }
 
 pub fn f2i(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let value = frame.operand_stack.pop_float();
     frame.operand_stack.push_integer(value as i32);
 }
 
 pub fn i2l(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let value = frame.operand_stack.pop_integer();
     frame.operand_stack.push_long(value as i64);
 }
 
 pub fn fmul(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let value2 = frame.operand_stack.pop_float();
     let value1 = frame.operand_stack.pop_float();
     frame.operand_stack.push_float(value1 * value2);
 }
 
 pub fn fcmpg(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value2 = frame.operand_stack.pop_float();
+    let value1 = frame.operand_stack.pop_float();
+    frame
+        .operand_stack
+        .push_integer(compare_floating(value1 as f64, value2 as f64, 1));
+}
+
+pub fn fcmpl(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let value2 = frame.operand_stack.pop_float();
     let value1 = frame.operand_stack.pop_float();
+    frame
+        .operand_stack
+        .push_integer(compare_floating(value1 as f64, value2 as f64, -1));
+}
+
+/// `nan_result` is what `*cmpg` (1) or `*cmpl` (-1) push when either value is NaN.
+fn compare_floating(value1: f64, value2: f64, nan_result: i32) -> i32 {
     if value1 > value2 {
-        frame.operand_stack.push_integer(1)
+        1
     } else if value1 < value2 {
-        frame.operand_stack.push_integer(-1)
+        -1
+    } else if value1 == value2 {
+        0
     } else {
-        frame.operand_stack.push_integer(0)
+        nan_result
     }
 }
 
 pub fn ldc2_w(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let offset = frame.read_u16().unwrap();
     let n = match class.constant_pool().get_const_pool_info_at(offset) {
         ConstPoolInfo::ConstantLongInfo(n) => Operand::Long(*n),
@@ -864,104 +1255,944 @@ pub fn ldc2_w(jenv: &mut JvmEnv, class: &Class) {
 }
 
 pub fn sipush(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
-    let n = frame.read_u16().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let n = frame.read_i16().unwrap();
     frame.operand_stack.push_integer(n as i32);
 }
 
 pub fn lshl(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val2 = frame.operand_stack.pop_integer();
     let val1 = frame.operand_stack.pop_long();
-    frame.operand_stack.push_long(val1 << (val2 & 0x0011_1111));
+    frame.operand_stack.push_long(val1 << (val2 & 0x3f));
 }
 
 pub fn ishl(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val2 = frame.operand_stack.pop_integer();
     let val1 = frame.operand_stack.pop_integer();
-    frame
-        .operand_stack
-        .push_integer(val1 << (val2 & 0x0011_1111));
+    frame.operand_stack.push_integer(val1 << (val2 & 0x1f));
 }
 
 pub fn iushr(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val2 = frame.operand_stack.pop_integer();
     let val1 = frame.operand_stack.pop_integer();
     frame
         .operand_stack
-        .push_integer(val1 >> (val2 & 0x0001_1111));
+        .push_integer(((val1 as u32) >> (val2 & 0x1f)) as i32);
 }
 
 pub fn ixor(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val2 = frame.operand_stack.pop_integer();
     let val1 = frame.operand_stack.pop_integer();
-    frame.operand_stack.push_integer(val1 | val2);
+    frame.operand_stack.push_integer(val1 ^ val2);
 }
 
 pub fn land(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val2 = frame.operand_stack.pop_long();
     let val1 = frame.operand_stack.pop_long();
     frame.operand_stack.push_long(val1 & val2);
 }
 
 pub fn iand(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val2 = frame.operand_stack.pop_integer();
     let val1 = frame.operand_stack.pop_integer();
     frame.operand_stack.push_integer(val1 & val2);
 }
 
 pub fn isub(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let val2 = frame.operand_stack.pop_integer();
     let val1 = frame.operand_stack.pop_integer();
-    frame.operand_stack.push_integer(val1 - val2);
+    frame.operand_stack.push_integer(val1.wrapping_sub(val2));
 }
 
 pub fn instanceof(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let index = frame.read_u16().unwrap();
     let class_name = class.constant_pool().get_class_name_at(index);
-    let class = jenv.load_and_init_class(class_name);
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let class = match jenv.resolve_class(class_name) {
+        Some(class) => class,
+        None => return,
+    };
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     let obj_ref = frame.operand_stack.pop();
     if obj_ref == Operand::Null {
         frame.operand_stack.push_integer(0);
         return;
     }
-    let obj_class_name = jenv.heap.get_class_name(&obj_ref);
+    let obj_class_name = jenv.heap().get_class_name(&obj_ref);
     let obj_class = jenv.load_and_init_class(&obj_class_name);
     let v = if can_cast_to(jenv, obj_class, class) {
         1
     } else {
         0
     };
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     frame.operand_stack.push_integer(v);
 }
 
 pub fn athrow(jenv: &mut JvmEnv, class: &Class) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let exception = frame.operand_stack.pop();
+    if !check_not_null(jenv, &exception) {
+        return;
+    }
+    debug!(?exception, "athrow");
+    jenv.thread_mut().set_pending_exception(exception);
+}
+
+pub fn monitorenter(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let object = frame.operand_stack.pop();
+    if !check_not_null(jenv, &object) {
+        return;
+    }
+    monitor_enter(jenv, &object);
+}
+
+pub fn monitorexit(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let object = frame.operand_stack.pop();
+    if !check_not_null(jenv, &object) {
+        return;
+    }
+    monitor_exit(jenv, &object);
+}
+
+pub fn dconst_n(jenv: &mut JvmEnv, class: &Class, n: f64) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push_double(n);
+}
+
+pub fn fload(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let index = frame.read_u8().unwrap();
+    let val = frame.local_variable_array.get_float(index as u16);
+    frame.operand_stack.push_float(val);
+}
+
+pub fn dload_n(jenv: &mut JvmEnv, class: &Class, n: i32) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val = frame.local_variable_array.get_double(n as u16);
+    frame.operand_stack.push_double(val);
+}
+
+pub fn dload(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let index = frame.read_u8().unwrap();
+    let val = frame.local_variable_array.get_double(index as u16);
+    frame.operand_stack.push_double(val);
+}
+
+pub fn lstore_n(jenv: &mut JvmEnv, class: &Class, n: i32) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val = frame.operand_stack.pop_long();
+    frame.local_variable_array.set_long(n as u16, val);
+}
+
+pub fn lstore(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let index = frame.read_u8().unwrap();
+    let val = frame.operand_stack.pop_long();
+    frame.local_variable_array.set_long(index as u16, val);
+}
+
+pub fn fstore_n(jenv: &mut JvmEnv, class: &Class, n: i32) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val = frame.operand_stack.pop_float();
+    frame.local_variable_array.set_float(n as u16, val);
+}
+
+pub fn fstore(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let index = frame.read_u8().unwrap();
+    let val = frame.operand_stack.pop_float();
+    frame.local_variable_array.set_float(index as u16, val);
+}
+
+pub fn dstore_n(jenv: &mut JvmEnv, class: &Class, n: i32) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val = frame.operand_stack.pop_double();
+    frame.local_variable_array.set_double(n as u16, val);
+}
+
+pub fn dstore(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let index = frame.read_u8().unwrap();
+    let val = frame.operand_stack.pop_double();
+    frame.local_variable_array.set_double(index as u16, val);
+}
+
+/// `wide` widens the local variable index of the following instruction to
+/// 16 bits, and the increment of `iinc` too.
+pub fn wide(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let code = frame.read_u8().unwrap();
     let index = frame.read_u16().unwrap();
-    let class_name = class.constant_pool().get_class_name_at(index);
-    let class = jenv.load_and_init_class(class_name);
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
-    let obj_ref = frame.operand_stack.pop();
-    if obj_ref == Operand::Null {
-        frame.operand_stack.push_integer(0);
+    let locals = &mut frame.local_variable_array;
+    match code {
+        opcode::ILOAD => {
+            let val = locals.get_integer(index);
+            frame.operand_stack.push_integer(val);
+        }
+        opcode::LLOAD => {
+            let val = locals.get_long(index);
+            frame.operand_stack.push_long(val);
+        }
+        opcode::FLOAD => {
+            let val = locals.get_float(index);
+            frame.operand_stack.push_float(val);
+        }
+        opcode::DLOAD => {
+            let val = locals.get_double(index);
+            frame.operand_stack.push_double(val);
+        }
+        opcode::ALOAD => {
+            let val = locals.get_object(index);
+            frame.operand_stack.push(val);
+        }
+        opcode::ISTORE => {
+            let val = frame.operand_stack.pop_integer();
+            locals.set_integer(index, val);
+        }
+        opcode::LSTORE => {
+            let val = frame.operand_stack.pop_long();
+            locals.set_long(index, val);
+        }
+        opcode::FSTORE => {
+            let val = frame.operand_stack.pop_float();
+            locals.set_float(index, val);
+        }
+        opcode::DSTORE => {
+            let val = frame.operand_stack.pop_double();
+            locals.set_double(index, val);
+        }
+        opcode::ASTORE => {
+            let val = frame.operand_stack.pop();
+            locals.set(index, val);
+        }
+        opcode::IINC => {
+            let amount = frame.read_i16().unwrap() as i32;
+            let locals = &mut frame.local_variable_array;
+            let val = locals.get_integer(index);
+            locals.set_integer(index, val.wrapping_add(amount));
+        }
+        opcode::RET => {
+            let pc = locals.get_return_address(index);
+            frame.set_pc(pc);
+        }
+        op => unreachable!("wide {}", opcode::show_opcode(op)),
+    }
+}
+
+pub fn baload(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let index = frame.operand_stack.pop_integer();
+    let array_ref = frame.operand_stack.pop();
+    if !check_array_index(jenv, &array_ref, index) {
         return;
     }
-    let obj_class_name = jenv.heap.get_class_name(&obj_ref);
-    let obj_class = jenv.load_and_init_class(&obj_class_name);
-    let v = if can_cast_to(jenv, obj_class, class) {
-        1
+    let value = jenv.heap_mut().get_byte_array_mut(array_ref)[index as usize] as i32;
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push_integer(value);
+}
+
+pub fn saload(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let index = frame.operand_stack.pop_integer();
+    let array_ref = frame.operand_stack.pop();
+    if !check_array_index(jenv, &array_ref, index) {
+        return;
+    }
+    let value = jenv.heap_mut().get_short_array_mut(array_ref)[index as usize] as i32;
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push_integer(value);
+}
+
+pub fn iaload(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let index = frame.operand_stack.pop_integer();
+    let array_ref = frame.operand_stack.pop();
+    if !check_array_index(jenv, &array_ref, index) {
+        return;
+    }
+    let value = jenv.heap_mut().get_int_array_mut(array_ref)[index as usize];
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push_integer(value);
+}
+
+pub fn laload(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let index = frame.operand_stack.pop_integer();
+    let array_ref = frame.operand_stack.pop();
+    if !check_array_index(jenv, &array_ref, index) {
+        return;
+    }
+    let value = jenv.heap_mut().get_long_array_mut(array_ref)[index as usize];
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push_long(value);
+}
+
+pub fn faload(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let index = frame.operand_stack.pop_integer();
+    let array_ref = frame.operand_stack.pop();
+    if !check_array_index(jenv, &array_ref, index) {
+        return;
+    }
+    let value = jenv.heap_mut().get_float_array_mut(array_ref)[index as usize];
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push_float(value);
+}
+
+pub fn daload(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let index = frame.operand_stack.pop_integer();
+    let array_ref = frame.operand_stack.pop();
+    if !check_array_index(jenv, &array_ref, index) {
+        return;
+    }
+    let value = jenv.heap_mut().get_double_array_mut(array_ref)[index as usize];
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push_double(value);
+}
+
+pub fn bastore(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val = frame.operand_stack.pop_integer();
+    let index = frame.operand_stack.pop_integer();
+    let array_ref = frame.operand_stack.pop();
+    if !check_array_index(jenv, &array_ref, index) {
+        return;
+    }
+    // boolean arrays only keep the lowest bit
+    let val = if jenv.heap().get_class_name(&array_ref) == "[Z" {
+        val & 1
     } else {
-        0
+        val
     };
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
-    frame.operand_stack.push_integer(v);
+    let array = jenv.heap_mut().get_byte_array_mut(array_ref);
+    array[index as usize] = val as i8;
+}
+
+pub fn sastore(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val = frame.operand_stack.pop_integer();
+    let index = frame.operand_stack.pop_integer();
+    let array_ref = frame.operand_stack.pop();
+    if !check_array_index(jenv, &array_ref, index) {
+        return;
+    }
+    let array = jenv.heap_mut().get_short_array_mut(array_ref);
+    array[index as usize] = val as i16;
+}
+
+pub fn iastore(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val = frame.operand_stack.pop_integer();
+    let index = frame.operand_stack.pop_integer();
+    let array_ref = frame.operand_stack.pop();
+    if !check_array_index(jenv, &array_ref, index) {
+        return;
+    }
+    let array = jenv.heap_mut().get_int_array_mut(array_ref);
+    array[index as usize] = val;
+}
+
+pub fn lastore(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val = frame.operand_stack.pop_long();
+    let index = frame.operand_stack.pop_integer();
+    let array_ref = frame.operand_stack.pop();
+    if !check_array_index(jenv, &array_ref, index) {
+        return;
+    }
+    let array = jenv.heap_mut().get_long_array_mut(array_ref);
+    array[index as usize] = val;
+}
+
+pub fn fastore(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val = frame.operand_stack.pop_float();
+    let index = frame.operand_stack.pop_integer();
+    let array_ref = frame.operand_stack.pop();
+    if !check_array_index(jenv, &array_ref, index) {
+        return;
+    }
+    let array = jenv.heap_mut().get_float_array_mut(array_ref);
+    array[index as usize] = val;
+}
+
+pub fn dastore(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val = frame.operand_stack.pop_double();
+    let index = frame.operand_stack.pop_integer();
+    let array_ref = frame.operand_stack.pop();
+    if !check_array_index(jenv, &array_ref, index) {
+        return;
+    }
+    let array = jenv.heap_mut().get_double_array_mut(array_ref);
+    array[index as usize] = val;
+}
+
+pub fn multianewarray(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let index = frame.read_u16().unwrap();
+    let dimensions = frame.read_u8().unwrap();
+    let mut counts = Vec::with_capacity(dimensions as usize);
+    for _ in 0..dimensions {
+        counts.push(frame.operand_stack.pop_integer());
+    }
+    counts.reverse();
+    for count in &counts {
+        if !check_array_size(jenv, *count) {
+            return;
+        }
+    }
+    let class_name = class.constant_pool().get_class_name_at(index);
+    // nothing else can be collected while the inner arrays are only held here
+    if !jenv.reserve_heap(multi_array_size(class_name, &counts)) {
+        return;
+    }
+    let array_ref = new_multi_array(jenv, class_name, &counts);
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(Operand::ArrayRef(array_ref))
+}
+
+/// Estimated size of all arrays `new_multi_array` allocates.
+fn multi_array_size(descriptor: &str, counts: &[i32]) -> usize {
+    let mut size = 0usize;
+    let mut arrays = 1usize;
+    for (dimension, count) in counts.iter().enumerate() {
+        let element_size = match &descriptor[dimension + 1..] {
+            "Z" => JvmHeap::element_size(T_BOOLEAN),
+            "C" => JvmHeap::element_size(T_CHAR),
+            "F" => JvmHeap::element_size(T_FLOAT),
+            "D" => JvmHeap::element_size(T_DOUBLE),
+            "B" => JvmHeap::element_size(T_BYTE),
+            "S" => JvmHeap::element_size(T_SHORT),
+            "I" => JvmHeap::element_size(T_INT),
+            "J" => JvmHeap::element_size(T_LONG),
+            _ => 8,
+        };
+        size =
+            size.saturating_add(arrays.saturating_mul(JvmHeap::array_size(element_size, *count)));
+        arrays = arrays.saturating_mul(*count as usize);
+    }
+    size
+}
+
+/// Allocates `descriptor` (an array type) with `counts[0]` elements, each of
+/// them filled recursively with the remaining dimensions.
+fn new_multi_array(jenv: &mut JvmEnv, descriptor: &str, counts: &[i32]) -> u32 {
+    let component = &descriptor[1..];
+    let count = counts[0];
+    if counts.len() > 1 {
+        let array_ref = jenv
+            .heap_mut()
+            .new_reference_array(component.to_string(), count);
+        for i in 0..count as usize {
+            let element = new_multi_array(jenv, component, &counts[1..]);
+            jenv.heap_mut()
+                .get_object_array_mut(&Operand::ArrayRef(array_ref))[i] =
+                Operand::ArrayRef(element);
+        }
+        return array_ref;
+    }
+    let atype = match component.as_bytes() {
+        [b'Z'] => T_BOOLEAN,
+        [b'C'] => T_CHAR,
+        [b'F'] => T_FLOAT,
+        [b'D'] => T_DOUBLE,
+        [b'B'] => T_BYTE,
+        [b'S'] => T_SHORT,
+        [b'I'] => T_INT,
+        [b'J'] => T_LONG,
+        [b'L', name @ .., b';'] => {
+            let name = std::str::from_utf8(name).unwrap().to_string();
+            return jenv.heap_mut().new_reference_array(name, count);
+        }
+        _ => {
+            return jenv
+                .heap_mut()
+                .new_reference_array(component.to_string(), count)
+        }
+    };
+    jenv.heap_mut().new_empty_array(atype, count)
+}
+
+pub fn pop2(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val = frame.operand_stack.pop();
+    if !val.is_category2() {
+        let _ = frame.operand_stack.pop();
+    }
+}
+
+pub fn dup_x2(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val1 = frame.operand_stack.pop();
+    let val2 = frame.operand_stack.pop();
+    if val2.is_category2() {
+        frame.operand_stack.push(val1.clone());
+        frame.operand_stack.push(val2);
+        frame.operand_stack.push(val1);
+    } else {
+        let val3 = frame.operand_stack.pop();
+        frame.operand_stack.push(val1.clone());
+        frame.operand_stack.push(val3);
+        frame.operand_stack.push(val2);
+        frame.operand_stack.push(val1);
+    }
+}
+
+pub fn dup2(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val1 = frame.operand_stack.pop();
+    if val1.is_category2() {
+        frame.operand_stack.push(val1.clone());
+        frame.operand_stack.push(val1);
+    } else {
+        let val2 = frame.operand_stack.pop();
+        frame.operand_stack.push(val2.clone());
+        frame.operand_stack.push(val1.clone());
+        frame.operand_stack.push(val2);
+        frame.operand_stack.push(val1);
+    }
+}
+
+pub fn dup2_x1(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val1 = frame.operand_stack.pop();
+    if val1.is_category2() {
+        let val2 = frame.operand_stack.pop();
+        frame.operand_stack.push(val1.clone());
+        frame.operand_stack.push(val2);
+        frame.operand_stack.push(val1);
+    } else {
+        let val2 = frame.operand_stack.pop();
+        let val3 = frame.operand_stack.pop();
+        frame.operand_stack.push(val2.clone());
+        frame.operand_stack.push(val1.clone());
+        frame.operand_stack.push(val3);
+        frame.operand_stack.push(val2);
+        frame.operand_stack.push(val1);
+    }
+}
+
+pub fn dup2_x2(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val1 = frame.operand_stack.pop();
+    let val2 = frame.operand_stack.pop();
+    match (val1.is_category2(), val2.is_category2()) {
+        // form 4: value2, value1 (both category 2)
+        (true, true) => {
+            frame.operand_stack.push(val1.clone());
+            frame.operand_stack.push(val2);
+            frame.operand_stack.push(val1);
+        }
+        // form 2: value3, value2, value1 (category 2)
+        (true, false) => {
+            let val3 = frame.operand_stack.pop();
+            frame.operand_stack.push(val1.clone());
+            frame.operand_stack.push(val3);
+            frame.operand_stack.push(val2);
+            frame.operand_stack.push(val1);
+        }
+        _ => {
+            let val3 = frame.operand_stack.pop();
+            if val3.is_category2() {
+                // form 3: value3 (category 2), value2, value1
+                frame.operand_stack.push(val2.clone());
+                frame.operand_stack.push(val1.clone());
+                frame.operand_stack.push(val3);
+            } else {
+                // form 1: value4, value3, value2, value1
+                let val4 = frame.operand_stack.pop();
+                frame.operand_stack.push(val2.clone());
+                frame.operand_stack.push(val1.clone());
+                frame.operand_stack.push(val4);
+                frame.operand_stack.push(val3);
+            }
+            frame.operand_stack.push(val2);
+            frame.operand_stack.push(val1);
+        }
+    }
+}
+
+pub fn swap(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val1 = frame.operand_stack.pop();
+    let val2 = frame.operand_stack.pop();
+    frame.operand_stack.push(val1);
+    frame.operand_stack.push(val2);
+}
+
+pub fn fadd(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value2 = frame.operand_stack.pop_float();
+    let value1 = frame.operand_stack.pop_float();
+    frame.operand_stack.push_float(value1 + value2);
+}
+
+pub fn fsub(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value2 = frame.operand_stack.pop_float();
+    let value1 = frame.operand_stack.pop_float();
+    frame.operand_stack.push_float(value1 - value2);
+}
+
+pub fn fdiv(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value2 = frame.operand_stack.pop_float();
+    let value1 = frame.operand_stack.pop_float();
+    frame.operand_stack.push_float(value1 / value2);
+}
+
+pub fn frem(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value2 = frame.operand_stack.pop_float();
+    let value1 = frame.operand_stack.pop_float();
+    frame.operand_stack.push_float(value1 % value2);
+}
+
+pub fn fneg(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value = frame.operand_stack.pop_float();
+    frame.operand_stack.push_float(-value);
+}
+
+pub fn dadd(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value2 = frame.operand_stack.pop_double();
+    let value1 = frame.operand_stack.pop_double();
+    frame.operand_stack.push_double(value1 + value2);
+}
+
+pub fn dsub(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value2 = frame.operand_stack.pop_double();
+    let value1 = frame.operand_stack.pop_double();
+    frame.operand_stack.push_double(value1 - value2);
+}
+
+pub fn dmul(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value2 = frame.operand_stack.pop_double();
+    let value1 = frame.operand_stack.pop_double();
+    frame.operand_stack.push_double(value1 * value2);
+}
+
+pub fn ddiv(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value2 = frame.operand_stack.pop_double();
+    let value1 = frame.operand_stack.pop_double();
+    frame.operand_stack.push_double(value1 / value2);
+}
+
+pub fn drem(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value2 = frame.operand_stack.pop_double();
+    let value1 = frame.operand_stack.pop_double();
+    frame.operand_stack.push_double(value1 % value2);
+}
+
+pub fn dneg(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value = frame.operand_stack.pop_double();
+    frame.operand_stack.push_double(-value);
+}
+
+pub fn dcmpg(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value2 = frame.operand_stack.pop_double();
+    let value1 = frame.operand_stack.pop_double();
+    frame
+        .operand_stack
+        .push_integer(compare_floating(value1, value2, 1));
+}
+
+pub fn dcmpl(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value2 = frame.operand_stack.pop_double();
+    let value1 = frame.operand_stack.pop_double();
+    frame
+        .operand_stack
+        .push_integer(compare_floating(value1, value2, -1));
+}
+
+pub fn imul(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val2 = frame.operand_stack.pop_integer();
+    let val1 = frame.operand_stack.pop_integer();
+    frame.operand_stack.push_integer(val1.wrapping_mul(val2));
+}
+
+pub fn idiv(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val2 = frame.operand_stack.pop_integer();
+    let val1 = frame.operand_stack.pop_integer();
+    if val2 == 0 {
+        jenv.throw_exception(JAVA_LANG_ARITHMETIC_EXCEPTION, Some("/ by zero"));
+        return;
+    }
+    frame.operand_stack.push_integer(val1.wrapping_div(val2));
+}
+
+pub fn ineg(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val = frame.operand_stack.pop_integer();
+    frame.operand_stack.push_integer(val.wrapping_neg());
+}
+
+pub fn ior(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val2 = frame.operand_stack.pop_integer();
+    let val1 = frame.operand_stack.pop_integer();
+    frame.operand_stack.push_integer(val1 | val2);
+}
+
+pub fn ishr(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val2 = frame.operand_stack.pop_integer();
+    let val1 = frame.operand_stack.pop_integer();
+    frame.operand_stack.push_integer(val1 >> (val2 & 0x1f));
+}
+
+pub fn lsub(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val2 = frame.operand_stack.pop_long();
+    let val1 = frame.operand_stack.pop_long();
+    frame.operand_stack.push_long(val1.wrapping_sub(val2));
+}
+
+pub fn lmul(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val2 = frame.operand_stack.pop_long();
+    let val1 = frame.operand_stack.pop_long();
+    frame.operand_stack.push_long(val1.wrapping_mul(val2));
+}
+
+pub fn ldiv(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val2 = frame.operand_stack.pop_long();
+    let val1 = frame.operand_stack.pop_long();
+    if val2 == 0 {
+        jenv.throw_exception(JAVA_LANG_ARITHMETIC_EXCEPTION, Some("/ by zero"));
+        return;
+    }
+    frame.operand_stack.push_long(val1.wrapping_div(val2));
+}
+
+pub fn lrem(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val2 = frame.operand_stack.pop_long();
+    let val1 = frame.operand_stack.pop_long();
+    if val2 == 0 {
+        jenv.throw_exception(JAVA_LANG_ARITHMETIC_EXCEPTION, Some("/ by zero"));
+        return;
+    }
+    frame.operand_stack.push_long(val1.wrapping_rem(val2));
+}
+
+pub fn lneg(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val = frame.operand_stack.pop_long();
+    frame.operand_stack.push_long(val.wrapping_neg());
+}
+
+pub fn lor(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val2 = frame.operand_stack.pop_long();
+    let val1 = frame.operand_stack.pop_long();
+    frame.operand_stack.push_long(val1 | val2);
+}
+
+pub fn lxor(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val2 = frame.operand_stack.pop_long();
+    let val1 = frame.operand_stack.pop_long();
+    frame.operand_stack.push_long(val1 ^ val2);
+}
+
+pub fn lshr(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val2 = frame.operand_stack.pop_integer();
+    let val1 = frame.operand_stack.pop_long();
+    frame.operand_stack.push_long(val1 >> (val2 & 0x3f));
+}
+
+pub fn lushr(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val2 = frame.operand_stack.pop_integer();
+    let val1 = frame.operand_stack.pop_long();
+    frame
+        .operand_stack
+        .push_long(((val1 as u64) >> (val2 & 0x3f)) as i64);
+}
+
+pub fn lcmp(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val2 = frame.operand_stack.pop_long();
+    let val1 = frame.operand_stack.pop_long();
+    frame.operand_stack.push_integer(val1.cmp(&val2) as i32);
+}
+
+pub fn i2b(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value = frame.operand_stack.pop_integer();
+    frame.operand_stack.push_integer(value as i8 as i32);
+}
+
+pub fn i2c(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value = frame.operand_stack.pop_integer();
+    frame.operand_stack.push_integer(value as u16 as i32);
+}
+
+pub fn i2s(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value = frame.operand_stack.pop_integer();
+    frame.operand_stack.push_integer(value as i16 as i32);
+}
+
+pub fn i2d(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value = frame.operand_stack.pop_integer();
+    frame.operand_stack.push_double(value as f64);
+}
+
+pub fn l2i(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value = frame.operand_stack.pop_long();
+    frame.operand_stack.push_integer(value as i32);
+}
+
+pub fn l2f(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value = frame.operand_stack.pop_long();
+    frame.operand_stack.push_float(value as f32);
+}
+
+pub fn l2d(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value = frame.operand_stack.pop_long();
+    frame.operand_stack.push_double(value as f64);
+}
+
+// float to integer casts in Rust saturate and turn NaN into 0, as Java does.
+
+pub fn f2l(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value = frame.operand_stack.pop_float();
+    frame.operand_stack.push_long(value as i64);
+}
+
+pub fn f2d(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value = frame.operand_stack.pop_float();
+    frame.operand_stack.push_double(value as f64);
+}
+
+pub fn d2i(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value = frame.operand_stack.pop_double();
+    frame.operand_stack.push_integer(value as i32);
+}
+
+pub fn d2l(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value = frame.operand_stack.pop_double();
+    frame.operand_stack.push_long(value as i64);
+}
+
+pub fn d2f(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value = frame.operand_stack.pop_double();
+    frame.operand_stack.push_float(value as f32);
+}
+
+pub fn lreturn(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let val = frame.operand_stack.pop_long();
+    return_value(jenv, Operand::Long(val));
+}
+
+pub fn goto_w(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let pc = frame.opcode_pc();
+    let offset = frame.read_i32().unwrap();
+    frame.set_pc((pc as i32 + offset) as usize);
+}
+
+pub fn jsr(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let pc = frame.opcode_pc();
+    let offset = frame.read_i16().unwrap() as i32;
+    frame.operand_stack.push(Operand::ReturnAddress(frame.pc()));
+    frame.set_pc((pc as i32 + offset) as usize);
+}
+
+pub fn jsr_w(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let pc = frame.opcode_pc();
+    let offset = frame.read_i32().unwrap();
+    frame.operand_stack.push(Operand::ReturnAddress(frame.pc()));
+    frame.set_pc((pc as i32 + offset) as usize);
+}
+
+pub fn ret(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let index = frame.read_u8().unwrap();
+    let pc = frame.local_variable_array.get_return_address(index as u16);
+    frame.set_pc(pc);
+}
+
+/// Operands of the switch instructions start at the next 4-byte boundary,
+/// counted from the start of the method's code.
+fn skip_switch_padding(frame: &mut JvmFrame) {
+    let operands_pc = (frame.opcode_pc() + 4) & !3;
+    frame.set_pc(operands_pc);
+}
+
+pub fn tableswitch(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let pc = frame.opcode_pc();
+    skip_switch_padding(frame);
+    let default = frame.read_i32().unwrap();
+    let low = frame.read_i32().unwrap();
+    let high = frame.read_i32().unwrap();
+    let index = frame.operand_stack.pop_integer();
+    let offset = if index < low || index > high {
+        default
+    } else {
+        let offset_pc = frame.pc() + (index as i64 - low as i64) as usize * 4;
+        frame.set_pc(offset_pc);
+        frame.read_i32().unwrap()
+    };
+    frame.set_pc((pc as i32 + offset) as usize);
+}
+
+pub fn lookupswitch(jenv: &mut JvmEnv, class: &Class) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let pc = frame.opcode_pc();
+    skip_switch_padding(frame);
+    let default = frame.read_i32().unwrap();
+    let npairs = frame.read_i32().unwrap();
+    let key = frame.operand_stack.pop_integer();
+    let mut offset = default;
+    for _ in 0..npairs {
+        let match_ = frame.read_i32().unwrap();
+        let match_offset = frame.read_i32().unwrap();
+        if match_ == key {
+            offset = match_offset;
+            break;
+        }
+    }
+    frame.set_pc((pc as i32 + offset) as usize);
 }
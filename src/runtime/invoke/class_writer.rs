@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+
+const CONSTANT_UTF8: u8 = 1;
+const CONSTANT_CLASS: u8 = 7;
+const CONSTANT_FIELDREF: u8 = 9;
+const CONSTANT_METHODREF: u8 = 10;
+const CONSTANT_INTERFACE_METHODREF: u8 = 11;
+const CONSTANT_NAME_AND_TYPE: u8 = 12;
+const CONSTANT_METHOD_HANDLE: u8 = 15;
+const CONSTANT_INVOKE_DYNAMIC: u8 = 18;
+
+const MAGIC_NUMBER: u32 = 0xCAFE_BABE;
+/// Java 8, the generated code never needs a StackMapTable.
+const MAJOR_VERSION: u16 = 52;
+
+/// Writes just enough of a class file for classes spun by the VM itself.
+pub struct ClassWriter {
+    constant_pool: Vec<u8>,
+    constant_pool_count: u16,
+    constant_pool_index: HashMap<(u8, String), u16>,
+    access_flags: u16,
+    this_class: u16,
+    super_class: u16,
+    interfaces: Vec<u16>,
+    fields: Vec<u8>,
+    fields_count: u16,
+    methods: Vec<u8>,
+    methods_count: u16,
+    bootstrap_methods: Vec<u8>,
+    bootstrap_methods_count: u16,
+}
+
+impl ClassWriter {
+    pub fn new(access_flags: u16, name: &str, super_name: &str, interfaces: &[String]) -> Self {
+        let mut writer = ClassWriter {
+            constant_pool: Vec::new(),
+            constant_pool_count: 1,
+            constant_pool_index: HashMap::new(),
+            access_flags,
+            this_class: 0,
+            super_class: 0,
+            interfaces: Vec::new(),
+            fields: Vec::new(),
+            fields_count: 0,
+            methods: Vec::new(),
+            methods_count: 0,
+            bootstrap_methods: Vec::new(),
+            bootstrap_methods_count: 0,
+        };
+        writer.this_class = writer.class(name);
+        writer.super_class = writer.class(super_name);
+        writer.interfaces = interfaces.iter().map(|i| writer.class(i)).collect();
+        writer
+    }
+
+    fn constant(&mut self, tag: u8, key: String, data: Vec<u8>) -> u16 {
+        if let Some(index) = self.constant_pool_index.get(&(tag, key.clone())) {
+            return *index;
+        }
+        let index = self.constant_pool_count;
+        self.constant_pool.push(tag);
+        self.constant_pool.extend(data);
+        self.constant_pool_count += 1;
+        self.constant_pool_index.insert((tag, key), index);
+        index
+    }
+
+    pub fn utf8(&mut self, s: &str) -> u16 {
+        let bytes = cesu8::to_java_cesu8(s);
+        let mut data = (bytes.len() as u16).to_be_bytes().to_vec();
+        data.extend(bytes.iter());
+        self.constant(CONSTANT_UTF8, s.to_string(), data)
+    }
+
+    pub fn class(&mut self, name: &str) -> u16 {
+        let name_index = self.utf8(name);
+        self.constant(
+            CONSTANT_CLASS,
+            name.to_string(),
+            name_index.to_be_bytes().to_vec(),
+        )
+    }
+
+    fn name_and_type(&mut self, name: &str, descriptor: &str) -> u16 {
+        let name_index = self.utf8(name);
+        let descriptor_index = self.utf8(descriptor);
+        let mut data = name_index.to_be_bytes().to_vec();
+        data.extend(&descriptor_index.to_be_bytes());
+        self.constant(
+            CONSTANT_NAME_AND_TYPE,
+            format!("{}:{}", name, descriptor),
+            data,
+        )
+    }
+
+    fn member_ref(&mut self, tag: u8, class_name: &str, name: &str, descriptor: &str) -> u16 {
+        let class_index = self.class(class_name);
+        let name_and_type_index = self.name_and_type(name, descriptor);
+        let mut data = class_index.to_be_bytes().to_vec();
+        data.extend(&name_and_type_index.to_be_bytes());
+        self.constant(tag, format!("{}.{}:{}", class_name, name, descriptor), data)
+    }
+
+    pub fn field_ref(&mut self, class_name: &str, name: &str, descriptor: &str) -> u16 {
+        self.member_ref(CONSTANT_FIELDREF, class_name, name, descriptor)
+    }
+
+    pub fn method_ref(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+        is_interface: bool,
+    ) -> u16 {
+        let tag = if is_interface {
+            CONSTANT_INTERFACE_METHODREF
+        } else {
+            CONSTANT_METHODREF
+        };
+        self.member_ref(tag, class_name, name, descriptor)
+    }
+
+    /// A handle of the method `name` of `class_name` invoked with `kind`, one
+    /// of the `REF_` constants.
+    pub fn method_handle(
+        &mut self,
+        kind: u8,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+        is_interface: bool,
+    ) -> u16 {
+        let reference_index = self.method_ref(class_name, name, descriptor, is_interface);
+        let mut data = vec![kind];
+        data.extend(&reference_index.to_be_bytes());
+        self.constant(
+            CONSTANT_METHOD_HANDLE,
+            format!("{}:{}", kind, reference_index),
+            data,
+        )
+    }
+
+    /// An `invokedynamic` call site of the bootstrap method at
+    /// `bootstrap_method`, see `add_bootstrap_method`.
+    pub fn invoke_dynamic(&mut self, bootstrap_method: u16, name: &str, descriptor: &str) -> u16 {
+        let name_and_type_index = self.name_and_type(name, descriptor);
+        let mut data = bootstrap_method.to_be_bytes().to_vec();
+        data.extend(&name_and_type_index.to_be_bytes());
+        self.constant(
+            CONSTANT_INVOKE_DYNAMIC,
+            format!("{}:{}:{}", bootstrap_method, name, descriptor),
+            data,
+        )
+    }
+
+    /// Adds a bootstrap method with its static arguments, all indexes of
+    /// constants, and returns its index in the `BootstrapMethods` attribute.
+    pub fn add_bootstrap_method(&mut self, method_handle: u16, arguments: &[u16]) -> u16 {
+        self.utf8("BootstrapMethods");
+        self.bootstrap_methods.extend(&method_handle.to_be_bytes());
+        self.bootstrap_methods
+            .extend(&(arguments.len() as u16).to_be_bytes());
+        for argument in arguments {
+            self.bootstrap_methods.extend(&argument.to_be_bytes());
+        }
+        self.bootstrap_methods_count += 1;
+        self.bootstrap_methods_count - 1
+    }
+
+    pub fn add_field(&mut self, access_flags: u16, name: &str, descriptor: &str) {
+        let name_index = self.utf8(name);
+        let descriptor_index = self.utf8(descriptor);
+        self.fields.extend(&access_flags.to_be_bytes());
+        self.fields.extend(&name_index.to_be_bytes());
+        self.fields.extend(&descriptor_index.to_be_bytes());
+        // attributes_count
+        self.fields.extend(&0u16.to_be_bytes());
+        self.fields_count += 1;
+    }
+
+    pub fn add_method(
+        &mut self,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+        max_stack: u16,
+        max_locals: u16,
+        code: &[u8],
+    ) {
+        let name_index = self.utf8(name);
+        let descriptor_index = self.utf8(descriptor);
+        let code_index = self.utf8("Code");
+        self.methods.extend(&access_flags.to_be_bytes());
+        self.methods.extend(&name_index.to_be_bytes());
+        self.methods.extend(&descriptor_index.to_be_bytes());
+        // a single Code attribute without exception table or attributes
+        self.methods.extend(&1u16.to_be_bytes());
+        self.methods.extend(&code_index.to_be_bytes());
+        self.methods.extend(&(12 + code.len() as u32).to_be_bytes());
+        self.methods.extend(&max_stack.to_be_bytes());
+        self.methods.extend(&max_locals.to_be_bytes());
+        self.methods.extend(&(code.len() as u32).to_be_bytes());
+        self.methods.extend(code);
+        self.methods.extend(&0u16.to_be_bytes());
+        self.methods.extend(&0u16.to_be_bytes());
+        self.methods_count += 1;
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend(&MAGIC_NUMBER.to_be_bytes());
+        bytes.extend(&0u16.to_be_bytes());
+        bytes.extend(&MAJOR_VERSION.to_be_bytes());
+        bytes.extend(&self.constant_pool_count.to_be_bytes());
+        bytes.extend(self.constant_pool);
+        bytes.extend(&self.access_flags.to_be_bytes());
+        bytes.extend(&self.this_class.to_be_bytes());
+        bytes.extend(&self.super_class.to_be_bytes());
+        bytes.extend(&(self.interfaces.len() as u16).to_be_bytes());
+        for interface in self.interfaces {
+            bytes.extend(&interface.to_be_bytes());
+        }
+        bytes.extend(&self.fields_count.to_be_bytes());
+        bytes.extend(self.fields);
+        bytes.extend(&self.methods_count.to_be_bytes());
+        bytes.extend(self.methods);
+        if self.bootstrap_methods_count == 0 {
+            // attributes_count
+            bytes.extend(&0u16.to_be_bytes());
+            return bytes;
+        }
+        let name_index = self.constant_pool_index[&(CONSTANT_UTF8, "BootstrapMethods".to_string())];
+        bytes.extend(&1u16.to_be_bytes());
+        bytes.extend(&name_index.to_be_bytes());
+        bytes.extend(&(2 + self.bootstrap_methods.len() as u32).to_be_bytes());
+        bytes.extend(&self.bootstrap_methods_count.to_be_bytes());
+        bytes.extend(self.bootstrap_methods);
+        bytes
+    }
+}
@@ -0,0 +1,308 @@
+use crate::class_parser::constant_pool::MethodHandleRef;
+use crate::class_parser::{ACC_FINAL, ACC_PRIVATE, ACC_PUBLIC, ACC_SUPER, ACC_SYNTHETIC};
+use crate::runtime::class::Class;
+use crate::runtime::frame::operand_stack::Operand;
+use crate::runtime::heap::JAVA_LANG_OBJECT;
+use crate::runtime::invoke::class_writer::ClassWriter;
+use crate::runtime::invoke::{
+    parse_method_descriptor, REF_INVOKE_INTERFACE, REF_INVOKE_SPECIAL, REF_INVOKE_STATIC,
+    REF_INVOKE_VIRTUAL, REF_NEW_INVOKE_SPECIAL,
+};
+use crate::runtime::jvm_env::JvmEnv;
+use crate::runtime::opcode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tracing::debug;
+
+static LAMBDA_CLASS_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// What `LambdaMetafactory` is asked to implement at one call site.
+pub struct LambdaSpec<'a> {
+    pub interface_method_name: &'a str,
+    /// `(captured arguments)Linterface;`
+    pub invoked_type: &'a str,
+    pub sam_method_type: &'a str,
+    pub impl_method: MethodHandleRef<'a>,
+    pub marker_interfaces: Vec<String>,
+    /// Extra descriptors the interface method must also be reachable with.
+    pub bridges: Vec<String>,
+}
+
+/// Defines a class implementing the functional interface of `spec`, like
+/// `InnerClassLambdaMetafactory` does. Captured arguments become the fields
+/// `arg$1`, `arg$2`, ... and the interface method forwards to the
+/// implementation method.
+pub fn spin_lambda_class(jenv: &mut JvmEnv, host: &Class, spec: &LambdaSpec) -> Class {
+    let (captured, interface) = parse_method_descriptor(spec.invoked_type);
+    let interface = &interface[1..interface.len() - 1];
+    let class_name = format!(
+        "{}$$Lambda${}",
+        host.name(),
+        LAMBDA_CLASS_COUNT.fetch_add(1, Ordering::SeqCst)
+    );
+    debug!(%class_name, invoked_type = spec.invoked_type, impl_method = ?spec.impl_method, "spin_lambda_class");
+
+    let mut interfaces = vec![interface.to_string()];
+    interfaces.extend(spec.marker_interfaces.iter().cloned());
+    let mut cw = ClassWriter::new(
+        ACC_FINAL | ACC_SUPER | ACC_SYNTHETIC,
+        &class_name,
+        JAVA_LANG_OBJECT,
+        &interfaces,
+    );
+    for (i, descriptor) in captured.iter().enumerate() {
+        cw.add_field(ACC_PRIVATE | ACC_FINAL, &captured_field_name(i), descriptor);
+    }
+    let mut descriptors = vec![spec.sam_method_type];
+    descriptors.extend(
+        spec.bridges
+            .iter()
+            .map(|b| b.as_str())
+            .filter(|b| *b != spec.sam_method_type),
+    );
+    for descriptor in descriptors {
+        write_forwarding_method(&mut cw, &class_name, &captured, descriptor, spec);
+    }
+
+    jenv.bootstrap_class_loader_mut()
+        .define_generated_class(&class_name, cw.into_bytes());
+    jenv.load_and_init_class(&class_name)
+}
+
+/// Allocates an instance of a class spun by `spin_lambda_class`.
+pub fn new_lambda_object(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) -> u32 {
+    let fields: Vec<_> = (0..args.len())
+        .map(|i| {
+            class
+                .instance_fields()
+                .get(&captured_field_name(i))
+                .expect("captured field")
+                .index()
+        })
+        .collect();
+    let (object, addr) = jenv.heap_mut().new_object(class.clone());
+    for (index, arg) in fields.into_iter().zip(args) {
+        object.set_field(index, arg);
+    }
+    addr
+}
+
+fn captured_field_name(i: usize) -> String {
+    format!("arg${}", i + 1)
+}
+
+fn write_forwarding_method(
+    cw: &mut ClassWriter,
+    class_name: &str,
+    captured: &[String],
+    descriptor: &str,
+    spec: &LambdaSpec,
+) {
+    let impl_method = &spec.impl_method;
+    let (params, ret) = parse_method_descriptor(descriptor);
+    let (impl_params, impl_ret) = parse_method_descriptor(impl_method.descriptor);
+    let impl_class_descriptor = format!("L{};", impl_method.class_name);
+    // the receiver of an instance method is its first argument
+    let mut target_params = Vec::with_capacity(impl_params.len() + 1);
+    if matches!(
+        impl_method.reference_kind,
+        REF_INVOKE_VIRTUAL | REF_INVOKE_SPECIAL | REF_INVOKE_INTERFACE
+    ) {
+        target_params.push(impl_class_descriptor.clone());
+    }
+    target_params.extend(impl_params.iter().cloned());
+    assert_eq!(
+        captured.len() + params.len(),
+        target_params.len(),
+        "lambda {} can't forward {} to {}",
+        class_name,
+        descriptor,
+        impl_method.descriptor
+    );
+
+    let mut code = Vec::new();
+    if impl_method.reference_kind == REF_NEW_INVOKE_SPECIAL {
+        let index = cw.class(impl_method.class_name);
+        code.push(opcode::NEW);
+        code.extend(&index.to_be_bytes());
+        code.push(opcode::DUP);
+    }
+    let mut targets = target_params.iter();
+    for (i, descriptor) in captured.iter().enumerate() {
+        let field = cw.field_ref(class_name, &captured_field_name(i), descriptor);
+        code.push(opcode::ALOAD_0);
+        code.push(opcode::GETFIELD);
+        code.extend(&field.to_be_bytes());
+        convert(cw, &mut code, descriptor, targets.next().unwrap());
+    }
+    let mut slot = 1;
+    for descriptor in &params {
+        code.push(load_opcode(descriptor));
+        code.push(slot as u8);
+        slot += slots(descriptor);
+        convert(cw, &mut code, descriptor, targets.next().unwrap());
+    }
+
+    let method_ref = cw.method_ref(
+        impl_method.class_name,
+        impl_method.name,
+        impl_method.descriptor,
+        impl_method.is_interface,
+    );
+    let invoke = match impl_method.reference_kind {
+        REF_INVOKE_VIRTUAL => opcode::INVOKEVIRTUAL,
+        REF_INVOKE_STATIC => opcode::INVOKESTATIC,
+        REF_INVOKE_SPECIAL | REF_NEW_INVOKE_SPECIAL => opcode::INVOKESPECIAL,
+        REF_INVOKE_INTERFACE => opcode::INVOKEINTERFACE,
+        kind => unreachable!("method handle kind {}", kind),
+    };
+    code.push(invoke);
+    code.extend(&method_ref.to_be_bytes());
+    if invoke == opcode::INVOKEINTERFACE {
+        let count: usize = target_params.iter().map(|p| slots(p)).sum();
+        code.push(count as u8);
+        code.push(0);
+    }
+    let impl_ret = if impl_method.reference_kind == REF_NEW_INVOKE_SPECIAL {
+        impl_class_descriptor
+    } else {
+        impl_ret
+    };
+    convert(cw, &mut code, &impl_ret, &ret);
+    code.push(return_opcode(&ret));
+
+    let target_slots: usize = target_params.iter().map(|p| slots(p)).sum();
+    // `new`/`dup` and a boxed value on top of all arguments
+    let max_stack = target_slots + 4;
+    cw.add_method(
+        ACC_PUBLIC,
+        spec.interface_method_name,
+        descriptor,
+        max_stack as u16,
+        slot as u16,
+        &code,
+    );
+}
+
+fn slots(descriptor: &str) -> usize {
+    match descriptor {
+        "J" | "D" => 2,
+        "V" => 0,
+        _ => 1,
+    }
+}
+
+fn is_primitive(descriptor: &str) -> bool {
+    descriptor.len() == 1
+}
+
+fn load_opcode(descriptor: &str) -> u8 {
+    match descriptor {
+        "Z" | "B" | "C" | "S" | "I" => opcode::ILOAD,
+        "J" => opcode::LLOAD,
+        "F" => opcode::FLOAD,
+        "D" => opcode::DLOAD,
+        _ => opcode::ALOAD,
+    }
+}
+
+fn return_opcode(descriptor: &str) -> u8 {
+    match descriptor {
+        "Z" | "B" | "C" | "S" | "I" => opcode::IRETURN,
+        "J" => opcode::LRETURN,
+        "F" => opcode::FRETURN,
+        "D" => opcode::DRETURN,
+        "V" => opcode::RETURN,
+        _ => opcode::ARETURN,
+    }
+}
+
+/// Wrapper class and unboxing method of a primitive type.
+fn wrapper(descriptor: &str) -> (&'static str, &'static str) {
+    match descriptor {
+        "Z" => ("java/lang/Boolean", "booleanValue"),
+        "B" => ("java/lang/Byte", "byteValue"),
+        "C" => ("java/lang/Character", "charValue"),
+        "S" => ("java/lang/Short", "shortValue"),
+        "I" => ("java/lang/Integer", "intValue"),
+        "J" => ("java/lang/Long", "longValue"),
+        "F" => ("java/lang/Float", "floatValue"),
+        "D" => ("java/lang/Double", "doubleValue"),
+        d => unreachable!("{}", d),
+    }
+}
+
+fn primitive_of_wrapper(descriptor: &str) -> Option<&'static str> {
+    ["Z", "B", "C", "S", "I", "J", "F", "D"]
+        .iter()
+        .find(|p| format!("L{};", wrapper(p).0) == descriptor)
+        .copied()
+}
+
+/// Emits the casts, boxing, unboxing and widening that turn a value of type
+/// `from` on top of the stack into a `to`.
+fn convert(cw: &mut ClassWriter, code: &mut Vec<u8>, from: &str, to: &str) {
+    if from == to {
+        return;
+    }
+    match (is_primitive(from), is_primitive(to)) {
+        _ if to == "V" => match slots(from) {
+            0 => {}
+            1 => code.push(opcode::POP),
+            _ => code.push(opcode::POP2),
+        },
+        (true, true) => widen(code, from, to),
+        (true, false) => {
+            let (class_name, _) = wrapper(from);
+            let method = cw.method_ref(
+                class_name,
+                "valueOf",
+                &format!("({})L{};", from, class_name),
+                false,
+            );
+            code.push(opcode::INVOKESTATIC);
+            code.extend(&method.to_be_bytes());
+        }
+        (false, true) => {
+            // unbox from the declared wrapper type if there is one, then widen
+            let primitive = primitive_of_wrapper(from).unwrap_or(to);
+            let (class_name, unbox) = wrapper(primitive);
+            if primitive_of_wrapper(from).is_none() {
+                let index = cw.class(class_name);
+                code.push(opcode::CHECKCAST);
+                code.extend(&index.to_be_bytes());
+            }
+            let method = cw.method_ref(class_name, unbox, &format!("(){}", primitive), false);
+            code.push(opcode::INVOKEVIRTUAL);
+            code.extend(&method.to_be_bytes());
+            widen(code, primitive, to);
+        }
+        (false, false) => {
+            if to == "Ljava/lang/Object;" {
+                return;
+            }
+            let class_name = if to.starts_with('L') {
+                &to[1..to.len() - 1]
+            } else {
+                to
+            };
+            let index = cw.class(class_name);
+            code.push(opcode::CHECKCAST);
+            code.extend(&index.to_be_bytes());
+        }
+    }
+}
+
+fn widen(code: &mut Vec<u8>, from: &str, to: &str) {
+    let is_int = |d: &str| matches!(d, "Z" | "B" | "C" | "S" | "I");
+    match (from, to) {
+        (f, t) if is_int(f) && is_int(t) => {}
+        (f, "J") if is_int(f) => code.push(opcode::I2L),
+        (f, "F") if is_int(f) => code.push(opcode::I2F),
+        (f, "D") if is_int(f) => code.push(opcode::I2D),
+        ("J", "F") => code.push(opcode::L2F),
+        ("J", "D") => code.push(opcode::L2D),
+        ("F", "D") => code.push(opcode::F2D),
+        (f, t) if f == t => {}
+        (f, t) => unreachable!("widen {} to {}", f, t),
+    }
+}
@@ -0,0 +1,169 @@
+pub(super) mod class_writer;
+mod lambda;
+mod string_concat;
+
+use crate::class_parser::constant_pool::{ConstPool, ConstPoolInfo};
+use crate::class_parser::descriptor::method_descriptor;
+use crate::runtime::class::Class;
+use crate::runtime::frame::operand_stack::Operand;
+use crate::runtime::heap::JAVA_LANG_BOOTSTRAP_METHOD_ERROR;
+use crate::runtime::invoke::lambda::{new_lambda_object, spin_lambda_class, LambdaSpec};
+use crate::runtime::invoke::string_concat::{concat, java_double_to_string, java_float_to_string};
+use crate::runtime::jvm_env::JvmEnv;
+use tracing::debug;
+
+pub const REF_INVOKE_VIRTUAL: u8 = 5;
+pub const REF_INVOKE_STATIC: u8 = 6;
+pub const REF_INVOKE_SPECIAL: u8 = 7;
+pub const REF_NEW_INVOKE_SPECIAL: u8 = 8;
+pub const REF_INVOKE_INTERFACE: u8 = 9;
+
+const LAMBDA_METAFACTORY: &str = "java/lang/invoke/LambdaMetafactory";
+const STRING_CONCAT_FACTORY: &str = "java/lang/invoke/StringConcatFactory";
+
+/// Flags of `LambdaMetafactory.altMetafactory`.
+const FLAG_SERIALIZABLE: i32 = 1;
+const FLAG_MARKERS: i32 = 2;
+const FLAG_BRIDGES: i32 = 4;
+
+/// A linked `invokedynamic` call site, cached per instruction.
+#[derive(Debug, Clone)]
+pub struct CallSite {
+    parameters: Vec<String>,
+    target: CallSiteTarget,
+}
+
+#[derive(Debug, Clone)]
+enum CallSiteTarget {
+    /// Allocates an instance of the spun lambda class capturing the arguments.
+    Lambda(Class),
+    /// Evaluates a `StringConcatFactory` recipe, `None` for `makeConcat`.
+    StringConcat {
+        recipe: Option<String>,
+        constants: Vec<String>,
+    },
+}
+
+/// Runs the bootstrap method of the `CONSTANT_InvokeDynamic` at `index`.
+///
+/// Only the bootstrap methods javac emits are understood, they are linked by
+/// the VM instead of running `java.lang.invoke` code. Returns `None` with a
+/// pending `BootstrapMethodError` for anything else.
+pub fn link_call_site(jenv: &mut JvmEnv, class: &Class, index: u16) -> Option<CallSite> {
+    let constant_pool = class.constant_pool();
+    let invoke_dynamic = constant_pool.get_invoke_dynamic_at(index);
+    let bootstrap_method = class.bootstrap_method(invoke_dynamic.bootstrap_method_attr_index);
+    let method_handle = constant_pool.get_method_handle_at(bootstrap_method.bootstrap_method_ref);
+    let args = &bootstrap_method.bootstrap_arguments;
+    debug!(?invoke_dynamic, ?method_handle, "link_call_site");
+
+    let (parameters, _) = parse_method_descriptor(invoke_dynamic.descriptor);
+    let target = match (method_handle.class_name, method_handle.name) {
+        (LAMBDA_METAFACTORY, "metafactory") | (LAMBDA_METAFACTORY, "altMetafactory") => {
+            let mut spec = LambdaSpec {
+                interface_method_name: invoke_dynamic.name,
+                invoked_type: invoke_dynamic.descriptor,
+                sam_method_type: constant_pool.get_method_type_at(args[0]),
+                impl_method: constant_pool.get_method_handle_at(args[1]),
+                marker_interfaces: vec![],
+                bridges: vec![],
+            };
+            if method_handle.name == "altMetafactory" {
+                let flags = constant_pool.get_constant_integer_at(args[3]);
+                let mut rest = args[4..].iter();
+                if flags & FLAG_SERIALIZABLE != 0 {
+                    spec.marker_interfaces
+                        .push("java/io/Serializable".to_string());
+                }
+                if flags & FLAG_MARKERS != 0 {
+                    let count = constant_pool.get_constant_integer_at(*rest.next().unwrap());
+                    for index in rest.by_ref().take(count as usize) {
+                        let name = constant_pool.get_class_name_at(*index);
+                        spec.marker_interfaces.push(name.clone());
+                    }
+                }
+                if flags & FLAG_BRIDGES != 0 {
+                    let count = constant_pool.get_constant_integer_at(*rest.next().unwrap());
+                    for index in rest.by_ref().take(count as usize) {
+                        spec.bridges
+                            .push(constant_pool.get_method_type_at(*index).clone());
+                    }
+                }
+            }
+            CallSiteTarget::Lambda(spin_lambda_class(jenv, class, &spec))
+        }
+        (STRING_CONCAT_FACTORY, "makeConcatWithConstants") => {
+            let recipe = constant_string_at(constant_pool, args[0]);
+            let constants = args[1..]
+                .iter()
+                .map(|index| constant_string_at(constant_pool, *index))
+                .collect();
+            CallSiteTarget::StringConcat {
+                recipe: Some(recipe),
+                constants,
+            }
+        }
+        (STRING_CONCAT_FACTORY, "makeConcat") => CallSiteTarget::StringConcat {
+            recipe: None,
+            constants: vec![],
+        },
+        (class_name, name) => {
+            let message = format!(
+                "unsupported bootstrap method {}.{}",
+                class_name.replace('/', "."),
+                name
+            );
+            jenv.throw_exception(JAVA_LANG_BOOTSTRAP_METHOD_ERROR, Some(&message));
+            return None;
+        }
+    };
+    Some(CallSite { parameters, target })
+}
+
+/// Pops the arguments of `call_site` and pushes its result.
+pub fn invoke_call_site(jenv: &mut JvmEnv, call_site: &CallSite) {
+    let n_args = call_site.parameters.len();
+    // the arguments stay on the stack while `toString()` may collect garbage
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let args = frame.operand_stack.last_n(n_args).to_vec();
+
+    let result = match &call_site.target {
+        CallSiteTarget::Lambda(class) => new_lambda_object(jenv, class, args),
+        CallSiteTarget::StringConcat { recipe, constants } => {
+            match concat(
+                jenv,
+                recipe.as_deref(),
+                constants,
+                &call_site.parameters,
+                args,
+            ) {
+                Some(addr) => addr,
+                None => return,
+            }
+        }
+    };
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    for _ in 0..n_args {
+        frame.operand_stack.pop();
+    }
+    frame.operand_stack.push(Operand::ObjectRef(result));
+}
+
+fn parse_method_descriptor(descriptor: &str) -> (Vec<String>, String) {
+    let (_, (params, ret)) = method_descriptor(descriptor).expect("parse descriptor");
+    (params, ret)
+}
+
+/// A loadable constant used as a `StringConcatFactory` argument, as text.
+fn constant_string_at(constant_pool: &ConstPool, index: u16) -> String {
+    match constant_pool.get_const_pool_info_at(index) {
+        ConstPoolInfo::ConstantStringInfo { string_index } => {
+            constant_pool.get_utf8_string_at(*string_index).clone()
+        }
+        ConstPoolInfo::ConstantIntegerInfo(n) => n.to_string(),
+        ConstPoolInfo::ConstantLongInfo(n) => n.to_string(),
+        ConstPoolInfo::ConstantFloatInfo(n) => java_float_to_string(*n),
+        ConstPoolInfo::ConstantDoubleInfo(n) => java_double_to_string(*n),
+        info => unreachable!("string concat constant {:?}", info),
+    }
+}
@@ -0,0 +1,124 @@
+use crate::runtime::execute_method;
+use crate::runtime::frame::operand_stack::Operand;
+use crate::runtime::heap::{JAVA_LANG_OBJECT, JAVA_LANG_STRING};
+use crate::runtime::jvm_env::JvmEnv;
+use std::fmt::{Display, LowerExp};
+
+/// Marks where `makeConcatWithConstants` inserts the next argument.
+const TAG_ARG: char = '\u{1}';
+/// Marks where `makeConcatWithConstants` inserts the next constant.
+const TAG_CONST: char = '\u{2}';
+
+/// Concatenates `args` following `recipe`, or just all of them for
+/// `makeConcat`. Returns the new `java.lang.String`, or `None` if a
+/// `toString()` call threw.
+pub fn concat(
+    jenv: &mut JvmEnv,
+    recipe: Option<&str>,
+    constants: &[String],
+    descriptors: &[String],
+    args: Vec<Operand>,
+) -> Option<u32> {
+    let mut args = descriptors.iter().zip(args);
+    let mut constants = constants.iter();
+    let mut result = String::new();
+    match recipe {
+        Some(recipe) => {
+            for c in recipe.chars() {
+                match c {
+                    TAG_ARG => {
+                        let (descriptor, value) = args.next().expect("concat argument");
+                        result.push_str(&stringify(jenv, descriptor, value)?);
+                    }
+                    TAG_CONST => result.push_str(constants.next().expect("concat constant")),
+                    c => result.push(c),
+                }
+            }
+        }
+        None => {
+            for (descriptor, value) in args {
+                result.push_str(&stringify(jenv, descriptor, value)?);
+            }
+        }
+    }
+    jenv.new_java_lang_string(&result)
+}
+
+/// `String.valueOf` of a value of type `descriptor`.
+fn stringify(jenv: &mut JvmEnv, descriptor: &str, value: Operand) -> Option<String> {
+    let s = match (descriptor, value) {
+        ("Z", Operand::Int(v)) => (v != 0).to_string(),
+        ("C", Operand::Int(v)) => String::from_utf16_lossy(&[v as u16]),
+        ("B", Operand::Int(v)) | ("S", Operand::Int(v)) | ("I", Operand::Int(v)) => v.to_string(),
+        ("J", Operand::Long(v)) => v.to_string(),
+        ("F", Operand::Float(v)) => java_float_to_string(v),
+        ("D", Operand::Double(v)) => java_double_to_string(v),
+        (_, Operand::Null) => "null".to_string(),
+        (_, value) => {
+            let class_name = jenv.heap().get_class_name(&value);
+            if class_name == JAVA_LANG_STRING {
+                return Some(jenv.get_java_string(&value));
+            }
+            // arrays inherit toString() from Object
+            let class_name = if class_name.starts_with('[') {
+                JAVA_LANG_OBJECT
+            } else {
+                &class_name
+            };
+            let class = jenv.load_and_init_class(class_name);
+            let method = class
+                .get_method("toString", "()Ljava/lang/String;", false)
+                .expect("toString");
+            execute_method(jenv, method, vec![value]);
+            if jenv.thread().has_pending_exception() {
+                return None;
+            }
+            let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+            match frame.operand_stack.pop() {
+                Operand::Null => "null".to_string(),
+                s => jenv.get_java_string(&s),
+            }
+        }
+    };
+    Some(s)
+}
+
+pub fn java_float_to_string(v: f32) -> String {
+    java_floating_to_string(v, v as f64)
+}
+
+pub fn java_double_to_string(v: f64) -> String {
+    java_floating_to_string(v, v)
+}
+
+/// Formats like `Float.toString`/`Double.toString`: plain decimals with at
+/// least one fraction digit in [1e-3, 1e7), computerized scientific notation
+/// outside of it.
+fn java_floating_to_string<T: Display + LowerExp>(v: T, value: f64) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    if value == 0.0 {
+        return if value.is_sign_negative() {
+            "-0.0"
+        } else {
+            "0.0"
+        }
+        .to_string();
+    }
+    if (1e-3..1e7).contains(&value.abs()) {
+        let s = v.to_string();
+        return if s.contains('.') { s } else { s + ".0" };
+    }
+    let s = format!("{:e}", v);
+    let (mantissa, exponent) = s.split_at(s.find('e').unwrap());
+    let mantissa = if mantissa.contains('.') {
+        mantissa.to_string()
+    } else {
+        format!("{}.0", mantissa)
+    };
+    format!("{}E{}", mantissa, &exponent[1..])
+}
@@ -0,0 +1,794 @@
+#![allow(non_snake_case, unused_variables)]
+//! The natives behind `java.io`: the streams over file descriptors, which
+//! `System.in`, `System.out` and `System.err` are too, `RandomAccessFile`,
+//! the file system operations of `java.io.File` and the console.
+//!
+//! Reads and writes give up the global lock, they copy the bytes between the
+//! Java array and a buffer of their own while they hold it.
+use crate::runtime::class::Class;
+use crate::runtime::frame::operand_stack::Operand;
+use crate::runtime::heap::{
+    JvmHeap, JAVA_IO_FILE_NOT_FOUND_EXCEPTION, JAVA_IO_IO_EXCEPTION, JAVA_IO_SYNC_FAILED_EXCEPTION,
+    JAVA_LANG_INDEX_OUT_OF_BOUNDS_EXCEPTION, JAVA_LANG_NULL_POINTER_EXCEPTION, JAVA_LANG_STRING,
+};
+use crate::runtime::jvm_env::JvmEnv;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+const JAVA_IO_FILE_DESCRIPTOR_DESCRIPTOR: &str = "Ljava/io/FileDescriptor;";
+
+/// The `RandomAccessFile` open modes.
+const O_RDWR: i32 = 2;
+
+/// The bits `UnixFileSystem.getBooleanAttributes0` returns.
+const BA_EXISTS: i32 = 0x01;
+const BA_REGULAR: i32 = 0x02;
+const BA_DIRECTORY: i32 = 0x04;
+
+/// The `which` of `UnixFileSystem.getSpace`.
+const SPACE_TOTAL: i32 = 0;
+const SPACE_FREE: i32 = 1;
+const SPACE_USABLE: i32 = 2;
+
+/// What a file descriptor of the VM reads from or writes to.
+#[derive(Debug, Clone)]
+pub enum Stream {
+    Stdin,
+    Stdout,
+    Stderr,
+    File(Arc<File>),
+}
+
+impl Stream {
+    fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Stdin => io::stdin().read(buf),
+            Stream::File(file) => (&**file).read(buf),
+            _ => Err(io::Error::from_raw_os_error(libc::EBADF)),
+        }
+    }
+
+    fn write_all(&self, buf: &[u8]) -> io::Result<()> {
+        match self {
+            // Java buffers on its own, what it writes is meant to show up
+            Stream::Stdout => {
+                let mut stdout = io::stdout().lock();
+                stdout.write_all(buf)?;
+                stdout.flush()
+            }
+            Stream::Stderr => io::stderr().write_all(buf),
+            Stream::File(file) => (&**file).write_all(buf),
+            Stream::Stdin => Err(io::Error::from_raw_os_error(libc::EBADF)),
+        }
+    }
+
+    /// The file, the standard streams can't seek.
+    fn file(&self) -> io::Result<&File> {
+        match self {
+            Stream::File(file) => Ok(file),
+            _ => Err(io::Error::from_raw_os_error(libc::ESPIPE)),
+        }
+    }
+}
+
+/// The open files of the VM by the number `FileDescriptor.fd` holds, which
+/// is the one of the operating system.
+#[derive(Debug)]
+pub struct FileTable {
+    streams: HashMap<i32, Stream>,
+}
+
+impl FileTable {
+    pub fn new() -> Self {
+        let streams = vec![(0, Stream::Stdin), (1, Stream::Stdout), (2, Stream::Stderr)];
+        FileTable {
+            streams: streams.into_iter().collect(),
+        }
+    }
+
+    pub fn open(&mut self, file: File) -> i32 {
+        let fd = file.as_raw_fd();
+        self.streams.insert(fd, Stream::File(Arc::new(file)));
+        fd
+    }
+
+    pub fn get(&self, fd: i32) -> Option<Stream> {
+        self.streams.get(&fd).cloned()
+    }
+
+    /// The file is closed once a read or write of another thread on it is
+    /// done too.
+    pub fn close(&mut self, fd: i32) {
+        self.streams.remove(&fd);
+    }
+}
+
+impl Default for FileTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn push(jenv: &mut JvmEnv, value: Operand) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(value);
+}
+
+/// The message of `error` without the error number, like `strerror`.
+pub fn error_message(error: &io::Error) -> String {
+    let message = error.to_string();
+    match message.find(" (os error ") {
+        Some(end) => message[..end].to_string(),
+        None => message,
+    }
+}
+
+fn throw_io_exception(jenv: &mut JvmEnv, error: &io::Error) {
+    jenv.throw_exception(JAVA_IO_IO_EXCEPTION, Some(&error_message(error)));
+}
+
+/// The `FileDescriptor` in the `fd` field of the stream `holder`.
+fn file_descriptor(jenv: &JvmEnv, holder: &Operand) -> Operand {
+    jenv.heap()
+        .get_object(holder)
+        .get_field_by_name("fd", JAVA_IO_FILE_DESCRIPTOR_DESCRIPTOR)
+        .clone()
+}
+
+fn descriptor_fd(jenv: &JvmEnv, holder: &Operand) -> i32 {
+    let descriptor = file_descriptor(jenv, holder);
+    jenv.heap()
+        .get_object(&descriptor)
+        .get_field_by_name("fd", "I")
+        .get_int()
+}
+
+fn set_descriptor_fd(jenv: &mut JvmEnv, holder: &Operand, fd: i32) {
+    let descriptor = file_descriptor(jenv, holder);
+    let descriptor = jenv.heap_mut().get_object_mut(&descriptor);
+    descriptor.set_field_by_name("fd", "I", Operand::Int(fd));
+}
+
+/// The stream of the `FileDescriptor` of `holder`, throws `IOException` if
+/// it's closed.
+fn stream(jenv: &mut JvmEnv, holder: &Operand) -> Option<Stream> {
+    let fd = descriptor_fd(jenv, holder);
+    let stream = jenv.files().get(fd);
+    if stream.is_none() {
+        jenv.throw_exception(JAVA_IO_IO_EXCEPTION, Some("Stream Closed"));
+    }
+    stream
+}
+
+/// Opens `path` with `options` for the stream `holder`, throws
+/// `FileNotFoundException` if it can't.
+fn open(jenv: &mut JvmEnv, holder: &Operand, path: &Operand, options: &OpenOptions) {
+    let path = jenv.get_java_string(path);
+    let opened = options.open(&path).and_then(|file| {
+        if file.metadata()?.is_dir() {
+            return Err(io::Error::from_raw_os_error(libc::EISDIR));
+        }
+        Ok(file)
+    });
+    match opened {
+        Ok(file) => {
+            let fd = jenv.files_mut().open(file);
+            set_descriptor_fd(jenv, holder, fd);
+        }
+        Err(error) => {
+            let message = format!("{} ({})", path, error_message(&error));
+            jenv.throw_exception(JAVA_IO_FILE_NOT_FOUND_EXCEPTION, Some(&message));
+        }
+    }
+}
+
+fn close(jenv: &mut JvmEnv, holder: &Operand) {
+    let fd = descriptor_fd(jenv, holder);
+    if fd != -1 {
+        set_descriptor_fd(jenv, holder, -1);
+        jenv.files_mut().close(fd);
+    }
+}
+
+/// Checks the `len` bytes from `off` are within the `byte[]` `array`, throws
+/// like the JDK if they aren't.
+fn check_bounds(jenv: &mut JvmEnv, array: &Operand, off: i32, len: i32) -> bool {
+    if *array == Operand::Null {
+        jenv.throw_exception(JAVA_LANG_NULL_POINTER_EXCEPTION, None);
+        return false;
+    }
+    let length = jenv.heap_mut().get_array_length(array);
+    if off < 0 || len < 0 || len > length - off {
+        jenv.throw_exception(JAVA_LANG_INDEX_OUT_OF_BOUNDS_EXCEPTION, None);
+        return false;
+    }
+    true
+}
+
+/// Reads a byte from the stream `holder` and returns it, or -1 at the end.
+fn read_byte(jenv: &mut JvmEnv, holder: &Operand) {
+    let stream = match stream(jenv, holder) {
+        Some(stream) => stream,
+        None => return,
+    };
+    let mut buf = [0];
+    match jenv.blocking(|| stream.read(&mut buf)) {
+        Ok(0) => push(jenv, Operand::Int(-1)),
+        Ok(_) => push(jenv, Operand::Int(buf[0] as i32)),
+        Err(error) => throw_io_exception(jenv, &error),
+    }
+}
+
+/// Reads up to `len` bytes from the stream `holder` into `array` from `off`
+/// and returns how many, or -1 at the end.
+fn read_bytes(jenv: &mut JvmEnv, holder: &Operand, array: &Operand, off: i32, len: i32) {
+    if !check_bounds(jenv, array, off, len) {
+        return;
+    }
+    if len == 0 {
+        return push(jenv, Operand::Int(0));
+    }
+    let stream = match stream(jenv, holder) {
+        Some(stream) => stream,
+        None => return,
+    };
+    let mut buf = vec![0; len as usize];
+    match jenv.blocking(|| stream.read(&mut buf)) {
+        Ok(0) => push(jenv, Operand::Int(-1)),
+        Ok(n) => {
+            let bytes = jenv.heap_mut().get_byte_array_mut(array.clone());
+            let off = off as usize;
+            for (dst, src) in bytes[off..off + n].iter_mut().zip(&buf) {
+                *dst = *src as i8;
+            }
+            push(jenv, Operand::Int(n as i32));
+        }
+        Err(error) => throw_io_exception(jenv, &error),
+    }
+}
+
+/// Writes the `len` bytes from `off` of `array` to the stream `holder`.
+fn write_bytes(jenv: &mut JvmEnv, holder: &Operand, array: &Operand, off: i32, len: i32) {
+    if !check_bounds(jenv, array, off, len) {
+        return;
+    }
+    let stream = match stream(jenv, holder) {
+        Some(stream) => stream,
+        None => return,
+    };
+    let off = off as usize;
+    let bytes = jenv.heap_mut().get_byte_array_mut(array.clone());
+    let buf: Vec<u8> = bytes[off..off + len as usize]
+        .iter()
+        .map(|b| *b as u8)
+        .collect();
+    if let Err(error) = jenv.blocking(|| stream.write_all(&buf)) {
+        throw_io_exception(jenv, &error);
+    }
+}
+
+fn write_byte(jenv: &mut JvmEnv, holder: &Operand, b: i32) {
+    let stream = match stream(jenv, holder) {
+        Some(stream) => stream,
+        None => return,
+    };
+    if let Err(error) = jenv.blocking(|| stream.write_all(&[b as u8])) {
+        throw_io_exception(jenv, &error);
+    }
+}
+
+/// Runs `f` on the file of the stream `holder`, throws `IOException` and
+/// returns `None` if it fails.
+fn with_file<R>(
+    jenv: &mut JvmEnv,
+    holder: &Operand,
+    f: impl FnOnce(&File) -> io::Result<R>,
+) -> Option<R> {
+    let stream = stream(jenv, holder)?;
+    match stream.file().and_then(f) {
+        Ok(value) => Some(value),
+        Err(error) => {
+            throw_io_exception(jenv, &error);
+            None
+        }
+    }
+}
+
+pub fn java_io_FileDescriptor_initIDs(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {}
+
+pub fn java_io_FileDescriptor_sync(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let fd = jenv
+        .heap()
+        .get_object(&args[0])
+        .get_field_by_name("fd", "I")
+        .get_int();
+    let synced = match jenv.files().get(fd) {
+        Some(Stream::File(file)) => jenv.blocking(|| file.sync_all()).is_ok(),
+        Some(_) => true,
+        None => false,
+    };
+    if !synced {
+        jenv.throw_exception(JAVA_IO_SYNC_FAILED_EXCEPTION, Some("sync failed"));
+    }
+}
+
+pub fn java_io_FileInputStream_initIDs(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {}
+
+pub fn java_io_FileInputStream_open0(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    open(jenv, &args[0], &args[1], OpenOptions::new().read(true));
+}
+
+pub fn java_io_FileInputStream_read0(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    read_byte(jenv, &args[0]);
+}
+
+pub fn java_io_FileInputStream_readBytes(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    read_bytes(
+        jenv,
+        &args[0],
+        &args[1],
+        args[2].get_int(),
+        args[3].get_int(),
+    );
+}
+
+/// Like the JDK it seeks, so skipping on a pipe or terminal fails.
+pub fn java_io_FileInputStream_skip(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let n = args[1].get_long();
+    let skipped = with_file(jenv, &args[0], |mut file| {
+        let current = file.stream_position()?;
+        Ok(file.seek(SeekFrom::Current(n))? as i64 - current as i64)
+    });
+    if let Some(skipped) = skipped {
+        push(jenv, Operand::Long(skipped));
+    }
+}
+
+/// The bytes left in a regular file, or those buffered by a pipe or terminal.
+pub fn java_io_FileInputStream_available(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let stream = match stream(jenv, &args[0]) {
+        Some(stream) => stream,
+        None => return,
+    };
+    let available = match &stream {
+        Stream::File(file) => (|| {
+            let mut file = &**file;
+            let metadata = file.metadata()?;
+            if !metadata.is_file() {
+                return Ok(0);
+            }
+            Ok(metadata.len().saturating_sub(file.stream_position()?))
+        })(),
+        Stream::Stdin => {
+            let mut n: libc::c_int = 0;
+            // SAFETY: FIONREAD stores an int through the pointer
+            match unsafe { libc::ioctl(libc::STDIN_FILENO, libc::FIONREAD, &mut n) } {
+                0 => Ok(n as u64),
+                _ => Ok(0),
+            }
+        }
+        _ => Ok(0),
+    };
+    match available {
+        Ok(n) => push(jenv, Operand::Int(n.min(i32::MAX as u64) as i32)),
+        Err(error) => throw_io_exception(jenv, &error),
+    }
+}
+
+pub fn java_io_FileInputStream_close0(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    close(jenv, &args[0]);
+}
+
+pub fn java_io_FileOutputStream_initIDs(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {}
+
+pub fn java_io_FileOutputStream_open0(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let append = args[2].get_int() != 0;
+    let mut options = OpenOptions::new();
+    options.write(true).create(true);
+    if append {
+        options.append(true);
+    } else {
+        options.truncate(true);
+    }
+    open(jenv, &args[0], &args[1], &options);
+}
+
+/// Files opened to append are opened with `O_APPEND`, so `append` isn't
+/// needed.
+pub fn java_io_FileOutputStream_write(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    write_byte(jenv, &args[0], args[1].get_int());
+}
+
+pub fn java_io_FileOutputStream_writeBytes(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    write_bytes(
+        jenv,
+        &args[0],
+        &args[1],
+        args[2].get_int(),
+        args[3].get_int(),
+    );
+}
+
+pub fn java_io_FileOutputStream_close0(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    close(jenv, &args[0]);
+}
+
+pub fn java_io_RandomAccessFile_initIDs(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {}
+
+/// `O_SYNC` and `O_DSYNC` aren't passed on, the writes aren't synchronous.
+pub fn java_io_RandomAccessFile_open0(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let mode = args[2].get_int();
+    let mut options = OpenOptions::new();
+    options.read(true);
+    if mode & O_RDWR != 0 {
+        options.write(true).create(true);
+    }
+    open(jenv, &args[0], &args[1], &options);
+}
+
+pub fn java_io_RandomAccessFile_read0(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    read_byte(jenv, &args[0]);
+}
+
+pub fn java_io_RandomAccessFile_readBytes(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    read_bytes(
+        jenv,
+        &args[0],
+        &args[1],
+        args[2].get_int(),
+        args[3].get_int(),
+    );
+}
+
+pub fn java_io_RandomAccessFile_write0(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    write_byte(jenv, &args[0], args[1].get_int());
+}
+
+pub fn java_io_RandomAccessFile_writeBytes(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    write_bytes(
+        jenv,
+        &args[0],
+        &args[1],
+        args[2].get_int(),
+        args[3].get_int(),
+    );
+}
+
+pub fn java_io_RandomAccessFile_getFilePointer(
+    jenv: &mut JvmEnv,
+    _class: &Class,
+    args: Vec<Operand>,
+) {
+    if let Some(pos) = with_file(jenv, &args[0], |mut file| file.stream_position()) {
+        push(jenv, Operand::Long(pos as i64));
+    }
+}
+
+/// `RandomAccessFile.seek` checks the position isn't negative.
+pub fn java_io_RandomAccessFile_seek0(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let pos = args[1].get_long();
+    with_file(jenv, &args[0], |mut file| {
+        file.seek(SeekFrom::Start(pos as u64))
+    });
+}
+
+pub fn java_io_RandomAccessFile_length(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    if let Some(len) = with_file(jenv, &args[0], |file| Ok(file.metadata()?.len())) {
+        push(jenv, Operand::Long(len as i64));
+    }
+}
+
+/// Truncating moves the file pointer back to the new end if it was past it.
+pub fn java_io_RandomAccessFile_setLength(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let len = args[1].get_long();
+    with_file(jenv, &args[0], |mut file| {
+        let pos = file.stream_position()?;
+        file.set_len(len as u64)?;
+        if pos > len as u64 {
+            file.seek(SeekFrom::Start(len as u64))?;
+        }
+        Ok(())
+    });
+}
+
+pub fn java_io_RandomAccessFile_close0(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    close(jenv, &args[0]);
+}
+
+/// The `path` of the `java.io.File` `file`.
+fn file_path(jenv: &mut JvmEnv, file: &Operand) -> PathBuf {
+    let path = jenv
+        .heap()
+        .get_object(file)
+        .get_field_by_name("path", "Ljava/lang/String;")
+        .clone();
+    PathBuf::from(jenv.get_java_string(&path))
+}
+
+fn c_path(path: &Path) -> CString {
+    CString::new(path.as_os_str().as_bytes()).unwrap_or_default()
+}
+
+/// `path` with the `.` and `..` resolved without following symbolic links.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Like `fs::canonicalize`, but for paths that don't exist too: the longest
+/// prefix that does is resolved and the rest appended.
+fn canonicalize(path: &Path) -> PathBuf {
+    if let Ok(canonical) = fs::canonicalize(path) {
+        return canonical;
+    }
+    let path = normalize(path);
+    let mut prefix = path.as_path();
+    while let Some(parent) = prefix.parent() {
+        prefix = parent;
+        if let Ok(canonical) = fs::canonicalize(prefix) {
+            return canonical.join(path.strip_prefix(prefix).unwrap());
+        }
+    }
+    path
+}
+
+fn push_boolean(jenv: &mut JvmEnv, value: bool) {
+    push(jenv, Operand::Int(value as i32));
+}
+
+pub fn java_io_UnixFileSystem_initIDs(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {}
+
+pub fn java_io_UnixFileSystem_canonicalize0(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let path = jenv.get_java_string(&args[1]);
+    let canonical = canonicalize(Path::new(&path));
+    if let Some(addr) = jenv.new_java_lang_string(&canonical.to_string_lossy()) {
+        push(jenv, Operand::ObjectRef(addr));
+    }
+}
+
+/// Whether the file exists and is a regular file or a directory, whether it's
+/// hidden is decided by `UnixFileSystem` from its name.
+pub fn java_io_UnixFileSystem_getBooleanAttributes0(
+    jenv: &mut JvmEnv,
+    _class: &Class,
+    args: Vec<Operand>,
+) {
+    let path = file_path(jenv, &args[1]);
+    let attributes = match fs::metadata(path) {
+        Ok(metadata) => {
+            let mut attributes = BA_EXISTS;
+            if metadata.is_file() {
+                attributes |= BA_REGULAR;
+            }
+            if metadata.is_dir() {
+                attributes |= BA_DIRECTORY;
+            }
+            attributes
+        }
+        Err(_) => 0,
+    };
+    push(jenv, Operand::Int(attributes));
+}
+
+/// The `ACCESS_READ`, `ACCESS_WRITE` and `ACCESS_EXECUTE` of `FileSystem`
+/// are the bits of `access(2)`.
+pub fn java_io_UnixFileSystem_checkAccess(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let path = c_path(&file_path(jenv, &args[1]));
+    let mode = args[2].get_int();
+    // SAFETY: the path is a valid C string
+    let accessible = unsafe { libc::access(path.as_ptr(), mode) } == 0;
+    push_boolean(jenv, accessible);
+}
+
+pub fn java_io_UnixFileSystem_getLastModifiedTime(
+    jenv: &mut JvmEnv,
+    _class: &Class,
+    args: Vec<Operand>,
+) {
+    let path = file_path(jenv, &args[1]);
+    let millis = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_millis() as i64);
+    push(jenv, Operand::Long(millis));
+}
+
+pub fn java_io_UnixFileSystem_getLength(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let path = file_path(jenv, &args[1]);
+    let len = fs::metadata(path).map_or(0, |metadata| metadata.len() as i64);
+    push(jenv, Operand::Long(len));
+}
+
+/// Sets or clears the `access` bits of the owner, or of everybody.
+pub fn java_io_UnixFileSystem_setPermission(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let path = file_path(jenv, &args[1]);
+    let access = args[2].get_int() as u32;
+    let enable = args[3].get_int() != 0;
+    let owner_only = args[4].get_int() != 0;
+    let bits = if owner_only {
+        access << 6
+    } else {
+        access * 0o111
+    };
+    let set = fs::metadata(&path).and_then(|metadata| {
+        let mut permissions = metadata.permissions();
+        let mode = permissions.mode();
+        permissions.set_mode(if enable { mode | bits } else { mode & !bits });
+        fs::set_permissions(&path, permissions)
+    });
+    push_boolean(jenv, set.is_ok());
+}
+
+/// Returns false if the file exists already.
+pub fn java_io_UnixFileSystem_createFileExclusively(
+    jenv: &mut JvmEnv,
+    _class: &Class,
+    args: Vec<Operand>,
+) {
+    let path = jenv.get_java_string(&args[1]);
+    let created = OpenOptions::new().write(true).create_new(true).open(path);
+    match created {
+        Ok(_) => push_boolean(jenv, true),
+        Err(error) if error.kind() == io::ErrorKind::AlreadyExists => push_boolean(jenv, false),
+        Err(error) => throw_io_exception(jenv, &error),
+    }
+}
+
+pub fn java_io_UnixFileSystem_delete0(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let path = file_path(jenv, &args[1]);
+    let deleted = fs::symlink_metadata(&path).and_then(|metadata| {
+        if metadata.is_dir() {
+            fs::remove_dir(&path)
+        } else {
+            fs::remove_file(&path)
+        }
+    });
+    push_boolean(jenv, deleted.is_ok());
+}
+
+/// The names of the entries of the directory, null if it can't be read.
+pub fn java_io_UnixFileSystem_list(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let path = file_path(jenv, &args[1]);
+    let names: io::Result<Vec<_>> = fs::read_dir(path).and_then(|entries| {
+        entries
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect()
+    });
+    let names = match names {
+        Ok(names) => names,
+        Err(_) => return push(jenv, Operand::Null),
+    };
+    let count = names.len() as i32;
+    if !jenv.reserve_heap(JvmHeap::reference_array_size(count)) {
+        return;
+    }
+    let array = Operand::ArrayRef(
+        jenv.heap_mut()
+            .new_reference_array(JAVA_LANG_STRING.to_string(), count),
+    );
+    // the array keeps the names alive while the next ones are allocated
+    jenv.thread_mut().native_args.push(vec![array.clone()]);
+    for (i, name) in names.iter().enumerate() {
+        match jenv.new_java_lang_string(name) {
+            Some(addr) => {
+                jenv.heap_mut().get_object_array_mut(&array)[i] = Operand::ObjectRef(addr)
+            }
+            None => break,
+        }
+    }
+    jenv.thread_mut().native_args.pop();
+    if !jenv.thread().has_pending_exception() {
+        push(jenv, array);
+    }
+}
+
+pub fn java_io_UnixFileSystem_createDirectory(
+    jenv: &mut JvmEnv,
+    _class: &Class,
+    args: Vec<Operand>,
+) {
+    let path = file_path(jenv, &args[1]);
+    push_boolean(jenv, fs::create_dir(path).is_ok());
+}
+
+pub fn java_io_UnixFileSystem_rename0(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let from = file_path(jenv, &args[1]);
+    let to = file_path(jenv, &args[2]);
+    push_boolean(jenv, fs::rename(from, to).is_ok());
+}
+
+pub fn java_io_UnixFileSystem_setLastModifiedTime(
+    jenv: &mut JvmEnv,
+    _class: &Class,
+    args: Vec<Operand>,
+) {
+    let path = file_path(jenv, &args[1]);
+    let time = UNIX_EPOCH + Duration::from_millis(args[2].get_long() as u64);
+    let set = File::open(path).and_then(|file| file.set_modified(time));
+    push_boolean(jenv, set.is_ok());
+}
+
+pub fn java_io_UnixFileSystem_setReadOnly(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let path = file_path(jenv, &args[1]);
+    let set = fs::metadata(&path).and_then(|metadata| {
+        let mut permissions = metadata.permissions();
+        permissions.set_mode(permissions.mode() & !0o222);
+        fs::set_permissions(&path, permissions)
+    });
+    push_boolean(jenv, set.is_ok());
+}
+
+/// The total, free or usable bytes of the file system of the file, zero if it
+/// can't be told.
+pub fn java_io_UnixFileSystem_getSpace(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let path = c_path(&file_path(jenv, &args[1]));
+    let which = args[2].get_int();
+    // SAFETY: all zeroes is a valid statvfs, which the call fills in
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    let space = match unsafe { libc::statvfs(path.as_ptr(), &mut stat) } {
+        0 => {
+            let blocks = match which {
+                SPACE_TOTAL => stat.f_blocks,
+                SPACE_FREE => stat.f_bfree,
+                SPACE_USABLE => stat.f_bavail,
+                _ => 0,
+            };
+            (blocks as u64 * stat.f_frsize as u64) as i64
+        }
+        _ => 0,
+    };
+    push(jenv, Operand::Long(space));
+}
+
+/// The charset of the console, null for the default one.
+pub fn java_io_Console_encoding(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    push(jenv, Operand::Null);
+}
+
+/// Turns echoing of the terminal on standard input on or off and returns
+/// whether it was on.
+pub fn java_io_Console_echo(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let on = args[0].get_int() != 0;
+    // SAFETY: all zeroes is a valid termios, which tcgetattr fills in
+    let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut termios) } != 0 {
+        return throw_io_exception(jenv, &io::Error::last_os_error());
+    }
+    let was_on = termios.c_lflag & libc::ECHO != 0;
+    if on {
+        termios.c_lflag |= libc::ECHO;
+    } else {
+        termios.c_lflag &= !libc::ECHO;
+    }
+    if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &termios) } != 0 {
+        return throw_io_exception(jenv, &io::Error::last_os_error());
+    }
+    push_boolean(jenv, was_on);
+}
+
+/// Whether both standard input and output are a terminal, there's no console
+/// otherwise.
+pub fn java_io_Console_istty(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    // SAFETY: isatty only looks at the descriptor
+    let istty =
+        unsafe { libc::isatty(libc::STDIN_FILENO) == 1 && libc::isatty(libc::STDOUT_FILENO) == 1 };
+    push_boolean(jenv, istty);
+}
@@ -0,0 +1,1328 @@
+//! The functions of the `JNIEnv` and `JavaVM` tables.
+//!
+//! Each function takes the global lock for as long as it uses the VM. An
+//! exception is left pending for the native method to check, the function
+//! returns zero or null then.
+//!
+//! C can't call Rust with variable arguments, so the `Call<Type>Method`
+//! functions taking `...` or a `va_list` are in `varargs.c`. They read the
+//! arguments into a `jvalue` array and call the `A` function here.
+use crate::class_parser::{ACC_ABSTRACT, ACC_INTERFACE};
+use crate::runtime::class::Class;
+use crate::runtime::execute_method;
+use crate::runtime::frame::operand_stack::Operand;
+use crate::runtime::heap::{
+    JvmHeap, JAVA_LANG_ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION, JAVA_LANG_ARRAY_STORE_EXCEPTION,
+    JAVA_LANG_INSTANTIATION_EXCEPTION, JAVA_LANG_NEGATIVE_ARRAY_SIZE_EXCEPTION,
+    JAVA_LANG_NO_SUCH_FIELD_ERROR, JAVA_LANG_NO_SUCH_METHOD_ERROR,
+    JAVA_LANG_NULL_POINTER_EXCEPTION, JAVA_LANG_STRING_INDEX_OUT_OF_BOUNDS_EXCEPTION, T_BOOLEAN,
+    T_BYTE, T_CHAR, T_DOUBLE, T_FLOAT, T_INT, T_LONG, T_SHORT,
+};
+use crate::runtime::instruction::{can_cast_to, select_method, virtual_call};
+use crate::runtime::jni::*;
+use crate::runtime::jvm_env::JvmEnv;
+use crate::runtime::mirror::MirroredType;
+use crate::runtime::monitor::{monitor_enter, monitor_exit};
+use crate::runtime::report_uncaught_exception;
+use std::ffi::{CStr, CString};
+use std::ops::{Deref, DerefMut};
+use std::os::raw::c_char;
+use std::{process, ptr};
+
+/// The slots of the `JNIEnv` table up to `GetModule`, the last one of JNI 9.
+const FUNCTION_COUNT: usize = 234;
+
+/// Where the `Call<Type>Method`, `CallNonvirtual<Type>Method` and
+/// `CallStatic<Type>Method` families start in the table. Each has a plain, a
+/// `V` and an `A` function for every type.
+const CALL_FUNCTIONS: [usize; 3] = [34, 64, 114];
+
+/// `JNIInvalidRefType`, `JNILocalRefType` and `JNIGlobalRefType`.
+const INVALID_REF_TYPE: jint = 0;
+const LOCAL_REF_TYPE: jint = 1;
+const GLOBAL_REF_TYPE: jint = 2;
+
+pub struct FunctionTable([*const c_void; FUNCTION_COUNT]);
+
+// SAFETY: it only holds the addresses of functions
+unsafe impl Sync for FunctionTable {}
+
+lazy_static::lazy_static! {
+    static ref FUNCTIONS: FunctionTable = new_function_table();
+}
+
+pub fn function_table() -> *const FunctionTable {
+    &*FUNCTIONS
+}
+
+#[repr(C)]
+pub struct JavaVm {
+    functions: *const InvokeInterface,
+}
+
+#[repr(C)]
+struct InvokeInterface {
+    reserved: [*const c_void; 3],
+    DestroyJavaVM: unsafe extern "C" fn(*mut JavaVm) -> jint,
+    AttachCurrentThread: unsafe extern "C" fn(*mut JavaVm, *mut *mut c_void, *mut c_void) -> jint,
+    DetachCurrentThread: unsafe extern "C" fn(*mut JavaVm) -> jint,
+    GetEnv: unsafe extern "C" fn(*mut JavaVm, *mut *mut c_void, jint) -> jint,
+    AttachCurrentThreadAsDaemon:
+        unsafe extern "C" fn(*mut JavaVm, *mut *mut c_void, *mut c_void) -> jint,
+}
+
+// SAFETY: both only hold the addresses of functions and never change
+unsafe impl Sync for JavaVm {}
+unsafe impl Sync for InvokeInterface {}
+
+static INVOKE_INTERFACE: InvokeInterface = InvokeInterface {
+    reserved: [ptr::null(); 3],
+    DestroyJavaVM,
+    AttachCurrentThread,
+    DetachCurrentThread,
+    GetEnv,
+    AttachCurrentThreadAsDaemon: AttachCurrentThread,
+};
+
+static JAVA_VM: JavaVm = JavaVm {
+    functions: &INVOKE_INTERFACE,
+};
+
+/// The only `JavaVM`, all threads share it.
+pub fn java_vm() -> *mut JavaVm {
+    &JAVA_VM as *const JavaVm as *mut JavaVm
+}
+
+#[repr(C)]
+struct JNINativeMethod {
+    name: *const c_char,
+    signature: *const c_char,
+    fn_ptr: *mut c_void,
+}
+
+extern "C" {
+    /// The plain and the `V` functions of the `Call` families by family and
+    /// type, in the order of the table.
+    static hippo_jni_call_varargs: [[[*const c_void; 2]; 10]; 3];
+    /// `NewObject` and `NewObjectV`.
+    static hippo_jni_new_object_varargs: [*const c_void; 2];
+}
+
+/// The VM of the thread a JNI function is called on, with the global lock
+/// held until it is dropped.
+struct Vm(*mut JvmEnv);
+
+impl Vm {
+    unsafe fn enter(env: *mut JniEnv) -> Vm {
+        let jenv = (*env).jenv;
+        (*jenv).acquire_global_lock();
+        Vm(jenv)
+    }
+}
+
+impl Drop for Vm {
+    fn drop(&mut self) {
+        // SAFETY: the environment outlives the native method
+        unsafe { (*self.0).release_global_lock() }
+    }
+}
+
+impl Deref for Vm {
+    type Target = JvmEnv;
+
+    fn deref(&self) -> &JvmEnv {
+        // SAFETY: the caller of the native method doesn't use the
+        // environment until it returns
+        unsafe { &*self.0 }
+    }
+}
+
+impl DerefMut for Vm {
+    fn deref_mut(&mut self) -> &mut JvmEnv {
+        // SAFETY: see `deref`
+        unsafe { &mut *self.0 }
+    }
+}
+
+/// The C types of Java values, `()` for `void`.
+trait JniType {
+    /// The first character of the descriptor of the Java type.
+    const DESCRIPTOR: u8;
+
+    fn from_jvalue(value: jvalue) -> Self;
+
+    fn into_jvalue(self) -> jvalue;
+
+    fn from_operand(jenv: &mut JvmEnv, value: Operand) -> Self
+    where
+        Self: Sized,
+    {
+        Self::from_jvalue(to_jvalue(jenv, Self::DESCRIPTOR, value))
+    }
+
+    fn into_operand(self, jenv: &JvmEnv) -> Operand
+    where
+        Self: Sized,
+    {
+        from_jvalue(jenv, Self::DESCRIPTOR, self.into_jvalue())
+    }
+}
+
+/// The C types of the elements of primitive arrays.
+trait JniPrimitive: JniType + Copy {
+    /// The `atype` of `newarray`.
+    const ARRAY_TYPE: u8;
+}
+
+macro_rules! jni_types {
+    ($($ty:ty, $member:ident, $descriptor:expr, $array_type:expr;)*) => {$(
+        impl JniType for $ty {
+            const DESCRIPTOR: u8 = $descriptor;
+
+            fn from_jvalue(value: jvalue) -> Self {
+                // SAFETY: every member is plain data
+                unsafe { value.$member }
+            }
+
+            fn into_jvalue(self) -> jvalue {
+                jvalue { $member: self }
+            }
+        }
+
+        impl JniPrimitive for $ty {
+            const ARRAY_TYPE: u8 = $array_type;
+        }
+    )*};
+}
+
+jni_types! {
+    jboolean, z, b'Z', T_BOOLEAN;
+    jbyte, b, b'B', T_BYTE;
+    jchar, c, b'C', T_CHAR;
+    jshort, s, b'S', T_SHORT;
+    jint, i, b'I', T_INT;
+    jlong, j, b'J', T_LONG;
+    jfloat, f, b'F', T_FLOAT;
+    jdouble, d, b'D', T_DOUBLE;
+}
+
+impl JniType for jobject {
+    const DESCRIPTOR: u8 = b'L';
+
+    fn from_jvalue(value: jvalue) -> Self {
+        // SAFETY: every member is plain data
+        unsafe { value.l }
+    }
+
+    fn into_jvalue(self) -> jvalue {
+        jvalue { l: self }
+    }
+}
+
+impl JniType for () {
+    const DESCRIPTOR: u8 = b'V';
+
+    fn from_jvalue(_value: jvalue) -> Self {}
+
+    fn into_jvalue(self) -> jvalue {
+        jvalue { j: 0 }
+    }
+}
+
+/// The value of type `T` native code gets when a function fails.
+fn zero<T: JniType>() -> T {
+    T::from_jvalue(jvalue { j: 0 })
+}
+
+/// A string in modified UTF-8 from native code.
+unsafe fn utf_string(s: *const c_char) -> String {
+    let bytes = CStr::from_ptr(s).to_bytes();
+    match cesu8::from_java_cesu8(bytes) {
+        Ok(s) => s.into_owned(),
+        Err(_) => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// The class the `java.lang.Class` `clazz` refers to.
+fn class_of(jenv: &JvmEnv, clazz: jclass) -> Class {
+    match jenv.mirrored_type(&referent(jenv, clazz)) {
+        MirroredType::Class(class) => class,
+        ty => unreachable!("{:?}", ty),
+    }
+}
+
+fn class_of_object(jenv: &mut JvmEnv, obj: &Operand) -> Class {
+    let class_name = jenv.heap().get_class_name(obj);
+    jenv.load_class(&class_name)
+}
+
+/// The `char[]` holding the value of the string `string`.
+fn string_value(jenv: &JvmEnv, string: jstring) -> Operand {
+    let string = referent(jenv, string);
+    jenv.heap()
+        .get_object(&string)
+        .get_field_by_name("value", "[C")
+        .clone()
+}
+
+fn new_local_mirror(jenv: &mut JvmEnv, class: &Class) -> jclass {
+    let mirror = jenv.class_mirror(class);
+    local_ref(jenv, Operand::ObjectRef(mirror))
+}
+
+/// Throws an `ArrayIndexOutOfBoundsException` or `exception` unless `start`
+/// and `len` are a range within `length` elements.
+fn check_range(
+    jenv: &mut JvmEnv,
+    start: jsize,
+    len: jsize,
+    length: jsize,
+    exception: &str,
+) -> bool {
+    if start < 0 || len < 0 || start as i64 + len as i64 > length as i64 {
+        jenv.throw_exception(exception, None);
+        return false;
+    }
+    true
+}
+
+fn new_function_table() -> FunctionTable {
+    macro_rules! for_field_types {
+        ($function:ident) => {
+            [
+                $function::<jobject> as *const c_void,
+                $function::<jboolean> as *const c_void,
+                $function::<jbyte> as *const c_void,
+                $function::<jchar> as *const c_void,
+                $function::<jshort> as *const c_void,
+                $function::<jint> as *const c_void,
+                $function::<jlong> as *const c_void,
+                $function::<jfloat> as *const c_void,
+                $function::<jdouble> as *const c_void,
+            ]
+        };
+    }
+    macro_rules! for_primitive_types {
+        ($function:ident) => {
+            [
+                $function::<jboolean> as *const c_void,
+                $function::<jbyte> as *const c_void,
+                $function::<jchar> as *const c_void,
+                $function::<jshort> as *const c_void,
+                $function::<jint> as *const c_void,
+                $function::<jlong> as *const c_void,
+                $function::<jfloat> as *const c_void,
+                $function::<jdouble> as *const c_void,
+            ]
+        };
+    }
+
+    let mut table = [unsupported as *const c_void; FUNCTION_COUNT];
+    // reserved
+    table[..4].copy_from_slice(&[ptr::null(); 4]);
+    table[4] = GetVersion as *const c_void;
+    table[6] = FindClass as *const c_void;
+    table[10] = GetSuperclass as *const c_void;
+    table[11] = IsAssignableFrom as *const c_void;
+    table[13] = Throw as *const c_void;
+    table[14] = ThrowNew as *const c_void;
+    table[15] = ExceptionOccurred as *const c_void;
+    table[16] = ExceptionDescribe as *const c_void;
+    table[17] = ExceptionClear as *const c_void;
+    table[18] = FatalError as *const c_void;
+    table[19] = PushLocalFrame as *const c_void;
+    table[20] = PopLocalFrame as *const c_void;
+    table[21] = NewGlobalRef as *const c_void;
+    table[22] = DeleteGlobalRef as *const c_void;
+    table[23] = DeleteLocalRef as *const c_void;
+    table[24] = IsSameObject as *const c_void;
+    table[25] = NewLocalRef as *const c_void;
+    table[26] = EnsureLocalCapacity as *const c_void;
+    table[27] = AllocObject as *const c_void;
+    // SAFETY: defined in `varargs.c`
+    table[28..30].copy_from_slice(unsafe { &hippo_jni_new_object_varargs });
+    table[30] = hippo_jni_NewObjectA as *const c_void;
+    table[31] = GetObjectClass as *const c_void;
+    table[32] = IsInstanceOf as *const c_void;
+    table[33] = GetMethodID as *const c_void;
+    let call_functions = call_functions();
+    for (family, start) in CALL_FUNCTIONS.iter().enumerate() {
+        for ty in 0..10 {
+            let slot = start + 3 * ty;
+            // SAFETY: defined in `varargs.c`
+            table[slot..slot + 2].copy_from_slice(unsafe { &hippo_jni_call_varargs[family][ty] });
+            table[slot + 2] = call_functions[family][ty];
+        }
+    }
+    table[94] = GetFieldID as *const c_void;
+    table[95..104].copy_from_slice(&for_field_types!(GetField));
+    table[104..113].copy_from_slice(&for_field_types!(SetField));
+    table[113] = GetStaticMethodID as *const c_void;
+    table[144] = GetStaticFieldID as *const c_void;
+    table[145..154].copy_from_slice(&for_field_types!(GetStaticField));
+    table[154..163].copy_from_slice(&for_field_types!(SetStaticField));
+    table[163] = NewString as *const c_void;
+    table[164] = GetStringLength as *const c_void;
+    table[165] = GetStringChars as *const c_void;
+    table[166] = ReleaseStringChars as *const c_void;
+    table[167] = NewStringUTF as *const c_void;
+    table[168] = GetStringUTFLength as *const c_void;
+    table[169] = GetStringUTFChars as *const c_void;
+    table[170] = ReleaseStringUTFChars as *const c_void;
+    table[171] = GetArrayLength as *const c_void;
+    table[172] = NewObjectArray as *const c_void;
+    table[173] = GetObjectArrayElement as *const c_void;
+    table[174] = SetObjectArrayElement as *const c_void;
+    table[175..183].copy_from_slice(&for_primitive_types!(NewPrimitiveArray));
+    table[183..191].copy_from_slice(&for_primitive_types!(GetArrayElements));
+    table[191..199].copy_from_slice(&for_primitive_types!(ReleaseArrayElements));
+    table[199..207].copy_from_slice(&for_primitive_types!(GetArrayRegion));
+    table[207..215].copy_from_slice(&for_primitive_types!(SetArrayRegion));
+    table[215] = RegisterNatives as *const c_void;
+    table[216] = UnregisterNatives as *const c_void;
+    table[217] = MonitorEnter as *const c_void;
+    table[218] = MonitorExit as *const c_void;
+    table[219] = GetJavaVM as *const c_void;
+    table[220] = GetStringRegion as *const c_void;
+    table[221] = GetStringUTFRegion as *const c_void;
+    table[222] = GetPrimitiveArrayCritical as *const c_void;
+    table[223] = ReleasePrimitiveArrayCritical as *const c_void;
+    table[224] = GetStringChars as *const c_void;
+    table[225] = ReleaseStringChars as *const c_void;
+    table[226] = NewGlobalRef as *const c_void;
+    table[227] = DeleteGlobalRef as *const c_void;
+    table[228] = ExceptionCheck as *const c_void;
+    table[229] = NewDirectByteBuffer as *const c_void;
+    table[230] = GetDirectBufferAddress as *const c_void;
+    table[231] = GetDirectBufferCapacity as *const c_void;
+    table[232] = GetObjectRefType as *const c_void;
+    FunctionTable(table)
+}
+
+/// Stands in for the functions the VM lacks, e.g. `DefineClass` or the
+/// reflection ones. Native code calling one is stopped right there.
+unsafe extern "C" fn unsupported() {
+    eprintln!("hippo: unsupported JNI function called");
+    process::abort();
+}
+
+unsafe extern "C" fn GetVersion(_env: *mut JniEnv) -> jint {
+    JNI_VERSION_1_8
+}
+
+unsafe extern "C" fn FindClass(env: *mut JniEnv, name: *const c_char) -> jclass {
+    let mut jenv = Vm::enter(env);
+    let name = utf_string(name);
+    match jenv.resolve_and_init_class(&name) {
+        Some(class) => new_local_mirror(&mut jenv, &class),
+        None => ptr::null_mut(),
+    }
+}
+
+unsafe extern "C" fn GetSuperclass(env: *mut JniEnv, clazz: jclass) -> jclass {
+    let mut jenv = Vm::enter(env);
+    let class = class_of(&jenv, clazz);
+    match class.super_class() {
+        // interfaces have none here
+        Some(super_class) if !class.is_interface() => new_local_mirror(&mut jenv, &super_class),
+        _ => ptr::null_mut(),
+    }
+}
+
+unsafe extern "C" fn IsAssignableFrom(
+    env: *mut JniEnv,
+    clazz1: jclass,
+    clazz2: jclass,
+) -> jboolean {
+    let mut jenv = Vm::enter(env);
+    let class1 = class_of(&jenv, clazz1);
+    let class2 = class_of(&jenv, clazz2);
+    can_cast_to(&mut jenv, class1, class2) as jboolean
+}
+
+unsafe extern "C" fn Throw(env: *mut JniEnv, obj: jthrowable) -> jint {
+    let mut jenv = Vm::enter(env);
+    let exception = referent(&jenv, obj);
+    jenv.thread_mut().set_pending_exception(exception);
+    JNI_OK
+}
+
+unsafe extern "C" fn ThrowNew(env: *mut JniEnv, clazz: jclass, msg: *const c_char) -> jint {
+    let mut jenv = Vm::enter(env);
+    let class = class_of(&jenv, clazz);
+    let message = if msg.is_null() {
+        None
+    } else {
+        Some(utf_string(msg))
+    };
+    jenv.throw_exception(class.name(), message.as_deref());
+    JNI_OK
+}
+
+unsafe extern "C" fn ExceptionOccurred(env: *mut JniEnv) -> jthrowable {
+    let mut jenv = Vm::enter(env);
+    match jenv.thread().pending_exception.clone() {
+        Some(exception) => local_ref(&mut jenv, exception),
+        None => ptr::null_mut(),
+    }
+}
+
+unsafe extern "C" fn ExceptionDescribe(env: *mut JniEnv) {
+    let mut jenv = Vm::enter(env);
+    if let Some(exception) = jenv.thread_mut().take_pending_exception() {
+        report_uncaught_exception(&mut jenv, exception);
+    }
+}
+
+unsafe extern "C" fn ExceptionClear(env: *mut JniEnv) {
+    let mut jenv = Vm::enter(env);
+    jenv.thread_mut().take_pending_exception();
+}
+
+unsafe extern "C" fn FatalError(_env: *mut JniEnv, msg: *const c_char) {
+    eprintln!("FATAL ERROR in native method: {}", utf_string(msg));
+    process::abort();
+}
+
+unsafe extern "C" fn ExceptionCheck(env: *mut JniEnv) -> jboolean {
+    let jenv = Vm::enter(env);
+    jenv.thread().has_pending_exception() as jboolean
+}
+
+unsafe extern "C" fn PushLocalFrame(env: *mut JniEnv, _capacity: jint) -> jint {
+    let mut jenv = Vm::enter(env);
+    push_local_frame(&mut jenv);
+    JNI_OK
+}
+
+unsafe extern "C" fn PopLocalFrame(env: *mut JniEnv, result: jobject) -> jobject {
+    let mut jenv = Vm::enter(env);
+    let result = referent(&jenv, result);
+    pop_local_frame(&mut jenv);
+    local_ref(&mut jenv, result)
+}
+
+/// Weak global references are strong ones, they keep their objects alive.
+unsafe extern "C" fn NewGlobalRef(env: *mut JniEnv, obj: jobject) -> jobject {
+    let mut jenv = Vm::enter(env);
+    let value = referent(&jenv, obj);
+    jenv.jni_mut().new_global_ref(value)
+}
+
+unsafe extern "C" fn DeleteGlobalRef(env: *mut JniEnv, global_ref: jobject) {
+    let mut jenv = Vm::enter(env);
+    if !global_ref.is_null() {
+        jenv.jni_mut().delete_global_ref(global_ref);
+    }
+}
+
+unsafe extern "C" fn DeleteLocalRef(env: *mut JniEnv, local_ref: jobject) {
+    let mut jenv = Vm::enter(env);
+    let handle = local_ref as usize;
+    if handle != 0 && handle & 1 == 0 {
+        jenv.thread_mut().jni_local_refs[(handle >> 1) - 1] = Operand::Null;
+    }
+}
+
+unsafe extern "C" fn IsSameObject(env: *mut JniEnv, ref1: jobject, ref2: jobject) -> jboolean {
+    let jenv = Vm::enter(env);
+    (referent(&jenv, ref1) == referent(&jenv, ref2)) as jboolean
+}
+
+unsafe extern "C" fn NewLocalRef(env: *mut JniEnv, obj: jobject) -> jobject {
+    let mut jenv = Vm::enter(env);
+    let value = referent(&jenv, obj);
+    local_ref(&mut jenv, value)
+}
+
+unsafe extern "C" fn EnsureLocalCapacity(_env: *mut JniEnv, _capacity: jint) -> jint {
+    JNI_OK
+}
+
+unsafe extern "C" fn GetObjectRefType(_env: *mut JniEnv, obj: jobject) -> jint {
+    match obj as usize {
+        0 => INVALID_REF_TYPE,
+        handle if handle & 1 == 0 => LOCAL_REF_TYPE,
+        _ => GLOBAL_REF_TYPE,
+    }
+}
+
+/// A new instance of `clazz` without running a constructor.
+fn alloc_object(jenv: &mut JvmEnv, clazz: jclass) -> Option<u32> {
+    let class = class_of(jenv, clazz);
+    if class.access_flags() & (ACC_ABSTRACT | ACC_INTERFACE) != 0 {
+        let class_name = class.name().replace('/', ".");
+        jenv.throw_exception(JAVA_LANG_INSTANTIATION_EXCEPTION, Some(&class_name));
+        return None;
+    }
+    if !jenv.initialize_class(&class) || !jenv.reserve_heap(JvmHeap::object_size(&class)) {
+        return None;
+    }
+    let (_, addr) = jenv.heap_mut().new_object(class);
+    Some(addr)
+}
+
+unsafe extern "C" fn AllocObject(env: *mut JniEnv, clazz: jclass) -> jobject {
+    let mut jenv = Vm::enter(env);
+    match alloc_object(&mut jenv, clazz) {
+        Some(addr) => local_ref(&mut jenv, Operand::ObjectRef(addr)),
+        None => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+unsafe extern "C" fn hippo_jni_NewObjectA(
+    env: *mut JniEnv,
+    clazz: jclass,
+    method_id: jmethodID,
+    args: *const jvalue,
+) -> jobject {
+    let obj = {
+        let mut jenv = Vm::enter(env);
+        match alloc_object(&mut jenv, clazz) {
+            Some(addr) => local_ref(&mut jenv, Operand::ObjectRef(addr)),
+            None => return ptr::null_mut(),
+        }
+    };
+    call_method::<()>(env, obj, method_id, args, Dispatch::Nonvirtual);
+    let jenv = Vm::enter(env);
+    if jenv.thread().has_pending_exception() {
+        return ptr::null_mut();
+    }
+    obj
+}
+
+unsafe extern "C" fn GetObjectClass(env: *mut JniEnv, obj: jobject) -> jclass {
+    let mut jenv = Vm::enter(env);
+    let obj = referent(&jenv, obj);
+    let class = class_of_object(&mut jenv, &obj);
+    new_local_mirror(&mut jenv, &class)
+}
+
+unsafe extern "C" fn IsInstanceOf(env: *mut JniEnv, obj: jobject, clazz: jclass) -> jboolean {
+    let mut jenv = Vm::enter(env);
+    let obj = referent(&jenv, obj);
+    if obj == Operand::Null {
+        return JNI_TRUE;
+    }
+    let class = class_of_object(&mut jenv, &obj);
+    let target = class_of(&jenv, clazz);
+    can_cast_to(&mut jenv, class, target) as jboolean
+}
+
+/// The `jmethodID` of the method of `clazz` or of its supertypes, which is
+/// initialized first.
+unsafe fn get_method_id(
+    env: *mut JniEnv,
+    clazz: jclass,
+    name: *const c_char,
+    sig: *const c_char,
+    is_static: bool,
+) -> jmethodID {
+    let mut jenv = Vm::enter(env);
+    let class = class_of(&jenv, clazz);
+    if !jenv.initialize_class(&class) {
+        return ptr::null_mut();
+    }
+    let name = utf_string(name);
+    let descriptor = utf_string(sig);
+    match class.get_method(&name, &descriptor, is_static) {
+        Some(method) => jenv.jni_mut().method_id(method),
+        None => {
+            jenv.throw_exception(JAVA_LANG_NO_SUCH_METHOD_ERROR, Some(&name));
+            ptr::null_mut()
+        }
+    }
+}
+
+unsafe extern "C" fn GetMethodID(
+    env: *mut JniEnv,
+    clazz: jclass,
+    name: *const c_char,
+    sig: *const c_char,
+) -> jmethodID {
+    get_method_id(env, clazz, name, sig, false)
+}
+
+unsafe extern "C" fn GetStaticMethodID(
+    env: *mut JniEnv,
+    clazz: jclass,
+    name: *const c_char,
+    sig: *const c_char,
+) -> jmethodID {
+    get_method_id(env, clazz, name, sig, true)
+}
+
+/// For the variadic `Call<Type>Method` functions of `varargs.c`.
+#[no_mangle]
+unsafe extern "C" fn hippo_jni_parameter_types(
+    env: *mut JniEnv,
+    method_id: jmethodID,
+) -> *const c_char {
+    let jenv = Vm::enter(env);
+    // the string stays where it is as long as the `jmethodID`
+    jenv.jni().method(method_id).parameter_types.as_ptr()
+}
+
+/// How the method of a `jmethodID` is selected.
+enum Dispatch {
+    /// Overridden by the class of the receiver, like `invokevirtual`.
+    Virtual,
+    /// Just that method on the receiver, like `invokespecial`.
+    Nonvirtual,
+    Static,
+}
+
+/// Calls the method `method_id` on `obj` with the arguments `args` and
+/// returns its result.
+unsafe fn call_method<T: JniType>(
+    env: *mut JniEnv,
+    obj: jobject,
+    method_id: jmethodID,
+    args: *const jvalue,
+    dispatch: Dispatch,
+) -> T {
+    let mut jenv = Vm::enter(env);
+    let method = jenv.jni().method(method_id).method.clone();
+    let mut call_args = Vec::with_capacity(method.n_args() + 1);
+    let method = match dispatch {
+        Dispatch::Static => {
+            let class = jenv.load_class(method.class_name());
+            if !jenv.initialize_class(&class) {
+                return zero();
+            }
+            method
+        }
+        Dispatch::Nonvirtual | Dispatch::Virtual => {
+            let receiver = referent(&jenv, obj);
+            if receiver == Operand::Null {
+                jenv.throw_exception(JAVA_LANG_NULL_POINTER_EXCEPTION, None);
+                return zero();
+            }
+            call_args.push(receiver.clone());
+            if matches!(dispatch, Dispatch::Nonvirtual)
+                || method.is_private()
+                || method.is_initialization_method()
+            {
+                method
+            } else {
+                let call = virtual_call(&mut jenv, method);
+                match select_method(&mut jenv, &call, &receiver) {
+                    Some(method) => method,
+                    None => return zero(),
+                }
+            }
+        }
+    };
+    for (i, descriptor) in method.param_descriptors().iter().enumerate() {
+        let value = *args.add(i);
+        call_args.push(from_jvalue(&jenv, descriptor.as_bytes()[0], value));
+    }
+
+    let return_type = method.return_descriptor().as_bytes()[0];
+    execute_method(&mut jenv, method, call_args);
+    if return_type == b'V' || jenv.thread().has_pending_exception() {
+        return zero();
+    }
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value = frame.operand_stack.pop();
+    T::from_operand(&mut jenv, value)
+}
+
+macro_rules! call_functions {
+    ($($ty:ty: $call:ident, $call_nonvirtual:ident, $call_static:ident;)*) => {
+        $(
+            #[no_mangle]
+            unsafe extern "C" fn $call(
+                env: *mut JniEnv,
+                obj: jobject,
+                method_id: jmethodID,
+                args: *const jvalue,
+            ) -> $ty {
+                call_method(env, obj, method_id, args, Dispatch::Virtual)
+            }
+
+            #[no_mangle]
+            unsafe extern "C" fn $call_nonvirtual(
+                env: *mut JniEnv,
+                obj: jobject,
+                _clazz: jclass,
+                method_id: jmethodID,
+                args: *const jvalue,
+            ) -> $ty {
+                call_method(env, obj, method_id, args, Dispatch::Nonvirtual)
+            }
+
+            #[no_mangle]
+            unsafe extern "C" fn $call_static(
+                env: *mut JniEnv,
+                _clazz: jclass,
+                method_id: jmethodID,
+                args: *const jvalue,
+            ) -> $ty {
+                call_method(env, ptr::null_mut(), method_id, args, Dispatch::Static)
+            }
+        )*
+
+        /// The `A` functions of the `Call` families by family and type.
+        fn call_functions() -> [[*const c_void; 10]; 3] {
+            [
+                [$($call as *const c_void),*],
+                [$($call_nonvirtual as *const c_void),*],
+                [$($call_static as *const c_void),*],
+            ]
+        }
+    };
+}
+
+call_functions! {
+    jobject: hippo_jni_CallObjectMethodA,
+        hippo_jni_CallNonvirtualObjectMethodA,
+        hippo_jni_CallStaticObjectMethodA;
+    jboolean: hippo_jni_CallBooleanMethodA,
+        hippo_jni_CallNonvirtualBooleanMethodA,
+        hippo_jni_CallStaticBooleanMethodA;
+    jbyte: hippo_jni_CallByteMethodA,
+        hippo_jni_CallNonvirtualByteMethodA,
+        hippo_jni_CallStaticByteMethodA;
+    jchar: hippo_jni_CallCharMethodA,
+        hippo_jni_CallNonvirtualCharMethodA,
+        hippo_jni_CallStaticCharMethodA;
+    jshort: hippo_jni_CallShortMethodA,
+        hippo_jni_CallNonvirtualShortMethodA,
+        hippo_jni_CallStaticShortMethodA;
+    jint: hippo_jni_CallIntMethodA,
+        hippo_jni_CallNonvirtualIntMethodA,
+        hippo_jni_CallStaticIntMethodA;
+    jlong: hippo_jni_CallLongMethodA,
+        hippo_jni_CallNonvirtualLongMethodA,
+        hippo_jni_CallStaticLongMethodA;
+    jfloat: hippo_jni_CallFloatMethodA,
+        hippo_jni_CallNonvirtualFloatMethodA,
+        hippo_jni_CallStaticFloatMethodA;
+    jdouble: hippo_jni_CallDoubleMethodA,
+        hippo_jni_CallNonvirtualDoubleMethodA,
+        hippo_jni_CallStaticDoubleMethodA;
+    (): hippo_jni_CallVoidMethodA,
+        hippo_jni_CallNonvirtualVoidMethodA,
+        hippo_jni_CallStaticVoidMethodA;
+}
+
+/// The `jfieldID` of the field of `clazz` or of its supertypes, which is
+/// initialized first. Static fields are only looked up in `clazz` itself.
+unsafe fn get_field_id(
+    env: *mut JniEnv,
+    clazz: jclass,
+    name: *const c_char,
+    sig: *const c_char,
+    is_static: bool,
+) -> jfieldID {
+    let mut jenv = Vm::enter(env);
+    let class = class_of(&jenv, clazz);
+    if !jenv.initialize_class(&class) {
+        return ptr::null_mut();
+    }
+    let name = utf_string(name);
+    let descriptor = utf_string(sig);
+    let field = if is_static {
+        class.get_static_field(&name, &descriptor)
+    } else {
+        class.get_field(&name, &descriptor)
+    };
+    match field {
+        Some(field) => jenv.jni_mut().field_id(class, field),
+        None => {
+            jenv.throw_exception(JAVA_LANG_NO_SUCH_FIELD_ERROR, Some(&name));
+            ptr::null_mut()
+        }
+    }
+}
+
+unsafe extern "C" fn GetFieldID(
+    env: *mut JniEnv,
+    clazz: jclass,
+    name: *const c_char,
+    sig: *const c_char,
+) -> jfieldID {
+    get_field_id(env, clazz, name, sig, false)
+}
+
+unsafe extern "C" fn GetStaticFieldID(
+    env: *mut JniEnv,
+    clazz: jclass,
+    name: *const c_char,
+    sig: *const c_char,
+) -> jfieldID {
+    get_field_id(env, clazz, name, sig, true)
+}
+
+unsafe extern "C" fn GetField<T: JniType>(env: *mut JniEnv, obj: jobject, field_id: jfieldID) -> T {
+    let mut jenv = Vm::enter(env);
+    let index = jenv.jni().field(field_id).field.index();
+    let obj = referent(&jenv, obj);
+    let value = jenv.heap().get_object(&obj).get_field(index).clone();
+    T::from_operand(&mut jenv, value)
+}
+
+unsafe extern "C" fn SetField<T: JniType>(
+    env: *mut JniEnv,
+    obj: jobject,
+    field_id: jfieldID,
+    value: T,
+) {
+    let mut jenv = Vm::enter(env);
+    let index = jenv.jni().field(field_id).field.index();
+    let obj = referent(&jenv, obj);
+    let value = value.into_operand(&jenv);
+    jenv.heap_mut().get_object_mut(&obj).set_field(index, value);
+}
+
+unsafe extern "C" fn GetStaticField<T: JniType>(
+    env: *mut JniEnv,
+    _clazz: jclass,
+    field_id: jfieldID,
+) -> T {
+    let mut jenv = Vm::enter(env);
+    let field_id = jenv.jni().field(field_id);
+    let value = field_id
+        .class
+        .get_static_field_value(field_id.field.index());
+    T::from_operand(&mut jenv, value)
+}
+
+unsafe extern "C" fn SetStaticField<T: JniType>(
+    env: *mut JniEnv,
+    _clazz: jclass,
+    field_id: jfieldID,
+    value: T,
+) {
+    let jenv = Vm::enter(env);
+    let value = value.into_operand(&jenv);
+    let field_id = jenv.jni().field(field_id);
+    field_id
+        .class
+        .set_static_field_value(field_id.field.index(), value);
+}
+
+unsafe extern "C" fn NewString(env: *mut JniEnv, unicode: *const jchar, len: jsize) -> jstring {
+    let mut jenv = Vm::enter(env);
+    let value = std::slice::from_raw_parts(unicode, len as usize).to_vec();
+    match jenv.new_java_lang_string_from_utf16(value) {
+        Some(string) => local_ref(&mut jenv, Operand::ObjectRef(string)),
+        None => ptr::null_mut(),
+    }
+}
+
+unsafe extern "C" fn GetStringLength(env: *mut JniEnv, string: jstring) -> jsize {
+    let mut jenv = Vm::enter(env);
+    let value = string_value(&jenv, string);
+    jenv.heap_mut().get_array_length(&value)
+}
+
+/// Where the characters of the string are, strings are immutable so they
+/// aren't copied.
+unsafe extern "C" fn GetStringChars(
+    env: *mut JniEnv,
+    string: jstring,
+    is_copy: *mut jboolean,
+) -> *const jchar {
+    let mut jenv = Vm::enter(env);
+    if !is_copy.is_null() {
+        *is_copy = JNI_FALSE;
+    }
+    let value = string_value(&jenv, string);
+    jenv.heap_mut().primitive_array_data(&value) as *const jchar
+}
+
+unsafe extern "C" fn ReleaseStringChars(_env: *mut JniEnv, _string: jstring, _chars: *const jchar) {
+}
+
+unsafe extern "C" fn NewStringUTF(env: *mut JniEnv, bytes: *const c_char) -> jstring {
+    let mut jenv = Vm::enter(env);
+    let s = utf_string(bytes);
+    match jenv.new_java_lang_string(&s) {
+        Some(string) => local_ref(&mut jenv, Operand::ObjectRef(string)),
+        None => ptr::null_mut(),
+    }
+}
+
+/// The string in modified UTF-8, unpaired surrogates become U+FFFD.
+fn modified_utf8(jenv: &JvmEnv, value: &Operand, start: usize, len: usize) -> Vec<u8> {
+    let chars = &jenv.heap().get_char_array(value)[start..start + len];
+    cesu8::to_java_cesu8(&String::from_utf16_lossy(chars)).into_owned()
+}
+
+unsafe extern "C" fn GetStringUTFLength(env: *mut JniEnv, string: jstring) -> jsize {
+    let mut jenv = Vm::enter(env);
+    let value = string_value(&jenv, string);
+    let length = jenv.heap_mut().get_array_length(&value) as usize;
+    modified_utf8(&jenv, &value, 0, length).len() as jsize
+}
+
+unsafe extern "C" fn GetStringUTFChars(
+    env: *mut JniEnv,
+    string: jstring,
+    is_copy: *mut jboolean,
+) -> *const c_char {
+    let mut jenv = Vm::enter(env);
+    if !is_copy.is_null() {
+        *is_copy = JNI_TRUE;
+    }
+    let value = string_value(&jenv, string);
+    let length = jenv.heap_mut().get_array_length(&value) as usize;
+    let bytes = modified_utf8(&jenv, &value, 0, length);
+    // modified UTF-8 has no zero bytes
+    CString::new(bytes).unwrap().into_raw()
+}
+
+unsafe extern "C" fn ReleaseStringUTFChars(
+    _env: *mut JniEnv,
+    _string: jstring,
+    utf: *const c_char,
+) {
+    drop(CString::from_raw(utf as *mut c_char));
+}
+
+unsafe extern "C" fn GetStringRegion(
+    env: *mut JniEnv,
+    string: jstring,
+    start: jsize,
+    len: jsize,
+    buf: *mut jchar,
+) {
+    let mut jenv = Vm::enter(env);
+    let value = string_value(&jenv, string);
+    let length = jenv.heap_mut().get_array_length(&value);
+    if !check_range(
+        &mut jenv,
+        start,
+        len,
+        length,
+        JAVA_LANG_STRING_INDEX_OUT_OF_BOUNDS_EXCEPTION,
+    ) {
+        return;
+    }
+    let chars = jenv.heap().get_char_array(&value);
+    ptr::copy_nonoverlapping(chars[start as usize..].as_ptr(), buf, len as usize);
+}
+
+/// Writes the characters `start..start + len` in modified UTF-8 with a
+/// terminating zero.
+unsafe extern "C" fn GetStringUTFRegion(
+    env: *mut JniEnv,
+    string: jstring,
+    start: jsize,
+    len: jsize,
+    buf: *mut c_char,
+) {
+    let mut jenv = Vm::enter(env);
+    let value = string_value(&jenv, string);
+    let length = jenv.heap_mut().get_array_length(&value);
+    if !check_range(
+        &mut jenv,
+        start,
+        len,
+        length,
+        JAVA_LANG_STRING_INDEX_OUT_OF_BOUNDS_EXCEPTION,
+    ) {
+        return;
+    }
+    let bytes = modified_utf8(&jenv, &value, start as usize, len as usize);
+    ptr::copy_nonoverlapping(bytes.as_ptr() as *const c_char, buf, bytes.len());
+    *buf.add(bytes.len()) = 0;
+}
+
+unsafe extern "C" fn GetArrayLength(env: *mut JniEnv, array: jarray) -> jsize {
+    let mut jenv = Vm::enter(env);
+    let array = referent(&jenv, array);
+    jenv.heap_mut().get_array_length(&array)
+}
+
+unsafe extern "C" fn NewObjectArray(
+    env: *mut JniEnv,
+    len: jsize,
+    element_class: jclass,
+    initial_element: jobject,
+) -> jarray {
+    let mut jenv = Vm::enter(env);
+    if len < 0 {
+        jenv.throw_exception(
+            JAVA_LANG_NEGATIVE_ARRAY_SIZE_EXCEPTION,
+            Some(&len.to_string()),
+        );
+        return ptr::null_mut();
+    }
+    let class_name = class_of(&jenv, element_class).name().to_string();
+    if !jenv.reserve_heap(JvmHeap::reference_array_size(len)) {
+        return ptr::null_mut();
+    }
+    let initial_element = referent(&jenv, initial_element);
+    let array = Operand::ArrayRef(jenv.heap_mut().new_reference_array(class_name, len));
+    for element in jenv.heap_mut().get_object_array_mut(&array) {
+        *element = initial_element.clone();
+    }
+    local_ref(&mut jenv, array)
+}
+
+unsafe extern "C" fn GetObjectArrayElement(
+    env: *mut JniEnv,
+    array: jarray,
+    index: jsize,
+) -> jobject {
+    let mut jenv = Vm::enter(env);
+    let array = referent(&jenv, array);
+    let length = jenv.heap_mut().get_array_length(&array);
+    if !check_range(
+        &mut jenv,
+        index,
+        1,
+        length,
+        JAVA_LANG_ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION,
+    ) {
+        return ptr::null_mut();
+    }
+    let element = jenv.heap().get_array_element(&array, index as usize);
+    local_ref(&mut jenv, element)
+}
+
+unsafe extern "C" fn SetObjectArrayElement(
+    env: *mut JniEnv,
+    array: jarray,
+    index: jsize,
+    value: jobject,
+) {
+    let mut jenv = Vm::enter(env);
+    let array = referent(&jenv, array);
+    let value = referent(&jenv, value);
+    let length = jenv.heap_mut().get_array_length(&array);
+    if !check_range(
+        &mut jenv,
+        index,
+        1,
+        length,
+        JAVA_LANG_ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION,
+    ) {
+        return;
+    }
+    if value != Operand::Null {
+        let array_class = class_of_object(&mut jenv, &array);
+        let element_class = jenv.load_class(&array_class.obj_array_class().class);
+        let value_class = class_of_object(&mut jenv, &value);
+        if !can_cast_to(&mut jenv, value_class.clone(), element_class) {
+            let message = value_class.name().replace('/', ".");
+            jenv.throw_exception(JAVA_LANG_ARRAY_STORE_EXCEPTION, Some(&message));
+            return;
+        }
+    }
+    jenv.heap_mut()
+        .set_array_element(&array, index as usize, value);
+}
+
+unsafe extern "C" fn NewPrimitiveArray<T: JniPrimitive>(env: *mut JniEnv, len: jsize) -> jarray {
+    let mut jenv = Vm::enter(env);
+    if len < 0 {
+        jenv.throw_exception(
+            JAVA_LANG_NEGATIVE_ARRAY_SIZE_EXCEPTION,
+            Some(&len.to_string()),
+        );
+        return ptr::null_mut();
+    }
+    let size = JvmHeap::array_size(JvmHeap::element_size(T::ARRAY_TYPE), len);
+    if !jenv.reserve_heap(size) {
+        return ptr::null_mut();
+    }
+    let array = jenv.heap_mut().new_empty_array(T::ARRAY_TYPE, len);
+    local_ref(&mut jenv, Operand::ArrayRef(array))
+}
+
+/// Where the elements of the array are, they aren't copied.
+unsafe extern "C" fn GetArrayElements<T: JniPrimitive>(
+    env: *mut JniEnv,
+    array: jarray,
+    is_copy: *mut jboolean,
+) -> *mut T {
+    let mut jenv = Vm::enter(env);
+    if !is_copy.is_null() {
+        *is_copy = JNI_FALSE;
+    }
+    let array = referent(&jenv, array);
+    jenv.heap_mut().primitive_array_data(&array) as *mut T
+}
+
+unsafe extern "C" fn ReleaseArrayElements<T: JniPrimitive>(
+    _env: *mut JniEnv,
+    _array: jarray,
+    _elems: *mut T,
+    _mode: jint,
+) {
+}
+
+unsafe extern "C" fn GetArrayRegion<T: JniPrimitive>(
+    env: *mut JniEnv,
+    array: jarray,
+    start: jsize,
+    len: jsize,
+    buf: *mut T,
+) {
+    let mut jenv = Vm::enter(env);
+    let array = referent(&jenv, array);
+    let length = jenv.heap_mut().get_array_length(&array);
+    if !check_range(
+        &mut jenv,
+        start,
+        len,
+        length,
+        JAVA_LANG_ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION,
+    ) {
+        return;
+    }
+    let data = jenv.heap_mut().primitive_array_data(&array) as *const T;
+    ptr::copy_nonoverlapping(data.add(start as usize), buf, len as usize);
+}
+
+unsafe extern "C" fn SetArrayRegion<T: JniPrimitive>(
+    env: *mut JniEnv,
+    array: jarray,
+    start: jsize,
+    len: jsize,
+    buf: *const T,
+) {
+    let mut jenv = Vm::enter(env);
+    let array = referent(&jenv, array);
+    let length = jenv.heap_mut().get_array_length(&array);
+    if !check_range(
+        &mut jenv,
+        start,
+        len,
+        length,
+        JAVA_LANG_ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION,
+    ) {
+        return;
+    }
+    let data = jenv.heap_mut().primitive_array_data(&array) as *mut T;
+    ptr::copy_nonoverlapping(buf, data.add(start as usize), len as usize);
+}
+
+unsafe extern "C" fn GetPrimitiveArrayCritical(
+    env: *mut JniEnv,
+    array: jarray,
+    is_copy: *mut jboolean,
+) -> *mut c_void {
+    GetArrayElements::<jbyte>(env, array, is_copy) as *mut c_void
+}
+
+unsafe extern "C" fn ReleasePrimitiveArrayCritical(
+    _env: *mut JniEnv,
+    _array: jarray,
+    _carray: *mut c_void,
+    _mode: jint,
+) {
+}
+
+unsafe extern "C" fn RegisterNatives(
+    env: *mut JniEnv,
+    clazz: jclass,
+    methods: *const JNINativeMethod,
+    n_methods: jint,
+) -> jint {
+    let mut jenv = Vm::enter(env);
+    let class = class_of(&jenv, clazz);
+    for method in std::slice::from_raw_parts(methods, n_methods as usize) {
+        let name = utf_string(method.name);
+        let descriptor = utf_string(method.signature);
+        let is_declared = class.methods().iter().any(|method| {
+            method.is_native() && method.name() == name && method.descriptor() == descriptor
+        });
+        if !is_declared {
+            jenv.throw_exception(JAVA_LANG_NO_SUCH_METHOD_ERROR, Some(&name));
+            return JNI_ERR;
+        }
+        let code = method.fn_ptr as usize;
+        jenv.jni_mut()
+            .register_native(class.name(), &name, &descriptor, code);
+    }
+    JNI_OK
+}
+
+unsafe extern "C" fn UnregisterNatives(env: *mut JniEnv, clazz: jclass) -> jint {
+    let mut jenv = Vm::enter(env);
+    let class = class_of(&jenv, clazz);
+    jenv.jni_mut().unregister_natives(class.name());
+    JNI_OK
+}
+
+unsafe extern "C" fn MonitorEnter(env: *mut JniEnv, obj: jobject) -> jint {
+    let mut jenv = Vm::enter(env);
+    let obj = referent(&jenv, obj);
+    monitor_enter(&mut jenv, &obj);
+    JNI_OK
+}
+
+unsafe extern "C" fn MonitorExit(env: *mut JniEnv, obj: jobject) -> jint {
+    let mut jenv = Vm::enter(env);
+    let obj = referent(&jenv, obj);
+    if monitor_exit(&mut jenv, &obj) {
+        JNI_OK
+    } else {
+        JNI_ERR
+    }
+}
+
+unsafe extern "C" fn GetJavaVM(_env: *mut JniEnv, vm: *mut *mut JavaVm) -> jint {
+    *vm = java_vm();
+    JNI_OK
+}
+
+/// Direct buffers aren't supported, which JNI allows.
+unsafe extern "C" fn NewDirectByteBuffer(
+    _env: *mut JniEnv,
+    _address: *mut c_void,
+    _capacity: jlong,
+) -> jobject {
+    ptr::null_mut()
+}
+
+unsafe extern "C" fn GetDirectBufferAddress(_env: *mut JniEnv, _buf: jobject) -> *mut c_void {
+    ptr::null_mut()
+}
+
+unsafe extern "C" fn GetDirectBufferCapacity(_env: *mut JniEnv, _buf: jobject) -> jlong {
+    -1
+}
+
+unsafe extern "C" fn DestroyJavaVM(_vm: *mut JavaVm) -> jint {
+    JNI_ERR
+}
+
+/// Only threads running Java code have a `JNIEnv`, other native threads
+/// can't attach.
+unsafe extern "C" fn AttachCurrentThread(
+    _vm: *mut JavaVm,
+    penv: *mut *mut c_void,
+    _args: *mut c_void,
+) -> jint {
+    match CURRENT_ENV.with(|current| current.get()) {
+        env if env.is_null() => JNI_ERR,
+        env => {
+            *penv = env as *mut c_void;
+            JNI_OK
+        }
+    }
+}
+
+unsafe extern "C" fn DetachCurrentThread(_vm: *mut JavaVm) -> jint {
+    JNI_ERR
+}
+
+unsafe extern "C" fn GetEnv(_vm: *mut JavaVm, penv: *mut *mut c_void, version: jint) -> jint {
+    if !is_supported_version(version) {
+        return JNI_EVERSION;
+    }
+    match CURRENT_ENV.with(|current| current.get()) {
+        env if env.is_null() => JNI_EDETACHED,
+        env => {
+            *penv = env as *mut c_void;
+            JNI_OK
+        }
+    }
+}
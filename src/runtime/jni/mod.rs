@@ -0,0 +1,580 @@
+#![allow(non_camel_case_types, non_snake_case)]
+//! The Java Native Interface: loading shared libraries, binding the `native`
+//! methods the VM doesn't implement to their C functions, and calling those
+//! with a `JNIEnv` through which they use the VM.
+//!
+//! Native code refers to objects by handles, see `local_ref`. What a handle
+//! refers to is a root of the collector until the handle is deleted, and
+//! objects never move, so arrays and strings are handed out as pointers into
+//! the heap instead of copies.
+mod functions;
+
+use crate::runtime::class::Class;
+use crate::runtime::field::Field;
+use crate::runtime::frame::operand_stack::Operand;
+use crate::runtime::heap::JAVA_LANG_UNSATISFIED_LINK_ERROR;
+use crate::runtime::jvm_env::JvmEnv;
+use crate::runtime::method::Method;
+use libffi::middle::{Arg, Cif, CodePtr, Type};
+use libloading::Library;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::ffi::{c_void, CString};
+use std::fmt::Write;
+use std::ptr;
+use tracing::debug;
+
+pub type jboolean = u8;
+pub type jbyte = i8;
+pub type jchar = u16;
+pub type jshort = i16;
+pub type jint = i32;
+pub type jlong = i64;
+pub type jfloat = f32;
+pub type jdouble = f64;
+pub type jsize = jint;
+pub type jobject = *mut c_void;
+pub type jclass = jobject;
+pub type jstring = jobject;
+pub type jarray = jobject;
+pub type jthrowable = jobject;
+pub type jmethodID = *mut c_void;
+pub type jfieldID = *mut c_void;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union jvalue {
+    pub z: jboolean,
+    pub b: jbyte,
+    pub c: jchar,
+    pub s: jshort,
+    pub i: jint,
+    pub j: jlong,
+    pub f: jfloat,
+    pub d: jdouble,
+    pub l: jobject,
+}
+
+pub const JNI_FALSE: jboolean = 0;
+pub const JNI_TRUE: jboolean = 1;
+
+pub const JNI_OK: jint = 0;
+pub const JNI_ERR: jint = -1;
+pub const JNI_EDETACHED: jint = -2;
+pub const JNI_EVERSION: jint = -3;
+
+pub const JNI_VERSION_1_1: jint = 0x0001_0001;
+pub const JNI_VERSION_1_2: jint = 0x0001_0002;
+pub const JNI_VERSION_1_4: jint = 0x0001_0004;
+pub const JNI_VERSION_1_6: jint = 0x0001_0006;
+pub const JNI_VERSION_1_8: jint = 0x0001_0008;
+
+/// Where libraries are searched after `LD_LIBRARY_PATH`, as by the JDK.
+const DEFAULT_LIBRARY_PATH: &str = "/usr/java/packages/lib/amd64:/usr/lib64:/lib64:/lib:/usr/lib";
+
+/// The `JNIEnv` a native method gets. C only knows the function table in
+/// front, the JNI functions find the VM behind it.
+#[repr(C)]
+pub struct JniEnv {
+    functions: *const functions::FunctionTable,
+    jenv: *mut JvmEnv,
+}
+
+thread_local! {
+    /// The `JNIEnv` of the innermost native method the thread runs, for
+    /// `GetEnv` of the `JavaVM`.
+    static CURRENT_ENV: Cell<*mut JniEnv> = const { Cell::new(ptr::null_mut()) };
+}
+
+/// A method native code calls through a `jmethodID`.
+#[derive(Debug)]
+struct MethodId {
+    method: Method,
+    /// The first characters of the descriptors of the parameters, for the
+    /// variadic `Call<Type>Method` functions.
+    parameter_types: CString,
+}
+
+/// A field native code accesses through a `jfieldID`.
+#[derive(Debug)]
+struct FieldId {
+    /// Where static fields are looked up like by `getstatic`.
+    class: Class,
+    field: Field,
+}
+
+/// The state of JNI shared by all threads.
+#[derive(Debug, Default)]
+pub struct Jni {
+    /// A `NativeLibrary.handle` is the index in here plus one.
+    libraries: Vec<Library>,
+    /// The C functions bound to native methods so far, by the class, name
+    /// and descriptor of the method.
+    natives: HashMap<(String, String, String), usize>,
+    /// What the global references refer to, null for deleted ones.
+    global_refs: Vec<Operand>,
+    free_global_refs: Vec<usize>,
+    /// A `jmethodID` is the index in here plus one, there is only one for
+    /// every method.
+    method_ids: Vec<MethodId>,
+    method_id_indexes: HashMap<(String, String, String), usize>,
+    /// A `jfieldID` is the index in here plus one.
+    field_ids: Vec<FieldId>,
+    field_id_indexes: HashMap<(String, String, bool), usize>,
+}
+
+impl Jni {
+    /// The objects referred to by global references, roots of the collector.
+    pub fn global_refs(&self) -> impl Iterator<Item = Operand> + '_ {
+        self.global_refs.iter().cloned()
+    }
+
+    fn new_global_ref(&mut self, value: Operand) -> jobject {
+        if value == Operand::Null {
+            return ptr::null_mut();
+        }
+        let index = match self.free_global_refs.pop() {
+            Some(index) => {
+                self.global_refs[index] = value;
+                index
+            }
+            None => {
+                self.global_refs.push(value);
+                self.global_refs.len() - 1
+            }
+        };
+        (((index + 1) << 1) | 1) as jobject
+    }
+
+    fn delete_global_ref(&mut self, obj: jobject) {
+        let index = ((obj as usize) >> 1) - 1;
+        self.global_refs[index] = Operand::Null;
+        self.free_global_refs.push(index);
+    }
+
+    fn method_id(&mut self, method: Method) -> jmethodID {
+        let key = (
+            method.class_name().to_string(),
+            method.name().to_string(),
+            method.descriptor().to_string(),
+        );
+        let method_ids = &mut self.method_ids;
+        let index = *self.method_id_indexes.entry(key).or_insert_with(|| {
+            let parameter_types: Vec<u8> = method
+                .param_descriptors()
+                .iter()
+                .map(|descriptor| descriptor.as_bytes()[0])
+                .collect();
+            method_ids.push(MethodId {
+                method,
+                parameter_types: CString::new(parameter_types).unwrap(),
+            });
+            method_ids.len() - 1
+        });
+        (index + 1) as jmethodID
+    }
+
+    fn method(&self, method_id: jmethodID) -> &MethodId {
+        &self.method_ids[method_id as usize - 1]
+    }
+
+    fn field_id(&mut self, class: Class, field: Field) -> jfieldID {
+        let key = (class.name().to_string(), field.name(), field.is_static());
+        let field_ids = &mut self.field_ids;
+        let index = *self.field_id_indexes.entry(key).or_insert_with(|| {
+            field_ids.push(FieldId { class, field });
+            field_ids.len() - 1
+        });
+        (index + 1) as jfieldID
+    }
+
+    fn field(&self, field_id: jfieldID) -> &FieldId {
+        &self.field_ids[field_id as usize - 1]
+    }
+
+    /// Binds a native method to a C function, as `RegisterNatives` does.
+    fn register_native(&mut self, class_name: &str, name: &str, descriptor: &str, code: usize) {
+        let key = (
+            class_name.to_string(),
+            name.to_string(),
+            descriptor.to_string(),
+        );
+        self.natives.insert(key, code);
+    }
+
+    fn unregister_natives(&mut self, class_name: &str) {
+        self.natives.retain(|(name, _, _), _| name != class_name);
+    }
+
+    /// The C function of the native `method`, registered with
+    /// `RegisterNatives` or exported by a loaded library under the short or
+    /// the long name of the method.
+    pub fn find_native(&mut self, method: &Method) -> Option<usize> {
+        let key = (
+            method.class_name().to_string(),
+            method.name().to_string(),
+            method.descriptor().to_string(),
+        );
+        if let Some(code) = self.natives.get(&key) {
+            return Some(*code);
+        }
+        let short_name = format!(
+            "Java_{}_{}",
+            mangle(method.class_name()),
+            mangle(method.name())
+        );
+        let descriptor = method.descriptor();
+        let parameters = &descriptor[1..descriptor.find(')').unwrap()];
+        let long_name = format!("{}__{}", short_name, mangle(parameters));
+        let code = [short_name, long_name].iter().find_map(|name| {
+            self.libraries
+                .iter()
+                .find_map(|library| find_symbol(library, name))
+        })?;
+        self.natives.insert(key, code);
+        Some(code)
+    }
+}
+
+fn find_symbol(library: &Library, name: &str) -> Option<usize> {
+    // SAFETY: the symbol is only called through a `Cif` matching the
+    // descriptor of the method it is bound to
+    let symbol = unsafe { library.get::<*const c_void>(name.as_bytes()) }.ok()?;
+    Some(*symbol as usize)
+}
+
+/// Escapes a class or method name or the parameter descriptors of a method
+/// for the name of a C function, `/` separates packages.
+fn mangle(name: &str) -> String {
+    let mut mangled = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '/' => mangled.push('_'),
+            '_' => mangled.push_str("_1"),
+            ';' => mangled.push_str("_2"),
+            '[' => mangled.push_str("_3"),
+            c if c.is_ascii_alphanumeric() => mangled.push(c),
+            c => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    write!(mangled, "_0{:04x}", unit).unwrap();
+                }
+            }
+        }
+    }
+    mangled
+}
+
+/// The default of `java.library.path`, `LD_LIBRARY_PATH` first.
+pub fn library_path() -> String {
+    match std::env::var("LD_LIBRARY_PATH") {
+        Ok(path) if !path.is_empty() => format!("{}:{}", path, DEFAULT_LIBRARY_PATH),
+        _ => DEFAULT_LIBRARY_PATH.to_string(),
+    }
+}
+
+/// A local reference to `value`, valid until the native method it is created
+/// for returns or its local frame is popped.
+///
+/// References are handles rather than addresses so null stays null. Local
+/// ones are even, `(index + 1) << 1` with the index into
+/// `JvmThread::jni_local_refs`, global ones are odd.
+pub fn local_ref(jenv: &mut JvmEnv, value: Operand) -> jobject {
+    if value == Operand::Null {
+        return ptr::null_mut();
+    }
+    let refs = &mut jenv.thread_mut().jni_local_refs;
+    refs.push(value);
+    (refs.len() << 1) as jobject
+}
+
+/// What the local or global reference `obj` refers to.
+pub fn referent(jenv: &JvmEnv, obj: jobject) -> Operand {
+    let handle = obj as usize;
+    if handle == 0 {
+        Operand::Null
+    } else if handle & 1 == 0 {
+        jenv.thread().jni_local_refs[(handle >> 1) - 1].clone()
+    } else {
+        jenv.jni().global_refs[(handle >> 1) - 1].clone()
+    }
+}
+
+fn push_local_frame(jenv: &mut JvmEnv) {
+    let start = jenv.thread().jni_local_refs.len();
+    jenv.thread_mut().jni_local_frames.push(start);
+}
+
+fn pop_local_frame(jenv: &mut JvmEnv) {
+    if let Some(start) = jenv.thread_mut().jni_local_frames.pop() {
+        jenv.thread_mut().jni_local_refs.truncate(start);
+    }
+}
+
+/// `value` of the type `descriptor` as native code gets it, references become
+/// local references.
+fn to_jvalue(jenv: &mut JvmEnv, descriptor: u8, value: Operand) -> jvalue {
+    match descriptor {
+        b'Z' => jvalue {
+            z: value.get_int() as jboolean,
+        },
+        b'B' => jvalue {
+            b: value.get_int() as jbyte,
+        },
+        b'C' => jvalue {
+            c: value.get_int() as jchar,
+        },
+        b'S' => jvalue {
+            s: value.get_int() as jshort,
+        },
+        b'I' => jvalue { i: value.get_int() },
+        b'J' => jvalue {
+            j: value.get_long(),
+        },
+        b'F' => jvalue {
+            f: value.get_float(),
+        },
+        b'D' => jvalue {
+            d: value.get_double(),
+        },
+        _ => jvalue {
+            l: local_ref(jenv, value),
+        },
+    }
+}
+
+/// A value of the type `descriptor` from native code as an operand.
+fn from_jvalue(jenv: &JvmEnv, descriptor: u8, value: jvalue) -> Operand {
+    // SAFETY: native code passes the member of the type
+    unsafe {
+        match descriptor {
+            b'Z' => Operand::Int((value.z != JNI_FALSE) as i32),
+            b'B' => Operand::Int(value.b as i32),
+            b'C' => Operand::Int(value.c as i32),
+            b'S' => Operand::Int(value.s as i32),
+            b'I' => Operand::Int(value.i),
+            b'J' => Operand::Long(value.j),
+            b'F' => Operand::Float(value.f),
+            b'D' => Operand::Double(value.d),
+            _ => referent(jenv, value.l),
+        }
+    }
+}
+
+fn ffi_type(descriptor: u8) -> Type {
+    match descriptor {
+        b'Z' => Type::u8(),
+        b'B' => Type::i8(),
+        b'C' => Type::u16(),
+        b'S' => Type::i16(),
+        b'I' => Type::i32(),
+        b'J' => Type::i64(),
+        b'F' => Type::f32(),
+        b'D' => Type::f64(),
+        b'V' => Type::void(),
+        _ => Type::pointer(),
+    }
+}
+
+/// Runs `f` with the `JNIEnv` of the thread and without the global lock, the
+/// JNI functions take it while they use the VM.
+fn in_native<R>(jenv: &mut JvmEnv, f: impl FnOnce(*mut JniEnv) -> R) -> R {
+    let mut env = JniEnv {
+        functions: functions::function_table(),
+        jenv,
+    };
+    let env: *mut JniEnv = &mut env;
+    let outer_env = CURRENT_ENV.with(|current| current.replace(env));
+    jenv.release_global_lock();
+    let result = f(env);
+    jenv.acquire_global_lock();
+    CURRENT_ENV.with(|current| current.set(outer_env));
+    result
+}
+
+/// Calls the C function at `code` bound to the native `method` of `class`
+/// and pushes what it returns, unless it left an exception pending.
+pub fn call_native(
+    jenv: &mut JvmEnv,
+    class: &Class,
+    method: &Method,
+    code: usize,
+    args: Vec<Operand>,
+) {
+    let depth = jenv.thread().jni_local_frames.len();
+    push_local_frame(jenv);
+
+    // the `JNIEnv` is only known in `in_native`
+    let mut types = vec![Type::pointer(), Type::pointer()];
+    let mut values = vec![jvalue { l: ptr::null_mut() }];
+    let mut args = args.into_iter();
+    let receiver = if method.is_static() {
+        Operand::ObjectRef(jenv.class_mirror(class))
+    } else {
+        args.next().unwrap()
+    };
+    values.push(jvalue {
+        l: local_ref(jenv, receiver),
+    });
+    for (descriptor, arg) in method.param_descriptors().iter().zip(args) {
+        let descriptor = descriptor.as_bytes()[0];
+        types.push(ffi_type(descriptor));
+        values.push(to_jvalue(jenv, descriptor, arg));
+    }
+    let return_type = method.return_descriptor().as_bytes()[0];
+    let cif = Cif::new(types, ffi_type(return_type));
+    let code = CodePtr::from_ptr(code as *const c_void);
+
+    let result = in_native(jenv, |env| {
+        values[0].l = env as jobject;
+        let args: Vec<Arg> = values.iter().map(Arg::new).collect();
+        // SAFETY: the types of the `Cif` are those of the method, which the
+        // function is declared with if it follows JNI
+        unsafe {
+            match return_type {
+                b'V' => {
+                    cif.call::<()>(code, &args);
+                    jvalue { j: 0 }
+                }
+                b'J' => jvalue {
+                    j: cif.call(code, &args),
+                },
+                b'F' => jvalue {
+                    f: cif.call(code, &args),
+                },
+                b'D' => jvalue {
+                    d: cif.call(code, &args),
+                },
+                // libffi widens smaller results to a whole register
+                _ => widened_jvalue(return_type, cif.call::<usize>(code, &args)),
+            }
+        }
+    });
+    let result = from_jvalue(jenv, return_type, result);
+    while jenv.thread().jni_local_frames.len() > depth {
+        pop_local_frame(jenv);
+    }
+
+    if return_type != b'V' && !jenv.thread().has_pending_exception() {
+        let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+        frame.operand_stack.push(result);
+    }
+}
+
+fn widened_jvalue(descriptor: u8, value: usize) -> jvalue {
+    match descriptor {
+        b'Z' => jvalue {
+            z: value as jboolean,
+        },
+        b'B' => jvalue { b: value as jbyte },
+        b'C' => jvalue { c: value as jchar },
+        b'S' => jvalue { s: value as jshort },
+        b'I' => jvalue { i: value as jint },
+        _ => jvalue {
+            l: value as jobject,
+        },
+    }
+}
+
+/// Loads the library at `path` and runs its `JNI_OnLoad`, returns the handle
+/// of the library and the JNI version it needs.
+fn load_library(jenv: &mut JvmEnv, path: &str) -> Result<(jlong, jint), String> {
+    // SAFETY: running the initializers of a library is what loading it is for
+    let library = unsafe { Library::new(path) }.map_err(|e| e.to_string())?;
+    type OnLoad = unsafe extern "C" fn(*mut functions::JavaVm, *mut c_void) -> jint;
+    // SAFETY: `JNI_OnLoad` has this signature
+    let on_load = unsafe { library.get::<OnLoad>(b"JNI_OnLoad") }
+        .ok()
+        .map(|on_load| *on_load);
+    jenv.jni_mut().libraries.push(library);
+    let handle = jenv.jni().libraries.len() as jlong;
+    debug!(path, handle, "load_library");
+
+    let version = match on_load {
+        Some(on_load) => {
+            push_local_frame(jenv);
+            let version = in_native(jenv, |_| unsafe {
+                on_load(functions::java_vm(), ptr::null_mut())
+            });
+            pop_local_frame(jenv);
+            version
+        }
+        None => JNI_VERSION_1_1,
+    };
+    if !is_supported_version(version) {
+        return Err(format!(
+            "unsupported JNI version 0x{:x} required by {}",
+            version, path
+        ));
+    }
+    Ok((handle, version))
+}
+
+fn is_supported_version(version: jint) -> bool {
+    matches!(
+        version,
+        JNI_VERSION_1_1 | JNI_VERSION_1_2 | JNI_VERSION_1_4 | JNI_VERSION_1_6 | JNI_VERSION_1_8
+    )
+}
+
+/// Loads the library at the path `args[1]` for the `NativeLibrary` `args[0]`,
+/// which is marked as loaded unless it fails with an `UnsatisfiedLinkError`.
+pub fn java_lang_ClassLoader_NativeLibrary_load(
+    jenv: &mut JvmEnv,
+    _class: &Class,
+    args: Vec<Operand>,
+) {
+    let path = jenv.get_java_string(&args[1]);
+    match load_library(jenv, &path) {
+        Ok(_) if jenv.thread().has_pending_exception() => {}
+        Ok((handle, version)) => {
+            let library = jenv.heap_mut().get_object_mut(&args[0]);
+            library.set_field_by_name("handle", "J", Operand::Long(handle));
+            library.set_field_by_name("jniVersion", "I", Operand::Int(version));
+            library.set_field_by_name("loaded", "Z", Operand::Int(1));
+        }
+        Err(message) => {
+            jenv.throw_exception(JAVA_LANG_UNSATISFIED_LINK_ERROR, Some(&message));
+        }
+    }
+}
+
+/// The address of the symbol `args[1]` in the library of the `NativeLibrary`
+/// `args[0]`, zero if there is none.
+pub fn java_lang_ClassLoader_NativeLibrary_find(
+    jenv: &mut JvmEnv,
+    _class: &Class,
+    args: Vec<Operand>,
+) {
+    let name = jenv.get_java_string(&args[1]);
+    let handle = jenv
+        .heap()
+        .get_object(&args[0])
+        .get_field_by_name("handle", "J")
+        .get_long();
+    let library = (handle as usize)
+        .checked_sub(1)
+        .and_then(|index| jenv.jni().libraries.get(index));
+    let address = library
+        .and_then(|library| find_symbol(library, &name))
+        .unwrap_or(0);
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(Operand::Long(address as i64));
+}
+
+/// Libraries stay loaded, their natives may still be bound to methods.
+pub fn java_lang_ClassLoader_NativeLibrary_unload(
+    _jenv: &mut JvmEnv,
+    _class: &Class,
+    _args: Vec<Operand>,
+) {
+}
+
+/// No library is linked into the VM, so the name of none is returned.
+pub fn java_lang_ClassLoader_NativeLibrary_findBuiltinLib(
+    jenv: &mut JvmEnv,
+    _class: &Class,
+    _args: Vec<Operand>,
+) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(Operand::Null);
+}
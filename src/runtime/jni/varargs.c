@@ -0,0 +1,114 @@
+/*
+ * The JNI functions taking variable arguments, which can't be written in
+ * Rust. They read the arguments by the parameter types of the method into a
+ * jvalue array and call the `A` function taking that array.
+ */
+#include <stdarg.h>
+#include <stdint.h>
+
+typedef void *jobject;
+typedef jobject jclass;
+typedef void *jmethodID;
+typedef uint8_t jboolean;
+typedef int8_t jbyte;
+typedef uint16_t jchar;
+typedef int16_t jshort;
+typedef int32_t jint;
+typedef int64_t jlong;
+typedef float jfloat;
+typedef double jdouble;
+
+typedef union {
+    jboolean z;
+    jbyte b;
+    jchar c;
+    jshort s;
+    jint i;
+    jlong j;
+    jfloat f;
+    jdouble d;
+    jobject l;
+} jvalue;
+
+/* A method descriptor has at most 255 parameters. */
+#define MAX_PARAMETERS 255
+
+/* The first characters of the parameter descriptors of `method`. */
+const char *hippo_jni_parameter_types(void *env, jmethodID method);
+
+static void read_args(void *env, jmethodID method, va_list args, jvalue *values) {
+    const char *types = hippo_jni_parameter_types(env, method);
+    for (int i = 0; types[i]; i++) {
+        /* variadic arguments smaller than int are promoted to int, floats to double */
+        switch (types[i]) {
+        case 'Z': values[i].z = (jboolean) va_arg(args, int); break;
+        case 'B': values[i].b = (jbyte) va_arg(args, int); break;
+        case 'C': values[i].c = (jchar) va_arg(args, int); break;
+        case 'S': values[i].s = (jshort) va_arg(args, int); break;
+        case 'I': values[i].i = va_arg(args, jint); break;
+        case 'J': values[i].j = va_arg(args, jlong); break;
+        case 'F': values[i].f = (jfloat) va_arg(args, double); break;
+        case 'D': values[i].d = va_arg(args, double); break;
+        default: values[i].l = va_arg(args, jobject); break;
+        }
+    }
+}
+
+#define UNPAREN(...) __VA_ARGS__
+
+/*
+ * `Name` and `NameV` calling `NameA`. `Params` are the parameters between the
+ * JNIEnv and the jmethodID, `Args` their names. `RETURN` is empty for void.
+ */
+#define VARARGS(Name, Result, RETURN, Params, Args)                             \
+    Result hippo_jni_##Name##A(void *env, UNPAREN Params, jmethodID method,     \
+                               const jvalue *args);                             \
+    static Result hippo_jni_##Name##V(void *env, UNPAREN Params,                \
+                                      jmethodID method, va_list args) {         \
+        jvalue values[MAX_PARAMETERS];                                          \
+        read_args(env, method, args, values);                                   \
+        RETURN hippo_jni_##Name##A(env, UNPAREN Args, method, values);          \
+    }                                                                           \
+    static Result hippo_jni_##Name(void *env, UNPAREN Params, jmethodID method, \
+                                   ...) {                                       \
+        jvalue values[MAX_PARAMETERS];                                          \
+        va_list args;                                                           \
+        va_start(args, method);                                                 \
+        read_args(env, method, args, values);                                   \
+        va_end(args);                                                           \
+        RETURN hippo_jni_##Name##A(env, UNPAREN Args, method, values);          \
+    }
+
+#define CALLS(Type, Result, RETURN)                                             \
+    VARARGS(Call##Type##Method, Result, RETURN, (jobject obj), (obj))           \
+    VARARGS(CallNonvirtual##Type##Method, Result, RETURN,                       \
+            (jobject obj, jclass clazz), (obj, clazz))                          \
+    VARARGS(CallStatic##Type##Method, Result, RETURN, (jclass clazz), (clazz))
+
+CALLS(Object, jobject, return)
+CALLS(Boolean, jboolean, return)
+CALLS(Byte, jbyte, return)
+CALLS(Char, jchar, return)
+CALLS(Short, jshort, return)
+CALLS(Int, jint, return)
+CALLS(Long, jlong, return)
+CALLS(Float, jfloat, return)
+CALLS(Double, jdouble, return)
+CALLS(Void, void, )
+VARARGS(NewObject, jobject, return, (jclass clazz), (clazz))
+
+#define PAIR(Name) {(const void *) hippo_jni_##Name, (const void *) hippo_jni_##Name##V}
+#define FAMILY(Kind)                                                            \
+    {PAIR(Call##Kind##ObjectMethod), PAIR(Call##Kind##BooleanMethod),           \
+     PAIR(Call##Kind##ByteMethod), PAIR(Call##Kind##CharMethod),                \
+     PAIR(Call##Kind##ShortMethod), PAIR(Call##Kind##IntMethod),                \
+     PAIR(Call##Kind##LongMethod), PAIR(Call##Kind##FloatMethod),               \
+     PAIR(Call##Kind##DoubleMethod), PAIR(Call##Kind##VoidMethod)}
+
+const void *const hippo_jni_call_varargs[3][10][2] = {
+    FAMILY(),
+    FAMILY(Nonvirtual),
+    FAMILY(Static),
+};
+
+const void *const hippo_jni_new_object_varargs[2] = PAIR(NewObject);
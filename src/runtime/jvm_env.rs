@@ -1,23 +1,54 @@
 use crate::class_path::ClassPath;
-use crate::runtime::class::Class;
-use crate::runtime::class_loader::BootstrapClassLoader;
+use crate::runtime::class::{Class, InitState};
+use crate::runtime::class_loader::{BootstrapClassLoader, LoadError};
 use crate::runtime::execute_method;
 use crate::runtime::frame::operand_stack::Operand;
+use crate::runtime::global_lock::{GlobalLock, Shared};
 use crate::runtime::heap::{
-    JvmHeap, JAVA_LANG_CLASS, JAVA_LANG_STRING, JAVA_LANG_STRING_DESCRIPTOR, JAVA_LANG_THREAD,
-    JAVA_LANG_THREAD_GROUP, JAVA_LANG_THREAD_GROUP_DESCRIPTOR,
+    JvmHeap, JAVA_LANG_CLASS, JAVA_LANG_ERROR, JAVA_LANG_EXCEPTION_IN_INITIALIZER_ERROR,
+    JAVA_LANG_NO_CLASS_DEF_FOUND_ERROR, JAVA_LANG_OUT_OF_MEMORY_ERROR,
+    JAVA_LANG_STACK_OVERFLOW_ERROR, JAVA_LANG_STRING, JAVA_LANG_STRING_DESCRIPTOR,
+    JAVA_LANG_THREAD, JAVA_LANG_THREAD_GROUP, JAVA_LANG_THREAD_GROUP_DESCRIPTOR,
+    JAVA_LANG_VERIFY_ERROR,
 };
+use crate::runtime::instruction::can_cast_to;
+use crate::runtime::io::FileTable;
+use crate::runtime::jni::Jni;
 use crate::runtime::jvm_thread::JvmThread;
-use crate::runtime::method::Method;
+use crate::runtime::mirror::{MirroredType, Mirrors};
+use crate::runtime::monitor::{monitor_enter, monitor_exit, monitor_notify};
+use crate::runtime::native_registry::NativeRegistry;
+use crate::runtime::report_uncaught_exception;
+use crate::runtime::string_table::StringTable;
+use crate::runtime::zip_file::ZipTable;
 use nom::lib::std::collections::HashMap;
 use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 use tracing::{debug, debug_span};
 
 const JAVA_STRING_FIELD_VALUE_INDEX: usize = 0;
 const JAVA_STRING_FIELD_HASH_INDEX: usize = 1;
 
+/// `Thread.threadStatus` values, as `sun.misc.VM.toThreadState` reads them.
+const JVMTI_THREAD_STATE_RUNNABLE: i32 = 0x0004;
+const JVMTI_THREAD_STATE_TERMINATED: i32 = 0x0002;
+/// How often a thread blocked without a wake-up checks its condition again.
+const THREAD_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 pub type JvmPC = usize;
 
+/// Estimated size of a frame, a thread has at most its stack size divided by
+/// it frames.
+pub const FRAME_SIZE: usize = 64;
+/// The stack size of threads without `-Xss`.
+const DEFAULT_STACK_SIZE: usize = 1 << 20;
+/// The most interpreter loops a thread runs inside each other, e.g. for
+/// `Method.invoke` calling a method that calls `Method.invoke`. Unlike frames
+/// they take room on the native stack, which is 2 MB for spawned threads.
+const MAX_NESTED_INTERPRETERS: usize = 128;
+
 #[derive(Debug)]
 pub struct ClassId {
     name: String,
@@ -38,31 +69,279 @@ impl PartialEq for ClassId {
 
 impl Eq for ClassId {}
 
+/// Instructions a thread runs before it lets waiting threads take over.
+const YIELD_INTERVAL: u32 = 1000;
+
+/// The view of the VM of one Java thread. Everything but the thread-local
+/// state is shared with the environments of the other threads.
 #[derive(Debug)]
 pub struct JvmEnv {
-    pub heap: JvmHeap,
-    pub thread: JvmThread,
-    pub bootstrap_class_loader: BootstrapClassLoader,
-    pub defining_classes: HashMap<ClassId, Class>,
-    pub initiating_classes: HashMap<ClassId, Class>,
+    heap: Shared<JvmHeap>,
+    thread: Shared<JvmThread>,
+    bootstrap_class_loader: Shared<BootstrapClassLoader>,
+    defining_classes: Shared<HashMap<ClassId, Class>>,
+    initiating_classes: Shared<HashMap<ClassId, Class>>,
+    mirrors: Shared<Mirrors>,
+    string_table: Shared<StringTable>,
+    natives: Shared<NativeRegistry>,
+    jni: Shared<Jni>,
+    files: Shared<FileTable>,
+    zips: Shared<ZipTable>,
+    /// Set while the `OutOfMemoryError` itself is allocated.
+    throwing_out_of_memory_error: bool,
+    /// The most frames a thread can have, see `set_stack_size`.
+    pub max_stack_depth: usize,
+    /// Set while the `StackOverflowError` itself is constructed, its
+    /// constructors need frames beyond the limit.
+    throwing_stack_overflow_error: bool,
+    /// The interpreter loops the thread runs inside each other, see
+    /// `enter_interpreter`.
+    nested_interpreters: usize,
+    /// Added to the defaults of `System.getProperties()`, e.g. from `-D`.
+    pub system_properties: Vec<(String, String)>,
+    /// Set by `Runtime.halt()`, every frame of every thread unwinds once it is.
+    exit_status: Shared<Option<i32>>,
+    /// All started threads that haven't terminated, including this one.
+    threads: Shared<Vec<Shared<JvmThread>>>,
+    global_lock: Arc<GlobalLock>,
+    instructions_until_yield: u32,
+}
+
+/// Methods reading and changing the shared values of the environment. They are
+/// safe since an environment is only used by its own thread, which holds the
+/// global lock while it does, and holds one handle to every value but its
+/// thread, which is also in `threads`. Borrowing the whole environment keeps
+/// the returned references from overlapping with a mutable one to the value.
+macro_rules! shared {
+    ($($field:ident: $name:ident -> $ty:ty),* $(,)?) => {
+        impl JvmEnv {
+            $(
+                pub fn $field(&self) -> &$ty {
+                    // SAFETY: see `shared`
+                    unsafe { self.$field.get() }
+                }
+
+                pub fn $name(&mut self) -> &mut $ty {
+                    // SAFETY: see `shared`
+                    unsafe { self.$field.get_mut() }
+                }
+            )*
+        }
+    };
+}
+
+shared! {
+    heap: heap_mut -> JvmHeap,
+    thread: thread_mut -> JvmThread,
+    bootstrap_class_loader: bootstrap_class_loader_mut -> BootstrapClassLoader,
+    defining_classes: defining_classes_mut -> HashMap<ClassId, Class>,
+    initiating_classes: initiating_classes_mut -> HashMap<ClassId, Class>,
+    mirrors: mirrors_mut -> Mirrors,
+    string_table: string_table_mut -> StringTable,
+    natives: natives_mut -> NativeRegistry,
+    jni: jni_mut -> Jni,
+    files: files_mut -> FileTable,
+    zips: zips_mut -> ZipTable,
+    exit_status: exit_status_mut -> Option<i32>,
+    threads: threads_mut -> Vec<Shared<JvmThread>>,
 }
 
 impl JvmEnv {
-    pub fn new(jre_opt: Option<String>, cp_opt: Option<String>) -> Self {
-        let mut jenv = JvmEnv {
-            heap: JvmHeap::new(),
-            thread: JvmThread::new(),
-            bootstrap_class_loader: BootstrapClassLoader::new(ClassPath::new(jre_opt, cp_opt)),
-            defining_classes: Default::default(),
-            initiating_classes: Default::default(),
-        };
+    pub fn new(class_path: ClassPath) -> Self {
+        let mut jenv = JvmEnv::with_class_path(class_path);
         let thread_addr = jenv.new_java_lang_thread("main");
-        jenv.thread.object_addr = thread_addr;
+        jenv.thread_mut().object_addr = thread_addr;
         jenv
     }
 
+    /// An environment without the `java.lang.Thread` object of the main thread,
+    /// for running classes that don't need the JDK.
+    pub fn with_class_path(class_path: ClassPath) -> Self {
+        let thread = Shared::new(JvmThread::new());
+        JvmEnv {
+            heap: Shared::new(JvmHeap::new()),
+            thread: thread.clone(),
+            bootstrap_class_loader: Shared::new(BootstrapClassLoader::new(class_path)),
+            defining_classes: Shared::new(Default::default()),
+            initiating_classes: Shared::new(Default::default()),
+            mirrors: Shared::new(Default::default()),
+            string_table: Shared::new(Default::default()),
+            natives: Shared::new(NativeRegistry::new()),
+            jni: Shared::new(Default::default()),
+            files: Shared::new(FileTable::new()),
+            zips: Shared::new(ZipTable::new()),
+            throwing_out_of_memory_error: false,
+            max_stack_depth: DEFAULT_STACK_SIZE / FRAME_SIZE,
+            throwing_stack_overflow_error: false,
+            nested_interpreters: 0,
+            system_properties: Vec::new(),
+            exit_status: Shared::new(None),
+            threads: Shared::new(vec![thread]),
+            // the creating thread runs first
+            global_lock: Arc::new(GlobalLock::new_held()),
+            instructions_until_yield: YIELD_INTERVAL,
+        }
+    }
+
+    /// An environment for running `thread` over the same VM.
+    fn with_thread(&self, thread: Shared<JvmThread>) -> Self {
+        JvmEnv {
+            heap: self.heap.clone(),
+            thread,
+            bootstrap_class_loader: self.bootstrap_class_loader.clone(),
+            defining_classes: self.defining_classes.clone(),
+            initiating_classes: self.initiating_classes.clone(),
+            mirrors: self.mirrors.clone(),
+            string_table: self.string_table.clone(),
+            natives: self.natives.clone(),
+            jni: self.jni.clone(),
+            files: self.files.clone(),
+            zips: self.zips.clone(),
+            throwing_out_of_memory_error: false,
+            max_stack_depth: self.max_stack_depth,
+            throwing_stack_overflow_error: false,
+            nested_interpreters: 0,
+            system_properties: self.system_properties.clone(),
+            exit_status: self.exit_status.clone(),
+            threads: self.threads.clone(),
+            global_lock: self.global_lock.clone(),
+            instructions_until_yield: YIELD_INTERVAL,
+        }
+    }
+
+    /// Called by the interpreter before every instruction, lets other threads
+    /// run now and then.
+    pub fn safepoint(&mut self) {
+        self.instructions_until_yield -= 1;
+        if self.instructions_until_yield == 0 {
+            self.instructions_until_yield = YIELD_INTERVAL;
+            if self.global_lock.has_waiters() {
+                self.blocking(thread::yield_now);
+            }
+        }
+    }
+
+    /// Runs `f` without the global lock so other threads can run meanwhile,
+    /// e.g. to sleep. `f` can't touch the VM.
+    pub fn blocking<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        self.global_lock.release();
+        let result = f();
+        self.global_lock.acquire();
+        result
+    }
+
+    /// Gives up the global lock before calling into native code that may
+    /// block, unlike `blocking` the code can take it back to use the VM.
+    pub fn release_global_lock(&self) {
+        self.global_lock.release();
+    }
+
+    pub fn acquire_global_lock(&self) {
+        self.global_lock.acquire();
+    }
+
+    /// All started threads that haven't terminated, including this one.
+    fn live_threads(&self) -> impl Iterator<Item = &JvmThread> {
+        // SAFETY: like the values of `shared`, a thread only changes its own
+        // state while it holds the global lock, and this one can't while its
+        // environment is borrowed
+        self.threads().iter().map(|t| unsafe { t.get() })
+    }
+
+    /// The live thread whose `java.lang.Thread` is `thread_addr`.
+    pub fn find_thread(&self, thread_addr: u32) -> Option<&JvmThread> {
+        self.live_threads()
+            .find(|t| t.object_addr == Some(thread_addr))
+    }
+
+    /// Runs the `java.lang.Thread` at `thread_addr` in a new native thread,
+    /// which starts interpreting once it gets the global lock.
+    pub fn start_thread(&mut self, thread_addr: u32) {
+        let thread_ref = Operand::ObjectRef(thread_addr);
+        let object = self.heap().get_object(&thread_ref);
+        let name = object
+            .get_field_by_name("name", JAVA_LANG_STRING_DESCRIPTOR)
+            .clone();
+        let name = self.get_java_string(&name);
+        let mut thread = JvmThread::new();
+        thread.object_addr = Some(thread_addr);
+        thread.daemon = self
+            .heap()
+            .get_object(&thread_ref)
+            .get_field_by_name("daemon", "Z")
+            != &Operand::Int(0);
+        set_thread_status(self, thread_addr, JVMTI_THREAD_STATE_RUNNABLE);
+        let thread = Shared::new(thread);
+        self.threads_mut().push(thread.clone());
+
+        let mut jenv = self.with_thread(thread);
+        thread::Builder::new()
+            .name(name)
+            .spawn(move || {
+                jenv.global_lock.acquire();
+                jenv.run_thread(thread_addr);
+                jenv.global_lock.release();
+            })
+            .expect("spawn thread");
+    }
+
+    fn run_thread(&mut self, thread_addr: u32) {
+        let thread_ref = Operand::ObjectRef(thread_addr);
+        let class_name = self.heap().get_class_name(&thread_ref);
+        let class = self.load_and_init_class(&class_name);
+        let run_method = class.get_method("run", "()V", false).expect("Thread.run");
+        execute_method(self, run_method, vec![thread_ref]);
+        if let Some(exception) = self.thread_mut().take_pending_exception() {
+            if self.exit_status().is_none() {
+                report_uncaught_exception(self, exception);
+            }
+        }
+        // lets the thread group forget the thread
+        if let Some(exit_method) = class.get_method("exit", "()V", false) {
+            if self.exit_status().is_none() {
+                execute_method(self, exit_method, vec![Operand::ObjectRef(thread_addr)]);
+                let _ = self.thread_mut().take_pending_exception();
+            }
+        }
+
+        // like `ensure_join`, `Thread.join` waits on the thread object
+        let thread_ref = Operand::ObjectRef(thread_addr);
+        monitor_enter(self, &thread_ref);
+        set_thread_status(self, thread_addr, JVMTI_THREAD_STATE_TERMINATED);
+        let thread = self.thread.clone();
+        self.threads_mut().retain(|t| !t.ptr_eq(&thread));
+        monitor_notify(self, &thread_ref, true);
+        monitor_exit(self, &thread_ref);
+    }
+
+    /// Blocks until every non-daemon thread but this one has terminated, or
+    /// the VM halts.
+    pub fn wait_for_non_daemon_threads(&mut self) {
+        loop {
+            let current = self.thread();
+            let running = self
+                .live_threads()
+                .any(|t| !t.daemon && !std::ptr::eq(t, current));
+            if !running || self.exit_status().is_some() {
+                return;
+            }
+            let parker = self.thread().parker.clone();
+            let deadline = Instant::now() + THREAD_POLL_INTERVAL;
+            self.blocking(|| parker.park(Some(deadline)));
+        }
+    }
+
+    /// Stops the VM with `status`, all threads unwind without running any more
+    /// Java code.
+    pub fn halt(&mut self, status: i32) {
+        *self.exit_status_mut() = Some(status);
+        for thread in self.live_threads() {
+            thread.parker.unpark();
+        }
+    }
+
     pub fn get_classloader(&self, class: &Class) -> Operand {
-        self.defining_classes
+        self.defining_classes()
             .keys()
             .find_map(|k| {
                 if k.name == class.name() {
@@ -74,21 +353,59 @@ impl JvmEnv {
             .unwrap_or(Operand::Null)
     }
 
+    /// Loads and initializes a class the VM itself relies on, it must link.
+    /// An exception thrown by its initialization is left pending.
     pub fn load_and_init_class(&mut self, class_name: &str) -> Class {
-        let current_class = self.thread.current_class();
+        let class = self.load_class(class_name);
+        self.initialize_class(&class);
+        class
+    }
+
+    /// Loads and links a class the VM itself relies on, it must link.
+    pub fn load_class(&mut self, class_name: &str) -> Class {
+        self.resolve_class(class_name)
+            .unwrap_or_else(|| panic!("link class: {}", class_name))
+    }
+
+    /// Resolves a class referenced by an instruction that initializes it,
+    /// like `new` or `getstatic`.
+    pub fn resolve_and_init_class(&mut self, class_name: &str) -> Option<Class> {
+        let class = self.resolve_class(class_name)?;
+        if self.initialize_class(&class) {
+            Some(class)
+        } else {
+            None
+        }
+    }
+
+    /// Loads and links a class referenced by the running code. Throws
+    /// `NoClassDefFoundError` or `VerifyError` and returns `None` if it can't
+    /// be found or fails verification.
+    pub fn resolve_class(&mut self, class_name: &str) -> Option<Class> {
+        let current_class = self.thread().current_class();
         let class_loader_addr = (current_class.as_ref())
             .map(|c| self.get_classloader(c))
             .unwrap_or(Operand::Null);
-        if let Some(class) = self.initiating_classes.get(&ClassId {
+        if let Some(class) = self.initiating_classes().get(&ClassId {
             name: class_name.to_string(),
             classloader: class_loader_addr.clone(),
         }) {
-            return class.clone();
+            return Some(class.clone());
         }
 
-        let mut class = if class_loader_addr == Operand::Null {
-            let class = self.bootstrap_class_loader.load_class(class_name);
-            self.defining_classes.insert(
+        let class = if class_loader_addr == Operand::Null {
+            let class = match self.bootstrap_class_loader_mut().load_class(class_name) {
+                Ok(class) => class,
+                Err(LoadError::NotFound(message)) => {
+                    self.throw_exception(JAVA_LANG_NO_CLASS_DEF_FOUND_ERROR, Some(&message));
+                    return None;
+                }
+                Err(LoadError::Verify(e)) => {
+                    self.throw_exception(JAVA_LANG_VERIFY_ERROR, Some(&e.message));
+                    return None;
+                }
+            };
+            self.defining_classes_mut().insert(
                 ClassId {
                     name: class_name.to_string(),
                     classloader: class_loader_addr.clone(),
@@ -97,73 +414,232 @@ impl JvmEnv {
             );
             class
         } else {
-            let class_loader = self.heap.get_object(&class_loader_addr);
+            let class_loader = self.heap().get_object(&class_loader_addr);
             let load_class_method = class_loader
                 .get_method_by_name("loadClass", "(Ljava/lang/String;)Ljava/lang/Class;", false)
                 .unwrap();
-            let jclass_name = self.new_java_lang_string(class_name);
+            let jclass_name = self.new_java_lang_string(class_name)?;
             execute_method(
                 self,
                 load_class_method,
                 vec![Operand::ObjectRef(jclass_name)],
             );
-            let _jclass_addr = self.thread.current_frame_mut().operand_stack.pop();
-            self.load_and_init_class(class_name)
+            let _jclass_addr = self.thread_mut().current_frame_mut().operand_stack.pop();
+            self.resolve_class(class_name)?
         };
 
-        self.initiating_classes.insert(
+        self.initiating_classes_mut().insert(
             ClassId {
                 name: class_name.to_string(),
                 classloader: class_loader_addr,
             },
             class.clone(),
         );
+        Some(class)
+    }
 
-        if let Class::InstanceClass(class) = &mut class {
-            if !class.is_inited() {
-                let span = debug_span!("init_class", %class_name);
-                let _s = span.enter();
-                class.set_inited();
-                debug!("init successfully.");
-                let clinit_method = class.clinit_method();
-                if let Some(clinit_method) = clinit_method {
-                    execute_method(self, clinit_method, vec![]);
+    /// Initializes `class` unless it is already (JVMS §5.5): its super class
+    /// and the super interfaces with default methods first, then `<clinit>`.
+    /// Throws and returns false if any of them fails. A thread asking for a
+    /// class that another thread initializes waits until it's done.
+    pub fn initialize_class(&mut self, class: &Class) -> bool {
+        let class = match class {
+            Class::InstanceClass(class) => class,
+            _ => return true,
+        };
+        let thread_id = self.thread().id;
+        loop {
+            match class.init_state() {
+                InitState::Loaded | InitState::Linked => break,
+                InitState::Initialized => return true,
+                // a recursive request, e.g. from `<clinit>` itself
+                InitState::BeingInitialized(id) if id == thread_id => return true,
+                InitState::BeingInitialized(_) => {
+                    let class = class.clone();
+                    self.blocking(|| class.wait_for_init(thread_id));
+                    if self.exit_status().is_some() {
+                        return false;
+                    }
+                }
+                InitState::Erroneous => {
+                    let message = format!(
+                        "Could not initialize class {}",
+                        class.name().replace('/', ".")
+                    );
+                    self.throw_exception(JAVA_LANG_NO_CLASS_DEF_FOUND_ERROR, Some(&message));
+                    return false;
                 }
             }
         }
-        class
+
+        let span = debug_span!("init_class", %class);
+        let _s = span.enter();
+        class.set_init_state(InitState::BeingInitialized(thread_id));
+        for (index, value) in class.constant_strings() {
+            let addr = match self.intern_string(value) {
+                Some(addr) => addr,
+                None => {
+                    class.set_init_state(InitState::Erroneous);
+                    return false;
+                }
+            };
+            class.set_static_field_value(*index, Operand::ObjectRef(addr));
+        }
+        if class.is_class() {
+            let supers = class
+                .super_class()
+                .into_iter()
+                .chain(class.default_method_interfaces());
+            for super_class in supers {
+                if !self.initialize_class(&super_class.into()) {
+                    class.set_init_state(InitState::Erroneous);
+                    return false;
+                }
+            }
+        }
+        if let Some(clinit_method) = class.clinit_method() {
+            execute_method(self, clinit_method, vec![]);
+        }
+
+        let exception = match self.thread_mut().take_pending_exception() {
+            Some(exception) => exception,
+            None => {
+                class.set_init_state(InitState::Initialized);
+                debug!("init successfully.");
+                return true;
+            }
+        };
+        class.set_init_state(InitState::Erroneous);
+        let exception_class = self.load_class(&self.heap().get_class_name(&exception));
+        let error_class = self.load_class(JAVA_LANG_ERROR);
+        if can_cast_to(self, exception_class, error_class) {
+            self.thread_mut().set_pending_exception(exception);
+        } else {
+            self.throw_wrapped_exception(JAVA_LANG_EXCEPTION_IN_INITIALIZER_ERROR, exception);
+        }
+        false
     }
 
-    pub fn new_java_lang_string(&mut self, s: &str) -> u32 {
-        let bytes_str = s.encode_utf16();
-        let array = self.heap.new_char_array(bytes_str.collect());
+    /// Throws an exception of `class_name` made by its `(Throwable)`
+    /// constructor, like an `ExceptionInInitializerError` wrapping the
+    /// exception `<clinit>` threw.
+    pub fn throw_wrapped_exception(&mut self, class_name: &str, cause: Operand) {
+        // the cause must survive a collection while the class initializes
+        self.thread_mut().native_args.push(vec![cause.clone()]);
+        let class = self.load_and_init_class(class_name);
+        self.thread_mut().native_args.pop();
+        let (_, addr) = self.heap_mut().new_object(class.clone());
+        let init_method = class
+            .get_method("<init>", "(Ljava/lang/Throwable;)V", false)
+            .unwrap_or_else(|| panic!("{}.<init>(Throwable)", class_name));
+        execute_method(self, init_method, vec![Operand::ObjectRef(addr), cause]);
+        if !self.thread().has_pending_exception() {
+            self.thread_mut()
+                .set_pending_exception(Operand::ObjectRef(addr));
+        }
+    }
 
+    /// A new `java.lang.String` of `s`. Like every allocation it may collect
+    /// garbage first, and it returns None with an `OutOfMemoryError` pending
+    /// if the heap is full.
+    pub fn new_java_lang_string(&mut self, s: &str) -> Option<u32> {
+        self.new_java_lang_string_from_utf16(s.encode_utf16().collect())
+    }
+
+    /// A string of UTF-16 code units which might not be valid Unicode.
+    pub fn new_java_lang_string_from_utf16(&mut self, value: Vec<u16>) -> Option<u32> {
         let class = self.load_and_init_class(JAVA_LANG_STRING);
-        let (object, addr) = self.heap.new_object(class);
+        // one reservation for both, so nothing collects the array before the
+        // string refers to it
+        let size = JvmHeap::array_size(2, value.len() as i32) + JvmHeap::object_size(&class);
+        if !self.reserve_heap(size) {
+            return None;
+        }
+        let array = self.heap_mut().new_char_array(value);
+        let (object, addr) = self.heap_mut().new_object(class);
         object.set_field_by_name("value", "[C", Operand::ArrayRef(array));
         object.set_field_by_name("hash", "I", Operand::Int(addr as i32));
-        addr
+        Some(addr)
+    }
+
+    /// The interned `java.lang.String` of `s`, the same one as of every
+    /// literal and every `String.intern()` with the same value.
+    pub fn intern_string(&mut self, s: &str) -> Option<u32> {
+        let value: Vec<u16> = s.encode_utf16().collect();
+        if let Some(addr) = self.string_table().get(&value) {
+            return Some(addr);
+        }
+        let addr = self.new_java_lang_string(s)?;
+        self.string_table_mut().insert(value, addr);
+        Some(addr)
+    }
+
+    /// The `java.lang.Class` object of `class`, the same one every time.
+    pub fn class_mirror(&mut self, class: &Class) -> u32 {
+        self.mirror(MirroredType::Class(class.clone()))
     }
 
-    pub fn new_java_lang_class(&mut self, name: &str) -> u32 {
+    /// The `java.lang.Class` object of `ty`, allocated when first asked for.
+    pub fn mirror(&mut self, ty: MirroredType) -> u32 {
+        if let Some(addr) = self.mirrors().get(&ty) {
+            return addr;
+        }
         let class = self.load_and_init_class(JAVA_LANG_CLASS);
-        class.set_mirror_class_name(name.to_string());
-        let (_, addr) = self.heap.new_object(class);
+        let (_, addr) = self.heap_mut().new_object(class);
+        self.mirrors_mut().insert(ty, addr);
         addr
     }
 
-    pub fn new_java_lang_thread(&mut self, name: &str) -> u32 {
-        let jstring_main = self.new_java_lang_string("main");
-        let jstring_thread_name = self.new_java_lang_string(name);
+    /// Resolves a class referenced by the running code and returns its mirror.
+    pub fn resolve_class_mirror(&mut self, class_name: &str) -> Option<u32> {
+        let class = self.resolve_class(class_name)?;
+        Some(self.class_mirror(&class))
+    }
+
+    /// The mirror of the type of a field descriptor, e.g. `I` or
+    /// `Ljava/lang/String;`.
+    pub fn descriptor_mirror(&mut self, descriptor: &str) -> Option<u32> {
+        match descriptor.as_bytes() {
+            [ty] => Some(self.mirror(MirroredType::Primitive(*ty))),
+            [b'L', .., b';'] => self.resolve_class_mirror(&descriptor[1..descriptor.len() - 1]),
+            _ => self.resolve_class_mirror(descriptor),
+        }
+    }
+
+    /// The type the `java.lang.Class` object `mirror` stands for.
+    pub fn mirrored_type(&self, mirror: &Operand) -> MirroredType {
+        match mirror {
+            Operand::ObjectRef(addr) => self
+                .mirrors()
+                .mirrored_type(*addr)
+                .cloned()
+                .unwrap_or_else(|| unreachable!("not a mirror: {}", addr)),
+            v => unreachable!("{:?}", v),
+        }
+    }
+
+    pub fn new_java_lang_thread(&mut self, name: &str) -> Option<u32> {
         let thread_class = self.load_and_init_class(JAVA_LANG_THREAD);
         let thread_group_class = self.load_and_init_class(JAVA_LANG_THREAD_GROUP);
-        let (jthread_group, jthread_group_addr) = self.heap.new_object(thread_group_class);
+        let size = JvmHeap::object_size(&thread_class) + JvmHeap::object_size(&thread_group_class);
+        let jstring_main = self.new_java_lang_string("main")?;
+        self.thread_mut()
+            .native_args
+            .push(vec![Operand::ObjectRef(jstring_main)]);
+        let jstring_thread_name = self.new_java_lang_string(name);
+        let reserved = jstring_thread_name.is_some() && self.reserve_heap(size);
+        self.thread_mut().native_args.pop();
+        if !reserved {
+            return None;
+        }
+        let jstring_thread_name = jstring_thread_name.unwrap();
+        let (jthread_group, jthread_group_addr) = self.heap_mut().new_object(thread_group_class);
         jthread_group.set_field_by_name(
             "name",
             JAVA_LANG_STRING_DESCRIPTOR,
             Operand::ObjectRef(jstring_main),
         );
-        let (jthread, jthread_addr) = self.heap.new_object(thread_class);
+        let (jthread, jthread_addr) = self.heap_mut().new_object(thread_class);
         jthread.set_field_by_name(
             "name",
             JAVA_LANG_STRING_DESCRIPTOR,
@@ -176,39 +652,132 @@ impl JvmEnv {
         );
         jthread.set_field_by_name("priority", "I", Operand::Int(5));
 
-        jthread_addr
+        Some(jthread_addr)
     }
 
-    pub fn get_java_string(&mut self, str_ref: &Operand) -> String {
-        let string_operand = self.heap.get_object(str_ref);
-        let chars_ref = string_operand.get_field_by_name("value", "[C");
-        String::from_utf16(self.heap.get_char_array(chars_ref)).unwrap()
+    /// Allocates an exception of `class_name` and makes it the pending exception of
+    /// the current thread, so the interpreter unwinds to a matching handler.
+    pub fn throw_exception(&mut self, class_name: &str, message: Option<&str>) {
+        let class = self.load_and_init_class(class_name);
+        let message = match message {
+            Some(message) => match self.new_java_lang_string(message) {
+                Some(addr) => Operand::ObjectRef(addr),
+                // the `OutOfMemoryError` is thrown instead
+                None => return,
+            },
+            None => Operand::Null,
+        };
+        let (_, addr) = self.heap_mut().new_object(class.clone());
+        let init_method = class
+            .get_method("<init>", "(Ljava/lang/String;)V", false)
+            .unwrap_or_else(|| panic!("{}.<init>(String)", class_name));
+        execute_method(self, init_method, vec![Operand::ObjectRef(addr), message]);
+        // an exception raised by the constructor itself takes precedence
+        if !self.thread().has_pending_exception() {
+            self.thread_mut()
+                .set_pending_exception(Operand::ObjectRef(addr));
+        }
     }
 
-    pub fn did_override_method(&mut self, method: &Method, other: &Method) -> bool {
-        if method == other {
-            return true;
+    /// Makes room for allocating `size` bytes, collecting garbage first if the
+    /// heap grew enough since the last collection. Throws `OutOfMemoryError`
+    /// and returns false if the heap limit would be exceeded anyway.
+    ///
+    /// A collection only knows the references held by the thread and by
+    /// classes, so this must be called before an instruction allocates and
+    /// never while native code keeps references elsewhere.
+    pub fn reserve_heap(&mut self, size: usize) -> bool {
+        if self.heap().should_collect(size) {
+            self.collect_garbage();
         }
-        let this_class = self.load_and_init_class(method.name()).instance_class();
-        let other_class = self.load_and_init_class(other.name()).instance_class();
-        if !this_class.is_subclass_of(other_class) {
+        if self.heap().would_exceed_max_size(size) && !self.throwing_out_of_memory_error {
+            self.throwing_out_of_memory_error = true;
+            self.throw_exception(JAVA_LANG_OUT_OF_MEMORY_ERROR, Some("Java heap space"));
+            self.throwing_out_of_memory_error = false;
             return false;
         }
-        if method.name() != other.name() {
+        true
+    }
+
+    /// Sets the stack size of threads, like `-Xss`. Frames aren't stored on
+    /// the native stack, so the size only limits how deep calls can nest.
+    pub fn set_stack_size(&mut self, stack_size: usize) {
+        self.max_stack_depth = (stack_size / FRAME_SIZE).max(1);
+    }
+
+    /// Makes room for a new frame. Throws `StackOverflowError` and returns
+    /// false if the thread has as many frames as it can have.
+    pub fn reserve_frame(&mut self) -> bool {
+        if self.thread().stack.frames.len() >= self.max_stack_depth
+            && !self.throwing_stack_overflow_error
+        {
+            self.throw_stack_overflow_error();
             return false;
         }
-        if method.descriptor() != other.descriptor() {
+        true
+    }
+
+    /// Accounts for an interpreter loop run inside the current one, see
+    /// `execute_method`. Throws `StackOverflowError` and returns false if the
+    /// thread runs as many as it can.
+    pub fn enter_interpreter(&mut self) -> bool {
+        if self.nested_interpreters >= MAX_NESTED_INTERPRETERS
+            && !self.throwing_stack_overflow_error
+        {
+            self.throw_stack_overflow_error();
             return false;
         }
-        if method.is_private() {
-            return false;
+        self.nested_interpreters += 1;
+        true
+    }
+
+    pub fn exit_interpreter(&mut self) {
+        self.nested_interpreters -= 1;
+    }
+
+    fn throw_stack_overflow_error(&mut self) {
+        self.throwing_stack_overflow_error = true;
+        self.throw_exception(JAVA_LANG_STACK_OVERFLOW_ERROR, None);
+        self.throwing_stack_overflow_error = false;
+    }
+
+    pub fn collect_garbage(&mut self) {
+        let span = debug_span!("collect_garbage", used = self.heap().used());
+        let _span = span.enter();
+        let mut roots = Vec::new();
+        for thread in self.live_threads() {
+            roots.extend(thread.gc_roots());
         }
-        if (other.is_protected() || other.is_public())
-            || (!other.is_public() && !other.is_private() && !other.is_protected())
-        {
-            return true;
+        let classes = self
+            .bootstrap_class_loader()
+            .classes()
+            .chain(self.defining_classes().values());
+        for class in classes {
+            if let Class::InstanceClass(class) = class {
+                roots.extend(class.static_field_values());
+            }
         }
+        roots.extend(self.mirrors().addrs().map(Operand::ObjectRef));
+        roots.extend(self.jni().global_refs());
+        let freed = self.heap_mut().collect(roots);
+        let mut string_table = std::mem::take(self.string_table_mut());
+        string_table.retain(|addr| !self.heap().is_free(addr));
+        *self.string_table_mut() = string_table;
+        debug!(freed, used = self.heap().used(), "collect_garbage");
+    }
 
-        false
+    pub fn get_java_string(&mut self, str_ref: &Operand) -> String {
+        let string_operand = self.heap().get_object(str_ref);
+        let chars_ref = string_operand.get_field_by_name("value", "[C");
+        String::from_utf16(self.heap().get_char_array(chars_ref)).unwrap()
+    }
+}
+
+fn set_thread_status(jenv: &mut JvmEnv, thread_addr: u32, status: i32) {
+    let object = jenv
+        .heap_mut()
+        .get_object_mut(&Operand::ObjectRef(thread_addr));
+    if object.class().get_field("threadStatus", "I").is_some() {
+        object.set_field_by_name("threadStatus", "I", Operand::Int(status));
     }
 }
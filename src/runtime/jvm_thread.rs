@@ -1,7 +1,13 @@
 use crate::runtime::class::Class;
+use crate::runtime::frame::operand_stack::Operand;
 use crate::runtime::frame::JvmFrame;
 use crate::runtime::method::Method;
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Instant;
+
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
 
 #[derive(Debug)]
 pub struct JvmStack {
@@ -10,17 +16,94 @@ pub struct JvmStack {
 
 #[derive(Debug)]
 pub struct JvmThread {
+    /// Identifies the thread owning a monitor.
+    pub id: u64,
     pub stack: JvmStack,
-    pub object_addr: u32,
+    /// The `java.lang.Thread` of this thread, if the JDK is available.
+    pub object_addr: Option<u32>,
+    pub pending_exception: Option<Operand>,
+    /// Arguments of the native methods being executed, they have no frame
+    /// but must survive a collection.
+    pub native_args: Vec<Vec<Operand>>,
+    /// What the JNI local references of the native methods being executed
+    /// refer to, see `jni::local_ref`.
+    pub jni_local_refs: Vec<Operand>,
+    /// Where the local references of each native method or `PushLocalFrame`
+    /// start in `jni_local_refs`.
+    pub jni_local_frames: Vec<usize>,
+    /// The VM exits without waiting for daemon threads.
+    pub daemon: bool,
+    pub parker: Arc<Parker>,
+}
+
+/// Lets a thread blocked in native code, e.g. in `Thread.sleep`, be woken up
+/// by other threads.
+#[derive(Debug, Default)]
+pub struct Parker {
+    state: Mutex<ParkerState>,
+    condvar: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct ParkerState {
+    interrupted: bool,
+    permit: bool,
+}
+
+impl Parker {
+    /// Blocks until `unpark` or `interrupt` is called or `deadline` passes.
+    /// Must be called without holding the global lock.
+    pub fn park(&self, deadline: Option<Instant>) {
+        let mut state = self.state.lock().unwrap();
+        while !state.permit && !state.interrupted {
+            match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break;
+                    }
+                    state = self.condvar.wait_timeout(state, deadline - now).unwrap().0;
+                }
+                None => state = self.condvar.wait(state).unwrap(),
+            }
+        }
+        state.permit = false;
+    }
+
+    pub fn unpark(&self) {
+        self.state.lock().unwrap().permit = true;
+        self.condvar.notify_all();
+    }
+
+    pub fn interrupt(&self) {
+        self.state.lock().unwrap().interrupted = true;
+        self.condvar.notify_all();
+    }
+
+    pub fn is_interrupted(&self, clear_interrupted: bool) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let interrupted = state.interrupted;
+        if clear_interrupted {
+            state.interrupted = false;
+        }
+        interrupted
+    }
 }
 
 impl JvmThread {
     pub fn new() -> Self {
         JvmThread {
+            id: NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed),
             stack: JvmStack {
                 frames: Default::default(),
             },
-            object_addr: 0,
+            object_addr: None,
+            pending_exception: None,
+            native_args: Vec::new(),
+            jni_local_refs: Vec::new(),
+            jni_local_frames: Vec::new(),
+            daemon: false,
+            parker: Default::default(),
         }
     }
 
@@ -39,4 +122,38 @@ impl JvmThread {
     pub fn current_method(&self) -> Option<Method> {
         Some(self.current_frame()?.method.clone())
     }
+
+    pub fn set_pending_exception(&mut self, exception: Operand) {
+        self.pending_exception = Some(exception);
+    }
+
+    pub fn take_pending_exception(&mut self) -> Option<Operand> {
+        self.pending_exception.take()
+    }
+
+    pub fn has_pending_exception(&self) -> bool {
+        self.pending_exception.is_some()
+    }
+
+    /// Every reference the thread holds: locals, operand stacks and locks of
+    /// all frames, native arguments, JNI local references, the pending exception
+    /// and the thread object.
+    pub fn gc_roots(&self) -> Vec<Operand> {
+        let mut roots: Vec<_> = self
+            .object_addr
+            .map(Operand::ObjectRef)
+            .into_iter()
+            .collect();
+        for frame in &self.stack.frames {
+            roots.extend(frame.local_variable_array.iter().cloned());
+            roots.extend(frame.operand_stack.iter().cloned());
+            roots.extend(frame.lock.iter().cloned());
+        }
+        for args in &self.native_args {
+            roots.extend(args.iter().cloned());
+        }
+        roots.extend(self.jni_local_refs.iter().cloned());
+        roots.extend(self.pending_exception.iter().cloned());
+        roots
+    }
 }
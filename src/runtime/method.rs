@@ -1,12 +1,14 @@
+use crate::class_parser::attribute_info::predefined_attribute::PredefinedAttribute;
 use crate::class_parser::constant_pool::ConstPool;
 use crate::class_parser::descriptor::method_descriptor;
 use crate::class_parser::method_info::MethodInfo;
 use crate::class_parser::{
     is_bit_set, ACC_ABSTRACT, ACC_FINAL, ACC_NATIVE, ACC_PRIVATE, ACC_PROTECTED, ACC_PUBLIC,
-    ACC_STATIC, ACC_VARARGS,
+    ACC_STATIC, ACC_SYNCHRONIZED, ACC_VARARGS,
 };
 use crate::runtime::class::Class;
-use crate::runtime::cp_cache::CpCache;
+use crate::runtime::cp_cache::{CpCache, MethodCall};
+use crate::runtime::invoke::CallSite;
 use crate::runtime::jvm_env::JvmPC;
 use std::fmt;
 use std::sync::{Arc, Mutex};
@@ -16,6 +18,21 @@ pub struct Method {
     inner: Arc<InnerMethod>,
 }
 
+#[derive(Debug)]
+pub struct ExceptionHandler {
+    pub start_pc: JvmPC,
+    pub end_pc: JvmPC,
+    pub handler_pc: JvmPC,
+    /// `None` catches everything, as used by `finally` blocks.
+    pub catch_type: Option<String>,
+}
+
+impl ExceptionHandler {
+    pub fn covers(&self, pc: JvmPC) -> bool {
+        self.start_pc <= pc && pc < self.end_pc
+    }
+}
+
 #[derive(Debug)]
 pub struct Parameter {
     name: String,
@@ -31,7 +48,12 @@ struct InnerMethod {
     max_stack: usize,
     n_args: usize,
     code: Arc<Vec<u8>>,
+    exception_table: Vec<ExceptionHandler>,
+    /// The `LineNumberTable`, the first pc of each line ordered by pc.
+    line_numbers: Vec<(JvmPC, u16)>,
     parameters: Vec<Parameter>,
+    /// Classes of the checked exceptions it declares.
+    exceptions: Vec<String>,
     class_name: String,
     param_descriptors: Vec<String>,
     return_descriptor: String,
@@ -57,6 +79,11 @@ impl Method {
         } else {
             vec![]
         };
+        let exceptions = method_info
+            .exceptions()
+            .iter()
+            .map(|index| const_pool.get_class_name_at(*index).to_string())
+            .collect();
 
         if is_bit_set(access_flags, ACC_NATIVE) || is_bit_set(access_flags, ACC_ABSTRACT) {
             Method {
@@ -67,8 +94,11 @@ impl Method {
                     max_locals: 0,
                     max_stack: 0,
                     code: Arc::new(vec![]),
+                    exception_table: vec![],
+                    line_numbers: vec![],
                     n_args,
                     parameters,
+                    exceptions,
                     class_name,
                     param_descriptors: params,
                     return_descriptor,
@@ -79,6 +109,33 @@ impl Method {
             let code_attr = method_info
                 .code_attr()
                 .unwrap_or_else(|| panic!("get method code attr: {}", name));
+            let exception_table = code_attr
+                .exception_table
+                .iter()
+                .map(|handler| ExceptionHandler {
+                    start_pc: handler.start_pc as JvmPC,
+                    end_pc: handler.end_pc as JvmPC,
+                    handler_pc: handler.handler_pc as JvmPC,
+                    catch_type: if handler.catch_type == 0 {
+                        None
+                    } else {
+                        Some(const_pool.get_class_name_at(handler.catch_type).clone())
+                    },
+                })
+                .collect();
+            let mut line_numbers: Vec<_> = code_attr
+                .attributes
+                .iter()
+                .filter_map(|attr| match &attr.attribute {
+                    PredefinedAttribute::LineNumberTableAttribute(attr) => {
+                        Some(&attr.line_number_table)
+                    }
+                    _ => None,
+                })
+                .flatten()
+                .map(|entry| (entry.start_pc as JvmPC, entry.line_number))
+                .collect();
+            line_numbers.sort_unstable();
 
             Method {
                 inner: Arc::new(InnerMethod {
@@ -89,8 +146,11 @@ impl Method {
                     max_locals: code_attr.max_locals as usize,
                     max_stack: code_attr.max_stack as usize,
                     code: Arc::new(code_attr.code),
+                    exception_table,
+                    line_numbers,
                     n_args,
                     parameters,
+                    exceptions,
                     class_name,
                     param_descriptors: params,
                     return_descriptor,
@@ -123,10 +183,34 @@ impl Method {
             .set_static_field(pc, class, field_index)
     }
 
+    pub fn resolve_call_site(&self, pc: JvmPC) -> Option<CallSite> {
+        self.inner.cp_cache.lock().unwrap().resolve_call_site(pc)
+    }
+
+    pub fn set_call_site(&self, pc: JvmPC, call_site: CallSite) {
+        self.inner
+            .cp_cache
+            .lock()
+            .unwrap()
+            .set_call_site(pc, call_site)
+    }
+
+    pub fn resolve_method(&self, pc: JvmPC) -> Option<MethodCall> {
+        self.inner.cp_cache.lock().unwrap().resolve_method(pc)
+    }
+
+    pub fn set_method(&self, pc: JvmPC, call: MethodCall) {
+        self.inner.cp_cache.lock().unwrap().set_method(pc, call)
+    }
+
     pub fn n_args(&self) -> usize {
         self.inner.n_args
     }
 
+    pub fn param_descriptors(&self) -> &[String] {
+        &self.inner.param_descriptors
+    }
+
     pub fn return_descriptor(&self) -> &str {
         &self.inner.return_descriptor
     }
@@ -147,6 +231,10 @@ impl Method {
         &self.inner.parameters
     }
 
+    pub fn exceptions(&self) -> &[String] {
+        &self.inner.exceptions
+    }
+
     pub fn name(&self) -> &str {
         &self.inner.name
     }
@@ -167,10 +255,31 @@ impl Method {
         self.inner.code.clone()
     }
 
+    pub fn exception_table(&self) -> &[ExceptionHandler] {
+        &self.inner.exception_table
+    }
+
+    /// The source line of the instruction at `pc`, -1 if it isn't known and
+    /// -2 for native methods, as `StackTraceElement` has it.
+    pub fn line_number(&self, pc: JvmPC) -> i32 {
+        if self.is_native() {
+            return -2;
+        }
+        let lines = &self.inner.line_numbers;
+        match lines.iter().rposition(|(start_pc, _)| *start_pc <= pc) {
+            Some(i) => lines[i].1 as i32,
+            None => -1,
+        }
+    }
+
     pub fn is_static(&self) -> bool {
         self.access_flags() & ACC_STATIC != 0
     }
 
+    pub fn is_synchronized(&self) -> bool {
+        self.access_flags() & ACC_SYNCHRONIZED != 0
+    }
+
     pub fn is_native(&self) -> bool {
         self.access_flags() & ACC_NATIVE != 0
     }
@@ -0,0 +1,105 @@
+use crate::runtime::class::Class;
+use std::collections::HashMap;
+
+/// The primitive types with their Java names, descriptors and wrapper
+/// classes, `void` included as `Void.TYPE` has a mirror too.
+const PRIMITIVE_TYPES: [(&str, u8, &str); 9] = [
+    ("boolean", b'Z', "java/lang/Boolean"),
+    ("byte", b'B', "java/lang/Byte"),
+    ("char", b'C', "java/lang/Character"),
+    ("short", b'S', "java/lang/Short"),
+    ("int", b'I', "java/lang/Integer"),
+    ("long", b'J', "java/lang/Long"),
+    ("float", b'F', "java/lang/Float"),
+    ("double", b'D', "java/lang/Double"),
+    ("void", b'V', "java/lang/Void"),
+];
+
+/// What a `java.lang.Class` object stands for.
+#[derive(Debug, Clone)]
+pub enum MirroredType {
+    Class(Class),
+    /// A primitive type, by its descriptor like `I`.
+    Primitive(u8),
+}
+
+impl MirroredType {
+    /// The name `Class.getName()` returns, e.g. `java.lang.String`, `[I` or `int`.
+    pub fn java_name(&self) -> String {
+        match self {
+            MirroredType::Class(class) => class.name().replace('/', "."),
+            MirroredType::Primitive(descriptor) => primitive_name(*descriptor).to_string(),
+        }
+    }
+}
+
+/// The Java name of the primitive type with `descriptor`.
+pub fn primitive_name(descriptor: u8) -> &'static str {
+    primitive_type(descriptor).0
+}
+
+/// The class boxing values of the primitive type with `descriptor`.
+pub fn wrapper_class_name(descriptor: u8) -> &'static str {
+    primitive_type(descriptor).2
+}
+
+/// The descriptor of the primitive type named `name`, e.g. `int`.
+pub fn primitive_descriptor(name: &str) -> Option<u8> {
+    PRIMITIVE_TYPES
+        .iter()
+        .find(|(n, _, _)| *n == name)
+        .map(|(_, d, _)| *d)
+}
+
+/// The descriptor of the primitive type boxed by `class_name`, e.g. `I` for
+/// `java/lang/Integer`.
+pub fn wrapped_descriptor(class_name: &str) -> Option<u8> {
+    PRIMITIVE_TYPES
+        .iter()
+        .find(|(_, _, wrapper)| *wrapper == class_name)
+        .map(|(_, d, _)| *d)
+}
+
+fn primitive_type(descriptor: u8) -> &'static (&'static str, u8, &'static str) {
+    PRIMITIVE_TYPES
+        .iter()
+        .find(|(_, d, _)| *d == descriptor)
+        .unwrap_or_else(|| unreachable!("{}", descriptor as char))
+}
+
+/// The `java.lang.Class` objects of the VM, exactly one for every class and
+/// primitive type. They are never collected, like the classes themselves.
+#[derive(Debug, Default)]
+pub struct Mirrors {
+    /// Keyed by the class name, e.g. `java/lang/String` or `[I`, or by the
+    /// Java name of a primitive type, which can't be a class name.
+    addrs: HashMap<String, u32>,
+    types: HashMap<u32, MirroredType>,
+}
+
+impl Mirrors {
+    pub fn get(&self, ty: &MirroredType) -> Option<u32> {
+        self.addrs.get(&key(ty)).copied()
+    }
+
+    pub fn insert(&mut self, ty: MirroredType, addr: u32) {
+        self.addrs.insert(key(&ty), addr);
+        self.types.insert(addr, ty);
+    }
+
+    /// The type the mirror at `addr` stands for, `None` if it isn't a mirror.
+    pub fn mirrored_type(&self, addr: u32) -> Option<&MirroredType> {
+        self.types.get(&addr)
+    }
+
+    pub fn addrs(&self) -> impl Iterator<Item = u32> + '_ {
+        self.types.keys().copied()
+    }
+}
+
+fn key(ty: &MirroredType) -> String {
+    match ty {
+        MirroredType::Class(class) => class.name().to_string(),
+        MirroredType::Primitive(descriptor) => primitive_name(*descriptor).to_string(),
+    }
+}
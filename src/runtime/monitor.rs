@@ -0,0 +1,164 @@
+use crate::runtime::frame::operand_stack::Operand;
+use crate::runtime::heap::{
+    JAVA_LANG_ILLEGAL_ARGUMENT_EXCEPTION, JAVA_LANG_ILLEGAL_MONITOR_STATE_EXCEPTION,
+    JAVA_LANG_INTERRUPTED_EXCEPTION,
+};
+use crate::runtime::jvm_env::JvmEnv;
+use crate::runtime::jvm_thread::Parker;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The monitor in the header of every object. It's only touched while holding
+/// the global lock, threads blocked on it park until the owner wakes them.
+#[derive(Debug, Default)]
+pub struct Monitor {
+    /// Id of the owning `JvmThread`.
+    owner: Option<u64>,
+    /// How many times the owner entered the monitor.
+    count: u32,
+    /// Threads blocked in `monitor_enter`.
+    entry_list: Vec<Arc<Parker>>,
+    /// Threads in `Object.wait`, notified in the order they started waiting.
+    wait_set: Vec<(u64, Arc<Parker>)>,
+}
+
+impl Monitor {
+    /// A monitor in use keeps its object alive.
+    pub fn is_in_use(&self) -> bool {
+        self.owner.is_some() || !self.wait_set.is_empty()
+    }
+
+    fn wake_entry_list(&mut self) {
+        for parker in self.entry_list.drain(..) {
+            parker.unpark();
+        }
+    }
+}
+
+/// Enters the monitor of `object`, blocking while another thread owns it.
+pub fn monitor_enter(jenv: &mut JvmEnv, object: &Operand) {
+    let thread_id = jenv.thread().id;
+    let parker = jenv.thread().parker.clone();
+    loop {
+        let monitor = jenv.heap_mut().monitor_mut(object);
+        match monitor.owner {
+            None => {
+                monitor.owner = Some(thread_id);
+                monitor.count = 1;
+                return;
+            }
+            Some(owner) if owner == thread_id => {
+                monitor.count += 1;
+                return;
+            }
+            Some(_) => {
+                if !monitor.entry_list.iter().any(|p| Arc::ptr_eq(p, &parker)) {
+                    monitor.entry_list.push(parker.clone());
+                }
+            }
+        }
+        jenv.blocking(|| parker.park(None));
+        if jenv.exit_status().is_some() {
+            return;
+        }
+    }
+}
+
+/// Exits the monitor of `object`. Throws `IllegalMonitorStateException` and
+/// returns false if the current thread doesn't own it.
+pub fn monitor_exit(jenv: &mut JvmEnv, object: &Operand) -> bool {
+    if !check_owner(jenv, object) {
+        return false;
+    }
+    let monitor = jenv.heap_mut().monitor_mut(object);
+    monitor.count -= 1;
+    if monitor.count == 0 {
+        monitor.owner = None;
+        monitor.wake_entry_list();
+    }
+    true
+}
+
+/// `Object.wait(millis)`, waits forever for a notification if `millis` is 0.
+pub fn monitor_wait(jenv: &mut JvmEnv, object: &Operand, millis: i64) {
+    if millis < 0 {
+        jenv.throw_exception(
+            JAVA_LANG_ILLEGAL_ARGUMENT_EXCEPTION,
+            Some("timeout value is negative"),
+        );
+        return;
+    }
+    if !check_owner(jenv, object) {
+        return;
+    }
+    let thread_id = jenv.thread().id;
+    let parker = jenv.thread().parker.clone();
+    if parker.is_interrupted(true) {
+        jenv.throw_exception(JAVA_LANG_INTERRUPTED_EXCEPTION, None);
+        return;
+    }
+
+    let monitor = jenv.heap_mut().monitor_mut(object);
+    let count = monitor.count;
+    monitor.owner = None;
+    monitor.count = 0;
+    monitor.wake_entry_list();
+    monitor.wait_set.push((thread_id, parker.clone()));
+
+    let deadline = match millis {
+        0 => None,
+        millis => Some(Instant::now() + Duration::from_millis(millis as u64)),
+    };
+    loop {
+        jenv.blocking(|| parker.park(deadline));
+        let monitor = jenv.heap_mut().monitor_mut(object);
+        // `notify` takes the thread out of the wait set
+        let notified = !monitor.wait_set.iter().any(|(id, _)| *id == thread_id);
+        let timed_out = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        if notified || timed_out || parker.is_interrupted(false) || jenv.exit_status().is_some() {
+            break;
+        }
+    }
+    let monitor = jenv.heap_mut().monitor_mut(object);
+    monitor.wait_set.retain(|(id, _)| *id != thread_id);
+    if jenv.exit_status().is_some() {
+        return;
+    }
+
+    monitor_enter(jenv, object);
+    jenv.heap_mut().monitor_mut(object).count = count;
+    if parker.is_interrupted(true) {
+        jenv.throw_exception(JAVA_LANG_INTERRUPTED_EXCEPTION, None);
+    }
+}
+
+/// `Object.notify` or, if `all`, `Object.notifyAll`.
+pub fn monitor_notify(jenv: &mut JvmEnv, object: &Operand, all: bool) {
+    if !check_owner(jenv, object) {
+        return;
+    }
+    let monitor = jenv.heap_mut().monitor_mut(object);
+    let n = if all { monitor.wait_set.len() } else { 1 };
+    let n = n.min(monitor.wait_set.len());
+    for (_, parker) in monitor.wait_set.drain(..n) {
+        parker.unpark();
+    }
+}
+
+/// Throws `IllegalMonitorStateException` unless the current thread owns the
+/// monitor of `object`. Monitors aren't checked any more once the VM halts,
+/// the threads are unwinding anyway.
+fn check_owner(jenv: &mut JvmEnv, object: &Operand) -> bool {
+    if jenv.exit_status().is_some() {
+        return false;
+    }
+    let thread_id = jenv.thread().id;
+    if jenv.heap_mut().monitor_mut(object).owner != Some(thread_id) {
+        jenv.throw_exception(
+            JAVA_LANG_ILLEGAL_MONITOR_STATE_EXCEPTION,
+            Some("current thread is not owner"),
+        );
+        return false;
+    }
+    true
+}
@@ -1,8 +1,17 @@
 #![allow(non_snake_case, unused_variables)]
-use crate::runtime::class::Class;
+use crate::runtime::class::{Class, InitState};
 use crate::runtime::execute_method;
 use crate::runtime::frame::operand_stack::Operand;
+use crate::runtime::heap::{
+    JAVA_LANG_CLASS_DESCRIPTOR, JAVA_LANG_ILLEGAL_ARGUMENT_EXCEPTION,
+    JAVA_LANG_INTERRUPTED_EXCEPTION, JAVA_LANG_NULL_POINTER_EXCEPTION, JAVA_LANG_OBJECT,
+};
+use crate::runtime::instruction::can_cast_to;
+use crate::runtime::jni;
 use crate::runtime::jvm_env::JvmEnv;
+use crate::runtime::mirror::{primitive_descriptor, MirroredType};
+use crate::runtime::monitor::{monitor_notify, monitor_wait};
+use std::time::{Duration, Instant};
 
 pub fn java_lang_Class_getPrimitiveClass(
     jenv: &mut JvmEnv,
@@ -10,32 +19,35 @@ pub fn java_lang_Class_getPrimitiveClass(
     mut args: Vec<Operand>,
 ) {
     let string_ref = args.pop().unwrap();
-    let class_name = jenv.get_java_string(&string_ref);
-    let addr = jenv.new_java_lang_class(&class_name);
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
-    frame.operand_stack.push(Operand::ObjectRef(addr));
+    let name = jenv.get_java_string(&string_ref);
+    let mirror = match primitive_descriptor(&name) {
+        Some(descriptor) => Operand::ObjectRef(jenv.mirror(MirroredType::Primitive(descriptor))),
+        None => Operand::Null,
+    };
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(mirror);
 }
 
 pub fn jvm_desiredAssertionStatus0(jenv: &mut JvmEnv, _class: &Class, _args: Vec<Operand>) {
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     frame.operand_stack.push_integer(0);
 }
 
 pub fn java_lang_Float_floatToRawIntBits(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
     let n = args[0].get_float();
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     frame.operand_stack.push_integer(n.to_bits() as i32);
 }
 
 pub fn java_lang_Double_doubleToRawLongBits(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
     let n = args[0].get_double();
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     frame.operand_stack.push_long(n.to_bits() as i64);
 }
 
 pub fn java_lang_Double_longBitsToDouble(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
     let n = args[0].get_long();
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     frame
         .operand_stack
         .push_double(f64::from_be_bytes(n.to_be_bytes()));
@@ -43,7 +55,7 @@ pub fn java_lang_Double_longBitsToDouble(jenv: &mut JvmEnv, _class: &Class, args
 
 pub fn java_lang_System_initProperties(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
     let props_ref = &args[0];
-    let properties = jenv.heap.get_object(props_ref);
+    let properties = jenv.heap().get_object(props_ref);
     let class_name = properties.class_name().to_string();
     let propertiesClass = jenv.load_and_init_class(&class_name);
     let method = propertiesClass
@@ -53,36 +65,88 @@ pub fn java_lang_System_initProperties(jenv: &mut JvmEnv, _class: &Class, args:
             false,
         )
         .unwrap();
-    let systemProperties = vec![
-        ("java.version", "1.8"),
-        ("java.vendor", "hippo"),
-        ("java.vendor.url", "https://github.com/gfreezy/hippo"),
-        ("java.home", "/Users/feichao"),
-        ("java.class.version", "1.8"),
-        ("java.class.path", "/Users/feichao"),
-        ("os.name", "macos"),
-        ("os.arch", "x64"),
-        ("os.version", "10.115.4"),
-        ("file.separator", "/"),
-        ("path.separator", ":"),
-        ("line.separator", "\n"),
-        ("user.name", "feichao"),
-        ("user.home", "/Users/feichao"),
-        ("user.dir", "/Users/feichao"),
+    let env_var = |name: &str| std::env::var(name).unwrap_or_default();
+    let os_name = match std::env::consts::OS {
+        "linux" => "Linux",
+        "macos" => "Mac OS X",
+        "windows" => "Windows",
+        os => os,
+    };
+    let os_arch = match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        arch => arch,
+    };
+    let user_dir = std::env::current_dir()
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_default();
+    let mut systemProperties = vec![
+        ("java.version".to_string(), "1.8".to_string()),
+        ("java.vendor".to_string(), "hippo".to_string()),
+        (
+            "java.vendor.url".to_string(),
+            "https://github.com/gfreezy/hippo".to_string(),
+        ),
+        ("java.class.version".to_string(), "52.0".to_string()),
+        ("os.name".to_string(), os_name.to_string()),
+        ("os.arch".to_string(), os_arch.to_string()),
+        ("file.separator".to_string(), "/".to_string()),
+        ("path.separator".to_string(), ":".to_string()),
+        ("line.separator".to_string(), "\n".to_string()),
+        ("file.encoding".to_string(), "UTF-8".to_string()),
+        ("user.name".to_string(), env_var("USER")),
+        ("user.home".to_string(), env_var("HOME")),
+        ("user.dir".to_string(), user_dir),
+        ("java.library.path".to_string(), jni::library_path()),
     ];
+    // -D options come last so they override the defaults
+    systemProperties.extend(jenv.system_properties.iter().cloned());
     for (key, value) in systemProperties {
-        let key = Operand::ObjectRef(jenv.new_java_lang_string(key));
-        let value = Operand::ObjectRef(jenv.new_java_lang_string(value));
+        let key = match jenv.new_java_lang_string(&key) {
+            Some(addr) => Operand::ObjectRef(addr),
+            None => return,
+        };
+        // allocating the value may collect garbage
+        jenv.thread_mut().native_args.push(vec![key.clone()]);
+        let value = jenv.new_java_lang_string(&value);
+        jenv.thread_mut().native_args.pop();
+        let value = match value {
+            Some(addr) => Operand::ObjectRef(addr),
+            None => return,
+        };
         let args = vec![props_ref.clone(), key, value];
         execute_method(jenv, method.clone(), args);
+        // drop the previous value `put` returns
+        let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+        let _ = frame.operand_stack.pop();
+        if jenv.thread().has_pending_exception() {
+            return;
+        }
     }
-    let frame = jenv.thread.stack.frames.back_mut().unwrap();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
     frame.operand_stack.push(props_ref.clone());
 }
 
+/// The file name of the library `args[0]` on this platform, e.g.
+/// `libzip.so` for `zip`.
+pub fn java_lang_System_mapLibraryName(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let name = jenv.get_java_string(&args[0]);
+    let file_name = format!(
+        "{}{}{}",
+        std::env::consts::DLL_PREFIX,
+        name,
+        std::env::consts::DLL_SUFFIX
+    );
+    let file_name = match jenv.new_java_lang_string(&file_name) {
+        Some(addr) => addr,
+        None => return,
+    };
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(Operand::ObjectRef(file_name));
+}
+
 pub fn java_lang_Object_hashCode(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
     let obj = &args[0];
-    jenv.thread
+    jenv.thread_mut()
         .stack
         .frames
         .back_mut()
@@ -91,18 +155,43 @@ pub fn java_lang_Object_hashCode(jenv: &mut JvmEnv, _class: &Class, args: Vec<Op
         .push_integer(obj.hash_code());
 }
 
-pub fn java_lang_System_registerNatives(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {}
-
-pub fn java_lang_Object_registerNatives(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {}
+pub fn java_lang_String_intern(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let this = match args[0] {
+        Operand::ObjectRef(addr) => addr,
+        ref v => unreachable!("{:?}", v),
+    };
+    let value_ref = jenv
+        .heap()
+        .get_object(&args[0])
+        .get_field_by_name("value", "[C");
+    let value = jenv.heap().get_char_array(value_ref).clone();
+    // the first string with the value becomes the interned one
+    let addr = match jenv.string_table().get(&value) {
+        Some(addr) => addr,
+        None => {
+            jenv.string_table_mut().insert(value, this);
+            this
+        }
+    };
+    jenv.thread_mut()
+        .stack
+        .frames
+        .back_mut()
+        .unwrap()
+        .operand_stack
+        .push(Operand::ObjectRef(addr));
+}
 
-pub fn registerNatives(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {}
+/// The `registerNatives` of every class with one, binds the other natives
+/// of the class.
+pub fn registerNatives(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    jenv.natives_mut().register_class_natives(class.name());
+}
 
 pub fn sun_misc_VM_initalize(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {}
 
-pub fn sun_misc_Unsafe_registerNatives(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {}
-
 pub fn sun_misc_Unsafe_arrayBaseOffset(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
-    jenv.thread
+    jenv.thread_mut()
         .stack
         .frames
         .back_mut()
@@ -112,7 +201,7 @@ pub fn sun_misc_Unsafe_arrayBaseOffset(jenv: &mut JvmEnv, _class: &Class, args:
 }
 
 pub fn sun_misc_Unsafe_arrayIndexScale(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
-    jenv.thread
+    jenv.thread_mut()
         .stack
         .frames
         .back_mut()
@@ -122,7 +211,7 @@ pub fn sun_misc_Unsafe_arrayIndexScale(jenv: &mut JvmEnv, _class: &Class, args:
 }
 
 pub fn sun_misc_Unsafe_addressSize(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
-    jenv.thread
+    jenv.thread_mut()
         .stack
         .frames
         .back_mut()
@@ -136,17 +225,15 @@ pub fn sun_reflect_Reflection_getCallerClass(
     _class: &Class,
     args: Vec<Operand>,
 ) {
-    let frames = &jenv.thread.stack.frames;
+    let frames = &jenv.thread().stack.frames;
     let len = frames.len();
     let caller_class = if len >= 2 {
-        let frame = &frames[len - 2];
-        let class_name = frame.method.class_name().to_string();
-        let addr = jenv.new_java_lang_class(&class_name);
-        Operand::ObjectRef(addr)
+        let class = frames[len - 2].class.clone();
+        Operand::ObjectRef(jenv.class_mirror(&class))
     } else {
         Operand::Null
     };
-    jenv.thread
+    jenv.thread_mut()
         .stack
         .frames
         .back_mut()
@@ -155,28 +242,13 @@ pub fn sun_reflect_Reflection_getCallerClass(
         .push(caller_class);
 }
 
-pub fn java_io_FileInputStream_initIDs(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {}
-pub fn java_io_FileDescriptor_initIDs(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {}
-pub fn java_lang_Throwable_fillInStackTrace(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
-    let obj = &args[0];
-    jenv.thread
-        .stack
-        .frames
-        .back_mut()
-        .unwrap()
-        .operand_stack
-        .push(obj.clone());
-}
-
-pub fn java_io_FileOutputStream_initIDs(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {}
-
 pub fn java_security_AccessController_doPrivileged(
     jenv: &mut JvmEnv,
     class: &Class,
     args: Vec<Operand>,
 ) {
     let action = &args[0];
-    let class_name = jenv.heap.get_class_name(action);
+    let class_name = jenv.heap().get_class_name(action);
     let class = jenv.load_and_init_class(&class_name);
     let method = class
         .get_method("run", "()Ljava/lang/Object;", false)
@@ -185,18 +257,23 @@ pub fn java_security_AccessController_doPrivileged(
 }
 
 pub fn java_lang_Thread_currentThread(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
-    jenv.thread
+    let thread_addr = jenv.thread().object_addr.expect("java.lang.Thread object");
+    jenv.thread_mut()
         .stack
         .frames
         .back_mut()
         .unwrap()
         .operand_stack
-        .push(Operand::ObjectRef(jenv.thread.object_addr));
+        .push(Operand::ObjectRef(thread_addr));
 }
 
 pub fn java_lang_Class_getName0(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
-    let addr = jenv.new_java_lang_string(&class.mirror_class_name());
-    jenv.thread
+    let name = jenv.mirrored_type(&args[0]).java_name();
+    let addr = match jenv.new_java_lang_string(&name) {
+        Some(addr) => addr,
+        None => return,
+    };
+    jenv.thread_mut()
         .stack
         .frames
         .back_mut()
@@ -209,9 +286,16 @@ pub fn java_lang_Class_for_Name0(jenv: &mut JvmEnv, class: &Class, args: Vec<Ope
     let name = jenv.get_java_string(&args[0]);
     let class_name = name.replace('.', "/");
     eprintln!("class_for_Name0: {}", &class_name);
-    let _class = jenv.load_and_init_class(&class_name);
-    let class_addr = jenv.new_java_lang_class(&class_name);
-    jenv.thread
+    let class = match jenv.resolve_class(&class_name) {
+        Some(class) => class,
+        None => return,
+    };
+    let initialize = args[1].get_int() != 0;
+    if initialize && !jenv.initialize_class(&class) {
+        return;
+    }
+    let class_addr = jenv.class_mirror(&class);
+    jenv.thread_mut()
         .stack
         .frames
         .back_mut()
@@ -220,12 +304,91 @@ pub fn java_lang_Class_for_Name0(jenv: &mut JvmEnv, class: &Class, args: Vec<Ope
         .push(Operand::ObjectRef(class_addr));
 }
 
+pub fn java_lang_Object_getClass(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let class_name = jenv.heap().get_class_name(&args[0]);
+    let class = jenv.load_class(&class_name);
+    let addr = jenv.class_mirror(&class);
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(Operand::ObjectRef(addr));
+}
+
+pub fn java_lang_Class_getSuperclass(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let super_class = match jenv.mirrored_type(&args[0]) {
+        MirroredType::Class(Class::InstanceClass(class)) if class.is_interface() => None,
+        MirroredType::Class(Class::InstanceClass(class)) => class.super_class().map(Class::from),
+        MirroredType::Class(_) => Some(jenv.load_class(JAVA_LANG_OBJECT)),
+        MirroredType::Primitive(_) => None,
+    };
+    let mirror = match super_class {
+        Some(class) => Operand::ObjectRef(jenv.class_mirror(&class)),
+        None => Operand::Null,
+    };
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(mirror);
+}
+
+pub fn java_lang_Class_isInterface(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let is_interface = match jenv.mirrored_type(&args[0]) {
+        MirroredType::Class(Class::InstanceClass(class)) => class.is_interface(),
+        _ => false,
+    };
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push_integer(is_interface as i32);
+}
+
+pub fn java_lang_Class_isArray(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let is_array = match jenv.mirrored_type(&args[0]) {
+        MirroredType::Class(Class::InstanceClass(_)) | MirroredType::Primitive(_) => false,
+        MirroredType::Class(_) => true,
+    };
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push_integer(is_array as i32);
+}
+
+pub fn java_lang_Class_isPrimitive(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let is_primitive = matches!(jenv.mirrored_type(&args[0]), MirroredType::Primitive(_));
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push_integer(is_primitive as i32);
+}
+
+pub fn java_lang_Class_getComponentType(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let component = match jenv.mirrored_type(&args[0]) {
+        MirroredType::Class(Class::ObjArrayClass(class)) => {
+            Some(MirroredType::Class(jenv.load_class(&class.class)))
+        }
+        MirroredType::Class(Class::TypeArrayClass(class)) => {
+            Some(MirroredType::Primitive(class.ty))
+        }
+        _ => None,
+    };
+    let mirror = match component {
+        Some(ty) => Operand::ObjectRef(jenv.mirror(ty)),
+        None => Operand::Null,
+    };
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(mirror);
+}
+
+pub fn java_lang_Class_isAssignableFrom(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    if args[1] == Operand::Null {
+        jenv.throw_exception(JAVA_LANG_NULL_POINTER_EXCEPTION, None);
+        return;
+    }
+    let assignable = match (jenv.mirrored_type(&args[0]), jenv.mirrored_type(&args[1])) {
+        (MirroredType::Class(class), MirroredType::Class(other)) => can_cast_to(jenv, other, class),
+        (MirroredType::Primitive(ty), MirroredType::Primitive(other)) => ty == other,
+        _ => false,
+    };
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push_integer(assignable as i32);
+}
+
 pub fn java_security_AccessController_getStackAccessControlContext(
     jenv: &mut JvmEnv,
     class: &Class,
     args: Vec<Operand>,
 ) {
-    jenv.thread
+    jenv.thread_mut()
         .stack
         .frames
         .back_mut()
@@ -238,7 +401,226 @@ pub fn java_lang_Thread_setPriority0(jenv: &mut JvmEnv, class: &Class, args: Vec
     let priority = args[1].get_int();
     if priority < 1 {
         let object_ref = &args[0];
-        let object = jenv.heap.get_object_mut(&object_ref);
+        let object = jenv.heap_mut().get_object_mut(&object_ref);
         object.set_field_by_name("priority", "I", Operand::Int(5));
     }
 }
+
+pub fn java_lang_Shutdown_halt0(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    jenv.halt(args[0].get_int());
+}
+
+pub fn java_lang_Thread_start0(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    match args[0] {
+        Operand::ObjectRef(thread_addr) => jenv.start_thread(thread_addr),
+        ref v => unreachable!("{:?}", v),
+    }
+}
+
+pub fn java_lang_Thread_isAlive(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let alive = match args[0] {
+        Operand::ObjectRef(thread_addr) => jenv.find_thread(thread_addr).is_some(),
+        _ => false,
+    };
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push_integer(alive as i32);
+}
+
+pub fn java_lang_Thread_sleep(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let millis = args[0].get_long();
+    if millis < 0 {
+        jenv.throw_exception(
+            JAVA_LANG_ILLEGAL_ARGUMENT_EXCEPTION,
+            Some("timeout value is negative"),
+        );
+        return;
+    }
+    let deadline = Instant::now() + Duration::from_millis(millis as u64);
+    let parker = jenv.thread().parker.clone();
+    loop {
+        if parker.is_interrupted(true) {
+            jenv.throw_exception(JAVA_LANG_INTERRUPTED_EXCEPTION, Some("sleep interrupted"));
+            return;
+        }
+        if Instant::now() >= deadline || jenv.exit_status().is_some() {
+            return;
+        }
+        jenv.blocking(|| parker.park(Some(deadline)));
+    }
+}
+
+pub fn java_lang_Thread_yield(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    jenv.blocking(std::thread::yield_now);
+}
+
+pub fn java_lang_Thread_interrupt0(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    if let Operand::ObjectRef(thread_addr) = args[0] {
+        // interrupting a thread that isn't alive has no effect
+        if let Some(thread) = jenv.find_thread(thread_addr) {
+            thread.parker.interrupt();
+        }
+    }
+}
+
+pub fn java_lang_Thread_isInterrupted(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let clear_interrupted = args[1].get_int() != 0;
+    let interrupted = match args[0] {
+        Operand::ObjectRef(thread_addr) => jenv
+            .find_thread(thread_addr)
+            .map(|thread| thread.parker.is_interrupted(clear_interrupted))
+            .unwrap_or(false),
+        _ => false,
+    };
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push_integer(interrupted as i32);
+}
+
+pub fn java_lang_Object_wait(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    monitor_wait(jenv, &args[0], args[1].get_long());
+}
+
+pub fn java_lang_Object_notify(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    monitor_notify(jenv, &args[0], false);
+}
+
+pub fn java_lang_Object_notifyAll(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    monitor_notify(jenv, &args[0], true);
+}
+
+pub fn sun_misc_Unsafe_compareAndSwap(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let swapped = compare_and_swap(jenv, &args[1], args[2].get_long(), &args[3], &args[4]);
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push_integer(swapped as i32);
+}
+
+/// Shared by the `compareAndSwap` natives. Atomic since only the thread
+/// holding the global lock runs.
+fn compare_and_swap(
+    jenv: &mut JvmEnv,
+    object: &Operand,
+    offset: i64,
+    expected: &Operand,
+    x: &Operand,
+) -> bool {
+    if unsafe_get(jenv, object, offset) != *expected {
+        return false;
+    }
+    unsafe_put(jenv, object, offset, x.clone());
+    true
+}
+
+/// Added to the index of a static field by `staticFieldOffset`, so accessing
+/// a mirror tells its static fields from the fields of the mirror itself.
+const STATIC_FIELD_OFFSET: i64 = 1 << 32;
+
+/// Reads the value at `offset` of `object`, as `objectFieldOffset`,
+/// `staticFieldOffset` and `arrayBaseOffset` define offsets: the index of a
+/// field or of an array element.
+fn unsafe_get(jenv: &mut JvmEnv, object: &Operand, offset: i64) -> Operand {
+    match object {
+        Operand::ArrayRef(_) => jenv.heap().get_array_element(object, offset as usize),
+        _ if offset & STATIC_FIELD_OFFSET != 0 => {
+            let class = static_field_base_class(jenv, object);
+            class.get_static_field_value((offset & !STATIC_FIELD_OFFSET) as usize)
+        }
+        _ => jenv
+            .heap()
+            .get_object(object)
+            .get_field(offset as usize)
+            .clone(),
+    }
+}
+
+/// Stores `value` at `offset` of `object`, see `unsafe_get`.
+fn unsafe_put(jenv: &mut JvmEnv, object: &Operand, offset: i64, value: Operand) {
+    match object {
+        Operand::ArrayRef(_) => jenv
+            .heap_mut()
+            .set_array_element(object, offset as usize, value),
+        _ if offset & STATIC_FIELD_OFFSET != 0 => {
+            let class = static_field_base_class(jenv, object);
+            class.set_static_field_value((offset & !STATIC_FIELD_OFFSET) as usize, value)
+        }
+        _ => jenv
+            .heap_mut()
+            .get_object_mut(object)
+            .set_field(offset as usize, value),
+    }
+}
+
+/// The class holding the static fields of the mirror `staticFieldBase` returned.
+fn static_field_base_class(jenv: &JvmEnv, mirror: &Operand) -> Class {
+    match jenv.mirrored_type(mirror) {
+        MirroredType::Class(class) => class,
+        ty => unreachable!("{:?}", ty),
+    }
+}
+
+pub fn sun_misc_Unsafe_objectFieldOffset(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let slot = jenv
+        .heap()
+        .get_object(&args[1])
+        .get_field_by_name("slot", "I")
+        .get_int();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push_long(slot as i64);
+}
+
+pub fn sun_misc_Unsafe_staticFieldOffset(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let slot = jenv
+        .heap()
+        .get_object(&args[1])
+        .get_field_by_name("slot", "I")
+        .get_int();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame
+        .operand_stack
+        .push_long(slot as i64 | STATIC_FIELD_OFFSET);
+}
+
+/// The mirror of the class declaring the field, it stands for its static fields.
+pub fn sun_misc_Unsafe_staticFieldBase(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let mirror = jenv
+        .heap()
+        .get_object(&args[1])
+        .get_field_by_name("clazz", JAVA_LANG_CLASS_DESCRIPTOR)
+        .clone();
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(mirror);
+}
+
+pub fn sun_misc_Unsafe_ensureClassInitialized(
+    jenv: &mut JvmEnv,
+    class: &Class,
+    args: Vec<Operand>,
+) {
+    if let MirroredType::Class(class) = jenv.mirrored_type(&args[1]) {
+        jenv.initialize_class(&class);
+    }
+}
+
+pub fn sun_misc_Unsafe_shouldBeInitialized(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let should_be_initialized = match jenv.mirrored_type(&args[1]) {
+        MirroredType::Class(Class::InstanceClass(class)) => {
+            class.init_state() != InitState::Initialized
+        }
+        _ => false,
+    };
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame
+        .operand_stack
+        .push_integer(should_be_initialized as i32);
+}
+
+/// All of `getInt(Object, long)`, `getObjectVolatile(Object, long)` and the like.
+pub fn sun_misc_Unsafe_get(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let value = unsafe_get(jenv, &args[1], args[2].get_long());
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(value);
+}
+
+/// All of `putInt(Object, long, int)`, `putOrderedObject(Object, long, Object)`
+/// and the like.
+pub fn sun_misc_Unsafe_put(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    unsafe_put(jenv, &args[1], args[2].get_long(), args[3].clone());
+}
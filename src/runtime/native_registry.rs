@@ -0,0 +1,590 @@
+use crate::runtime::class::Class;
+use crate::runtime::frame::operand_stack::Operand;
+use crate::runtime::io::*;
+use crate::runtime::jni::*;
+use crate::runtime::jvm_env::JvmEnv;
+use crate::runtime::native::*;
+use crate::runtime::reflection::*;
+use crate::runtime::stack_trace::*;
+use crate::runtime::zip_file::*;
+use derivative::Derivative;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A Rust function bound to a Java `native` method. It gets the class
+/// declaring the method and the arguments, `this` first for an instance
+/// method, and pushes the result, if any, onto the frame of the caller.
+pub type NativeMethod = Box<dyn Fn(&mut JvmEnv, &Class, Vec<Operand>) + Send + Sync>;
+
+/// A bound native, shared so it can be called while the registry changes.
+pub type BoundNative = Arc<dyn Fn(&mut JvmEnv, &Class, Vec<Operand>) + Send + Sync>;
+
+type NativeFn = fn(&mut JvmEnv, &Class, Vec<Operand>);
+
+type NativeTable = &'static [(&'static str, &'static str, NativeFn)];
+
+/// The `registerNatives` of the classes that have one, calling it binds the
+/// rest of the natives of the class, see `class_natives`.
+const REGISTER_NATIVES_CLASSES: [&str; 5] = [
+    "java/lang/Object",
+    "java/lang/System",
+    "java/lang/Class",
+    "java/lang/Thread",
+    "sun/misc/Unsafe",
+];
+
+/// The classes with natives but without a `registerNatives`, their natives
+/// are bound from the start.
+const BOOT_CLASSES: [&str; 20] = [
+    "java/lang/Float",
+    "java/lang/Double",
+    "java/lang/String",
+    "java/lang/Throwable",
+    "java/lang/Shutdown",
+    "sun/misc/VM",
+    "sun/reflect/Reflection",
+    "sun/reflect/NativeMethodAccessorImpl",
+    "sun/reflect/NativeConstructorAccessorImpl",
+    "java/io/FileDescriptor",
+    "java/io/FileInputStream",
+    "java/io/FileOutputStream",
+    "java/io/RandomAccessFile",
+    "java/io/UnixFileSystem",
+    "java/io/Console",
+    "java/security/AccessController",
+    "java/lang/ClassLoader$NativeLibrary",
+    "java/util/zip/ZipFile",
+    "java/util/jar/JarFile",
+    "java/util/zip/Inflater",
+];
+
+/// The types `Unsafe` reads and writes with `get<Type>(Object, long)` and
+/// `put<Type>(Object, long, <type>)`, with their descriptors.
+const UNSAFE_ACCESSOR_TYPES: [(&str, &str); 9] = [
+    ("Object", "Ljava/lang/Object;"),
+    ("Boolean", "Z"),
+    ("Byte", "B"),
+    ("Short", "S"),
+    ("Char", "C"),
+    ("Int", "I"),
+    ("Long", "J"),
+    ("Float", "F"),
+    ("Double", "D"),
+];
+
+/// The natives the VM calls, by the name of their class and their name and
+/// descriptor. Java `native` methods without an entry throw
+/// `UnsatisfiedLinkError`.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct NativeRegistry {
+    /// By class name, method name and descriptor.
+    #[derivative(Debug = "ignore")]
+    methods: HashMap<(String, String, String), BoundNative>,
+}
+
+impl NativeRegistry {
+    pub fn new() -> Self {
+        let mut registry = NativeRegistry {
+            methods: HashMap::new(),
+        };
+        for class_name in &REGISTER_NATIVES_CLASSES {
+            registry.register_fn(class_name, "registerNatives", "()V", registerNatives);
+        }
+        for class_name in &BOOT_CLASSES {
+            registry.register_class_natives(class_name);
+        }
+        registry
+    }
+
+    /// Binds `method` to the native `name` with `descriptor` of `class_name`,
+    /// replacing what it was bound to.
+    pub fn register(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+        method: NativeMethod,
+    ) {
+        let key = (
+            class_name.to_string(),
+            name.to_string(),
+            descriptor.to_string(),
+        );
+        self.methods.insert(key, Arc::from(method));
+    }
+
+    fn register_fn(&mut self, class_name: &str, name: &str, descriptor: &str, method: NativeFn) {
+        self.register(class_name, name, descriptor, Box::new(method));
+    }
+
+    pub fn get(&self, class_name: &str, name: &str, descriptor: &str) -> Option<BoundNative> {
+        let key = (
+            class_name.to_string(),
+            name.to_string(),
+            descriptor.to_string(),
+        );
+        self.methods.get(&key).cloned()
+    }
+
+    /// Binds all the natives the VM implements for `class_name`.
+    pub fn register_class_natives(&mut self, class_name: &str) {
+        for (name, descriptor, method) in class_natives(class_name) {
+            self.register_fn(class_name, name, descriptor, *method);
+        }
+        if class_name == "sun/misc/Unsafe" {
+            for (type_name, descriptor) in &UNSAFE_ACCESSOR_TYPES {
+                for suffix in &["", "Volatile"] {
+                    let get = format!("get{}{}", type_name, suffix);
+                    let get_descriptor = format!("(Ljava/lang/Object;J){}", descriptor);
+                    self.register_fn(class_name, &get, &get_descriptor, sun_misc_Unsafe_get);
+                    let put = format!("put{}{}", type_name, suffix);
+                    let put_descriptor = format!("(Ljava/lang/Object;J{})V", descriptor);
+                    self.register_fn(class_name, &put, &put_descriptor, sun_misc_Unsafe_put);
+                }
+            }
+            for (type_name, descriptor) in &[
+                ("Object", "Ljava/lang/Object;"),
+                ("Int", "I"),
+                ("Long", "J"),
+            ] {
+                let put = format!("putOrdered{}", type_name);
+                let put_descriptor = format!("(Ljava/lang/Object;J{})V", descriptor);
+                self.register_fn(class_name, &put, &put_descriptor, sun_misc_Unsafe_put);
+            }
+        }
+    }
+}
+
+impl Default for NativeRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn class_natives(class_name: &str) -> NativeTable {
+    match class_name {
+        "java/lang/Object" => &[
+            ("hashCode", "()I", java_lang_Object_hashCode),
+            ("getClass", "()Ljava/lang/Class;", java_lang_Object_getClass),
+            ("wait", "(J)V", java_lang_Object_wait),
+            ("notify", "()V", java_lang_Object_notify),
+            ("notifyAll", "()V", java_lang_Object_notifyAll),
+        ],
+        "java/lang/System" => &[
+            (
+                "initProperties",
+                "(Ljava/util/Properties;)Ljava/util/Properties;",
+                java_lang_System_initProperties,
+            ),
+            (
+                "mapLibraryName",
+                "(Ljava/lang/String;)Ljava/lang/String;",
+                java_lang_System_mapLibraryName,
+            ),
+        ],
+        "java/lang/Class" => &[
+            (
+                "getPrimitiveClass",
+                "(Ljava/lang/String;)Ljava/lang/Class;",
+                java_lang_Class_getPrimitiveClass,
+            ),
+            (
+                "desiredAssertionStatus0",
+                "(Ljava/lang/Class;)Z",
+                jvm_desiredAssertionStatus0,
+            ),
+            ("getName0", "()Ljava/lang/String;", java_lang_Class_getName0),
+            (
+                "forName0",
+                "(Ljava/lang/String;ZLjava/lang/ClassLoader;Ljava/lang/Class;)Ljava/lang/Class;",
+                java_lang_Class_for_Name0,
+            ),
+            (
+                "getSuperclass",
+                "()Ljava/lang/Class;",
+                java_lang_Class_getSuperclass,
+            ),
+            ("isInterface", "()Z", java_lang_Class_isInterface),
+            ("isArray", "()Z", java_lang_Class_isArray),
+            ("isPrimitive", "()Z", java_lang_Class_isPrimitive),
+            (
+                "getComponentType",
+                "()Ljava/lang/Class;",
+                java_lang_Class_getComponentType,
+            ),
+            (
+                "isAssignableFrom",
+                "(Ljava/lang/Class;)Z",
+                java_lang_Class_isAssignableFrom,
+            ),
+            ("getModifiers", "()I", java_lang_Class_getModifiers),
+            (
+                "getInterfaces0",
+                "()[Ljava/lang/Class;",
+                java_lang_Class_getInterfaces0,
+            ),
+            (
+                "getDeclaredFields0",
+                "(Z)[Ljava/lang/reflect/Field;",
+                java_lang_Class_getDeclaredFields0,
+            ),
+            (
+                "getDeclaredMethods0",
+                "(Z)[Ljava/lang/reflect/Method;",
+                java_lang_Class_getDeclaredMethods0,
+            ),
+            (
+                "getDeclaredConstructors0",
+                "(Z)[Ljava/lang/reflect/Constructor;",
+                java_lang_Class_getDeclaredConstructors0,
+            ),
+        ],
+        "java/lang/Thread" => &[
+            (
+                "currentThread",
+                "()Ljava/lang/Thread;",
+                java_lang_Thread_currentThread,
+            ),
+            ("setPriority0", "(I)V", java_lang_Thread_setPriority0),
+            ("start0", "()V", java_lang_Thread_start0),
+            ("isAlive", "()Z", java_lang_Thread_isAlive),
+            ("sleep", "(J)V", java_lang_Thread_sleep),
+            ("yield", "()V", java_lang_Thread_yield),
+            ("interrupt0", "()V", java_lang_Thread_interrupt0),
+            ("isInterrupted", "(Z)Z", java_lang_Thread_isInterrupted),
+        ],
+        "sun/misc/Unsafe" => &[
+            (
+                "arrayBaseOffset",
+                "(Ljava/lang/Class;)I",
+                sun_misc_Unsafe_arrayBaseOffset,
+            ),
+            (
+                "arrayIndexScale",
+                "(Ljava/lang/Class;)I",
+                sun_misc_Unsafe_arrayIndexScale,
+            ),
+            ("addressSize", "()I", sun_misc_Unsafe_addressSize),
+            (
+                "compareAndSwapInt",
+                "(Ljava/lang/Object;JII)Z",
+                sun_misc_Unsafe_compareAndSwap,
+            ),
+            (
+                "compareAndSwapLong",
+                "(Ljava/lang/Object;JJJ)Z",
+                sun_misc_Unsafe_compareAndSwap,
+            ),
+            (
+                "compareAndSwapObject",
+                "(Ljava/lang/Object;JLjava/lang/Object;Ljava/lang/Object;)Z",
+                sun_misc_Unsafe_compareAndSwap,
+            ),
+            (
+                "objectFieldOffset",
+                "(Ljava/lang/reflect/Field;)J",
+                sun_misc_Unsafe_objectFieldOffset,
+            ),
+            (
+                "staticFieldOffset",
+                "(Ljava/lang/reflect/Field;)J",
+                sun_misc_Unsafe_staticFieldOffset,
+            ),
+            (
+                "staticFieldBase",
+                "(Ljava/lang/reflect/Field;)Ljava/lang/Object;",
+                sun_misc_Unsafe_staticFieldBase,
+            ),
+            (
+                "ensureClassInitialized",
+                "(Ljava/lang/Class;)V",
+                sun_misc_Unsafe_ensureClassInitialized,
+            ),
+            (
+                "shouldBeInitialized",
+                "(Ljava/lang/Class;)Z",
+                sun_misc_Unsafe_shouldBeInitialized,
+            ),
+        ],
+        "java/lang/Float" => &[(
+            "floatToRawIntBits",
+            "(F)I",
+            java_lang_Float_floatToRawIntBits,
+        )],
+        "java/lang/Double" => &[
+            (
+                "doubleToRawLongBits",
+                "(D)J",
+                java_lang_Double_doubleToRawLongBits,
+            ),
+            (
+                "longBitsToDouble",
+                "(J)D",
+                java_lang_Double_longBitsToDouble,
+            ),
+        ],
+        "java/lang/String" => &[("intern", "()Ljava/lang/String;", java_lang_String_intern)],
+        "java/lang/Throwable" => &[
+            (
+                "fillInStackTrace",
+                "(I)Ljava/lang/Throwable;",
+                java_lang_Throwable_fillInStackTrace,
+            ),
+            (
+                "getStackTraceDepth",
+                "()I",
+                java_lang_Throwable_getStackTraceDepth,
+            ),
+            (
+                "getStackTraceElement",
+                "(I)Ljava/lang/StackTraceElement;",
+                java_lang_Throwable_getStackTraceElement,
+            ),
+        ],
+        "java/lang/Shutdown" => &[("halt0", "(I)V", java_lang_Shutdown_halt0)],
+        "sun/misc/VM" => &[("initialize", "()V", sun_misc_VM_initalize)],
+        "sun/reflect/Reflection" => &[(
+            "getCallerClass",
+            "()Ljava/lang/Class;",
+            sun_reflect_Reflection_getCallerClass,
+        )],
+        "sun/reflect/NativeMethodAccessorImpl" => &[(
+            "invoke0",
+            "(Ljava/lang/reflect/Method;Ljava/lang/Object;[Ljava/lang/Object;)Ljava/lang/Object;",
+            sun_reflect_NativeMethodAccessorImpl_invoke0,
+        )],
+        "sun/reflect/NativeConstructorAccessorImpl" => &[(
+            "newInstance0",
+            "(Ljava/lang/reflect/Constructor;[Ljava/lang/Object;)Ljava/lang/Object;",
+            sun_reflect_NativeConstructorAccessorImpl_newInstance0,
+        )],
+        "java/io/FileDescriptor" => &[
+            ("initIDs", "()V", java_io_FileDescriptor_initIDs),
+            ("sync", "()V", java_io_FileDescriptor_sync),
+        ],
+        "java/io/FileInputStream" => &[
+            ("initIDs", "()V", java_io_FileInputStream_initIDs),
+            (
+                "open0",
+                "(Ljava/lang/String;)V",
+                java_io_FileInputStream_open0,
+            ),
+            ("read0", "()I", java_io_FileInputStream_read0),
+            ("readBytes", "([BII)I", java_io_FileInputStream_readBytes),
+            ("skip", "(J)J", java_io_FileInputStream_skip),
+            ("available", "()I", java_io_FileInputStream_available),
+            ("close0", "()V", java_io_FileInputStream_close0),
+        ],
+        "java/io/FileOutputStream" => &[
+            ("initIDs", "()V", java_io_FileOutputStream_initIDs),
+            (
+                "open0",
+                "(Ljava/lang/String;Z)V",
+                java_io_FileOutputStream_open0,
+            ),
+            ("write", "(IZ)V", java_io_FileOutputStream_write),
+            (
+                "writeBytes",
+                "([BIIZ)V",
+                java_io_FileOutputStream_writeBytes,
+            ),
+            ("close0", "()V", java_io_FileOutputStream_close0),
+        ],
+        "java/io/RandomAccessFile" => &[
+            ("initIDs", "()V", java_io_RandomAccessFile_initIDs),
+            (
+                "open0",
+                "(Ljava/lang/String;I)V",
+                java_io_RandomAccessFile_open0,
+            ),
+            ("read0", "()I", java_io_RandomAccessFile_read0),
+            ("readBytes", "([BII)I", java_io_RandomAccessFile_readBytes),
+            ("write0", "(I)V", java_io_RandomAccessFile_write0),
+            ("writeBytes", "([BII)V", java_io_RandomAccessFile_writeBytes),
+            (
+                "getFilePointer",
+                "()J",
+                java_io_RandomAccessFile_getFilePointer,
+            ),
+            ("seek0", "(J)V", java_io_RandomAccessFile_seek0),
+            ("length", "()J", java_io_RandomAccessFile_length),
+            ("setLength", "(J)V", java_io_RandomAccessFile_setLength),
+            ("close0", "()V", java_io_RandomAccessFile_close0),
+        ],
+        "java/io/UnixFileSystem" => &[
+            ("initIDs", "()V", java_io_UnixFileSystem_initIDs),
+            (
+                "canonicalize0",
+                "(Ljava/lang/String;)Ljava/lang/String;",
+                java_io_UnixFileSystem_canonicalize0,
+            ),
+            (
+                "getBooleanAttributes0",
+                "(Ljava/io/File;)I",
+                java_io_UnixFileSystem_getBooleanAttributes0,
+            ),
+            (
+                "checkAccess",
+                "(Ljava/io/File;I)Z",
+                java_io_UnixFileSystem_checkAccess,
+            ),
+            (
+                "getLastModifiedTime",
+                "(Ljava/io/File;)J",
+                java_io_UnixFileSystem_getLastModifiedTime,
+            ),
+            (
+                "getLength",
+                "(Ljava/io/File;)J",
+                java_io_UnixFileSystem_getLength,
+            ),
+            (
+                "setPermission",
+                "(Ljava/io/File;IZZ)Z",
+                java_io_UnixFileSystem_setPermission,
+            ),
+            (
+                "createFileExclusively",
+                "(Ljava/lang/String;)Z",
+                java_io_UnixFileSystem_createFileExclusively,
+            ),
+            (
+                "delete0",
+                "(Ljava/io/File;)Z",
+                java_io_UnixFileSystem_delete0,
+            ),
+            (
+                "list",
+                "(Ljava/io/File;)[Ljava/lang/String;",
+                java_io_UnixFileSystem_list,
+            ),
+            (
+                "createDirectory",
+                "(Ljava/io/File;)Z",
+                java_io_UnixFileSystem_createDirectory,
+            ),
+            (
+                "rename0",
+                "(Ljava/io/File;Ljava/io/File;)Z",
+                java_io_UnixFileSystem_rename0,
+            ),
+            (
+                "setLastModifiedTime",
+                "(Ljava/io/File;J)Z",
+                java_io_UnixFileSystem_setLastModifiedTime,
+            ),
+            (
+                "setReadOnly",
+                "(Ljava/io/File;)Z",
+                java_io_UnixFileSystem_setReadOnly,
+            ),
+            (
+                "getSpace",
+                "(Ljava/io/File;I)J",
+                java_io_UnixFileSystem_getSpace,
+            ),
+        ],
+        "java/io/Console" => &[
+            ("encoding", "()Ljava/lang/String;", java_io_Console_encoding),
+            ("echo", "(Z)Z", java_io_Console_echo),
+            ("istty", "()Z", java_io_Console_istty),
+        ],
+        "java/security/AccessController" => &[
+            (
+                "doPrivileged",
+                "(Ljava/security/PrivilegedExceptionAction;)Ljava/lang/Object;",
+                java_security_AccessController_doPrivileged,
+            ),
+            (
+                "doPrivileged",
+                "(Ljava/security/PrivilegedAction;)Ljava/lang/Object;",
+                java_security_AccessController_doPrivileged,
+            ),
+            (
+                "getStackAccessControlContext",
+                "()Ljava/security/AccessControlContext;",
+                java_security_AccessController_getStackAccessControlContext,
+            ),
+        ],
+        "java/lang/ClassLoader$NativeLibrary" => &[
+            (
+                "load",
+                "(Ljava/lang/String;Z)V",
+                java_lang_ClassLoader_NativeLibrary_load,
+            ),
+            (
+                "find",
+                "(Ljava/lang/String;)J",
+                java_lang_ClassLoader_NativeLibrary_find,
+            ),
+            (
+                "unload",
+                "(Ljava/lang/String;Z)V",
+                java_lang_ClassLoader_NativeLibrary_unload,
+            ),
+            (
+                "findBuiltinLib",
+                "(Ljava/lang/String;)Ljava/lang/String;",
+                java_lang_ClassLoader_NativeLibrary_findBuiltinLib,
+            ),
+        ],
+        "java/util/zip/ZipFile" => &[
+            ("initIDs", "()V", java_util_zip_ZipFile_initIDs),
+            (
+                "open",
+                "(Ljava/lang/String;IJZ)J",
+                java_util_zip_ZipFile_open,
+            ),
+            ("getTotal", "(J)I", java_util_zip_ZipFile_getTotal),
+            ("startsWithLOC", "(J)Z", java_util_zip_ZipFile_startsWithLOC),
+            ("read", "(JJJ[BII)I", java_util_zip_ZipFile_read),
+            ("getEntry", "(J[BZ)J", java_util_zip_ZipFile_getEntry),
+            ("freeEntry", "(JJ)V", java_util_zip_ZipFile_freeEntry),
+            ("getNextEntry", "(JI)J", java_util_zip_ZipFile_getNextEntry),
+            ("close", "(J)V", java_util_zip_ZipFile_close),
+            ("getEntryTime", "(J)I", java_util_zip_ZipFile_getEntryTime),
+            ("getEntryCrc", "(J)J", java_util_zip_ZipFile_getEntryCrc),
+            ("getEntryCSize", "(J)J", java_util_zip_ZipFile_getEntryCSize),
+            ("getEntrySize", "(J)J", java_util_zip_ZipFile_getEntrySize),
+            (
+                "getEntryMethod",
+                "(J)I",
+                java_util_zip_ZipFile_getEntryMethod,
+            ),
+            ("getEntryFlag", "(J)I", java_util_zip_ZipFile_getEntryFlag),
+            (
+                "getEntryBytes",
+                "(JI)[B",
+                java_util_zip_ZipFile_getEntryBytes,
+            ),
+            (
+                "getCommentBytes",
+                "(J)[B",
+                java_util_zip_ZipFile_getCommentBytes,
+            ),
+            (
+                "getZipMessage",
+                "(J)Ljava/lang/String;",
+                java_util_zip_ZipFile_getZipMessage,
+            ),
+        ],
+        "java/util/jar/JarFile" => &[(
+            "getMetaInfEntryNames",
+            "()[Ljava/lang/String;",
+            java_util_jar_JarFile_getMetaInfEntryNames,
+        )],
+        "java/util/zip/Inflater" => &[
+            ("initIDs", "()V", java_util_zip_Inflater_initIDs),
+            ("init", "(Z)J", java_util_zip_Inflater_init),
+            (
+                "inflateBytes",
+                "(J[BII)I",
+                java_util_zip_Inflater_inflateBytes,
+            ),
+            ("reset", "(J)V", java_util_zip_Inflater_reset),
+            ("end", "(J)V", java_util_zip_Inflater_end),
+        ],
+        _ => &[],
+    }
+}
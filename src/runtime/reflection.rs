@@ -0,0 +1,572 @@
+#![allow(non_snake_case, unused_variables)]
+//! The natives behind `java.lang.reflect`: the fields, methods and
+//! constructors of a class, and invoking methods and constructors with boxed
+//! arguments.
+//!
+//! Types are resolved before any reflection object is allocated. Resolving may
+//! run Java code and collect garbage, allocating doesn't, so the objects are
+//! reachable from the operand stack before the next collection.
+use crate::class_parser::{
+    ACC_ABSTRACT, ACC_ANNOTATION, ACC_BRIDGE, ACC_ENUM, ACC_FINAL, ACC_INTERFACE, ACC_NATIVE,
+    ACC_PRIVATE, ACC_PROTECTED, ACC_PUBLIC, ACC_STATIC, ACC_STRICT, ACC_SYNCHRONIZED,
+    ACC_SYNTHETIC, ACC_TRANSIENT, ACC_VARARGS, ACC_VOLATILE,
+};
+use crate::runtime::class::Class;
+use crate::runtime::execute_method;
+use crate::runtime::field::Field;
+use crate::runtime::frame::operand_stack::Operand;
+use crate::runtime::heap::{
+    JvmHeap, JAVA_IO_SERIALIZABLE, JAVA_LANG_CLASS, JAVA_LANG_CLASS_DESCRIPTOR,
+    JAVA_LANG_CLONEABLE, JAVA_LANG_ILLEGAL_ARGUMENT_EXCEPTION, JAVA_LANG_INSTANTIATION_EXCEPTION,
+    JAVA_LANG_NULL_POINTER_EXCEPTION, JAVA_LANG_REFLECT_CONSTRUCTOR, JAVA_LANG_REFLECT_FIELD,
+    JAVA_LANG_REFLECT_INVOCATION_TARGET_EXCEPTION, JAVA_LANG_REFLECT_METHOD,
+    JAVA_LANG_STRING_DESCRIPTOR,
+};
+use crate::runtime::instruction::{can_cast_to, select_method, virtual_call};
+use crate::runtime::jvm_env::JvmEnv;
+use crate::runtime::method::Method;
+use crate::runtime::mirror::{wrapped_descriptor, wrapper_class_name, MirroredType};
+
+/// `ACC_SUPER` and the flags only the VM cares about aren't modifiers.
+const CLASS_MODIFIERS: u16 = ACC_PUBLIC
+    | ACC_FINAL
+    | ACC_INTERFACE
+    | ACC_ABSTRACT
+    | ACC_SYNTHETIC
+    | ACC_ANNOTATION
+    | ACC_ENUM;
+const FIELD_MODIFIERS: u16 = ACC_PUBLIC
+    | ACC_PRIVATE
+    | ACC_PROTECTED
+    | ACC_STATIC
+    | ACC_FINAL
+    | ACC_VOLATILE
+    | ACC_TRANSIENT
+    | ACC_SYNTHETIC
+    | ACC_ENUM;
+const METHOD_MODIFIERS: u16 = ACC_PUBLIC
+    | ACC_PRIVATE
+    | ACC_PROTECTED
+    | ACC_STATIC
+    | ACC_FINAL
+    | ACC_SYNCHRONIZED
+    | ACC_BRIDGE
+    | ACC_VARARGS
+    | ACC_NATIVE
+    | ACC_ABSTRACT
+    | ACC_STRICT
+    | ACC_SYNTHETIC;
+
+/// The mirrors of the types in the signature of a method.
+struct ResolvedSignature {
+    parameter_types: Vec<u32>,
+    return_type: u32,
+    exception_types: Vec<u32>,
+}
+
+pub fn java_lang_Class_getDeclaredFields0(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let public_only = args[1].get_int() != 0;
+    let mut fields: Vec<Field> = match declaring_class(jenv, &args[0]) {
+        Some(class) => class
+            .instance_fields()
+            .values()
+            .chain(class.static_fields().values())
+            .filter(|field| !public_only || field.access_flags() & ACC_PUBLIC != 0)
+            .cloned()
+            .collect(),
+        None => vec![],
+    };
+    // in the order they are declared, instance fields first
+    fields.sort_by_key(|field| (field.is_static(), field.index()));
+
+    let field_class = jenv.load_and_init_class(JAVA_LANG_REFLECT_FIELD);
+    let mut types = Vec::with_capacity(fields.len());
+    for field in &fields {
+        match jenv.descriptor_mirror(&field.descriptor()) {
+            Some(mirror) => types.push(mirror),
+            None => return,
+        }
+    }
+
+    let names = match new_strings(jenv, fields.iter().map(|field| field.name())) {
+        Some(names) => names,
+        None => return,
+    };
+
+    let mut objects = Vec::with_capacity(fields.len());
+    for ((field, ty), name) in fields.iter().zip(types).zip(names) {
+        let (object, addr) = jenv.heap_mut().new_object(field_class.clone());
+        object.set_field_by_name("clazz", JAVA_LANG_CLASS_DESCRIPTOR, args[0].clone());
+        object.set_field_by_name(
+            "name",
+            JAVA_LANG_STRING_DESCRIPTOR,
+            Operand::ObjectRef(name),
+        );
+        object.set_field_by_name("type", JAVA_LANG_CLASS_DESCRIPTOR, Operand::ObjectRef(ty));
+        let modifiers = field.access_flags() & FIELD_MODIFIERS;
+        object.set_field_by_name("modifiers", "I", Operand::Int(modifiers as i32));
+        object.set_field_by_name("slot", "I", Operand::Int(field.index() as i32));
+        objects.push(addr);
+    }
+    let array = new_reference_array(jenv, JAVA_LANG_REFLECT_FIELD, &objects);
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(array);
+}
+
+pub fn java_lang_Class_getDeclaredMethods0(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let public_only = args[1].get_int() != 0;
+    let methods = declared_methods(jenv, &args[0], public_only, |method| {
+        method.name() != "<init>" && method.name() != "<clinit>"
+    });
+
+    let method_class = jenv.load_and_init_class(JAVA_LANG_REFLECT_METHOD);
+    let mut signatures = Vec::with_capacity(methods.len());
+    for (_, method) in &methods {
+        match resolve_signature(jenv, method) {
+            Some(signature) => signatures.push(signature),
+            None => return,
+        }
+    }
+
+    let names = match new_strings(jenv, methods.iter().map(|(_, method)| method.name())) {
+        Some(names) => names,
+        None => return,
+    };
+
+    let mut objects = Vec::with_capacity(methods.len());
+    for (((slot, method), signature), name) in methods.iter().zip(signatures).zip(names) {
+        let parameter_types =
+            new_reference_array(jenv, JAVA_LANG_CLASS, &signature.parameter_types);
+        let exception_types =
+            new_reference_array(jenv, JAVA_LANG_CLASS, &signature.exception_types);
+        let return_type = Operand::ObjectRef(signature.return_type);
+        let (object, addr) = jenv.heap_mut().new_object(method_class.clone());
+        object.set_field_by_name("clazz", JAVA_LANG_CLASS_DESCRIPTOR, args[0].clone());
+        object.set_field_by_name(
+            "name",
+            JAVA_LANG_STRING_DESCRIPTOR,
+            Operand::ObjectRef(name),
+        );
+        object.set_field_by_name("returnType", JAVA_LANG_CLASS_DESCRIPTOR, return_type);
+        object.set_field_by_name("parameterTypes", "[Ljava/lang/Class;", parameter_types);
+        object.set_field_by_name("exceptionTypes", "[Ljava/lang/Class;", exception_types);
+        let modifiers = method.access_flags() & METHOD_MODIFIERS;
+        object.set_field_by_name("modifiers", "I", Operand::Int(modifiers as i32));
+        object.set_field_by_name("slot", "I", Operand::Int(*slot as i32));
+        objects.push(addr);
+    }
+    let array = new_reference_array(jenv, JAVA_LANG_REFLECT_METHOD, &objects);
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(array);
+}
+
+pub fn java_lang_Class_getDeclaredConstructors0(
+    jenv: &mut JvmEnv,
+    class: &Class,
+    args: Vec<Operand>,
+) {
+    let public_only = args[1].get_int() != 0;
+    let constructors = declared_methods(jenv, &args[0], public_only, |method| {
+        method.name() == "<init>"
+    });
+
+    let constructor_class = jenv.load_and_init_class(JAVA_LANG_REFLECT_CONSTRUCTOR);
+    let mut signatures = Vec::with_capacity(constructors.len());
+    for (_, constructor) in &constructors {
+        match resolve_signature(jenv, constructor) {
+            Some(signature) => signatures.push(signature),
+            None => return,
+        }
+    }
+
+    let mut objects = Vec::with_capacity(constructors.len());
+    for ((slot, constructor), signature) in constructors.iter().zip(signatures) {
+        let parameter_types =
+            new_reference_array(jenv, JAVA_LANG_CLASS, &signature.parameter_types);
+        let exception_types =
+            new_reference_array(jenv, JAVA_LANG_CLASS, &signature.exception_types);
+        let (object, addr) = jenv.heap_mut().new_object(constructor_class.clone());
+        object.set_field_by_name("clazz", JAVA_LANG_CLASS_DESCRIPTOR, args[0].clone());
+        object.set_field_by_name("parameterTypes", "[Ljava/lang/Class;", parameter_types);
+        object.set_field_by_name("exceptionTypes", "[Ljava/lang/Class;", exception_types);
+        let modifiers = constructor.access_flags() & METHOD_MODIFIERS;
+        object.set_field_by_name("modifiers", "I", Operand::Int(modifiers as i32));
+        object.set_field_by_name("slot", "I", Operand::Int(*slot as i32));
+        objects.push(addr);
+    }
+    let array = new_reference_array(jenv, JAVA_LANG_REFLECT_CONSTRUCTOR, &objects);
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(array);
+}
+
+pub fn java_lang_Class_getModifiers(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let ty = jenv.mirrored_type(&args[0]);
+    let modifiers = class_modifiers(jenv, &ty);
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push_integer(modifiers as i32);
+}
+
+pub fn java_lang_Class_getInterfaces0(jenv: &mut JvmEnv, class: &Class, args: Vec<Operand>) {
+    let interfaces: Vec<Class> = match jenv.mirrored_type(&args[0]) {
+        MirroredType::Class(Class::InstanceClass(class)) => class
+            .interfaces()
+            .iter()
+            .cloned()
+            .map(Class::from)
+            .collect(),
+        // arrays implement exactly these two interfaces
+        MirroredType::Class(_) => vec![
+            jenv.load_class(JAVA_LANG_CLONEABLE),
+            jenv.load_class(JAVA_IO_SERIALIZABLE),
+        ],
+        MirroredType::Primitive(_) => vec![],
+    };
+    let mirrors: Vec<u32> = interfaces
+        .iter()
+        .map(|interface| jenv.class_mirror(interface))
+        .collect();
+    let array = new_reference_array(jenv, JAVA_LANG_CLASS, &mirrors);
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(array);
+}
+
+/// Invokes the method `args[0]` stands for on `args[1]` with the boxed
+/// arguments `args[2]`, and returns the boxed result. An exception the method
+/// throws is wrapped in an `InvocationTargetException`.
+pub fn sun_reflect_NativeMethodAccessorImpl_invoke0(
+    jenv: &mut JvmEnv,
+    class: &Class,
+    args: Vec<Operand>,
+) {
+    let method = reflected_method(jenv, &args[0]);
+    let declaring_class = jenv.load_class(method.class_name());
+    let mut call_args = Vec::with_capacity(method.n_args() + 1);
+    let method = if method.is_static() {
+        if !jenv.initialize_class(&declaring_class) {
+            return;
+        }
+        method
+    } else {
+        let receiver = args[1].clone();
+        if receiver == Operand::Null {
+            jenv.throw_exception(JAVA_LANG_NULL_POINTER_EXCEPTION, None);
+            return;
+        }
+        let receiver_class_name = jenv.heap().get_class_name(&receiver);
+        let receiver_class = jenv.load_class(&receiver_class_name);
+        if !can_cast_to(jenv, receiver_class.clone(), declaring_class.clone()) {
+            jenv.throw_exception(
+                JAVA_LANG_ILLEGAL_ARGUMENT_EXCEPTION,
+                Some("object is not an instance of declaring class"),
+            );
+            return;
+        }
+        let call = virtual_call(jenv, method);
+        let method = match select_method(jenv, &call, &receiver) {
+            Some(method) => method,
+            None => return,
+        };
+        call_args.push(receiver);
+        method
+    };
+    if !unbox_arguments(jenv, &method, &args[2], &mut call_args) {
+        return;
+    }
+
+    let return_descriptor = method.return_descriptor().to_string();
+    execute_method(jenv, method, call_args);
+    if !finish_invocation(jenv) {
+        return;
+    }
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    let value = if return_descriptor == "V" {
+        Operand::Null
+    } else {
+        frame.operand_stack.pop()
+    };
+    let value = box_value(jenv, &return_descriptor, value);
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(value);
+}
+
+/// Creates an instance with the constructor `args[0]` stands for and the
+/// boxed arguments `args[1]`.
+pub fn sun_reflect_NativeConstructorAccessorImpl_newInstance0(
+    jenv: &mut JvmEnv,
+    class: &Class,
+    args: Vec<Operand>,
+) {
+    let constructor = reflected_method(jenv, &args[0]);
+    let class = jenv.load_class(constructor.class_name());
+    if class.access_flags() & (ACC_ABSTRACT | ACC_INTERFACE) != 0 {
+        let class_name = class.name().replace('/', ".");
+        jenv.throw_exception(JAVA_LANG_INSTANTIATION_EXCEPTION, Some(&class_name));
+        return;
+    }
+    if !jenv.initialize_class(&class) {
+        return;
+    }
+    let mut call_args = Vec::with_capacity(constructor.n_args() + 1);
+    call_args.push(Operand::Null);
+    if !unbox_arguments(jenv, &constructor, &args[1], &mut call_args) {
+        return;
+    }
+
+    if !jenv.reserve_heap(JvmHeap::object_size(&class)) {
+        return;
+    }
+    let (_, addr) = jenv.heap_mut().new_object(class);
+    call_args[0] = Operand::ObjectRef(addr);
+    execute_method(jenv, constructor, call_args);
+    if !finish_invocation(jenv) {
+        return;
+    }
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(Operand::ObjectRef(addr));
+}
+
+/// Boxes a value of the type `descriptor` as returned by a method, `void`
+/// becomes null.
+pub fn box_value(jenv: &mut JvmEnv, descriptor: &str, value: Operand) -> Operand {
+    match descriptor.as_bytes()[0] {
+        b'V' => Operand::Null,
+        b'L' | b'[' => value,
+        ty => {
+            let class = jenv.load_and_init_class(wrapper_class_name(ty));
+            let (object, addr) = jenv.heap_mut().new_object(class);
+            object.set_field_by_name("value", descriptor, value);
+            Operand::ObjectRef(addr)
+        }
+    }
+}
+
+/// The class whose members are listed for the mirror, none for arrays and
+/// primitive types.
+fn declaring_class(jenv: &JvmEnv, mirror: &Operand) -> Option<Class> {
+    match jenv.mirrored_type(mirror) {
+        MirroredType::Class(class @ Class::InstanceClass(_)) => Some(class),
+        _ => None,
+    }
+}
+
+/// The methods of the mirrored class `filter` accepts, with their index in
+/// `InstanceClass::methods()` which is their slot.
+fn declared_methods(
+    jenv: &JvmEnv,
+    mirror: &Operand,
+    public_only: bool,
+    filter: impl Fn(&Method) -> bool,
+) -> Vec<(usize, Method)> {
+    match declaring_class(jenv, mirror) {
+        Some(class) => class
+            .methods()
+            .iter()
+            .enumerate()
+            .filter(|(_, method)| !public_only || method.is_public())
+            .filter(|(_, method)| filter(method))
+            .map(|(slot, method)| (slot, method.clone()))
+            .collect(),
+        None => vec![],
+    }
+}
+
+fn resolve_signature(jenv: &mut JvmEnv, method: &Method) -> Option<ResolvedSignature> {
+    let mut parameter_types = Vec::with_capacity(method.n_args());
+    for descriptor in method.param_descriptors() {
+        parameter_types.push(jenv.descriptor_mirror(descriptor)?);
+    }
+    let return_type = jenv.descriptor_mirror(method.return_descriptor())?;
+    let mut exception_types = Vec::with_capacity(method.exceptions().len());
+    for class_name in method.exceptions() {
+        exception_types.push(jenv.resolve_class_mirror(class_name)?);
+    }
+    Some(ResolvedSignature {
+        parameter_types,
+        return_type,
+        exception_types,
+    })
+}
+
+fn class_modifiers(jenv: &mut JvmEnv, ty: &MirroredType) -> u16 {
+    match ty {
+        MirroredType::Class(Class::InstanceClass(class)) => class.access_flags() & CLASS_MODIFIERS,
+        // the visibility of the component, and they can be neither extended
+        // nor instantiated like classes
+        MirroredType::Class(Class::ObjArrayClass(class)) => {
+            let component = MirroredType::Class(jenv.load_class(&class.class));
+            let visibility = ACC_PUBLIC | ACC_PRIVATE | ACC_PROTECTED;
+            (class_modifiers(jenv, &component) & visibility) | ACC_ABSTRACT | ACC_FINAL
+        }
+        MirroredType::Class(Class::TypeArrayClass(_)) | MirroredType::Primitive(_) => {
+            ACC_PUBLIC | ACC_ABSTRACT | ACC_FINAL
+        }
+    }
+}
+
+/// New strings of `values`, each kept alive while the next is allocated.
+/// None if the heap ran out.
+fn new_strings<S: AsRef<str>>(
+    jenv: &mut JvmEnv,
+    values: impl Iterator<Item = S>,
+) -> Option<Vec<u32>> {
+    jenv.thread_mut().native_args.push(vec![]);
+    let mut addrs = vec![];
+    for value in values {
+        let addr = match jenv.new_java_lang_string(value.as_ref()) {
+            Some(addr) => addr,
+            None => {
+                jenv.thread_mut().native_args.pop();
+                return None;
+            }
+        };
+        let roots = jenv.thread_mut().native_args.last_mut().unwrap();
+        roots.push(Operand::ObjectRef(addr));
+        addrs.push(addr);
+    }
+    jenv.thread_mut().native_args.pop();
+    Some(addrs)
+}
+
+fn new_reference_array(jenv: &mut JvmEnv, class_name: &str, elements: &[u32]) -> Operand {
+    let count = elements.len() as i32;
+    let array = Operand::ArrayRef(
+        jenv.heap_mut()
+            .new_reference_array(class_name.to_string(), count),
+    );
+    let values = jenv.heap_mut().get_object_array_mut(&array);
+    for (value, addr) in values.iter_mut().zip(elements) {
+        *value = Operand::ObjectRef(*addr);
+    }
+    array
+}
+
+/// The method or constructor a `Method` or `Constructor` object stands for.
+fn reflected_method(jenv: &JvmEnv, reflected: &Operand) -> Method {
+    let object = jenv.heap().get_object(reflected);
+    let mirror = object.get_field_by_name("clazz", JAVA_LANG_CLASS_DESCRIPTOR);
+    let slot = object.get_field_by_name("slot", "I").get_int();
+    match jenv.mirrored_type(mirror) {
+        MirroredType::Class(class) => class.methods()[slot as usize].clone(),
+        ty => unreachable!("{:?}", ty),
+    }
+}
+
+/// Unboxes the arguments in the `Object[]` `array` for the parameters of
+/// `method` and appends them to `call_args`. Throws `IllegalArgumentException`
+/// and returns false if they don't fit.
+fn unbox_arguments(
+    jenv: &mut JvmEnv,
+    method: &Method,
+    array: &Operand,
+    call_args: &mut Vec<Operand>,
+) -> bool {
+    // a method without parameters may be called with null
+    let values = match array {
+        Operand::Null => vec![],
+        array => jenv.heap_mut().get_object_array(array).clone(),
+    };
+    let descriptors = method.param_descriptors();
+    if values.len() != descriptors.len() {
+        jenv.throw_exception(
+            JAVA_LANG_ILLEGAL_ARGUMENT_EXCEPTION,
+            Some("wrong number of arguments"),
+        );
+        return false;
+    }
+    for (descriptor, value) in descriptors.iter().zip(values) {
+        match unbox_value(jenv, descriptor, value) {
+            Some(value) => call_args.push(value),
+            None => {
+                jenv.throw_exception(
+                    JAVA_LANG_ILLEGAL_ARGUMENT_EXCEPTION,
+                    Some("argument type mismatch"),
+                );
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Converts a boxed argument to the parameter type `descriptor`, unboxing and
+/// widening primitives. `None` if it can't be converted.
+fn unbox_value(jenv: &mut JvmEnv, descriptor: &str, value: Operand) -> Option<Operand> {
+    if value == Operand::Null {
+        return match descriptor.as_bytes()[0] {
+            b'L' | b'[' => Some(value),
+            _ => None,
+        };
+    }
+    let class_name = jenv.heap().get_class_name(&value);
+    match descriptor.as_bytes()[0] {
+        b'L' | b'[' => {
+            let parameter_class_name = if descriptor.starts_with('L') {
+                &descriptor[1..descriptor.len() - 1]
+            } else {
+                descriptor
+            };
+            let class = jenv.load_class(&class_name);
+            let parameter_class = jenv.load_class(parameter_class_name);
+            if can_cast_to(jenv, class, parameter_class) {
+                Some(value)
+            } else {
+                None
+            }
+        }
+        ty => {
+            let wrapped = wrapped_descriptor(&class_name)?;
+            let wrapped_descriptor = (wrapped as char).to_string();
+            let value = jenv
+                .heap()
+                .get_object(&value)
+                .get_field_by_name("value", &wrapped_descriptor)
+                .clone();
+            widen(wrapped, ty, value)
+        }
+    }
+}
+
+/// A widening primitive conversion of `value` from the type `from` to `to`
+/// (JLS §5.1.2), `None` if there is none.
+fn widen(from: u8, to: u8, value: Operand) -> Option<Operand> {
+    if from == to {
+        return Some(value);
+    }
+    let wider: &[u8] = match from {
+        b'B' => b"SIJFD",
+        b'S' | b'C' => b"IJFD",
+        b'I' => b"JFD",
+        b'J' => b"FD",
+        b'F' => b"D",
+        _ => b"",
+    };
+    if !wider.contains(&to) {
+        return None;
+    }
+    let value = match (value, to) {
+        (value @ Operand::Int(_), b'S') | (value @ Operand::Int(_), b'I') => value,
+        (Operand::Int(n), b'J') => Operand::Long(n as i64),
+        (Operand::Int(n), b'F') => Operand::Float(n as f32),
+        (Operand::Int(n), b'D') => Operand::Double(n as f64),
+        (Operand::Long(n), b'F') => Operand::Float(n as f32),
+        (Operand::Long(n), b'D') => Operand::Double(n as f64),
+        (Operand::Float(n), b'D') => Operand::Double(n as f64),
+        (value, to) => unreachable!("{:?} to {}", value, to as char),
+    };
+    Some(value)
+}
+
+/// Wraps the exception the invoked method threw, if any, in an
+/// `InvocationTargetException`. False if it threw or the VM is halting.
+fn finish_invocation(jenv: &mut JvmEnv) -> bool {
+    if jenv.exit_status().is_some() {
+        return false;
+    }
+    match jenv.thread_mut().take_pending_exception() {
+        Some(exception) => {
+            jenv.throw_wrapped_exception(JAVA_LANG_REFLECT_INVOCATION_TARGET_EXCEPTION, exception);
+            false
+        }
+        None => true,
+    }
+}
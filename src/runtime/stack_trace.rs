@@ -0,0 +1,259 @@
+#![allow(non_snake_case, unused_variables)]
+//! The stack traces of throwables. `fillInStackTrace` records the method and
+//! pc of the frames of the thread in the `backtrace` of the throwable, the
+//! `StackTraceElement`s are made of them when Java asks for them.
+//!
+//! Natives don't get frames of their own, so they aren't in stack traces.
+use crate::runtime::class::Class;
+use crate::runtime::frame::operand_stack::Operand;
+use crate::runtime::heap::{
+    JvmHeap, JAVA_LANG_CLASS, JAVA_LANG_INDEX_OUT_OF_BOUNDS_EXCEPTION, JAVA_LANG_OBJECT,
+    JAVA_LANG_STACK_TRACE_ELEMENT, JAVA_LANG_STRING_DESCRIPTOR, T_INT,
+};
+use crate::runtime::jvm_env::{JvmEnv, JvmPC};
+use crate::runtime::method::Method;
+use crate::runtime::mirror::MirroredType;
+use std::fmt;
+
+const BACKTRACE_DESCRIPTOR: &str = "Ljava/lang/Object;";
+/// The elements of a backtrace, an `Object[]` of arrays with one element per
+/// frame: the mirrors of the classes, the indexes of the methods in them and
+/// the pcs.
+const BACKTRACE_CLASSES: usize = 0;
+const BACKTRACE_METHODS: usize = 1;
+const BACKTRACE_PCS: usize = 2;
+
+/// A frame of a stack trace, the instruction at `pc` of `method` of `class`.
+#[derive(Debug, Clone)]
+pub struct StackFrame {
+    class: Class,
+    method: Method,
+    pc: JvmPC,
+}
+
+impl StackFrame {
+    fn declaring_class(&self) -> String {
+        self.class.name().replace('/', ".")
+    }
+
+    fn file_name(&self) -> Option<&str> {
+        match &self.class {
+            Class::InstanceClass(class) => class.source_file(),
+            _ => None,
+        }
+    }
+
+    fn line_number(&self) -> i32 {
+        self.method.line_number(self.pc)
+    }
+}
+
+/// Like `StackTraceElement.toString()`, e.g. `pkg.Main.run(Main.java:12)`.
+impl fmt::Display for StackFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}(", self.declaring_class(), self.method.name())?;
+        match (self.line_number(), self.file_name()) {
+            (-2, _) => write!(f, "Native Method")?,
+            (line, Some(file)) if line >= 0 => write!(f, "{}:{}", file, line)?,
+            (_, Some(file)) => write!(f, "{}", file)?,
+            (_, None) => write!(f, "Unknown Source")?,
+        }
+        write!(f, ")")
+    }
+}
+
+/// The frames of the current thread, the innermost first, without those
+/// making the throwable of `throwable_class`: `fillInStackTrace` and the
+/// constructors of the class and its superclasses.
+fn capture_stack_trace(jenv: &JvmEnv, throwable_class: &Class) -> Vec<StackFrame> {
+    let throwable_class = throwable_class.instance_class();
+    jenv.thread()
+        .stack
+        .frames
+        .iter()
+        .rev()
+        .map(|frame| StackFrame {
+            class: frame.class.clone(),
+            method: frame.method(),
+            pc: frame.opcode_pc(),
+        })
+        .skip_while(|frame| frame.method.name() == "fillInStackTrace")
+        .skip_while(|frame| {
+            let class = frame.class.instance_class();
+            frame.method.is_initialization_method()
+                && (class == throwable_class || throwable_class.is_subclass_of(class))
+        })
+        .collect()
+}
+
+/// The backtrace object of `frames`.
+fn new_backtrace(jenv: &mut JvmEnv, frames: &[StackFrame]) -> Operand {
+    // the mirrors first, making one may run Java code
+    let mirrors: Vec<u32> = frames
+        .iter()
+        .map(|frame| jenv.class_mirror(&frame.class))
+        .collect();
+    let count = frames.len() as i32;
+    let classes = Operand::ArrayRef(
+        jenv.heap_mut()
+            .new_reference_array(JAVA_LANG_CLASS.to_string(), count),
+    );
+    let methods = Operand::ArrayRef(jenv.heap_mut().new_empty_array(T_INT, count));
+    let pcs = Operand::ArrayRef(jenv.heap_mut().new_empty_array(T_INT, count));
+    for (i, (frame, mirror)) in frames.iter().zip(mirrors).enumerate() {
+        jenv.heap_mut()
+            .set_array_element(&classes, i, Operand::ObjectRef(mirror));
+        let slot = frame
+            .class
+            .methods()
+            .iter()
+            .position(|method| *method == frame.method)
+            .expect("method of the frame");
+        jenv.heap_mut().get_int_array_mut(methods.clone())[i] = slot as i32;
+        jenv.heap_mut().get_int_array_mut(pcs.clone())[i] = frame.pc as i32;
+    }
+    let backtrace = Operand::ArrayRef(
+        jenv.heap_mut()
+            .new_reference_array(JAVA_LANG_OBJECT.to_string(), 3),
+    );
+    jenv.heap_mut()
+        .set_array_element(&backtrace, BACKTRACE_CLASSES, classes);
+    jenv.heap_mut()
+        .set_array_element(&backtrace, BACKTRACE_METHODS, methods);
+    jenv.heap_mut()
+        .set_array_element(&backtrace, BACKTRACE_PCS, pcs);
+    backtrace
+}
+
+/// The backtrace of `throwable`, null if it has none.
+fn backtrace(jenv: &JvmEnv, throwable: &Operand) -> Operand {
+    let object = jenv.heap().get_object(throwable);
+    if object
+        .class()
+        .get_field("backtrace", BACKTRACE_DESCRIPTOR)
+        .is_none()
+    {
+        return Operand::Null;
+    }
+    object
+        .get_field_by_name("backtrace", BACKTRACE_DESCRIPTOR)
+        .clone()
+}
+
+fn backtrace_depth(jenv: &mut JvmEnv, backtrace: &Operand) -> usize {
+    if *backtrace == Operand::Null {
+        return 0;
+    }
+    let pcs = jenv.heap().get_array_element(backtrace, BACKTRACE_PCS);
+    jenv.heap_mut().get_array_length(&pcs) as usize
+}
+
+fn backtrace_frame(jenv: &JvmEnv, backtrace: &Operand, index: usize) -> StackFrame {
+    let element = |i| jenv.heap().get_array_element(backtrace, i);
+    let mirror = jenv
+        .heap()
+        .get_array_element(&element(BACKTRACE_CLASSES), index);
+    let slot = jenv
+        .heap()
+        .get_array_element(&element(BACKTRACE_METHODS), index)
+        .get_int();
+    let pc = jenv
+        .heap()
+        .get_array_element(&element(BACKTRACE_PCS), index)
+        .get_int();
+    let class = match jenv.mirrored_type(&mirror) {
+        MirroredType::Class(class) => class,
+        ty => unreachable!("{:?}", ty),
+    };
+    let method = class.methods()[slot as usize].clone();
+    StackFrame {
+        class,
+        method,
+        pc: pc as JvmPC,
+    }
+}
+
+/// The frames recorded by the last `fillInStackTrace` of `throwable`, the
+/// innermost first.
+pub fn stack_trace(jenv: &mut JvmEnv, throwable: &Operand) -> Vec<StackFrame> {
+    let backtrace = backtrace(jenv, throwable);
+    (0..backtrace_depth(jenv, &backtrace))
+        .map(|i| backtrace_frame(jenv, &backtrace, i))
+        .collect()
+}
+
+pub fn java_lang_Throwable_fillInStackTrace(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let throwable = &args[0];
+    let throwable_class = jenv.heap().get_object(throwable).class().clone();
+    if throwable_class
+        .get_field("backtrace", BACKTRACE_DESCRIPTOR)
+        .is_some()
+    {
+        let frames = capture_stack_trace(jenv, &throwable_class);
+        let backtrace = new_backtrace(jenv, &frames);
+        let object = jenv.heap_mut().get_object_mut(throwable);
+        object.set_field_by_name("backtrace", BACKTRACE_DESCRIPTOR, backtrace);
+    }
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(throwable.clone());
+}
+
+pub fn java_lang_Throwable_getStackTraceDepth(
+    jenv: &mut JvmEnv,
+    _class: &Class,
+    args: Vec<Operand>,
+) {
+    let backtrace = backtrace(jenv, &args[0]);
+    let depth = backtrace_depth(jenv, &backtrace);
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push_integer(depth as i32);
+}
+
+pub fn java_lang_Throwable_getStackTraceElement(
+    jenv: &mut JvmEnv,
+    _class: &Class,
+    args: Vec<Operand>,
+) {
+    let backtrace = backtrace(jenv, &args[0]);
+    let index = args[1].get_int();
+    if index < 0 || index as usize >= backtrace_depth(jenv, &backtrace) {
+        let message = index.to_string();
+        jenv.throw_exception(JAVA_LANG_INDEX_OUT_OF_BOUNDS_EXCEPTION, Some(&message));
+        return;
+    }
+    let stack_frame = backtrace_frame(jenv, &backtrace, index as usize);
+    let class = jenv.load_and_init_class(JAVA_LANG_STACK_TRACE_ELEMENT);
+    if !jenv.reserve_heap(JvmHeap::object_size(&class)) {
+        return;
+    }
+    // allocating a string may collect garbage, the element holds the earlier ones
+    let (_, addr) = jenv.heap_mut().new_object(class);
+    let element_ref = Operand::ObjectRef(addr);
+    jenv.thread_mut()
+        .native_args
+        .push(vec![element_ref.clone()]);
+    let declaring_class = stack_frame.declaring_class();
+    let strings = [
+        ("declaringClass", Some(declaring_class.as_str())),
+        ("methodName", Some(stack_frame.method.name())),
+        ("fileName", stack_frame.file_name()),
+    ];
+    for (name, value) in strings.iter().cloned() {
+        let value = match value.map(|value| jenv.new_java_lang_string(value)) {
+            Some(Some(addr)) => Operand::ObjectRef(addr),
+            Some(None) => break,
+            None => Operand::Null,
+        };
+        let element = jenv.heap_mut().get_object_mut(&element_ref);
+        element.set_field_by_name(name, JAVA_LANG_STRING_DESCRIPTOR, value);
+    }
+    jenv.thread_mut().native_args.pop();
+    if jenv.thread().has_pending_exception() {
+        return;
+    }
+    let element = jenv.heap_mut().get_object_mut(&element_ref);
+    let line_number = Operand::Int(stack_frame.line_number());
+    element.set_field_by_name("lineNumber", "I", line_number);
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(Operand::ObjectRef(addr));
+}
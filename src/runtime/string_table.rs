@@ -0,0 +1,28 @@
+use std::collections::HashMap;
+
+/// The interned `java.lang.String` objects of the VM, the ones of string
+/// literals and `String.intern()`, exactly one for every value.
+///
+/// The table doesn't keep its strings alive. The collector forgets the ones
+/// it freed, so their slots can be reused, and the next `intern` of such a
+/// value makes a new string, which nobody can tell apart.
+#[derive(Debug, Default)]
+pub struct StringTable {
+    /// Keyed by the UTF-16 value, which might not be valid Unicode.
+    addrs: HashMap<Vec<u16>, u32>,
+}
+
+impl StringTable {
+    pub fn get(&self, value: &[u16]) -> Option<u32> {
+        self.addrs.get(value).copied()
+    }
+
+    pub fn insert(&mut self, value: Vec<u16>, addr: u32) {
+        self.addrs.insert(value, addr);
+    }
+
+    /// Forgets the strings for which `is_live` returns false.
+    pub fn retain(&mut self, mut is_live: impl FnMut(u32) -> bool) {
+        self.addrs.retain(|_, addr| is_live(*addr));
+    }
+}
@@ -0,0 +1,1564 @@
+use crate::class_parser::attribute_info::predefined_attribute::{
+    CodeAttribute, PredefinedAttribute, StackMapFrame, VerificationTypeInfo,
+};
+use crate::class_parser::constant_pool::{ConstPool, ConstPoolInfo, FieldRef, MethodRef};
+use crate::class_parser::descriptor::method_descriptor;
+use crate::class_parser::method_info::MethodInfo;
+use crate::class_parser::{is_bit_set, ClassFile, ACC_INTERFACE, ACC_STATIC};
+use crate::runtime::heap::{JAVA_LANG_CLASS, JAVA_LANG_OBJECT, JAVA_LANG_STRING};
+use crate::runtime::opcode::*;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+
+const JAVA_LANG_THROWABLE: &str = "java/lang/Throwable";
+
+/// Which classes are verified when they are linked, like HotSpot's
+/// `-Xverify`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VerifyMode {
+    /// Trust every class.
+    None,
+    /// Verify classes that don't come from the boot class path.
+    #[default]
+    Remote,
+    All,
+}
+
+/// A class file failed verification, thrown as `java.lang.VerifyError`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyError {
+    pub message: String,
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// The super class of a class and whether it's an interface.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassInfo {
+    pub super_class: Option<String>,
+    pub is_interface: bool,
+}
+
+/// Answers questions about classes other than the one being verified, without
+/// loading them.
+pub trait ClassHierarchy {
+    /// `None` if the class can't be found.
+    fn class_info(&mut self, name: &str) -> Option<ClassInfo>;
+}
+
+/// Type checks every method of `class_file` against its `StackMapTable`
+/// (JVMS §4.10.1). Class files older than version 50 have no stack maps and
+/// would need the type inference verifier, they are trusted instead.
+pub fn verify_class(
+    class_file: &ClassFile,
+    hierarchy: &mut dyn ClassHierarchy,
+) -> Result<(), VerifyError> {
+    if class_file.major_version < 50 {
+        return Ok(());
+    }
+    for method in &class_file.methods {
+        if let Some(code) = method.code() {
+            MethodVerifier::new(class_file, method, code, hierarchy)?.verify()?;
+        }
+    }
+    Ok(())
+}
+
+/// A verification type (JVMS §4.10.1.2). References hold the class name as
+/// in the constant pool, arrays by their descriptor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum VType {
+    Top,
+    Int,
+    Float,
+    Long,
+    Double,
+    Null,
+    UninitializedThis,
+    /// An object created by the `new` at this pc, not yet initialized.
+    Uninitialized(u16),
+    Reference(String),
+}
+
+impl VType {
+    /// The type of a field descriptor, `boolean`, `byte`, `char` and `short`
+    /// are all `int` on the stack.
+    fn from_descriptor(descriptor: &str) -> VType {
+        match descriptor.as_bytes()[0] {
+            b'B' | b'C' | b'I' | b'S' | b'Z' => VType::Int,
+            b'F' => VType::Float,
+            b'J' => VType::Long,
+            b'D' => VType::Double,
+            b'L' => VType::Reference(descriptor[1..descriptor.len() - 1].to_string()),
+            _ => VType::Reference(descriptor.to_string()),
+        }
+    }
+
+    fn reference(name: &str) -> VType {
+        VType::Reference(name.to_string())
+    }
+
+    fn is_category2(&self) -> bool {
+        matches!(self, VType::Long | VType::Double)
+    }
+
+    fn size(&self) -> usize {
+        if self.is_category2() {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// An initialized reference or null.
+    fn is_reference(&self) -> bool {
+        matches!(self, VType::Null | VType::Reference(_))
+    }
+}
+
+impl Display for VType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            VType::Top => f.write_str("top"),
+            VType::Int => f.write_str("int"),
+            VType::Float => f.write_str("float"),
+            VType::Long => f.write_str("long"),
+            VType::Double => f.write_str("double"),
+            VType::Null => f.write_str("null"),
+            VType::UninitializedThis => f.write_str("uninitializedThis"),
+            VType::Uninitialized(pc) => write!(f, "uninitialized({})", pc),
+            VType::Reference(name) => f.write_str(name),
+        }
+    }
+}
+
+/// The locals and operand stack before an instruction. Locals always have
+/// `max_locals` entries, a `long` or `double` is followed by `Top`, while on
+/// the stack it's a single entry.
+#[derive(Debug, Clone, PartialEq)]
+struct Frame {
+    locals: Vec<VType>,
+    stack: Vec<VType>,
+}
+
+/// What an instruction does to the control flow.
+enum Flow {
+    /// Continues with the next instruction.
+    Next,
+    /// Never continues with the next instruction: a `goto`, switch, return
+    /// or `athrow`.
+    Stop,
+}
+
+type VerifyResult<T> = Result<T, VerifyError>;
+
+struct MethodVerifier<'a> {
+    class_file: &'a ClassFile,
+    class_name: &'a str,
+    method_name: &'a str,
+    descriptor: &'a str,
+    return_type: Option<VType>,
+    code: &'a CodeAttribute,
+    hierarchy: &'a mut dyn ClassHierarchy,
+    /// `is_instruction[pc]` tells whether an instruction starts at `pc`.
+    is_instruction: Vec<bool>,
+    initial_frame: Frame,
+    stack_map: BTreeMap<u16, Frame>,
+    /// The instruction being checked, for error messages.
+    pc: u16,
+}
+
+impl<'a> MethodVerifier<'a> {
+    fn new(
+        class_file: &'a ClassFile,
+        method: &'a MethodInfo,
+        code: &'a CodeAttribute,
+        hierarchy: &'a mut dyn ClassHierarchy,
+    ) -> VerifyResult<Self> {
+        let constant_pool = &class_file.constant_pool;
+        let descriptor = constant_pool.get_utf8_string_at(method.descriptor_index);
+        let mut verifier = MethodVerifier {
+            class_file,
+            class_name: constant_pool.get_class_name_at(class_file.this_class),
+            method_name: constant_pool.get_utf8_string_at(method.name_index),
+            descriptor,
+            return_type: None,
+            code,
+            hierarchy,
+            is_instruction: vec![false; code.code.len()],
+            initial_frame: Frame {
+                locals: vec![],
+                stack: vec![],
+            },
+            stack_map: BTreeMap::new(),
+            pc: 0,
+        };
+        let (_, return_descriptor) = verifier.method_descriptor(descriptor)?;
+        verifier.return_type = match return_descriptor.as_str() {
+            "V" => None,
+            ty => Some(VType::from_descriptor(ty)),
+        };
+        verifier.find_instructions()?;
+        verifier.initial_frame = verifier.initial_frame(method)?;
+        verifier.expand_stack_map()?;
+        Ok(verifier)
+    }
+
+    fn error<T>(&self, reason: impl Display) -> VerifyResult<T> {
+        Err(VerifyError {
+            message: format!(
+                "{} in method {}.{}{} at pc {}",
+                reason,
+                self.class_name.replace('/', "."),
+                self.method_name,
+                self.descriptor,
+                self.pc
+            ),
+        })
+    }
+
+    /// The parameter and return descriptors of `descriptor`.
+    fn method_descriptor(&self, descriptor: &str) -> VerifyResult<(Vec<String>, String)> {
+        match method_descriptor(descriptor) {
+            Ok(("", parsed)) => Ok(parsed),
+            _ => self.error(format!("Illegal method descriptor {}", descriptor)),
+        }
+    }
+
+    fn code(&self) -> &'a [u8] {
+        &self.code.code
+    }
+
+    fn constant_pool(&self) -> &'a ConstPool {
+        &self.class_file.constant_pool
+    }
+
+    /// The constant pool entry at `index`, which the class file can't be
+    /// trusted to have.
+    fn constant(&self, index: u16) -> VerifyResult<&'a ConstPoolInfo> {
+        match self.constant_pool().get(index) {
+            Some(info) => Ok(info),
+            None => self.error(format!("Illegal constant pool index {}", index)),
+        }
+    }
+
+    fn illegal_constant<T>(&self, index: u16) -> VerifyResult<T> {
+        self.error(format!("Illegal type at constant pool entry {}", index))
+    }
+
+    fn utf8(&self, index: u16) -> VerifyResult<&'a str> {
+        match self.constant(index)? {
+            ConstPoolInfo::ConstantUtf8Info(s) => Ok(s),
+            _ => self.illegal_constant(index),
+        }
+    }
+
+    fn class_name(&self, index: u16) -> VerifyResult<&'a str> {
+        match self.constant(index)? {
+            ConstPoolInfo::ConstantClassInfo { name_index } => self.utf8(*name_index),
+            _ => self.illegal_constant(index),
+        }
+    }
+
+    fn name_and_type(&self, index: u16) -> VerifyResult<(&'a str, &'a str)> {
+        match self.constant(index)? {
+            ConstPoolInfo::ConstantNameAndTypeInfo {
+                name_index,
+                descriptor_index,
+            } => Ok((self.utf8(*name_index)?, self.utf8(*descriptor_index)?)),
+            _ => self.illegal_constant(index),
+        }
+    }
+
+    fn field_ref(&self, index: u16) -> VerifyResult<FieldRef<'a>> {
+        match self.constant(index)? {
+            ConstPoolInfo::ConstantFieldRefInfo {
+                class_index,
+                name_and_type_index,
+            } => {
+                let class_name = self.class_name(*class_index)?;
+                let (field_name, descriptor) = self.name_and_type(*name_and_type_index)?;
+                Ok(FieldRef {
+                    class_name,
+                    field_name,
+                    descriptor,
+                })
+            }
+            _ => self.illegal_constant(index),
+        }
+    }
+
+    fn method_ref(&self, index: u16) -> VerifyResult<MethodRef<'a>> {
+        match self.constant(index)? {
+            ConstPoolInfo::ConstantMethodRefInfo {
+                class_index,
+                name_and_type_index,
+            }
+            | ConstPoolInfo::ConstantInterfaceMethodRefInfo {
+                class_index,
+                name_and_type_index,
+            } => {
+                let class_name = self.class_name(*class_index)?;
+                let (method_name, descriptor) = self.name_and_type(*name_and_type_index)?;
+                Ok(MethodRef {
+                    class_name,
+                    method_name,
+                    descriptor,
+                })
+            }
+            _ => self.illegal_constant(index),
+        }
+    }
+
+    /// The descriptor of the call site at `index`.
+    fn invoke_dynamic(&self, index: u16) -> VerifyResult<&'a str> {
+        match self.constant(index)? {
+            ConstPoolInfo::ConstantInvokeDynamicInfo {
+                name_and_type_index,
+                ..
+            } => Ok(self.name_and_type(*name_and_type_index)?.1),
+            _ => self.illegal_constant(index),
+        }
+    }
+
+    fn read_u8(&self, pc: usize) -> VerifyResult<u8> {
+        match self.code().get(pc) {
+            Some(b) => Ok(*b),
+            None => self.error("Instruction runs past the end of the code"),
+        }
+    }
+
+    fn read_u16(&self, pc: usize) -> VerifyResult<u16> {
+        Ok(u16::from_be_bytes([
+            self.read_u8(pc)?,
+            self.read_u8(pc + 1)?,
+        ]))
+    }
+
+    fn read_i32(&self, pc: usize) -> VerifyResult<i32> {
+        Ok(i32::from_be_bytes([
+            self.read_u8(pc)?,
+            self.read_u8(pc + 1)?,
+            self.read_u8(pc + 2)?,
+            self.read_u8(pc + 3)?,
+        ]))
+    }
+
+    /// Reads the default, then the other targets of a `tableswitch` or
+    /// `lookupswitch`, returning them with the length of the instruction.
+    fn switch_targets(&self, pc: usize) -> VerifyResult<(Vec<i32>, usize)> {
+        let base = (pc + 4) & !3;
+        let mut offsets = vec![self.read_i32(base)?];
+        let end = if self.code()[pc] == TABLESWITCH {
+            let low = self.read_i32(base + 4)?;
+            let high = self.read_i32(base + 8)?;
+            if low > high {
+                return self.error("low must be less than or equal to high in tableswitch");
+            }
+            let n = (high as i64 - low as i64 + 1) as usize;
+            for i in 0..n {
+                offsets.push(self.read_i32(base + 12 + i * 4)?);
+            }
+            base + 12 + n * 4
+        } else {
+            let n_pairs = self.read_i32(base + 4)?;
+            if n_pairs < 0 {
+                return self.error("npairs must be non-negative in lookupswitch");
+            }
+            for i in 0..n_pairs as usize {
+                offsets.push(self.read_i32(base + 12 + i * 8)?);
+            }
+            base + 8 + n_pairs as usize * 8
+        };
+        Ok((offsets, end - pc))
+    }
+
+    fn instruction_length(&self, pc: usize) -> VerifyResult<usize> {
+        let length = match self.code()[pc] {
+            BIPUSH | LDC | ILOAD | LLOAD | FLOAD | DLOAD | ALOAD | ISTORE | LSTORE | FSTORE
+            | DSTORE | ASTORE | NEWARRAY | RET => 2,
+            SIPUSH | LDC_W | LDC2_W | IINC | GETSTATIC | PUTSTATIC | GETFIELD | PUTFIELD
+            | INVOKEVIRTUAL | INVOKESPECIAL | INVOKESTATIC | NEW | ANEWARRAY | CHECKCAST
+            | INSTANCEOF | JSR | GOTO | IFNULL | IFNONNULL => 3,
+            IFEQ..=IF_ACMPNE => 3,
+            MULTIANEWARRAY => 4,
+            INVOKEINTERFACE | INVOKEDYNAMIC | GOTO_W | JSR_W => 5,
+            TABLESWITCH | LOOKUPSWITCH => self.switch_targets(pc)?.1,
+            WIDE => match self.read_u8(pc + 1)? {
+                IINC => 6,
+                ILOAD | LLOAD | FLOAD | DLOAD | ALOAD | ISTORE | LSTORE | FSTORE | DSTORE
+                | ASTORE | RET => 4,
+                opcode => return self.error(format!("Bad wide instruction {}", opcode)),
+            },
+            opcode if opcode <= JSR_W => 1,
+            opcode => return self.error(format!("Bad instruction {}", opcode)),
+        };
+        Ok(length)
+    }
+
+    fn find_instructions(&mut self) -> VerifyResult<()> {
+        if self.code().is_empty() {
+            return self.error("Code is empty");
+        }
+        let mut pc = 0;
+        while pc < self.code().len() {
+            self.pc = pc as u16;
+            self.is_instruction[pc] = true;
+            pc += self.instruction_length(pc)?;
+        }
+        if pc != self.code().len() {
+            return self.error("Instruction runs past the end of the code");
+        }
+        Ok(())
+    }
+
+    fn is_instruction(&self, pc: i64) -> bool {
+        pc >= 0 && (pc as usize) < self.is_instruction.len() && self.is_instruction[pc as usize]
+    }
+
+    /// The frame at method entry: `this` then the parameters. `this` stays
+    /// uninitialized in a constructor until it calls another one.
+    fn initial_frame(&self, method: &MethodInfo) -> VerifyResult<Frame> {
+        let mut locals = Vec::new();
+        if !is_bit_set(method.access_flags, ACC_STATIC) {
+            if self.method_name == "<init>" && self.class_name != JAVA_LANG_OBJECT {
+                locals.push(VType::UninitializedThis);
+            } else {
+                locals.push(VType::reference(self.class_name));
+            }
+        }
+        let (parameters, _) = self.method_descriptor(self.descriptor)?;
+        for parameter in parameters {
+            locals.push(VType::from_descriptor(&parameter));
+        }
+        self.expand_locals(&locals)
+    }
+
+    /// Expands the locals of a stack map frame, where `long` and `double` take
+    /// a single entry, to `max_locals` entries.
+    fn expand_locals(&self, types: &[VType]) -> VerifyResult<Frame> {
+        let mut locals = Vec::with_capacity(self.code.max_locals as usize);
+        for ty in types {
+            locals.push(ty.clone());
+            if ty.is_category2() {
+                locals.push(VType::Top);
+            }
+        }
+        if locals.len() > self.code.max_locals as usize {
+            return self.error("Local variables exceed max_locals");
+        }
+        locals.resize(self.code.max_locals as usize, VType::Top);
+        Ok(Frame {
+            locals,
+            stack: Vec::new(),
+        })
+    }
+
+    fn verification_type(&self, info: &VerificationTypeInfo) -> VerifyResult<VType> {
+        Ok(match info {
+            VerificationTypeInfo::TopVariableInfo => VType::Top,
+            VerificationTypeInfo::IntegerVariableInfo => VType::Int,
+            VerificationTypeInfo::FloatVariableInfo => VType::Float,
+            VerificationTypeInfo::LongVariableInfo => VType::Long,
+            VerificationTypeInfo::DoubleVariableInfo => VType::Double,
+            VerificationTypeInfo::NullVariableInfo => VType::Null,
+            VerificationTypeInfo::UninitializedThisVariableInfo => VType::UninitializedThis,
+            VerificationTypeInfo::UninitializedVariableInfo { offset } => {
+                VType::Uninitialized(*offset)
+            }
+            VerificationTypeInfo::ObjectVariableInfo { const_pool_index } => {
+                VType::reference(self.class_name(*const_pool_index)?)
+            }
+        })
+    }
+
+    /// Decodes the `StackMapTable` into a full frame for each offset, each
+    /// entry is relative to the previous one, the first to the initial frame.
+    fn expand_stack_map(&mut self) -> VerifyResult<()> {
+        let entries = self
+            .code
+            .attributes
+            .iter()
+            .find_map(|attr| match &attr.attribute {
+                PredefinedAttribute::StackMapTableAttribute(table) => Some(&table.entries),
+                _ => None,
+            });
+        let entries = match entries {
+            Some(entries) => entries,
+            None => return Ok(()),
+        };
+
+        let initial_locals = &self.initial_frame.locals;
+        let mut locals: Vec<VType> = initial_locals
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i == 0 || !initial_locals[i - 1].is_category2())
+            .map(|(_, ty)| ty.clone())
+            .collect();
+        while locals.last() == Some(&VType::Top) {
+            locals.pop();
+        }
+        let mut offset: Option<u16> = None;
+        for entry in entries {
+            let (offset_delta, stack) = match entry {
+                StackMapFrame::SameFrame { offset_delta }
+                | StackMapFrame::SameFrameExtended { offset_delta } => (*offset_delta, vec![]),
+                StackMapFrame::SameLocals1StackItemFrame {
+                    offset_delta,
+                    stack,
+                }
+                | StackMapFrame::SameLocals1StackItemFramExtended {
+                    offset_delta,
+                    stack,
+                } => (*offset_delta, vec![self.verification_type(stack)?]),
+                StackMapFrame::ChopFrame { k, offset_delta } => {
+                    if *k as usize > locals.len() {
+                        return self.error("Chop frame removes too many locals");
+                    }
+                    locals.truncate(locals.len() - *k as usize);
+                    (*offset_delta, vec![])
+                }
+                StackMapFrame::AppendFrame {
+                    offset_delta,
+                    locals: appended,
+                    ..
+                } => {
+                    for ty in appended {
+                        locals.push(self.verification_type(ty)?);
+                    }
+                    (*offset_delta, vec![])
+                }
+                StackMapFrame::FullFrame {
+                    offset_delta,
+                    locals: full_locals,
+                    stack,
+                } => {
+                    locals = full_locals
+                        .iter()
+                        .map(|ty| self.verification_type(ty))
+                        .collect::<VerifyResult<_>>()?;
+                    let stack = stack
+                        .iter()
+                        .map(|ty| self.verification_type(ty))
+                        .collect::<VerifyResult<_>>()?;
+                    (*offset_delta, stack)
+                }
+            };
+            let pc = match offset {
+                None => offset_delta as u32,
+                Some(offset) => offset as u32 + offset_delta as u32 + 1,
+            };
+            if !self.is_instruction(pc as i64) {
+                return self.error(format!("Stack map frame at invalid offset {}", pc));
+            }
+            self.pc = pc as u16;
+            let mut frame = self.expand_locals(&locals)?;
+            if stack.iter().map(VType::size).sum::<usize>() > self.code.max_stack as usize {
+                return self.error("Stack map frame exceeds max_stack");
+            }
+            frame.stack = stack;
+            self.stack_map.insert(pc as u16, frame);
+            offset = Some(pc as u16);
+        }
+        Ok(())
+    }
+
+    fn verify(mut self) -> VerifyResult<()> {
+        self.check_handlers()?;
+
+        let mut frame = self.initial_frame.clone();
+        let mut pc = 0;
+        let mut falls_through = true;
+        while pc < self.code().len() {
+            self.pc = pc as u16;
+            if let Some(map_frame) = self.stack_map.get(&(pc as u16)).cloned() {
+                if falls_through {
+                    self.check_frame_assignable(&frame, &map_frame)?;
+                }
+                frame = map_frame;
+            } else if !falls_through {
+                return self.error("Expecting a stack map frame");
+            }
+            self.check_handler_frames(&frame)?;
+            falls_through = match self.execute(pc, &mut frame)? {
+                Flow::Next => true,
+                Flow::Stop => false,
+            };
+            self.check_handler_frames(&frame)?;
+            pc += self.instruction_length(pc)?;
+        }
+        if falls_through {
+            return self.error("Falling off the end of the code");
+        }
+        Ok(())
+    }
+
+    fn check_handlers(&mut self) -> VerifyResult<()> {
+        for handler in &self.code.exception_table {
+            self.pc = handler.start_pc;
+            let end_pc = handler.end_pc as usize;
+            if !self.is_instruction(handler.start_pc as i64)
+                || handler.start_pc >= handler.end_pc
+                || !(end_pc == self.code().len() || self.is_instruction(end_pc as i64))
+            {
+                return self.error(format!(
+                    "Illegal exception table range [{}, {})",
+                    handler.start_pc, handler.end_pc
+                ));
+            }
+            if !self.is_instruction(handler.handler_pc as i64) {
+                return self.error(format!("Illegal exception handler {}", handler.handler_pc));
+            }
+            let catch_type = self.catch_type(handler.catch_type)?;
+            if !self.is_assignable(&catch_type, &VType::reference(JAVA_LANG_THROWABLE)) {
+                return self.error(format!("Catch type {} is not a Throwable", catch_type));
+            }
+            if !self.stack_map.contains_key(&handler.handler_pc) {
+                self.pc = handler.handler_pc;
+                return self.error("Expecting a stack map frame at exception handler");
+            }
+        }
+        Ok(())
+    }
+
+    fn catch_type(&self, index: u16) -> VerifyResult<VType> {
+        if index == 0 {
+            Ok(VType::reference(JAVA_LANG_THROWABLE))
+        } else {
+            Ok(VType::reference(self.class_name(index)?))
+        }
+    }
+
+    /// Both the locals before and after an instruction must be assignable to
+    /// the frame of every handler covering it, with the exception on the stack.
+    fn check_handler_frames(&mut self, frame: &Frame) -> VerifyResult<()> {
+        for handler in &self.code.exception_table {
+            if !(handler.start_pc..handler.end_pc).contains(&self.pc) {
+                continue;
+            }
+            let handler_frame = Frame {
+                locals: frame.locals.clone(),
+                stack: vec![self.catch_type(handler.catch_type)?],
+            };
+            let target = self.stack_map[&handler.handler_pc].clone();
+            if !self.is_frame_assignable(&handler_frame, &target) {
+                return self.error(format!(
+                    "Stack map frame at exception handler {} doesn't match",
+                    handler.handler_pc
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_frame_assignable(&mut self, from: &Frame, to: &Frame) -> VerifyResult<()> {
+        if self.is_frame_assignable(from, to) {
+            Ok(())
+        } else {
+            self.error("Current frame is not assignable to stack map frame")
+        }
+    }
+
+    fn is_frame_assignable(&mut self, from: &Frame, to: &Frame) -> bool {
+        from.stack.len() == to.stack.len()
+            && from
+                .locals
+                .iter()
+                .zip(&to.locals)
+                .chain(from.stack.iter().zip(&to.stack))
+                .all(|(from, to)| self.is_assignable(from, to))
+    }
+
+    fn check_branch(&mut self, pc: usize, offset: i32, frame: &Frame) -> VerifyResult<()> {
+        let target = pc as i64 + offset as i64;
+        if !self.is_instruction(target) {
+            return self.error(format!("Illegal target of jump or branch {}", target));
+        }
+        match self.stack_map.get(&(target as u16)).cloned() {
+            Some(target_frame) => {
+                if !self.is_frame_assignable(frame, &target_frame) {
+                    return self.error(format!(
+                        "Current frame is not assignable to stack map frame at branch target {}",
+                        target
+                    ));
+                }
+                Ok(())
+            }
+            None => self.error(format!(
+                "Expecting a stack map frame at branch target {}",
+                target
+            )),
+        }
+    }
+
+    fn is_assignable(&mut self, from: &VType, to: &VType) -> bool {
+        match (from, to) {
+            (from, to) if from == to => true,
+            (_, VType::Top) => true,
+            (VType::Null, VType::Reference(_)) => true,
+            (VType::Reference(from), VType::Reference(to)) => {
+                self.is_reference_assignable(from, to)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether class or array `from` is assignable to `to`. Interfaces are
+    /// treated like `Object` as in the JVMS, classes that can't be found are
+    /// left to be checked when they are used.
+    fn is_reference_assignable(&mut self, from: &str, to: &str) -> bool {
+        if from == to || to == JAVA_LANG_OBJECT {
+            return true;
+        }
+        match (from.starts_with('['), to.starts_with('[')) {
+            (true, true) => {
+                let (from, to) = (&from[1..], &to[1..]);
+                if from.len() == 1 || to.len() == 1 {
+                    from == to
+                } else {
+                    self.is_assignable(&VType::from_descriptor(from), &VType::from_descriptor(to))
+                }
+            }
+            (false, true) => false,
+            (true, false) => to == "java/lang/Cloneable" || to == "java/io/Serializable",
+            (false, false) => match self.class_info(to) {
+                None => true,
+                Some(info) if info.is_interface => true,
+                Some(_) => {
+                    let mut class = from.to_string();
+                    loop {
+                        match self.class_info(&class) {
+                            None => return true,
+                            Some(ClassInfo {
+                                super_class: Some(super_class),
+                                ..
+                            }) => {
+                                if super_class == to {
+                                    return true;
+                                }
+                                class = super_class;
+                            }
+                            Some(_) => return false,
+                        }
+                    }
+                }
+            },
+        }
+    }
+
+    fn class_info(&mut self, name: &str) -> Option<ClassInfo> {
+        if name == self.class_name {
+            let class_file = self.class_file;
+            let super_class = Some(class_file.super_class)
+                .filter(|index| *index != 0)
+                .map(|index| {
+                    class_file
+                        .constant_pool
+                        .get_class_name_at(index)
+                        .to_string()
+                });
+            return Some(ClassInfo {
+                super_class,
+                is_interface: is_bit_set(class_file.access_flags, ACC_INTERFACE),
+            });
+        }
+        self.hierarchy.class_info(name)
+    }
+
+    fn pop(&mut self, frame: &mut Frame, expected: &VType) -> VerifyResult<VType> {
+        let actual = match frame.stack.pop() {
+            Some(actual) => actual,
+            None => return self.error("Operand stack underflow"),
+        };
+        if !self.is_assignable(&actual, expected) {
+            return self.error(format!(
+                "Bad type on operand stack, expected {} but found {}",
+                expected, actual
+            ));
+        }
+        Ok(actual)
+    }
+
+    /// Pops an initialized reference or null.
+    fn pop_reference(&mut self, frame: &mut Frame) -> VerifyResult<VType> {
+        match frame.stack.pop() {
+            Some(actual) if actual.is_reference() => Ok(actual),
+            Some(actual) => self.error(format!(
+                "Bad type on operand stack, expected a reference but found {}",
+                actual
+            )),
+            None => self.error("Operand stack underflow"),
+        }
+    }
+
+    /// Pops null or an array whose component descriptor is accepted by
+    /// `is_component`.
+    fn pop_array(
+        &mut self,
+        frame: &mut Frame,
+        is_component: impl Fn(&str) -> bool,
+    ) -> VerifyResult<VType> {
+        let array = self.pop_reference(frame)?;
+        match &array {
+            VType::Null => Ok(array),
+            VType::Reference(name) if name.starts_with('[') && is_component(&name[1..]) => {
+                Ok(array)
+            }
+            _ => self.error(format!(
+                "Bad type on operand stack, expected an array but found {}",
+                array
+            )),
+        }
+    }
+
+    /// Pops the entries of `words` stack slots, they must not split a `long`
+    /// or `double`. Returns them bottom first.
+    fn pop_words(&mut self, frame: &mut Frame, words: usize) -> VerifyResult<Vec<VType>> {
+        let mut popped = Vec::new();
+        let mut size = 0;
+        while size < words {
+            match frame.stack.pop() {
+                Some(ty) => {
+                    size += ty.size();
+                    popped.push(ty);
+                }
+                None => return self.error("Operand stack underflow"),
+            }
+        }
+        if size != words {
+            return self.error("Bad type on operand stack, splits a long or double");
+        }
+        popped.reverse();
+        Ok(popped)
+    }
+
+    fn push(&self, frame: &mut Frame, ty: VType) -> VerifyResult<()> {
+        frame.stack.push(ty);
+        if frame.stack.iter().map(VType::size).sum::<usize>() > self.code.max_stack as usize {
+            return self.error("Operand stack overflow, exceeds max_stack");
+        }
+        Ok(())
+    }
+
+    fn push_all(&self, frame: &mut Frame, types: Vec<VType>) -> VerifyResult<()> {
+        for ty in types {
+            self.push(frame, ty)?;
+        }
+        Ok(())
+    }
+
+    fn check_local(&self, frame: &Frame, index: usize, expected: &VType) -> VerifyResult<()> {
+        if index + expected.size() > frame.locals.len() {
+            return self.error(format!("Illegal local variable number {}", index));
+        }
+        let actual = &frame.locals[index];
+        if actual != expected {
+            return self.error(format!(
+                "Bad local variable type, expected {} but found {}",
+                expected, actual
+            ));
+        }
+        Ok(())
+    }
+
+    fn load(&self, frame: &mut Frame, index: usize, ty: VType) -> VerifyResult<()> {
+        self.check_local(frame, index, &ty)?;
+        self.push(frame, ty)
+    }
+
+    fn load_reference(&self, frame: &mut Frame, index: usize) -> VerifyResult<()> {
+        match frame.locals.get(index) {
+            Some(ty)
+                if ty.is_reference()
+                    || matches!(ty, VType::Uninitialized(_) | VType::UninitializedThis) =>
+            {
+                let ty = ty.clone();
+                self.push(frame, ty)
+            }
+            Some(ty) => self.error(format!(
+                "Bad local variable type, expected a reference but found {}",
+                ty
+            )),
+            None => self.error(format!("Illegal local variable number {}", index)),
+        }
+    }
+
+    fn store(&self, frame: &mut Frame, index: usize, ty: VType) -> VerifyResult<()> {
+        if index + ty.size() > frame.locals.len() {
+            return self.error(format!("Illegal local variable number {}", index));
+        }
+        // overwriting the second half of a long or double invalidates it
+        if index > 0 && frame.locals[index - 1].is_category2() {
+            frame.locals[index - 1] = VType::Top;
+        }
+        if ty.is_category2() {
+            frame.locals[index + 1] = VType::Top;
+        }
+        frame.locals[index] = ty;
+        Ok(())
+    }
+
+    fn store_value(&mut self, frame: &mut Frame, index: usize, ty: VType) -> VerifyResult<()> {
+        let ty = self.pop(frame, &ty)?;
+        self.store(frame, index, ty)
+    }
+
+    fn store_reference(&mut self, frame: &mut Frame, index: usize) -> VerifyResult<()> {
+        let ty = match frame.stack.pop() {
+            Some(ty)
+                if ty.is_reference()
+                    || matches!(ty, VType::Uninitialized(_) | VType::UninitializedThis) =>
+            {
+                ty
+            }
+            Some(ty) => {
+                return self.error(format!(
+                    "Bad type on operand stack, expected a reference but found {}",
+                    ty
+                ))
+            }
+            None => return self.error("Operand stack underflow"),
+        };
+        self.store(frame, index, ty)
+    }
+
+    fn binary(&mut self, frame: &mut Frame, ty: VType) -> VerifyResult<()> {
+        self.pop(frame, &ty)?;
+        self.pop(frame, &ty)?;
+        self.push(frame, ty)
+    }
+
+    fn convert(&mut self, frame: &mut Frame, from: VType, to: VType) -> VerifyResult<()> {
+        self.pop(frame, &from)?;
+        self.push(frame, to)
+    }
+
+    fn pop_arguments(
+        &mut self,
+        frame: &mut Frame,
+        descriptor: &str,
+    ) -> VerifyResult<Option<VType>> {
+        let (parameters, return_descriptor) = self.method_descriptor(descriptor)?;
+        for parameter in parameters.iter().rev() {
+            self.pop(frame, &VType::from_descriptor(parameter))?;
+        }
+        Ok(match return_descriptor.as_str() {
+            "V" => None,
+            ty => Some(VType::from_descriptor(ty)),
+        })
+    }
+
+    fn ldc(&mut self, frame: &mut Frame, index: u16, wide: bool) -> VerifyResult<()> {
+        let ty = match self.constant(index)? {
+            ConstPoolInfo::ConstantIntegerInfo(_) => VType::Int,
+            ConstPoolInfo::ConstantFloatInfo(_) => VType::Float,
+            ConstPoolInfo::ConstantLongInfo(_) => VType::Long,
+            ConstPoolInfo::ConstantDoubleInfo(_) => VType::Double,
+            ConstPoolInfo::ConstantStringInfo { .. } => VType::reference(JAVA_LANG_STRING),
+            ConstPoolInfo::ConstantClassInfo { .. } => VType::reference(JAVA_LANG_CLASS),
+            ConstPoolInfo::ConstantMethodTypeInfo { .. } => {
+                VType::reference("java/lang/invoke/MethodType")
+            }
+            ConstPoolInfo::ConstantMethodHandleInfo { .. } => {
+                VType::reference("java/lang/invoke/MethodHandle")
+            }
+            _ => return self.error(format!("Illegal constant pool index {} for ldc", index)),
+        };
+        if ty.is_category2() != wide {
+            return self.error(format!("Illegal type of constant {} for ldc", ty));
+        }
+        self.push(frame, ty)
+    }
+
+    fn return_value(&mut self, frame: &mut Frame, opcode: u8) -> VerifyResult<Flow> {
+        let expected = match (&self.return_type, opcode) {
+            (None, RETURN) => None,
+            (Some(ty @ VType::Int), IRETURN)
+            | (Some(ty @ VType::Long), LRETURN)
+            | (Some(ty @ VType::Float), FRETURN)
+            | (Some(ty @ VType::Double), DRETURN)
+            | (Some(ty @ VType::Reference(_)), ARETURN) => Some(ty.clone()),
+            _ => return self.error("Method returns a value of the wrong type"),
+        };
+        if let Some(expected) = expected {
+            self.pop(frame, &expected)?;
+        }
+        if self.method_name == "<init>" && frame.locals.contains(&VType::UninitializedThis) {
+            return self.error("Constructor must call super() or this() before return");
+        }
+        Ok(Flow::Stop)
+    }
+
+    /// Replaces every occurrence of the uninitialized `object` in the frame
+    /// after a constructor call.
+    fn initialize(
+        &mut self,
+        frame: &mut Frame,
+        object: &VType,
+        class_name: &str,
+    ) -> VerifyResult<()> {
+        let initialized = match object {
+            VType::UninitializedThis => {
+                let super_class = self
+                    .class_info(self.class_name)
+                    .and_then(|info| info.super_class);
+                if class_name != self.class_name && Some(class_name) != super_class.as_deref() {
+                    return self.error(format!("Bad <init> method call to {}", class_name));
+                }
+                VType::reference(self.class_name)
+            }
+            VType::Uninitialized(new_pc) => {
+                let new_pc = *new_pc as usize;
+                if self.code().get(new_pc) != Some(&NEW) {
+                    return self.error(format!(
+                        "Uninitialized object from pc {} isn't created by new",
+                        new_pc
+                    ));
+                }
+                let new_class = self.class_name(self.read_u16(new_pc + 1)?)?;
+                if new_class != class_name {
+                    return self.error(format!("Call to wrong <init> method of {}", class_name));
+                }
+                VType::reference(class_name)
+            }
+            ty => {
+                return self.error(format!(
+                    "Bad type on operand stack, expected an uninitialized object but found {}",
+                    ty
+                ))
+            }
+        };
+        for ty in frame.locals.iter_mut().chain(frame.stack.iter_mut()) {
+            if ty == object {
+                *ty = initialized.clone();
+            }
+        }
+        Ok(())
+    }
+
+    fn invoke(&mut self, pc: usize, frame: &mut Frame, opcode: u8) -> VerifyResult<()> {
+        let index = self.read_u16(pc + 1)?;
+        if opcode == INVOKEDYNAMIC {
+            let descriptor = self.invoke_dynamic(index)?;
+            let return_type = self.pop_arguments(frame, descriptor)?;
+            if let Some(ty) = return_type {
+                self.push(frame, ty)?;
+            }
+            return Ok(());
+        }
+
+        let method_ref = self.method_ref(index)?;
+        let is_init = method_ref.method_name == "<init>";
+        if method_ref.method_name == "<clinit>" || (is_init && opcode != INVOKESPECIAL) {
+            return self.error(format!("Illegal call to {}", method_ref.method_name));
+        }
+        let return_type = self.pop_arguments(frame, method_ref.descriptor)?;
+        if opcode != INVOKESTATIC {
+            if is_init {
+                let object = match frame.stack.pop() {
+                    Some(object) => object,
+                    None => return self.error("Operand stack underflow"),
+                };
+                self.initialize(frame, &object, method_ref.class_name)?;
+            } else {
+                self.pop(frame, &VType::reference(method_ref.class_name))?;
+            }
+        }
+        if let Some(ty) = return_type {
+            self.push(frame, ty)?;
+        }
+        Ok(())
+    }
+
+    fn field(&mut self, pc: usize, frame: &mut Frame, opcode: u8) -> VerifyResult<()> {
+        let field_ref = self.field_ref(self.read_u16(pc + 1)?)?;
+        let ty = VType::from_descriptor(field_ref.descriptor);
+        let owner = VType::reference(field_ref.class_name);
+        match opcode {
+            GETSTATIC => self.push(frame, ty),
+            PUTSTATIC => self.pop(frame, &ty).map(|_| ()),
+            GETFIELD => {
+                self.pop(frame, &owner)?;
+                self.push(frame, ty)
+            }
+            _ => {
+                self.pop(frame, &ty)?;
+                // a constructor may set the fields of its class before super()
+                if frame.stack.last() == Some(&VType::UninitializedThis)
+                    && field_ref.class_name == self.class_name
+                {
+                    frame.stack.pop();
+                    Ok(())
+                } else {
+                    self.pop(frame, &owner).map(|_| ())
+                }
+            }
+        }
+    }
+
+    /// Checks the instruction at `pc` and applies it to `frame`.
+    fn execute(&mut self, pc: usize, frame: &mut Frame) -> VerifyResult<Flow> {
+        let opcode = self.code()[pc];
+        let index_u8 = || self.read_u8(pc + 1).map(|i| i as usize);
+        match opcode {
+            NOP => {}
+            ACONST_NULL => self.push(frame, VType::Null)?,
+            ICONST_M1..=ICONST_5 | BIPUSH | SIPUSH => self.push(frame, VType::Int)?,
+            LCONST_0 | LCONST_1 => self.push(frame, VType::Long)?,
+            FCONST_0..=FCONST_2 => self.push(frame, VType::Float)?,
+            DCONST_0 | DCONST_1 => self.push(frame, VType::Double)?,
+            LDC => self.ldc(frame, index_u8()? as u16, false)?,
+            LDC_W => self.ldc(frame, self.read_u16(pc + 1)?, false)?,
+            LDC2_W => self.ldc(frame, self.read_u16(pc + 1)?, true)?,
+            ILOAD => self.load(frame, index_u8()?, VType::Int)?,
+            LLOAD => self.load(frame, index_u8()?, VType::Long)?,
+            FLOAD => self.load(frame, index_u8()?, VType::Float)?,
+            DLOAD => self.load(frame, index_u8()?, VType::Double)?,
+            ALOAD => self.load_reference(frame, index_u8()?)?,
+            ILOAD_0..=ILOAD_3 => self.load(frame, (opcode - ILOAD_0) as usize, VType::Int)?,
+            LLOAD_0..=LLOAD_3 => self.load(frame, (opcode - LLOAD_0) as usize, VType::Long)?,
+            FLOAD_0..=FLOAD_3 => self.load(frame, (opcode - FLOAD_0) as usize, VType::Float)?,
+            DLOAD_0..=DLOAD_3 => self.load(frame, (opcode - DLOAD_0) as usize, VType::Double)?,
+            ALOAD_0..=ALOAD_3 => self.load_reference(frame, (opcode - ALOAD_0) as usize)?,
+            IALOAD | BALOAD | CALOAD | SALOAD | LALOAD | FALOAD | DALOAD => {
+                let (components, ty) = primitive_array(opcode - IALOAD);
+                self.pop(frame, &VType::Int)?;
+                self.pop_array(frame, |component| components.contains(&component))?;
+                self.push(frame, ty)?;
+            }
+            AALOAD => {
+                self.pop(frame, &VType::Int)?;
+                let component = match self.pop_array(frame, is_reference_component)? {
+                    VType::Reference(array) => VType::from_descriptor(&array[1..]),
+                    _ => VType::Null,
+                };
+                self.push(frame, component)?;
+            }
+            ISTORE => self.store_value(frame, index_u8()?, VType::Int)?,
+            LSTORE => self.store_value(frame, index_u8()?, VType::Long)?,
+            FSTORE => self.store_value(frame, index_u8()?, VType::Float)?,
+            DSTORE => self.store_value(frame, index_u8()?, VType::Double)?,
+            ASTORE => self.store_reference(frame, index_u8()?)?,
+            ISTORE_0..=ISTORE_3 => {
+                self.store_value(frame, (opcode - ISTORE_0) as usize, VType::Int)?
+            }
+            LSTORE_0..=LSTORE_3 => {
+                self.store_value(frame, (opcode - LSTORE_0) as usize, VType::Long)?
+            }
+            FSTORE_0..=FSTORE_3 => {
+                self.store_value(frame, (opcode - FSTORE_0) as usize, VType::Float)?
+            }
+            DSTORE_0..=DSTORE_3 => {
+                self.store_value(frame, (opcode - DSTORE_0) as usize, VType::Double)?
+            }
+            ASTORE_0..=ASTORE_3 => self.store_reference(frame, (opcode - ASTORE_0) as usize)?,
+            IASTORE | BASTORE | CASTORE | SASTORE | LASTORE | FASTORE | DASTORE => {
+                let (components, ty) = primitive_array(opcode - IASTORE);
+                self.pop(frame, &ty)?;
+                self.pop(frame, &VType::Int)?;
+                self.pop_array(frame, |component| components.contains(&component))?;
+            }
+            AASTORE => {
+                self.pop_reference(frame)?;
+                self.pop(frame, &VType::Int)?;
+                self.pop_array(frame, is_reference_component)?;
+            }
+            POP => {
+                self.pop_words(frame, 1)?;
+            }
+            POP2 => {
+                self.pop_words(frame, 2)?;
+            }
+            DUP | DUP_X1 | DUP_X2 | DUP2 | DUP2_X1 | DUP2_X2 => {
+                let (top, below) = match opcode {
+                    DUP => (1, 0),
+                    DUP_X1 => (1, 1),
+                    DUP_X2 => (1, 2),
+                    DUP2 => (2, 0),
+                    DUP2_X1 => (2, 1),
+                    _ => (2, 2),
+                };
+                let top = self.pop_words(frame, top)?;
+                let below = self.pop_words(frame, below)?;
+                self.push_all(frame, top.clone())?;
+                self.push_all(frame, below)?;
+                self.push_all(frame, top)?;
+            }
+            SWAP => {
+                let top = self.pop_words(frame, 1)?;
+                let below = self.pop_words(frame, 1)?;
+                self.push_all(frame, top)?;
+                self.push_all(frame, below)?;
+            }
+            IADD..=DREM => {
+                let ty = [VType::Int, VType::Long, VType::Float, VType::Double]
+                    [((opcode - IADD) % 4) as usize]
+                    .clone();
+                self.binary(frame, ty)?;
+            }
+            INEG..=DNEG => {
+                let ty = [VType::Int, VType::Long, VType::Float, VType::Double]
+                    [(opcode - INEG) as usize]
+                    .clone();
+                self.convert(frame, ty.clone(), ty)?;
+            }
+            ISHL | ISHR | IUSHR | LSHL | LSHR | LUSHR => {
+                let ty = match opcode {
+                    ISHL | ISHR | IUSHR => VType::Int,
+                    _ => VType::Long,
+                };
+                self.pop(frame, &VType::Int)?;
+                self.convert(frame, ty.clone(), ty)?;
+            }
+            IAND | IOR | IXOR => self.binary(frame, VType::Int)?,
+            LAND | LOR | LXOR => self.binary(frame, VType::Long)?,
+            IINC => self.check_local(frame, index_u8()?, &VType::Int)?,
+            I2L => self.convert(frame, VType::Int, VType::Long)?,
+            I2F => self.convert(frame, VType::Int, VType::Float)?,
+            I2D => self.convert(frame, VType::Int, VType::Double)?,
+            L2I => self.convert(frame, VType::Long, VType::Int)?,
+            L2F => self.convert(frame, VType::Long, VType::Float)?,
+            L2D => self.convert(frame, VType::Long, VType::Double)?,
+            F2I => self.convert(frame, VType::Float, VType::Int)?,
+            F2L => self.convert(frame, VType::Float, VType::Long)?,
+            F2D => self.convert(frame, VType::Float, VType::Double)?,
+            D2I => self.convert(frame, VType::Double, VType::Int)?,
+            D2L => self.convert(frame, VType::Double, VType::Long)?,
+            D2F => self.convert(frame, VType::Double, VType::Float)?,
+            I2B | I2C | I2S => self.convert(frame, VType::Int, VType::Int)?,
+            LCMP => {
+                self.pop(frame, &VType::Long)?;
+                self.convert(frame, VType::Long, VType::Int)?;
+            }
+            FCMPL | FCMPG => {
+                self.pop(frame, &VType::Float)?;
+                self.convert(frame, VType::Float, VType::Int)?;
+            }
+            DCMPL | DCMPG => {
+                self.pop(frame, &VType::Double)?;
+                self.convert(frame, VType::Double, VType::Int)?;
+            }
+            IFEQ..=IFLE | IF_ICMPEQ..=IF_ICMPLE | IF_ACMPEQ | IF_ACMPNE | IFNULL | IFNONNULL => {
+                match opcode {
+                    IFEQ..=IFLE => {
+                        self.pop(frame, &VType::Int)?;
+                    }
+                    IF_ICMPEQ..=IF_ICMPLE => {
+                        self.pop(frame, &VType::Int)?;
+                        self.pop(frame, &VType::Int)?;
+                    }
+                    IF_ACMPEQ | IF_ACMPNE => {
+                        self.pop_reference(frame)?;
+                        self.pop_reference(frame)?;
+                    }
+                    _ => {
+                        self.pop_reference(frame)?;
+                    }
+                }
+                let offset = self.read_u16(pc + 1)? as i16;
+                self.check_branch(pc, offset as i32, frame)?;
+            }
+            GOTO => {
+                let offset = self.read_u16(pc + 1)? as i16;
+                self.check_branch(pc, offset as i32, frame)?;
+                return Ok(Flow::Stop);
+            }
+            GOTO_W => {
+                let offset = self.read_i32(pc + 1)?;
+                self.check_branch(pc, offset, frame)?;
+                return Ok(Flow::Stop);
+            }
+            TABLESWITCH | LOOKUPSWITCH => {
+                self.pop(frame, &VType::Int)?;
+                let (offsets, _) = self.switch_targets(pc)?;
+                for offset in offsets {
+                    self.check_branch(pc, offset, frame)?;
+                }
+                return Ok(Flow::Stop);
+            }
+            IRETURN..=RETURN => return self.return_value(frame, opcode),
+            GETSTATIC | PUTSTATIC | GETFIELD | PUTFIELD => self.field(pc, frame, opcode)?,
+            INVOKEVIRTUAL | INVOKESPECIAL | INVOKESTATIC | INVOKEINTERFACE | INVOKEDYNAMIC => {
+                self.invoke(pc, frame, opcode)?
+            }
+            NEW => {
+                self.class_name(self.read_u16(pc + 1)?)?;
+                self.push(frame, VType::Uninitialized(pc as u16))?;
+            }
+            NEWARRAY => {
+                let component = match self.read_u8(pc + 1)? {
+                    4 => "Z",
+                    5 => "C",
+                    6 => "F",
+                    7 => "D",
+                    8 => "B",
+                    9 => "S",
+                    10 => "I",
+                    11 => "J",
+                    atype => return self.error(format!("Illegal newarray type {}", atype)),
+                };
+                self.convert(
+                    frame,
+                    VType::Int,
+                    VType::Reference(format!("[{}", component)),
+                )?;
+            }
+            ANEWARRAY => {
+                let class_name = self.class_name(self.read_u16(pc + 1)?)?;
+                let array = if class_name.starts_with('[') {
+                    format!("[{}", class_name)
+                } else {
+                    format!("[L{};", class_name)
+                };
+                self.convert(frame, VType::Int, VType::Reference(array))?;
+            }
+            ARRAYLENGTH => {
+                self.pop_array(frame, |_| true)?;
+                self.push(frame, VType::Int)?;
+            }
+            ATHROW => {
+                self.pop(frame, &VType::reference(JAVA_LANG_THROWABLE))?;
+                return Ok(Flow::Stop);
+            }
+            CHECKCAST | INSTANCEOF => {
+                self.pop_reference(frame)?;
+                let ty = if opcode == CHECKCAST {
+                    VType::reference(self.class_name(self.read_u16(pc + 1)?)?)
+                } else {
+                    VType::Int
+                };
+                self.push(frame, ty)?;
+            }
+            MONITORENTER | MONITOREXIT => {
+                self.pop_reference(frame)?;
+            }
+            WIDE => {
+                let index = self.read_u16(pc + 2)? as usize;
+                match self.read_u8(pc + 1)? {
+                    ILOAD => self.load(frame, index, VType::Int)?,
+                    LLOAD => self.load(frame, index, VType::Long)?,
+                    FLOAD => self.load(frame, index, VType::Float)?,
+                    DLOAD => self.load(frame, index, VType::Double)?,
+                    ALOAD => self.load_reference(frame, index)?,
+                    ISTORE => self.store_value(frame, index, VType::Int)?,
+                    LSTORE => self.store_value(frame, index, VType::Long)?,
+                    FSTORE => self.store_value(frame, index, VType::Float)?,
+                    DSTORE => self.store_value(frame, index, VType::Double)?,
+                    ASTORE => self.store_reference(frame, index)?,
+                    IINC => self.check_local(frame, index, &VType::Int)?,
+                    _ => {
+                        return self
+                            .error("jsr and ret are not allowed in class files with stack maps")
+                    }
+                }
+            }
+            MULTIANEWARRAY => {
+                let class_name = self.class_name(self.read_u16(pc + 1)?)?;
+                let dimensions = self.read_u8(pc + 3)? as usize;
+                if dimensions == 0
+                    || class_name.len() <= dimensions
+                    || !class_name[..dimensions].bytes().all(|b| b == b'[')
+                {
+                    return self.error("Illegal dimension in multianewarray");
+                }
+                for _ in 0..dimensions {
+                    self.pop(frame, &VType::Int)?;
+                }
+                self.push(frame, VType::reference(class_name))?;
+            }
+            JSR | JSR_W | RET => {
+                return self.error("jsr and ret are not allowed in class files with stack maps")
+            }
+            _ => return self.error(format!("Bad instruction {}", opcode)),
+        }
+        Ok(Flow::Next)
+    }
+}
+
+/// The component descriptors and element type of the primitive array
+/// loads and stores, in opcode order from `iaload` and `iastore`.
+fn primitive_array(n: u8) -> (&'static [&'static str], VType) {
+    match n {
+        0 => (&["I"], VType::Int),
+        1 => (&["J"], VType::Long),
+        2 => (&["F"], VType::Float),
+        3 => (&["D"], VType::Double),
+        5 => (&["B", "Z"], VType::Int),
+        6 => (&["C"], VType::Int),
+        _ => (&["S"], VType::Int),
+    }
+}
+
+/// Whether an array with this component descriptor holds references.
+fn is_reference_component(component: &str) -> bool {
+    component.len() > 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify_class, ClassHierarchy, ClassInfo};
+    use crate::class_parser::attribute_info::predefined_attribute::{
+        ExceptionHandler, PredefinedAttribute,
+    };
+    use crate::class_parser::{is_bit_set, parse_class_file, ClassFile, ACC_INTERFACE};
+    use crate::class_parser::{ACC_PUBLIC, ACC_STATIC};
+    use crate::runtime::invoke::class_writer::ClassWriter;
+    use crate::runtime::opcode::*;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// Knows the classes in `test_data`.
+    struct TestData;
+
+    impl ClassHierarchy for TestData {
+        fn class_info(&mut self, name: &str) -> Option<ClassInfo> {
+            let data = fs::read(format!("test_data/{}.class", name)).ok()?;
+            let (_, class_file) = parse_class_file(&data).ok()?;
+            let super_class = Some(class_file.super_class)
+                .filter(|index| *index != 0)
+                .map(|index| class_file.constant_pool.get_class_name_at(index).clone());
+            Some(ClassInfo {
+                super_class,
+                is_interface: is_bit_set(class_file.access_flags, ACC_INTERFACE),
+            })
+        }
+    }
+
+    fn find_classes(dir: &Path, classes: &mut Vec<PathBuf>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                find_classes(&path, classes);
+            } else if path.extension().is_some_and(|ext| ext == "class") {
+                classes.push(path);
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_test_data() {
+        let mut classes = Vec::new();
+        find_classes(Path::new("test_data"), &mut classes);
+        for class in classes {
+            let data = fs::read(&class).unwrap();
+            let (_, class_file) = parse_class_file(&data).unwrap();
+            if let Err(e) = verify_class(&class_file, &mut TestData) {
+                panic!("verify {}: {}", class.display(), e);
+            }
+        }
+    }
+
+    /// `Broken.run` with `code` and without a `StackMapTable`.
+    fn broken_method(descriptor: &str, max_stack: u16, max_locals: u16, code: &[u8]) -> Vec<u8> {
+        let mut writer = ClassWriter::new(ACC_PUBLIC, "Broken", "java/lang/Object", &[]);
+        let access_flags = ACC_PUBLIC | ACC_STATIC;
+        writer.add_method(access_flags, "run", descriptor, max_stack, max_locals, code);
+        writer.into_bytes()
+    }
+
+    fn verify_error(class_file: &ClassFile) -> String {
+        verify_class(class_file, &mut TestData).unwrap_err().message
+    }
+
+    fn verify_error_of(data: &[u8]) -> String {
+        verify_error(&parse_class_file(data).unwrap().1)
+    }
+
+    #[test]
+    fn test_verify_error() {
+        let data = broken_method("()V", 1, 0, &[ICONST_0, ICONST_1, POP2, RETURN]);
+        assert_eq!(
+            verify_error_of(&data),
+            "Operand stack overflow, exceeds max_stack in method Broken.run()V at pc 1"
+        );
+
+        let data = broken_method("()Ljava/lang/Object;", 1, 0, &[ICONST_0, ARETURN]);
+        assert_eq!(
+            verify_error_of(&data),
+            "Bad type on operand stack, expected java/lang/Object but found int \
+             in method Broken.run()Ljava/lang/Object; at pc 1"
+        );
+
+        let data = broken_method("(I)I", 1, 1, &[FLOAD_0, F2I, IRETURN]);
+        assert_eq!(
+            verify_error_of(&data),
+            "Bad local variable type, expected float but found int in method Broken.run(I)I at pc 0"
+        );
+
+        // into the middle of the goto itself
+        let data = broken_method("()V", 0, 0, &[GOTO, 0, 1, RETURN]);
+        assert_eq!(
+            verify_error_of(&data),
+            "Illegal target of jump or branch 1 in method Broken.run()V at pc 0"
+        );
+
+        let data = broken_method("(I)V", 1, 1, &[ILOAD_0, IFEQ, 0, 4, RETURN, RETURN]);
+        assert_eq!(
+            verify_error_of(&data),
+            "Expecting a stack map frame at branch target 5 in method Broken.run(I)V at pc 1"
+        );
+
+        // the range ends in the middle of sipush
+        let data = broken_method("()V", 1, 0, &[SIPUSH, 0, 0, POP, RETURN]);
+        let (_, mut class_file) = parse_class_file(&data).unwrap();
+        for attr in &mut class_file.methods[0].attributes {
+            if let PredefinedAttribute::CodeAttribute(code) = &mut attr.attribute {
+                code.exception_table.push(ExceptionHandler {
+                    start_pc: 0,
+                    end_pc: 2,
+                    handler_pc: 4,
+                    catch_type: 0,
+                });
+            }
+        }
+        assert_eq!(
+            verify_error(&class_file),
+            "Illegal exception table range [0, 2) in method Broken.run()V at pc 0"
+        );
+
+        let data = broken_method("(I", 0, 1, &[RETURN]);
+        assert_eq!(
+            verify_error_of(&data),
+            "Illegal method descriptor (I in method Broken.run(I at pc 0"
+        );
+
+        let mut writer = ClassWriter::new(ACC_PUBLIC, "Broken", "java/lang/Object", &[]);
+        let method = writer.method_ref("Broken", "run", "()", false);
+        let [high, low] = method.to_be_bytes();
+        let code = [INVOKESTATIC, high, low, RETURN];
+        writer.add_method(ACC_PUBLIC | ACC_STATIC, "run", "()V", 0, 0, &code);
+        assert_eq!(
+            verify_error_of(&writer.into_bytes()),
+            "Illegal method descriptor () in method Broken.run()V at pc 0"
+        );
+
+        let data = broken_method("()V", 1, 0, &[NEW, 0, 0, POP, RETURN]);
+        assert_eq!(
+            verify_error_of(&data),
+            "Illegal constant pool index 0 in method Broken.run()V at pc 0"
+        );
+
+        let mut writer = ClassWriter::new(ACC_PUBLIC, "Broken", "java/lang/Object", &[]);
+        let [high, low] = writer.utf8("java/lang/String").to_be_bytes();
+        let code = [ACONST_NULL, CHECKCAST, high, low, POP, RETURN];
+        writer.add_method(ACC_PUBLIC | ACC_STATIC, "run", "()V", 1, 0, &code);
+        let index = u16::from_be_bytes([high, low]);
+        assert_eq!(
+            verify_error_of(&writer.into_bytes()),
+            format!(
+                "Illegal type at constant pool entry {} in method Broken.run()V at pc 1",
+                index
+            )
+        );
+
+        let data = broken_method("()V", 1, 0, &[GETSTATIC, 0xff, 0xff, POP, RETURN]);
+        assert_eq!(
+            verify_error_of(&data),
+            "Illegal constant pool index 65535 in method Broken.run()V at pc 0"
+        );
+    }
+}
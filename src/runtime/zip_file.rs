@@ -0,0 +1,606 @@
+#![allow(non_snake_case, unused_variables)]
+//! The natives behind `java.util.zip.ZipFile`, `java.util.jar.JarFile` and
+//! `java.util.zip.Inflater`, which libzip has in the JDK.
+//!
+//! A `jzfile` is the number of an open zip file in the `ZipTable`, a
+//! `jzentry` has that number in its upper half and the index of the entry in
+//! the central directory, plus one, in its lower half. The address of an
+//! `Inflater` is the number of its stream. Like the other reads, reading an
+//! entry gives up the global lock.
+use crate::runtime::class::Class;
+use crate::runtime::frame::operand_stack::Operand;
+use crate::runtime::heap::{
+    JvmHeap, JAVA_IO_FILE_NOT_FOUND_EXCEPTION, JAVA_LANG_ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION,
+    JAVA_LANG_NULL_POINTER_EXCEPTION, JAVA_LANG_STRING, JAVA_UTIL_ZIP_DATA_FORMAT_EXCEPTION,
+    JAVA_UTIL_ZIP_ZIP_EXCEPTION, T_BYTE,
+};
+use crate::runtime::io::error_message;
+use crate::runtime::jvm_env::JvmEnv;
+use flate2::{Decompress, FlushDecompress, Status};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io;
+use std::os::unix::fs::FileExt;
+use std::sync::Arc;
+
+const LOCSIG: u32 = 0x0403_4b50;
+const CENSIG: u32 = 0x0201_4b50;
+const ENDSIG: u32 = 0x0605_4b50;
+
+/// The sizes of the local, central directory and END headers.
+const LOCHDR: usize = 30;
+const CENHDR: usize = 46;
+const ENDHDR: usize = 22;
+
+/// The END header is followed by a comment of at most this many bytes.
+const END_MAX_COMMENT: usize = 0xFFFF;
+
+const STORED: u16 = 0;
+
+/// The `mode` bit of `ZipFile.open` to delete the file once it's open.
+const OPEN_DELETE: i32 = 0x4;
+
+/// The `type` of `ZipFile.getEntryBytes`.
+const JZENTRY_NAME: i32 = 0;
+const JZENTRY_EXTRA: i32 = 1;
+const JZENTRY_COMMENT: i32 = 2;
+
+/// `ZipFile.read` reads at most this much at once, like the JDK.
+const BUF_SIZE: i32 = 8192;
+
+/// An entry of the central directory.
+#[derive(Debug)]
+struct ZipEntry {
+    name: Vec<u8>,
+    extra: Vec<u8>,
+    comment: Vec<u8>,
+    flag: u16,
+    method: u16,
+    /// The MS-DOS date and time.
+    time: u32,
+    crc: u32,
+    csize: u64,
+    size: u64,
+    /// Where the local header of the entry is in the file.
+    offset: u64,
+}
+
+impl ZipEntry {
+    /// How many bytes the entry takes up in the file.
+    fn data_size(&self) -> u64 {
+        if self.method == STORED {
+            self.size
+        } else {
+            self.csize
+        }
+    }
+}
+
+/// An open zip file with its central directory.
+#[derive(Debug)]
+pub struct ZipArchive {
+    file: File,
+    entries: Vec<ZipEntry>,
+    /// The first entry with each name.
+    names: HashMap<Vec<u8>, usize>,
+    comment: Vec<u8>,
+    starts_with_loc: bool,
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    let mut value = [0; 4];
+    value.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(value)
+}
+
+impl ZipArchive {
+    /// Reads the central directory of `file`, the error is the message of
+    /// the `ZipException`.
+    fn open(file: File) -> Result<Self, String> {
+        let read_error = |error: io::Error| error_message(&error);
+        let len = file.metadata().map_err(read_error)?.len();
+        if len == 0 {
+            return Err("zip file is empty".to_string());
+        }
+        let tail_len = len.min((ENDHDR + END_MAX_COMMENT) as u64);
+        let mut tail = vec![0; tail_len as usize];
+        file.read_exact_at(&mut tail, len - tail_len)
+            .map_err(read_error)?;
+        let end = (0..=tail.len().saturating_sub(ENDHDR))
+            .rev()
+            .find(|&i| {
+                i + ENDHDR <= tail.len()
+                    && u32_at(&tail, i) == ENDSIG
+                    && i + ENDHDR + u16_at(&tail, i + 20) as usize == tail.len()
+            })
+            .ok_or("zip END header not found")?;
+        let cen_size = u32_at(&tail, end + 12) as u64;
+        let cen_offset = u32_at(&tail, end + 16) as u64;
+        let comment = tail[end + ENDHDR..].to_vec();
+        // the offsets count from the start of the zip file, which may not be
+        // the start of `file`, e.g. after a launcher script
+        let end_position = len - tail_len + end as u64;
+        let base = (end_position.checked_sub(cen_size))
+            .and_then(|position| position.checked_sub(cen_offset))
+            .ok_or("invalid END header (bad central directory offset)")?;
+
+        let mut cen = vec![0; cen_size as usize];
+        file.read_exact_at(&mut cen, base + cen_offset)
+            .map_err(read_error)?;
+        let mut entries = Vec::new();
+        let mut position = 0;
+        while position < cen.len() {
+            if position + CENHDR > cen.len() || u32_at(&cen, position) != CENSIG {
+                return Err("invalid CEN header (bad signature)".to_string());
+            }
+            let header = &cen[position..];
+            let name_len = u16_at(header, 28) as usize;
+            let extra_len = u16_at(header, 30) as usize;
+            let comment_len = u16_at(header, 32) as usize;
+            let extra_start = CENHDR + name_len;
+            let comment_start = extra_start + extra_len;
+            let next = comment_start + comment_len;
+            if next > header.len() {
+                return Err("invalid CEN header (bad header size)".to_string());
+            }
+            entries.push(ZipEntry {
+                name: header[CENHDR..extra_start].to_vec(),
+                extra: header[extra_start..comment_start].to_vec(),
+                comment: header[comment_start..next].to_vec(),
+                flag: u16_at(header, 8),
+                method: u16_at(header, 10),
+                time: u32_at(header, 12),
+                crc: u32_at(header, 16),
+                csize: u32_at(header, 20) as u64,
+                size: u32_at(header, 24) as u64,
+                offset: base + u32_at(header, 42) as u64,
+            });
+            position += next;
+        }
+
+        let mut names = HashMap::new();
+        for (index, entry) in entries.iter().enumerate() {
+            names.entry(entry.name.clone()).or_insert(index);
+        }
+        let mut signature = [0; 4];
+        let starts_with_loc = file.read_exact_at(&mut signature, 0).is_ok()
+            && u32::from_le_bytes(signature) == LOCSIG;
+        Ok(ZipArchive {
+            file,
+            entries,
+            names,
+            comment,
+            starts_with_loc,
+        })
+    }
+
+    /// The index of the entry `name`, or of `name/` with `add_slash`.
+    fn find(&self, name: &[u8], add_slash: bool) -> Option<usize> {
+        if let Some(index) = self.names.get(name) {
+            return Some(*index);
+        }
+        if !add_slash || name.ends_with(b"/") {
+            return None;
+        }
+        let mut dir = name.to_vec();
+        dir.push(b'/');
+        self.names.get(&dir).copied()
+    }
+
+    /// Reads the data of `entry`, compressed if it is, from `position` into
+    /// `buf` and returns how many bytes it read.
+    fn read(&self, entry: &ZipEntry, position: u64, buf: &mut [u8]) -> Result<usize, String> {
+        let size = entry.data_size();
+        if position >= size {
+            return Err("ZIP_Read: specified offset out of range".to_string());
+        }
+        let len = buf.len().min((size - position) as usize);
+        let read_error = |_| "ZIP_Read: error reading zip file".to_string();
+        let mut header = [0; LOCHDR];
+        self.file
+            .read_exact_at(&mut header, entry.offset)
+            .map_err(read_error)?;
+        if u32_at(&header, 0) != LOCSIG {
+            return Err("invalid LOC header (bad signature)".to_string());
+        }
+        let start =
+            entry.offset + LOCHDR as u64 + u16_at(&header, 26) as u64 + u16_at(&header, 28) as u64;
+        self.file
+            .read_at(&mut buf[..len], start + position)
+            .map_err(read_error)
+    }
+}
+
+/// The state of an `Inflater`.
+#[derive(Debug)]
+struct InflaterStream {
+    stream: Decompress,
+    /// Raw deflate data without the zlib header and checksum.
+    nowrap: bool,
+}
+
+/// The open zip files and the streams of the inflaters of the VM.
+#[derive(Debug, Default)]
+pub struct ZipTable {
+    files: HashMap<i64, Arc<ZipArchive>>,
+    inflaters: HashMap<i64, InflaterStream>,
+    last_handle: i64,
+}
+
+impl ZipTable {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// A new handle, never 0 since that's null to Java.
+    fn next_handle(&mut self) -> i64 {
+        self.last_handle += 1;
+        self.last_handle
+    }
+
+    fn open(&mut self, zip: ZipArchive) -> i64 {
+        let jzfile = self.next_handle();
+        self.files.insert(jzfile, Arc::new(zip));
+        jzfile
+    }
+
+    fn get(&self, jzfile: i64) -> Option<Arc<ZipArchive>> {
+        self.files.get(&jzfile).cloned()
+    }
+
+    /// The file is closed once a read of another thread from it is done too.
+    fn close(&mut self, jzfile: i64) {
+        self.files.remove(&jzfile);
+    }
+}
+
+fn push(jenv: &mut JvmEnv, value: Operand) {
+    let frame = jenv.thread_mut().stack.frames.back_mut().unwrap();
+    frame.operand_stack.push(value);
+}
+
+fn throw_zip_exception(jenv: &mut JvmEnv, message: &str) {
+    jenv.throw_exception(JAVA_UTIL_ZIP_ZIP_EXCEPTION, Some(message));
+}
+
+/// The zip file `jzfile`, throws `ZipException` if it's closed.
+fn zip_file(jenv: &mut JvmEnv, jzfile: i64) -> Option<Arc<ZipArchive>> {
+    let zip = jenv.zips().get(jzfile);
+    if zip.is_none() {
+        throw_zip_exception(jenv, "zip file closed");
+    }
+    zip
+}
+
+/// The zip file of `jzentry` and the index of the entry.
+fn zip_entry(jenv: &mut JvmEnv, jzentry: i64) -> Option<(Arc<ZipArchive>, usize)> {
+    let zip = zip_file(jenv, jzentry >> 32)?;
+    let index = (jzentry & 0xFFFF_FFFF) as usize - 1;
+    Some((zip, index))
+}
+
+fn jzentry(jzfile: i64, index: usize) -> i64 {
+    jzfile << 32 | (index as i64 + 1)
+}
+
+/// Pushes a new `byte[]` with `bytes`.
+fn push_bytes(jenv: &mut JvmEnv, bytes: &[u8]) {
+    let len = bytes.len() as i32;
+    if !jenv.reserve_heap(JvmHeap::array_size(1, len)) {
+        return;
+    }
+    let array = Operand::ArrayRef(jenv.heap_mut().new_empty_array(T_BYTE, len));
+    let values = jenv.heap_mut().get_byte_array_mut(array.clone());
+    for (value, byte) in values.iter_mut().zip(bytes) {
+        *value = *byte as i8;
+    }
+    push(jenv, array);
+}
+
+/// Pushes a new `byte[]` with `bytes`, null if there are none.
+fn push_bytes_or_null(jenv: &mut JvmEnv, bytes: &[u8]) {
+    if bytes.is_empty() {
+        push(jenv, Operand::Null);
+    } else {
+        push_bytes(jenv, bytes);
+    }
+}
+
+fn byte_array(jenv: &mut JvmEnv, array: &Operand) -> Vec<u8> {
+    let values = jenv.heap_mut().get_byte_array_mut(array.clone());
+    values.iter().map(|value| *value as u8).collect()
+}
+
+pub fn java_util_zip_ZipFile_initIDs(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {}
+
+/// Opens the zip file `name`, throws `FileNotFoundException` if it can't
+/// and `ZipException` if it isn't a zip file.
+pub fn java_util_zip_ZipFile_open(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let name = jenv.get_java_string(&args[0]);
+    let mode = args[1].get_int();
+    let file = match File::open(&name) {
+        Ok(file) => file,
+        Err(error) => {
+            let message = format!("{} ({})", name, error_message(&error));
+            return jenv.throw_exception(JAVA_IO_FILE_NOT_FOUND_EXCEPTION, Some(&message));
+        }
+    };
+    if mode & OPEN_DELETE != 0 {
+        let _ = fs::remove_file(&name);
+    }
+    match jenv.blocking(|| ZipArchive::open(file)) {
+        Ok(zip) => {
+            let jzfile = jenv.zips_mut().open(zip);
+            push(jenv, Operand::Long(jzfile));
+        }
+        Err(message) => throw_zip_exception(jenv, &message),
+    }
+}
+
+pub fn java_util_zip_ZipFile_getTotal(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    if let Some(zip) = zip_file(jenv, args[0].get_long()) {
+        push(jenv, Operand::Int(zip.entries.len() as i32));
+    }
+}
+
+pub fn java_util_zip_ZipFile_startsWithLOC(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    if let Some(zip) = zip_file(jenv, args[0].get_long()) {
+        push(jenv, Operand::Int(zip.starts_with_loc as i32));
+    }
+}
+
+/// `int read(long jzfile, long jzentry, long pos, byte[] b, int off, int len)`,
+/// reads the data of the entry as it's stored.
+pub fn java_util_zip_ZipFile_read(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let (zip, index) = match zip_entry(jenv, args[1].get_long()) {
+        Some(entry) => entry,
+        None => return,
+    };
+    let position = args[2].get_long();
+    let array = &args[3];
+    let off = args[4].get_int();
+    let len = args[5].get_int().min(BUF_SIZE);
+    let length = jenv.heap_mut().get_array_length(array);
+    if off < 0 || len < 0 || len > length - off {
+        return jenv.throw_exception(JAVA_LANG_ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION, None);
+    }
+    if position < 0 {
+        return throw_zip_exception(jenv, "ZIP_Read: specified offset out of range");
+    }
+    let mut buf = vec![0; len as usize];
+    let entry = &zip.entries[index];
+    match jenv.blocking(|| zip.read(entry, position as u64, &mut buf)) {
+        Ok(n) => {
+            let bytes = jenv.heap_mut().get_byte_array_mut(array.clone());
+            let off = off as usize;
+            for (dst, src) in bytes[off..off + n].iter_mut().zip(&buf) {
+                *dst = *src as i8;
+            }
+            push(jenv, Operand::Int(n as i32));
+        }
+        Err(message) => throw_zip_exception(jenv, &message),
+    }
+}
+
+/// The entry `name`, or the directory `name/` if `addSlash`, 0 if there's
+/// none.
+pub fn java_util_zip_ZipFile_getEntry(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let jzfile = args[0].get_long();
+    let zip = match zip_file(jenv, jzfile) {
+        Some(zip) => zip,
+        None => return,
+    };
+    let name = byte_array(jenv, &args[1]);
+    let add_slash = args[2].get_int() != 0;
+    let jzentry = zip
+        .find(&name, add_slash)
+        .map_or(0, |index| jzentry(jzfile, index));
+    push(jenv, Operand::Long(jzentry));
+}
+
+/// Entries are part of their zip file, there's nothing to free.
+pub fn java_util_zip_ZipFile_freeEntry(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {}
+
+/// The entry at index `i` of the central directory, 0 past the last one.
+pub fn java_util_zip_ZipFile_getNextEntry(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let jzfile = args[0].get_long();
+    let i = args[1].get_int();
+    if let Some(zip) = zip_file(jenv, jzfile) {
+        let jzentry = match i {
+            i if i >= 0 && (i as usize) < zip.entries.len() => jzentry(jzfile, i as usize),
+            _ => 0,
+        };
+        push(jenv, Operand::Long(jzentry));
+    }
+}
+
+pub fn java_util_zip_ZipFile_close(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    jenv.zips_mut().close(args[0].get_long());
+}
+
+/// Pushes what `value` returns for the entry `jzentry`.
+fn push_entry_value(
+    jenv: &mut JvmEnv,
+    jzentry: &Operand,
+    value: impl FnOnce(&ZipEntry) -> Operand,
+) {
+    if let Some((zip, index)) = zip_entry(jenv, jzentry.get_long()) {
+        push(jenv, value(&zip.entries[index]));
+    }
+}
+
+pub fn java_util_zip_ZipFile_getEntryTime(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    push_entry_value(jenv, &args[0], |entry| Operand::Int(entry.time as i32));
+}
+
+pub fn java_util_zip_ZipFile_getEntryCrc(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    push_entry_value(jenv, &args[0], |entry| Operand::Long(entry.crc as i64));
+}
+
+pub fn java_util_zip_ZipFile_getEntryCSize(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    push_entry_value(jenv, &args[0], |entry| Operand::Long(entry.csize as i64));
+}
+
+pub fn java_util_zip_ZipFile_getEntrySize(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    push_entry_value(jenv, &args[0], |entry| Operand::Long(entry.size as i64));
+}
+
+pub fn java_util_zip_ZipFile_getEntryMethod(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    push_entry_value(jenv, &args[0], |entry| Operand::Int(entry.method as i32));
+}
+
+pub fn java_util_zip_ZipFile_getEntryFlag(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    push_entry_value(jenv, &args[0], |entry| Operand::Int(entry.flag as i32));
+}
+
+/// The name, extra data or comment of an entry by `JZENTRY_*`, null for an
+/// empty extra data or comment.
+pub fn java_util_zip_ZipFile_getEntryBytes(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let (zip, index) = match zip_entry(jenv, args[0].get_long()) {
+        Some(entry) => entry,
+        None => return,
+    };
+    let entry = &zip.entries[index];
+    match args[1].get_int() {
+        JZENTRY_NAME => push_bytes(jenv, &entry.name),
+        JZENTRY_EXTRA => push_bytes_or_null(jenv, &entry.extra),
+        JZENTRY_COMMENT => push_bytes_or_null(jenv, &entry.comment),
+        _ => push(jenv, Operand::Null),
+    }
+}
+
+/// The comment of the zip file, null if it has none.
+pub fn java_util_zip_ZipFile_getCommentBytes(
+    jenv: &mut JvmEnv,
+    _class: &Class,
+    args: Vec<Operand>,
+) {
+    if let Some(zip) = zip_file(jenv, args[0].get_long()) {
+        push_bytes_or_null(jenv, &zip.comment);
+    }
+}
+
+/// Errors are thrown when they happen, there's never one left to report.
+pub fn java_util_zip_ZipFile_getZipMessage(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    push(jenv, Operand::Null);
+}
+
+/// The names of the entries in `META-INF/`, in any case, null if there are
+/// none.
+pub fn java_util_jar_JarFile_getMetaInfEntryNames(
+    jenv: &mut JvmEnv,
+    _class: &Class,
+    args: Vec<Operand>,
+) {
+    let jzfile = jenv
+        .heap()
+        .get_object(&args[0])
+        .get_field_by_name("jzfile", "J")
+        .get_long();
+    let zip = match zip_file(jenv, jzfile) {
+        Some(zip) => zip,
+        None => return,
+    };
+    let names: Vec<_> = (zip.entries.iter())
+        .filter(|entry| entry.name.len() >= 9 && entry.name[..9].eq_ignore_ascii_case(b"META-INF/"))
+        .map(|entry| String::from_utf8_lossy(&entry.name))
+        .collect();
+    if names.is_empty() {
+        return push(jenv, Operand::Null);
+    }
+    let count = names.len() as i32;
+    if !jenv.reserve_heap(JvmHeap::reference_array_size(count)) {
+        return;
+    }
+    let array = Operand::ArrayRef(
+        jenv.heap_mut()
+            .new_reference_array(JAVA_LANG_STRING.to_string(), count),
+    );
+    // the array keeps the names alive while the next ones are allocated
+    jenv.thread_mut().native_args.push(vec![array.clone()]);
+    for (i, name) in names.iter().enumerate() {
+        match jenv.new_java_lang_string(name) {
+            Some(addr) => {
+                jenv.heap_mut().get_object_array_mut(&array)[i] = Operand::ObjectRef(addr)
+            }
+            None => break,
+        }
+    }
+    jenv.thread_mut().native_args.pop();
+    if !jenv.thread().has_pending_exception() {
+        push(jenv, array);
+    }
+}
+
+pub fn java_util_zip_Inflater_initIDs(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {}
+
+pub fn java_util_zip_Inflater_init(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let nowrap = args[0].get_int() != 0;
+    let zips = jenv.zips_mut();
+    let address = zips.next_handle();
+    let stream = Decompress::new(!nowrap);
+    zips.inflaters
+        .insert(address, InflaterStream { stream, nowrap });
+    push(jenv, Operand::Long(address));
+}
+
+pub fn java_util_zip_Inflater_reset(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    if let Some(inflater) = jenv.zips_mut().inflaters.get_mut(&args[0].get_long()) {
+        inflater.stream.reset(!inflater.nowrap);
+    }
+}
+
+pub fn java_util_zip_Inflater_end(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    jenv.zips_mut().inflaters.remove(&args[0].get_long());
+}
+
+/// `int inflateBytes(long addr, byte[] b, int off, int len)`, inflates the
+/// input in the `buf`, `off` and `len` fields of the `Inflater` into `b`,
+/// moves `off` and `len` past what it used and sets `finished` at the end of
+/// the data. Returns how many bytes it inflated.
+pub fn java_util_zip_Inflater_inflateBytes(jenv: &mut JvmEnv, _class: &Class, args: Vec<Operand>) {
+    let this = &args[0];
+    let object = jenv.heap().get_object(this);
+    let input_array = object.get_field_by_name("buf", "[B").clone();
+    let input_off = object.get_field_by_name("off", "I").get_int();
+    let input_len = object.get_field_by_name("len", "I").get_int();
+    let input = byte_array(jenv, &input_array);
+    let input = &input[input_off as usize..(input_off + input_len) as usize];
+    let array = &args[2];
+    let off = args[3].get_int() as usize;
+    let mut output = vec![0; args[4].get_int() as usize];
+
+    let inflater = match jenv.zips_mut().inflaters.get_mut(&args[1].get_long()) {
+        Some(inflater) => inflater,
+        None => return jenv.throw_exception(JAVA_LANG_NULL_POINTER_EXCEPTION, None),
+    };
+    let (total_in, total_out) = (inflater.stream.total_in(), inflater.stream.total_out());
+    let status = inflater
+        .stream
+        .decompress(input, &mut output, FlushDecompress::None);
+    let consumed = (inflater.stream.total_in() - total_in) as i32;
+    let produced = (inflater.stream.total_out() - total_out) as usize;
+    match status {
+        Ok(status) => {
+            let object = jenv.heap_mut().get_object_mut(this);
+            object.set_field_by_name("off", "I", Operand::Int(input_off + consumed));
+            object.set_field_by_name("len", "I", Operand::Int(input_len - consumed));
+            if status == Status::StreamEnd {
+                object.set_field_by_name("finished", "Z", Operand::Int(1));
+            }
+            let bytes = jenv.heap_mut().get_byte_array_mut(array.clone());
+            for (dst, src) in bytes[off..off + produced].iter_mut().zip(&output) {
+                *dst = *src as i8;
+            }
+            push(jenv, Operand::Int(produced as i32));
+        }
+        Err(error) => jenv.throw_exception(
+            JAVA_UTIL_ZIP_DATA_FORMAT_EXCEPTION,
+            Some(&error.to_string()),
+        ),
+    }
+}
@@ -0,0 +1,70 @@
+package bench;
+
+/** A call-heavy program for bench_dispatch, mostly virtual and interface calls. */
+public class Dispatch {
+    static final int ITERATIONS = 1000000;
+    static long result;
+
+    public static void main(String[] args) {
+        Shape[] shapes = {new Square(3), new Rectangle(2, 5), new Square(4)};
+        Polygon polygon = new Square(2);
+        long sum = 0;
+        for (int i = 0; i < ITERATIONS; i++) {
+            Shape shape = shapes[i % shapes.length];
+            sum += shape.area();
+            sum += polygon.sides();
+            sum += polygon.perimeter();
+        }
+        result = sum;
+    }
+}
+
+interface Shape {
+    int area();
+}
+
+abstract class Polygon implements Shape {
+    abstract int sides();
+
+    int perimeter() {
+        return sides() * side();
+    }
+
+    abstract int side();
+}
+
+class Rectangle extends Polygon {
+    final int width;
+    final int height;
+
+    Rectangle(int width, int height) {
+        this.width = width;
+        this.height = height;
+    }
+
+    public int area() {
+        return width * height;
+    }
+
+    int sides() {
+        return 4;
+    }
+
+    int side() {
+        return width;
+    }
+
+    int perimeter() {
+        return 2 * (width + height);
+    }
+}
+
+class Square extends Rectangle {
+    Square(int side) {
+        super(side, side);
+    }
+
+    int perimeter() {
+        return 4 * side();
+    }
+}
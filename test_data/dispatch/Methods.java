@@ -0,0 +1,73 @@
+package dispatch;
+
+import dispatch.a.Base;
+import dispatch.a.SubSub;
+import dispatch.b.Sub;
+
+/** Methods selected by invokevirtual and invokeinterface, see test_dispatch. */
+public class Methods {
+    static int[] packagePrivate;
+    static int[] defaults;
+    static int[] inherited;
+
+    public static void main(String[] args) {
+        packagePrivate = new int[] {
+            new Base().callId(),
+            new Sub().callId(),
+            new Sub().subId(),
+            new SubSub().callId(),
+            new SubSub().subId(),
+        };
+
+        Greeter greeter = new Loud();
+        Loud loud = new Loud();
+        Object object = loud;
+        defaults = new int[] {
+            greeter.greet(),
+            loud.greet(),
+            new Quiet().greet(),
+            greeter.hashCode(),
+            object.hashCode(),
+        };
+
+        Counter counter = new Full();
+        Partial partial = new Full();
+        inherited = new int[] {counter.count(), partial.hashCode()};
+    }
+}
+
+interface Greeter {
+    default int greet() {
+        return 10;
+    }
+}
+
+interface LoudGreeter extends Greeter {
+    default int greet() {
+        return 20;
+    }
+}
+
+class Loud implements Greeter, LoudGreeter {
+    public int hashCode() {
+        return 42;
+    }
+}
+
+class Quiet implements Greeter {}
+
+interface Counter {
+    int count();
+}
+
+abstract class Partial implements Counter {
+    public int hashCode() {
+        return 7;
+    }
+}
+
+class Full extends Partial {
+    public int count() {
+        return 5;
+    }
+}
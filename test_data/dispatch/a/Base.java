@@ -0,0 +1,11 @@
+package dispatch.a;
+
+public class Base {
+    int id() {
+        return 1;
+    }
+
+    public int callId() {
+        return id();
+    }
+}
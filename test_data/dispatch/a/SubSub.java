@@ -0,0 +1,8 @@
+package dispatch.a;
+
+/** Its `id` overrides that of `Base`, in the same package, not that of `Sub`. */
+public class SubSub extends dispatch.b.Sub {
+    int id() {
+        return 3;
+    }
+}
@@ -0,0 +1,12 @@
+package dispatch.b;
+
+/** Its `id` doesn't override the package-private one of `Base`. */
+public class Sub extends dispatch.a.Base {
+    int id() {
+        return 2;
+    }
+
+    public int subId() {
+        return id();
+    }
+}
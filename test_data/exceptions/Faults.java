@@ -0,0 +1,82 @@
+package exceptions;
+
+/** Runtime faults of the interpreter thrown as Java exceptions, see test_faults. */
+public class Faults {
+    static String arrayIndex;
+    static String charIndex;
+    static String divide;
+    static String remainder;
+    static String longDivide;
+    static String nullField;
+    static String nullCall;
+    static String cast;
+    static String negativeSize;
+    static String arrayStore;
+    static int recovered;
+
+    int field;
+
+    public static void main(String[] args) {
+        Object[] objects = new Object[2];
+        try {
+            Object o = objects[2];
+        } catch (ArrayIndexOutOfBoundsException e) {
+            arrayIndex = e.getMessage();
+        }
+        char[] chars = new char[1];
+        try {
+            char c = chars[-1];
+        } catch (IndexOutOfBoundsException e) {
+            charIndex = e.getMessage();
+        }
+        int zero = args.length;
+        try {
+            int i = 1 / zero;
+        } catch (ArithmeticException e) {
+            divide = e.getMessage();
+        }
+        try {
+            int i = 1 % zero;
+        } catch (ArithmeticException e) {
+            remainder = e.getMessage();
+        }
+        try {
+            long l = 1L / zero;
+        } catch (ArithmeticException e) {
+            longDivide = e.getMessage();
+        }
+        Faults faults = null;
+        try {
+            int i = faults.field;
+        } catch (NullPointerException e) {
+            nullField = "caught";
+        }
+        try {
+            faults.hashCode();
+        } catch (NullPointerException e) {
+            nullCall = "caught";
+        }
+        Object string = "string";
+        try {
+            Faults f = (Faults) string;
+        } catch (ClassCastException e) {
+            cast = e.getMessage();
+        }
+        try {
+            int[] array = new int[zero - 1];
+        } catch (NegativeArraySizeException e) {
+            negativeSize = e.getMessage();
+        }
+        Object[] strings = new String[1];
+        try {
+            strings[0] = new Faults();
+        } catch (ArrayStoreException e) {
+            arrayStore = e.getMessage();
+        }
+        strings[0] = "stored";
+        objects[1] = strings[0];
+        if (objects[1] == "stored") {
+            recovered = 1;
+        }
+    }
+}
@@ -0,0 +1,107 @@
+package exceptions;
+
+/** Exception tables, unwinding and finally blocks, see test_exceptions. */
+public class Handlers {
+    static int caughtByType;
+    static int innerSkipped;
+    static int outsideRange;
+    static int unwound;
+    static int finallyRuns;
+    static int rethrown;
+    static int afterFinally;
+    static Throwable uncaught;
+
+    public static void main(String[] args) {
+        // the first handler whose type matches wins
+        try {
+            fail("type");
+        } catch (IllegalArgumentException e) {
+            caughtByType = -1;
+        } catch (RuntimeException e) {
+            caughtByType = 1;
+        } catch (Exception e) {
+            caughtByType = -2;
+        }
+
+        // a handler of another type passes the exception on to the outer one
+        try {
+            try {
+                fail("inner");
+            } catch (IllegalArgumentException e) {
+                innerSkipped = -1;
+            }
+        } catch (RuntimeException e) {
+            innerSkipped = 1;
+        }
+
+        // the handler covers only the pc range of its try block
+        int value = 0;
+        try {
+            value = 1;
+        } catch (RuntimeException e) {
+            value = -1;
+        }
+        try {
+            fail("outside");
+        } catch (RuntimeException e) {
+            outsideRange = value;
+        }
+
+        // frames without a handler are unwound, running their finally blocks
+        try {
+            unwind(3);
+        } catch (RuntimeException e) {
+            unwound = e.getMessage() == "depth 0" ? 1 : -1;
+        }
+
+        // a catch block that throws runs the finally block before leaving
+        try {
+            rethrow();
+        } catch (Error e) {
+            rethrown = e.getMessage() == "rethrown" ? 1 : -1;
+        }
+
+        afterFinally = returnThroughFinally();
+
+        try {
+            fail("boom");
+        } catch (RuntimeException e) {
+            uncaught = e;
+        }
+    }
+
+    static void fail(String message) {
+        throw new RuntimeException(message);
+    }
+
+    static void unwind(int depth) {
+        try {
+            if (depth == 0) {
+                fail("depth 0");
+            }
+            unwind(depth - 1);
+        } finally {
+            finallyRuns++;
+        }
+    }
+
+    static void rethrow() {
+        try {
+            fail("first");
+        } catch (RuntimeException e) {
+            throw new Error("rethrown");
+        } finally {
+            finallyRuns += 10;
+        }
+    }
+
+    static int returnThroughFinally() {
+        int result = 1;
+        try {
+            return result;
+        } finally {
+            result = 2;
+            finallyRuns += 100;
+        }
+    }
+}
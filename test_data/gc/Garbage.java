@@ -0,0 +1,20 @@
+package gc;
+
+/** Allocates far more than the heap limit but only keeps a few arrays alive. */
+public class Garbage {
+    static int[] last;
+    static long sum;
+
+    static {
+        int[][] keep = new int[4][];
+        for (int i = 0; i < 2000; i++) {
+            int[] array = new int[1024];
+            array[0] = i;
+            keep[i % 4] = array;
+            last = array;
+        }
+        for (int[] array : keep) {
+            sum += array[0];
+        }
+    }
+}
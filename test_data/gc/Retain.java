@@ -0,0 +1,20 @@
+package gc;
+
+/** Keeps every allocation reachable until the heap limit is hit. */
+public class Retain {
+    static Object[] all = new Object[1000];
+    static int count;
+    static String message;
+
+    static {
+        try {
+            while (true) {
+                all[count] = new int[1024];
+                count++;
+            }
+        } catch (OutOfMemoryError e) {
+            all = null;
+            message = e.getMessage();
+        }
+    }
+}
@@ -0,0 +1,57 @@
+package indy;
+
+/** String concatenation through StringConcatFactory, compiled for Java 9+. */
+public class Concat {
+    static class Point {
+        int x;
+        int y;
+
+        Point(int x, int y) {
+            this.x = x;
+            this.y = y;
+        }
+
+        public String toString() {
+            return "(" + x + ", " + y + ")";
+        }
+    }
+
+    static String primitives;
+    static String floats;
+    static String nulls;
+    static String objects;
+    static String tags;
+    static String strings;
+
+    static {
+        int i = -7;
+        long l = 1234567890123L;
+        char c = 'x';
+        boolean z = true;
+        byte b = 1;
+        short s = 2;
+        primitives = "i=" + i + " l=" + l + " c=" + c + " z=" + z + " b=" + b + s;
+
+        float f = 1.5f;
+        float tenth = 0.1f;
+        double d = 1e10;
+        double small = 0.0001;
+        double hundred = 100;
+        double nan = 0.0 / 0.0;
+        double negZero = -0.0;
+        float inf = 1 / 0.0f;
+        floats = f + " " + tenth + " " + d + " " + small + " " + hundred + " " + nan + " "
+                + negZero + " " + inf;
+
+        Object o = null;
+        String str = null;
+        nulls = "o=" + o + " s=" + str;
+
+        objects = "p=" + new Point(1, 2) + "!";
+
+        tags = "\u0001" + i + "\u0002";
+
+        String hello = "hello";
+        strings = hello + hello;
+    }
+}
@@ -0,0 +1,102 @@
+package indy;
+
+/**
+ * Lambdas and method references compiled for Java 8, only using interfaces
+ * declared here so no JDK class has to be loaded.
+ */
+public class Lambdas {
+    interface IntOp {
+        int apply(int a, int b);
+    }
+
+    interface IntFn {
+        int apply(int n);
+    }
+
+    interface ToInt<T> {
+        int apply(T t);
+    }
+
+    interface Maker<T> {
+        T make(int value);
+    }
+
+    interface IntToLong {
+        long apply(int n);
+    }
+
+    interface Action {
+        void run();
+    }
+
+    static class Counter {
+        int value;
+
+        Counter(int value) {
+            this.value = value;
+        }
+
+        int get() {
+            return value;
+        }
+
+        int add(int n) {
+            return value + n;
+        }
+
+        int capturingThis() {
+            IntFn f = n -> n + value;
+            return f.apply(1);
+        }
+    }
+
+    static int capturing;
+    static int staticRef;
+    static int boundRef;
+    static int unboundRef;
+    static int constructorRef;
+    static int thisRef;
+    static long widening;
+    static int sameCallSite;
+    static int sideEffect;
+
+    static int sub(int a, int b) {
+        return a - b;
+    }
+
+    static int square(int n) {
+        return n * n;
+    }
+
+    static {
+        int base = 10;
+        IntOp add = (a, b) -> a + b + base;
+        capturing = add.apply(1, 2);
+
+        IntOp sub = Lambdas::sub;
+        staticRef = sub.apply(10, 3);
+
+        IntFn bound = new Counter(5)::add;
+        boundRef = bound.apply(4);
+
+        ToInt<Counter> unbound = Counter::get;
+        unboundRef = unbound.apply(new Counter(42));
+
+        Maker<Counter> maker = Counter::new;
+        constructorRef = maker.make(8).get();
+
+        thisRef = new Counter(3).capturingThis();
+
+        IntToLong square = Lambdas::square;
+        widening = square.apply(100000);
+
+        for (int i = 0; i < 3; i++) {
+            int k = i;
+            IntFn f = n -> n + k;
+            sameCallSite += f.apply(10);
+        }
+
+        Action action = () -> sideEffect = 99;
+        action.run();
+    }
+}
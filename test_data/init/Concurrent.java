@@ -0,0 +1,49 @@
+package init;
+
+/** Two threads use a class whose initialization takes a while. */
+public class Concurrent {
+    static int first;
+    static int second;
+
+    public static void main(String[] args) throws InterruptedException {
+        Reader first = new Reader(true);
+        Reader second = new Reader(false);
+        first.start();
+        second.start();
+        first.join();
+        second.join();
+    }
+}
+
+class Slow {
+    static int runs;
+    static int value;
+
+    static {
+        runs++;
+        try {
+            Thread.sleep(50);
+        } catch (InterruptedException e) {
+            // initialized anyway
+        }
+        value = 42;
+    }
+}
+
+class Reader extends Thread {
+    private final boolean first;
+
+    Reader(boolean first) {
+        super("reader");
+        this.first = first;
+    }
+
+    @Override
+    public void run() {
+        if (first) {
+            Concurrent.first = Slow.value;
+        } else {
+            Concurrent.second = Slow.value;
+        }
+    }
+}
@@ -0,0 +1,62 @@
+package init;
+
+/** Uses classes whose static initializers throw. */
+public class Failures {
+    static boolean wrapped;
+    static boolean notInitialized;
+    static String notInitializedMessage;
+    static boolean superFailed;
+    static boolean errorNotWrapped;
+
+    static boolean fail() {
+        return true;
+    }
+
+    public static void main(String[] args) {
+        try {
+            Failing.touch();
+        } catch (ExceptionInInitializerError e) {
+            wrapped = e.getException() instanceof RuntimeException;
+        }
+        try {
+            Failing.touch();
+        } catch (NoClassDefFoundError e) {
+            notInitialized = true;
+            notInitializedMessage = e.getMessage();
+        }
+        try {
+            new FailingChild();
+        } catch (NoClassDefFoundError e) {
+            superFailed = true;
+        }
+        try {
+            ThrowsError.touch();
+        } catch (ExceptionInInitializerError e) {
+            errorNotWrapped = false;
+        } catch (Error e) {
+            errorNotWrapped = true;
+        }
+    }
+}
+
+class Failing {
+    static {
+        if (Failures.fail()) {
+            throw new RuntimeException("boom");
+        }
+    }
+
+    static void touch() {}
+}
+
+class FailingChild extends Failing {}
+
+class ThrowsError {
+    static {
+        if (Failures.fail()) {
+            throw new Error("not wrapped");
+        }
+    }
+
+    static void touch() {}
+}
@@ -0,0 +1,51 @@
+package init;
+
+/** Records the order classes are initialized in. */
+public class Order {
+    static int counter;
+    static int parent;
+    static int withDefault;
+    static int withoutDefault;
+    static int child;
+    static int cycle;
+
+    static int next() {
+        return ++counter;
+    }
+
+    public static void main(String[] args) {
+        new Child();
+        cycle = Cycle.seen;
+    }
+}
+
+class Parent {
+    static {
+        Order.parent = Order.next();
+    }
+}
+
+interface WithDefault {
+    int ORDER = Order.withDefault = Order.next();
+
+    default void m() {}
+}
+
+interface WithoutDefault {
+    int ORDER = Order.withoutDefault = Order.next();
+}
+
+class Child extends Parent implements WithoutDefault, WithDefault {
+    static {
+        Order.child = Order.next();
+    }
+}
+
+/** Sees `Cycle.seen` before it's set, as `Cycle` is being initialized. */
+class Cycle {
+    static int seen = CycleOther.seen + 1;
+}
+
+class CycleOther {
+    static int seen = Cycle.seen + 1;
+}
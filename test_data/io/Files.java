@@ -0,0 +1,103 @@
+package io;
+
+import java.io.File;
+import java.io.FileInputStream;
+import java.io.FileNotFoundException;
+import java.io.FileOutputStream;
+import java.io.IOException;
+import java.io.RandomAccessFile;
+
+/** Streams into and out of files and file operations, on the paths in `args`. */
+public class Files {
+    static int available;
+    static int firstByte;
+    static long skipped;
+    static byte[] rest;
+    static int restRead;
+    static int atEnd;
+    static boolean outOfBounds;
+    static String closed;
+    static String notFound;
+    static long filePointer;
+    static long length;
+    static long truncatedPointer;
+    static byte[] patched;
+    static boolean isFile;
+    static long fileLength;
+    static boolean isDirectory;
+    static boolean canRead;
+    static boolean created;
+    static boolean createdAgain;
+    static boolean madeDirectory;
+    static String[] listed;
+    static String canonical;
+    static long lastModified;
+    static boolean renamed;
+    static boolean deleted;
+
+    public static void main(String[] args) throws IOException {
+        String dir = args[0];
+        String path = args[1];
+
+        FileOutputStream out = new FileOutputStream(path, false);
+        byte[] hippo = {'h', 'i', 'p', 'p', 'o'};
+        out.write(hippo, 0, hippo.length);
+        out.close();
+        out = new FileOutputStream(path, true);
+        out.write('!');
+        out.close();
+
+        FileInputStream in = new FileInputStream(path);
+        available = in.available();
+        firstByte = in.read();
+        skipped = in.skip(2);
+        rest = new byte[8];
+        restRead = in.read(rest, 1, 7);
+        atEnd = in.read(rest, 0, 1);
+        try {
+            in.read(rest, 4, 5);
+        } catch (IndexOutOfBoundsException e) {
+            outOfBounds = true;
+        }
+        in.close();
+        try {
+            in.read();
+        } catch (IOException e) {
+            closed = e.getMessage();
+        }
+        try {
+            new FileInputStream(args[2]);
+        } catch (FileNotFoundException e) {
+            notFound = e.getMessage();
+        }
+
+        RandomAccessFile file = new RandomAccessFile(path, "rw");
+        file.seek(1);
+        file.write('a');
+        filePointer = file.getFilePointer();
+        length = file.length();
+        file.seek(5);
+        file.setLength(3);
+        truncatedPointer = file.getFilePointer();
+        file.seek(0);
+        patched = new byte[3];
+        file.read(patched, 0, patched.length);
+        file.close();
+
+        File data = new File(path);
+        isFile = data.isFile() && data.exists();
+        fileLength = data.length();
+        isDirectory = new File(dir).isDirectory() && !data.isDirectory();
+        canRead = data.canRead();
+        created = new File(args[3]).createNewFile();
+        createdAgain = new File(args[3]).createNewFile();
+        madeDirectory = new File(args[4]).mkdir();
+        listed = new File(dir).list();
+        canonical = new File(args[5]).getCanonicalPath();
+        data.setLastModified(1234000);
+        lastModified = data.lastModified();
+        File moved = new File(args[6]);
+        renamed = data.renameTo(moved) && moved.exists() && !data.exists();
+        deleted = moved.delete() && new File(args[4]).delete() && !moved.exists();
+    }
+}
@@ -0,0 +1,7 @@
+package java.io;
+
+public class EOFException extends IOException {
+    public EOFException(String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,80 @@
+package java.io;
+
+/** Just enough of java.io.File for hippo tests without a JDK. */
+public class File {
+    private static final UnixFileSystem fs = new UnixFileSystem();
+
+    private final String path;
+
+    public File(String pathname) {
+        path = pathname;
+    }
+
+    public String getPath() {
+        return path;
+    }
+
+    public boolean exists() {
+        return (fs.getBooleanAttributes0(this) & UnixFileSystem.BA_EXISTS) != 0;
+    }
+
+    public boolean isFile() {
+        return (fs.getBooleanAttributes0(this) & UnixFileSystem.BA_REGULAR) != 0;
+    }
+
+    public boolean isDirectory() {
+        return (fs.getBooleanAttributes0(this) & UnixFileSystem.BA_DIRECTORY) != 0;
+    }
+
+    public boolean canRead() {
+        return fs.checkAccess(this, UnixFileSystem.ACCESS_READ);
+    }
+
+    public boolean canWrite() {
+        return fs.checkAccess(this, UnixFileSystem.ACCESS_WRITE);
+    }
+
+    public long length() {
+        return fs.getLength(this);
+    }
+
+    public long lastModified() {
+        return fs.getLastModifiedTime(this);
+    }
+
+    public boolean setLastModified(long time) {
+        return fs.setLastModifiedTime(this, time);
+    }
+
+    public boolean setReadOnly() {
+        return fs.setReadOnly(this);
+    }
+
+    public boolean setWritable(boolean writable) {
+        return fs.setPermission(this, UnixFileSystem.ACCESS_WRITE, writable, true);
+    }
+
+    public String getCanonicalPath() throws IOException {
+        return fs.canonicalize0(path);
+    }
+
+    public boolean createNewFile() throws IOException {
+        return fs.createFileExclusively(path);
+    }
+
+    public boolean delete() {
+        return fs.delete0(this);
+    }
+
+    public String[] list() {
+        return fs.list(this);
+    }
+
+    public boolean mkdir() {
+        return fs.createDirectory(this);
+    }
+
+    public boolean renameTo(File dest) {
+        return fs.rename0(this, dest);
+    }
+}
@@ -0,0 +1,28 @@
+package java.io;
+
+/** Just enough of java.io.FileDescriptor for hippo tests without a JDK. */
+public final class FileDescriptor {
+    private int fd;
+
+    public static final FileDescriptor in = new FileDescriptor(0);
+    public static final FileDescriptor out = new FileDescriptor(1);
+    public static final FileDescriptor err = new FileDescriptor(2);
+
+    private static native void initIDs();
+
+    static {
+        initIDs();
+    }
+
+    public FileDescriptor() {
+        fd = -1;
+    }
+
+    private FileDescriptor(int fd) {
+        this.fd = fd;
+    }
+
+    public boolean valid() {
+        return fd != -1;
+    }
+}
@@ -0,0 +1,45 @@
+package java.io;
+
+/** Just enough of java.io.FileInputStream for hippo tests without a JDK. */
+public class FileInputStream {
+    private final FileDescriptor fd;
+
+    private static native void initIDs();
+
+    static {
+        initIDs();
+    }
+
+    public FileInputStream(String name) throws FileNotFoundException {
+        fd = new FileDescriptor();
+        open0(name);
+    }
+
+    public FileInputStream(FileDescriptor fdObj) {
+        fd = fdObj;
+    }
+
+    private native void open0(String name) throws FileNotFoundException;
+
+    private native int read0() throws IOException;
+
+    private native int readBytes(byte[] b, int off, int len) throws IOException;
+
+    private native void close0() throws IOException;
+
+    public int read() throws IOException {
+        return read0();
+    }
+
+    public int read(byte[] b, int off, int len) throws IOException {
+        return readBytes(b, off, len);
+    }
+
+    public native long skip(long n) throws IOException;
+
+    public native int available() throws IOException;
+
+    public void close() throws IOException {
+        close0();
+    }
+}
@@ -0,0 +1,45 @@
+package java.io;
+
+/** Just enough of java.io.FileOutputStream for hippo tests without a JDK. */
+public class FileOutputStream {
+    private final FileDescriptor fd;
+    private final boolean append;
+
+    private static native void initIDs();
+
+    static {
+        initIDs();
+    }
+
+    public FileOutputStream(String name, boolean append) throws FileNotFoundException {
+        fd = new FileDescriptor();
+        this.append = append;
+        open0(name, append);
+    }
+
+    public FileOutputStream(FileDescriptor fdObj) {
+        fd = fdObj;
+        append = false;
+    }
+
+    private native void open0(String name, boolean append) throws FileNotFoundException;
+
+    private native void write(int b, boolean append) throws IOException;
+
+    private native void writeBytes(byte[] b, int off, int len, boolean append)
+            throws IOException;
+
+    private native void close0() throws IOException;
+
+    public void write(int b) throws IOException {
+        write(b, append);
+    }
+
+    public void write(byte[] b, int off, int len) throws IOException {
+        writeBytes(b, off, len, append);
+    }
+
+    public void close() throws IOException {
+        close0();
+    }
+}
@@ -0,0 +1,7 @@
+package java.io;
+
+public class IOException extends Exception {
+    public IOException(String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,33 @@
+package java.io;
+
+/** Just enough of java.io.InputStream for hippo tests without a JDK. */
+public abstract class InputStream {
+    public abstract int read() throws IOException;
+
+    public int read(byte[] b, int off, int len) throws IOException {
+        if (len == 0) {
+            return 0;
+        }
+        int c = read();
+        if (c == -1) {
+            return -1;
+        }
+        b[off] = (byte) c;
+        int i = 1;
+        for (; i < len; i++) {
+            c = read();
+            if (c == -1) {
+                break;
+            }
+            b[off + i] = (byte) c;
+        }
+        return i;
+    }
+
+    public int available() throws IOException {
+        return 0;
+    }
+
+    public void close() throws IOException {
+    }
+}
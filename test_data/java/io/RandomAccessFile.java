@@ -0,0 +1,67 @@
+package java.io;
+
+/** Just enough of java.io.RandomAccessFile for hippo tests without a JDK. */
+public class RandomAccessFile {
+    private static final int O_RDONLY = 1;
+    private static final int O_RDWR = 2;
+
+    private final FileDescriptor fd;
+
+    private static native void initIDs();
+
+    static {
+        initIDs();
+    }
+
+    public RandomAccessFile(String name, String mode) throws FileNotFoundException {
+        fd = new FileDescriptor();
+        open0(name, mode.equals("rw") ? O_RDWR : O_RDONLY);
+    }
+
+    private native void open0(String name, int mode) throws FileNotFoundException;
+
+    private native int read0() throws IOException;
+
+    private native int readBytes(byte[] b, int off, int len) throws IOException;
+
+    private native void write0(int b) throws IOException;
+
+    private native void writeBytes(byte[] b, int off, int len) throws IOException;
+
+    private native void seek0(long pos) throws IOException;
+
+    private native void close0() throws IOException;
+
+    public int read() throws IOException {
+        return read0();
+    }
+
+    public int read(byte[] b, int off, int len) throws IOException {
+        return readBytes(b, off, len);
+    }
+
+    public void write(int b) throws IOException {
+        write0(b);
+    }
+
+    public void write(byte[] b, int off, int len) throws IOException {
+        writeBytes(b, off, len);
+    }
+
+    public native long getFilePointer() throws IOException;
+
+    public void seek(long pos) throws IOException {
+        if (pos < 0) {
+            throw new IOException("Negative seek offset");
+        }
+        seek0(pos);
+    }
+
+    public native long length() throws IOException;
+
+    public native void setLength(long newLength) throws IOException;
+
+    public void close() throws IOException {
+        close0();
+    }
+}
@@ -0,0 +1,44 @@
+package java.io;
+
+/** Just enough of java.io.UnixFileSystem for hippo tests without a JDK. */
+class UnixFileSystem {
+    static final int BA_EXISTS = 0x01;
+    static final int BA_REGULAR = 0x02;
+    static final int BA_DIRECTORY = 0x04;
+    static final int ACCESS_READ = 0x04;
+    static final int ACCESS_WRITE = 0x02;
+
+    private static native void initIDs();
+
+    static {
+        initIDs();
+    }
+
+    native String canonicalize0(String path) throws IOException;
+
+    native int getBooleanAttributes0(File f);
+
+    native boolean checkAccess(File f, int access);
+
+    native long getLastModifiedTime(File f);
+
+    native long getLength(File f);
+
+    native boolean setPermission(File f, int access, boolean enable, boolean owneronly);
+
+    native boolean createFileExclusively(String path) throws IOException;
+
+    native boolean delete0(File f);
+
+    native String[] list(File f);
+
+    native boolean createDirectory(File f);
+
+    native boolean rename0(File f1, File f2);
+
+    native boolean setLastModifiedTime(File f, long time);
+
+    native boolean setReadOnly(File f);
+
+    native long getSpace(File f, int t);
+}
@@ -0,0 +1,7 @@
+package java.lang;
+
+public class AbstractMethodError extends IncompatibleClassChangeError {
+    public AbstractMethodError(String message) {
+        super(message);
+    }
+}
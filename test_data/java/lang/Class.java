@@ -0,0 +1,65 @@
+package java.lang;
+
+/** Just enough of java.lang.Class for hippo tests without a JDK. */
+public final class Class<T> {
+    private static native void registerNatives();
+
+    static {
+        registerNatives();
+    }
+
+    private transient String name;
+
+    private Class() {
+    }
+
+    public String getName() {
+        String name = this.name;
+        if (name == null) {
+            this.name = name = getName0();
+        }
+        return name;
+    }
+
+    private native String getName0();
+
+    public native Class<? super T> getSuperclass();
+
+    public native boolean isInterface();
+
+    public native boolean isArray();
+
+    public native boolean isPrimitive();
+
+    public native Class<?> getComponentType();
+
+    public native boolean isAssignableFrom(Class<?> cls);
+
+    public native int getModifiers();
+
+    public Class<?>[] getInterfaces() {
+        return getInterfaces0();
+    }
+
+    public java.lang.reflect.Field[] getDeclaredFields() {
+        return getDeclaredFields0(false);
+    }
+
+    public java.lang.reflect.Method[] getDeclaredMethods() {
+        return getDeclaredMethods0(false);
+    }
+
+    public java.lang.reflect.Constructor<?>[] getDeclaredConstructors() {
+        return getDeclaredConstructors0(false);
+    }
+
+    private native Class<?>[] getInterfaces0();
+
+    private native java.lang.reflect.Field[] getDeclaredFields0(boolean publicOnly);
+
+    private native java.lang.reflect.Method[] getDeclaredMethods0(boolean publicOnly);
+
+    private native java.lang.reflect.Constructor<T>[] getDeclaredConstructors0(boolean publicOnly);
+
+    static native Class<?> getPrimitiveClass(String name);
+}
@@ -0,0 +1,30 @@
+package java.lang;
+
+/**
+ * Just enough of java.lang.ClassLoader for hippo tests without a JDK, it only
+ * loads native libraries by their absolute path.
+ */
+public abstract class ClassLoader {
+    static void loadLibrary(String name) {
+        NativeLibrary library = new NativeLibrary(name);
+        library.load(name, false);
+        if (!library.loaded) {
+            throw new UnsatisfiedLinkError(name);
+        }
+    }
+
+    static class NativeLibrary {
+        long handle;
+        private int jniVersion;
+        String name;
+        boolean loaded;
+
+        NativeLibrary(String name) {
+            this.name = name;
+        }
+
+        native void load(String name, boolean isBuiltin);
+
+        native long find(String name);
+    }
+}
@@ -0,0 +1,7 @@
+package java.lang;
+
+public class Exception extends Throwable {
+    public Exception(String message) {
+        super(message);
+    }
+}
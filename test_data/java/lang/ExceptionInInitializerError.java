@@ -0,0 +1,14 @@
+package java.lang;
+
+public class ExceptionInInitializerError extends LinkageError {
+    private Throwable exception;
+
+    public ExceptionInInitializerError(Throwable thrown) {
+        super(null);
+        exception = thrown;
+    }
+
+    public Throwable getException() {
+        return exception;
+    }
+}
@@ -0,0 +1,7 @@
+package java.lang;
+
+public class IllegalStateException extends RuntimeException {
+    public IllegalStateException(String message) {
+        super(message);
+    }
+}
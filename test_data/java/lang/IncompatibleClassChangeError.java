@@ -0,0 +1,7 @@
+package java.lang;
+
+public class IncompatibleClassChangeError extends LinkageError {
+    public IncompatibleClassChangeError(String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,7 @@
+package java.lang;
+
+public class IndexOutOfBoundsException extends RuntimeException {
+    public IndexOutOfBoundsException(String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,20 @@
+package java.lang;
+
+/** Just enough of java.lang.Integer for hippo tests without a JDK. */
+public final class Integer {
+    public static final Class<Integer> TYPE = (Class<Integer>) Class.getPrimitiveClass("int");
+
+    private final int value;
+
+    public Integer(int value) {
+        this.value = value;
+    }
+
+    public static Integer valueOf(int value) {
+        return new Integer(value);
+    }
+
+    public int intValue() {
+        return value;
+    }
+}
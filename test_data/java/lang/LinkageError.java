@@ -0,0 +1,7 @@
+package java.lang;
+
+public class LinkageError extends Error {
+    public LinkageError(String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,20 @@
+package java.lang;
+
+/** Just enough of java.lang.Long for hippo tests without a JDK. */
+public final class Long {
+    public static final Class<Long> TYPE = (Class<Long>) Class.getPrimitiveClass("long");
+
+    private final long value;
+
+    public Long(long value) {
+        this.value = value;
+    }
+
+    public static Long valueOf(long value) {
+        return new Long(value);
+    }
+
+    public long longValue() {
+        return value;
+    }
+}
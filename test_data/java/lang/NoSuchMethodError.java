@@ -0,0 +1,7 @@
+package java.lang;
+
+public class NoSuchMethodError extends IncompatibleClassChangeError {
+    public NoSuchMethodError(String message) {
+        super(message);
+    }
+}
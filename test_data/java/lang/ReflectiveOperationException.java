@@ -0,0 +1,7 @@
+package java.lang;
+
+public class ReflectiveOperationException extends Exception {
+    public ReflectiveOperationException(String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,7 @@
+package java.lang;
+
+public class RuntimeException extends Exception {
+    public RuntimeException(String message) {
+        super(message);
+    }
+}
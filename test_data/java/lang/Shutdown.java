@@ -0,0 +1,6 @@
+package java.lang;
+
+/** Just the native the launcher tests exit through. */
+public class Shutdown {
+    public static native void halt0(int status);
+}
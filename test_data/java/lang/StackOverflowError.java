@@ -0,0 +1,11 @@
+package java.lang;
+
+public class StackOverflowError extends VirtualMachineError {
+    public StackOverflowError() {
+        super(null);
+    }
+
+    public StackOverflowError(String message) {
+        super(message);
+    }
+}
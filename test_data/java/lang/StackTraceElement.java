@@ -0,0 +1,33 @@
+package java.lang;
+
+/** Just enough of java.lang.StackTraceElement for hippo tests without a JDK. */
+public final class StackTraceElement {
+    private String declaringClass;
+    private String methodName;
+    private String fileName;
+    private int lineNumber;
+
+    public StackTraceElement(String declaringClass, String methodName, String fileName,
+            int lineNumber) {
+        this.declaringClass = declaringClass;
+        this.methodName = methodName;
+        this.fileName = fileName;
+        this.lineNumber = lineNumber;
+    }
+
+    public String getClassName() {
+        return declaringClass;
+    }
+
+    public String getMethodName() {
+        return methodName;
+    }
+
+    public String getFileName() {
+        return fileName;
+    }
+
+    public int getLineNumber() {
+        return lineNumber;
+    }
+}
@@ -0,0 +1,58 @@
+package java.lang;
+
+/**
+ * Just enough of java.lang.String for hippo tests without a JDK. Compile with
+ * `javac --patch-module java.base=. -d . java/lang/String.java`.
+ */
+public final class String {
+    char[] value;
+    int hash;
+
+    public String(char[] value) {
+        this.value = new char[value.length];
+        for (int i = 0; i < value.length; i++) {
+            this.value[i] = value[i];
+        }
+    }
+
+    public int length() {
+        return value.length;
+    }
+
+    public char[] toCharArray() {
+        char[] result = new char[value.length];
+        for (int i = 0; i < value.length; i++) {
+            result[i] = value[i];
+        }
+        return result;
+    }
+
+    public native String intern();
+
+    public static String valueOf(Object obj) {
+        return obj == null ? "null" : obj.toString();
+    }
+
+    public boolean equals(Object anObject) {
+        if (this == anObject) {
+            return true;
+        }
+        if (!(anObject instanceof String)) {
+            return false;
+        }
+        char[] other = ((String) anObject).value;
+        if (other.length != value.length) {
+            return false;
+        }
+        for (int i = 0; i < value.length; i++) {
+            if (value[i] != other[i]) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    public String toString() {
+        return this;
+    }
+}
@@ -0,0 +1,19 @@
+package java.lang;
+
+/** Just enough of java.lang.System for hippo tests without a JDK. */
+public final class System {
+    private static native void registerNatives();
+
+    static {
+        registerNatives();
+    }
+
+    private System() {
+    }
+
+    public static void load(String filename) {
+        ClassLoader.loadLibrary(filename);
+    }
+
+    public static native String mapLibraryName(String libname);
+}
@@ -0,0 +1,55 @@
+package java.lang;
+
+/** Just enough of java.lang.Thread for hippo tests without a JDK. */
+public class Thread {
+    private static native void registerNatives();
+
+    static {
+        registerNatives();
+    }
+
+    private volatile String name;
+    private boolean daemon;
+    private volatile int threadStatus;
+
+    public Thread(String name) {
+        this.name = name;
+    }
+
+    public void run() {
+    }
+
+    public synchronized void start() {
+        start0();
+    }
+
+    public final void setDaemon(boolean on) {
+        daemon = on;
+    }
+
+    public final synchronized void join() throws InterruptedException {
+        while (isAlive()) {
+            wait(0);
+        }
+    }
+
+    public void interrupt() {
+        interrupt0();
+    }
+
+    public boolean isInterrupted() {
+        return isInterrupted(false);
+    }
+
+    public final native boolean isAlive();
+
+    public static native void sleep(long millis) throws InterruptedException;
+
+    public static native void yield();
+
+    private native void start0();
+
+    private native void interrupt0();
+
+    private native boolean isInterrupted(boolean clearInterrupted);
+}
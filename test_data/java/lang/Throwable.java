@@ -0,0 +1,38 @@
+package java.lang;
+
+/** Just enough of java.lang.Throwable for hippo tests without a JDK. */
+public class Throwable {
+    private transient Object backtrace;
+    private String detailMessage;
+    private StackTraceElement[] stackTrace;
+
+    public Throwable(String message) {
+        fillInStackTrace();
+        detailMessage = message;
+    }
+
+    public String getMessage() {
+        return detailMessage;
+    }
+
+    public synchronized Throwable fillInStackTrace() {
+        stackTrace = null;
+        return fillInStackTrace(0);
+    }
+
+    private native Throwable fillInStackTrace(int dummy);
+
+    public StackTraceElement[] getStackTrace() {
+        if (stackTrace == null) {
+            stackTrace = new StackTraceElement[getStackTraceDepth()];
+            for (int i = 0; i < stackTrace.length; i++) {
+                stackTrace[i] = getStackTraceElement(i);
+            }
+        }
+        return stackTrace;
+    }
+
+    native int getStackTraceDepth();
+
+    native StackTraceElement getStackTraceElement(int index);
+}
@@ -0,0 +1,7 @@
+package java.lang;
+
+public class VerifyError extends LinkageError {
+    public VerifyError(String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,33 @@
+package java.lang.reflect;
+
+import sun.reflect.NativeConstructorAccessorImpl;
+
+/** Just enough of java.lang.reflect.Constructor for hippo tests without a JDK. */
+public final class Constructor<T> {
+    private Class<T> clazz;
+    private int slot;
+    private Class<?>[] parameterTypes;
+    private Class<?>[] exceptionTypes;
+    private int modifiers;
+    private transient String signature;
+    private byte[] annotations;
+
+    private Constructor() {
+    }
+
+    public Class<T> getDeclaringClass() {
+        return clazz;
+    }
+
+    public Class<?>[] getParameterTypes() {
+        return parameterTypes;
+    }
+
+    public int getModifiers() {
+        return modifiers;
+    }
+
+    public T newInstance(Object... initargs) throws InstantiationException, InvocationTargetException {
+        return (T) new NativeConstructorAccessorImpl(this).newInstance(initargs);
+    }
+}
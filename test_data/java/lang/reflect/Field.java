@@ -0,0 +1,69 @@
+package java.lang.reflect;
+
+import sun.misc.Unsafe;
+
+/**
+ * Just enough of java.lang.reflect.Field for hippo tests without a JDK. It
+ * accesses fields through Unsafe, like the field accessors of the JDK do.
+ */
+public final class Field {
+    private Class<?> clazz;
+    private int slot;
+    private String name;
+    private Class<?> type;
+    private int modifiers;
+    private transient String signature;
+    private byte[] annotations;
+
+    private Field() {
+    }
+
+    public Class<?> getDeclaringClass() {
+        return clazz;
+    }
+
+    public String getName() {
+        return name;
+    }
+
+    public Class<?> getType() {
+        return type;
+    }
+
+    public int getModifiers() {
+        return modifiers;
+    }
+
+    public Object get(Object obj) {
+        return Unsafe.getUnsafe().getObject(base(obj), offset());
+    }
+
+    public void set(Object obj, Object value) {
+        Unsafe.getUnsafe().putObject(base(obj), offset(), value);
+    }
+
+    public int getInt(Object obj) {
+        return Unsafe.getUnsafe().getInt(base(obj), offset());
+    }
+
+    public void setInt(Object obj, int value) {
+        Unsafe.getUnsafe().putInt(base(obj), offset(), value);
+    }
+
+    private boolean isStatic() {
+        return (modifiers & 0x0008) != 0;
+    }
+
+    private Object base(Object obj) {
+        if (!isStatic()) {
+            return obj;
+        }
+        Unsafe.getUnsafe().ensureClassInitialized(clazz);
+        return Unsafe.getUnsafe().staticFieldBase(this);
+    }
+
+    private long offset() {
+        Unsafe unsafe = Unsafe.getUnsafe();
+        return isStatic() ? unsafe.staticFieldOffset(this) : unsafe.objectFieldOffset(this);
+    }
+}
@@ -0,0 +1,15 @@
+package java.lang.reflect;
+
+/** Just enough of java.lang.reflect.InvocationTargetException for hippo tests without a JDK. */
+public class InvocationTargetException extends ReflectiveOperationException {
+    private Throwable target;
+
+    public InvocationTargetException(Throwable target) {
+        super(null);
+        this.target = target;
+    }
+
+    public Throwable getTargetException() {
+        return target;
+    }
+}
@@ -0,0 +1,47 @@
+package java.lang.reflect;
+
+import sun.reflect.NativeMethodAccessorImpl;
+
+/** Just enough of java.lang.reflect.Method for hippo tests without a JDK. */
+public final class Method {
+    private Class<?> clazz;
+    private int slot;
+    private String name;
+    private Class<?> returnType;
+    private Class<?>[] parameterTypes;
+    private Class<?>[] exceptionTypes;
+    private int modifiers;
+    private transient String signature;
+    private byte[] annotations;
+
+    private Method() {
+    }
+
+    public Class<?> getDeclaringClass() {
+        return clazz;
+    }
+
+    public String getName() {
+        return name;
+    }
+
+    public Class<?> getReturnType() {
+        return returnType;
+    }
+
+    public Class<?>[] getParameterTypes() {
+        return parameterTypes;
+    }
+
+    public Class<?>[] getExceptionTypes() {
+        return exceptionTypes;
+    }
+
+    public int getModifiers() {
+        return modifiers;
+    }
+
+    public Object invoke(Object obj, Object... args) throws InvocationTargetException {
+        return new NativeMethodAccessorImpl(this).invoke(obj, args);
+    }
+}
@@ -0,0 +1,20 @@
+package java.util.jar;
+
+import java.io.File;
+import java.io.IOException;
+import java.util.zip.ZipFile;
+
+/** Just enough of java.util.jar.JarFile for hippo tests without a JDK. */
+public class JarFile extends ZipFile {
+    public static final String MANIFEST_NAME = "META-INF/MANIFEST.MF";
+
+    public JarFile(String name) throws IOException {
+        this(new File(name), OPEN_READ);
+    }
+
+    public JarFile(File file, int mode) throws IOException {
+        super(file, mode);
+    }
+
+    private native String[] getMetaInfEntryNames();
+}
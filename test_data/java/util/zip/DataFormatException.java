@@ -0,0 +1,7 @@
+package java.util.zip;
+
+public class DataFormatException extends Exception {
+    public DataFormatException(String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,129 @@
+package java.util.zip;
+
+/** Just enough of java.util.zip.Inflater for hippo tests without a JDK. */
+public class Inflater {
+    private final ZStreamRef zsRef;
+    private byte[] buf = defaultBuf;
+    private int off, len;
+    private boolean finished;
+    private boolean needDict;
+    private long bytesRead;
+    private long bytesWritten;
+
+    private static final byte[] defaultBuf = new byte[0];
+
+    static {
+        initIDs();
+    }
+
+    public Inflater(boolean nowrap) {
+        zsRef = new ZStreamRef(init(nowrap));
+    }
+
+    public Inflater() {
+        this(false);
+    }
+
+    public void setInput(byte[] b, int off, int len) {
+        if (b == null) {
+            throw new NullPointerException(null);
+        }
+        if (off < 0 || len < 0 || off > b.length - len) {
+            throw new ArrayIndexOutOfBoundsException(null);
+        }
+        synchronized (zsRef) {
+            this.buf = b;
+            this.off = off;
+            this.len = len;
+        }
+    }
+
+    public int getRemaining() {
+        synchronized (zsRef) {
+            return len;
+        }
+    }
+
+    public boolean needsInput() {
+        synchronized (zsRef) {
+            return len <= 0;
+        }
+    }
+
+    public boolean needsDictionary() {
+        synchronized (zsRef) {
+            return needDict;
+        }
+    }
+
+    public boolean finished() {
+        synchronized (zsRef) {
+            return finished;
+        }
+    }
+
+    public int inflate(byte[] b, int off, int len) throws DataFormatException {
+        if (b == null) {
+            throw new NullPointerException(null);
+        }
+        if (off < 0 || len < 0 || off > b.length - len) {
+            throw new ArrayIndexOutOfBoundsException(null);
+        }
+        synchronized (zsRef) {
+            ensureOpen();
+            int thisLen = this.len;
+            int n = inflateBytes(zsRef.address(), b, off, len);
+            bytesWritten += n;
+            bytesRead += (thisLen - this.len);
+            return n;
+        }
+    }
+
+    public long getBytesRead() {
+        synchronized (zsRef) {
+            return bytesRead;
+        }
+    }
+
+    public long getBytesWritten() {
+        synchronized (zsRef) {
+            return bytesWritten;
+        }
+    }
+
+    public void reset() {
+        synchronized (zsRef) {
+            ensureOpen();
+            reset(zsRef.address());
+            buf = defaultBuf;
+            finished = false;
+            needDict = false;
+            off = len = 0;
+            bytesRead = bytesWritten = 0;
+        }
+    }
+
+    public void end() {
+        synchronized (zsRef) {
+            long addr = zsRef.address();
+            zsRef.clear();
+            if (addr != 0) {
+                end(addr);
+                buf = null;
+            }
+        }
+    }
+
+    private void ensureOpen() {
+        if (zsRef.address() == 0) {
+            throw new NullPointerException("Inflater has been closed");
+        }
+    }
+
+    private native static void initIDs();
+    private native static long init(boolean nowrap);
+    private native int inflateBytes(long addr, byte[] b, int off, int len)
+            throws DataFormatException;
+    private native static void reset(long addr);
+    private native static void end(long addr);
+}
@@ -0,0 +1,81 @@
+package java.util.zip;
+
+import java.io.EOFException;
+import java.io.IOException;
+import java.io.InputStream;
+
+/** Just enough of java.util.zip.InflaterInputStream for hippo tests without a JDK. */
+public class InflaterInputStream extends InputStream {
+    protected InputStream in;
+    protected Inflater inf;
+    protected byte[] buf;
+    protected int len;
+
+    private boolean closed = false;
+    private boolean reachEOF = false;
+    private byte[] singleByteBuf = new byte[1];
+
+    public InflaterInputStream(InputStream in, Inflater inf, int size) {
+        this.in = in;
+        this.inf = inf;
+        buf = new byte[size];
+    }
+
+    public int read() throws IOException {
+        return read(singleByteBuf, 0, 1) == -1 ? -1 : singleByteBuf[0] & 0xff;
+    }
+
+    public int read(byte[] b, int off, int len) throws IOException {
+        ensureOpen();
+        if (len == 0) {
+            return 0;
+        }
+        try {
+            int n;
+            while ((n = inf.inflate(b, off, len)) == 0) {
+                if (inf.finished() || inf.needsDictionary()) {
+                    reachEOF = true;
+                    return -1;
+                }
+                if (inf.needsInput()) {
+                    fill();
+                }
+            }
+            return n;
+        } catch (DataFormatException e) {
+            String s = e.getMessage();
+            if (s == null) {
+                s = "Invalid ZLIB data format";
+            }
+            throw new ZipException(s);
+        }
+    }
+
+    public int available() throws IOException {
+        ensureOpen();
+        return reachEOF ? 0 : 1;
+    }
+
+    public void close() throws IOException {
+        if (!closed) {
+            inf.end();
+            in.close();
+            closed = true;
+        }
+    }
+
+    protected void fill() throws IOException {
+        ensureOpen();
+        len = in.read(buf, 0, buf.length);
+        if (len == -1) {
+            throw new EOFException("Unexpected end of ZLIB input stream");
+        }
+        inf.setInput(buf, 0, len);
+    }
+
+    private void ensureOpen() throws IOException {
+        if (closed) {
+            throw new IOException("Stream closed");
+        }
+    }
+}
@@ -0,0 +1,18 @@
+package java.util.zip;
+
+/** The address of a zlib stream, as in the JDK. */
+class ZStreamRef {
+    private volatile long address;
+
+    ZStreamRef(long address) {
+        this.address = address;
+    }
+
+    long address() {
+        return address;
+    }
+
+    void clear() {
+        address = 0;
+    }
+}
@@ -0,0 +1,50 @@
+package java.util.zip;
+
+/** Just enough of java.util.zip.ZipCoder for hippo tests without a JDK, UTF-8 only. */
+final class ZipCoder {
+    static byte[] getBytes(String s) {
+        char[] chars = s.toCharArray();
+        int len = 0;
+        for (int i = 0; i < chars.length; i++) {
+            char c = chars[i];
+            len += c < 0x80 ? 1 : c < 0x800 ? 2 : 3;
+        }
+        byte[] bytes = new byte[len];
+        int j = 0;
+        for (int i = 0; i < chars.length; i++) {
+            char c = chars[i];
+            if (c < 0x80) {
+                bytes[j++] = (byte) c;
+            } else if (c < 0x800) {
+                bytes[j++] = (byte) (0xc0 | (c >> 6));
+                bytes[j++] = (byte) (0x80 | (c & 0x3f));
+            } else {
+                bytes[j++] = (byte) (0xe0 | (c >> 12));
+                bytes[j++] = (byte) (0x80 | ((c >> 6) & 0x3f));
+                bytes[j++] = (byte) (0x80 | (c & 0x3f));
+            }
+        }
+        return bytes;
+    }
+
+    static String toString(byte[] bytes, int len) {
+        char[] chars = new char[len];
+        int n = 0;
+        for (int i = 0; i < len; n++) {
+            int b = bytes[i++] & 0xff;
+            if (b < 0x80) {
+                chars[n] = (char) b;
+            } else if (b < 0xe0) {
+                chars[n] = (char) (((b & 0x1f) << 6) | (bytes[i++] & 0x3f));
+            } else {
+                int b2 = bytes[i++] & 0x3f;
+                chars[n] = (char) (((b & 0x0f) << 12) | (b2 << 6) | (bytes[i++] & 0x3f));
+            }
+        }
+        char[] value = new char[n];
+        for (int i = 0; i < n; i++) {
+            value[i] = chars[i];
+        }
+        return new String(value);
+    }
+}
@@ -0,0 +1,52 @@
+package java.util.zip;
+
+/** Just enough of java.util.zip.ZipEntry for hippo tests without a JDK. */
+public class ZipEntry {
+    public static final int STORED = 0;
+    public static final int DEFLATED = 8;
+
+    String name;
+    long xdostime = -1;
+    long crc = -1;
+    long size = -1;
+    long csize = -1;
+    int method = -1;
+    int flag = 0;
+    byte[] extra;
+    String comment;
+
+    ZipEntry() {
+    }
+
+    public String getName() {
+        return name;
+    }
+
+    public long getSize() {
+        return size;
+    }
+
+    public long getCompressedSize() {
+        return csize;
+    }
+
+    public long getCrc() {
+        return crc;
+    }
+
+    public int getMethod() {
+        return method;
+    }
+
+    public byte[] getExtra() {
+        return extra;
+    }
+
+    public String getComment() {
+        return comment;
+    }
+
+    public String toString() {
+        return getName();
+    }
+}
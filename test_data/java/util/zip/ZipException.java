@@ -0,0 +1,9 @@
+package java.util.zip;
+
+import java.io.IOException;
+
+public class ZipException extends IOException {
+    public ZipException(String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,312 @@
+package java.util.zip;
+
+import java.io.EOFException;
+import java.io.File;
+import java.io.IOException;
+import java.io.InputStream;
+
+/** Just enough of java.util.zip.ZipFile for hippo tests without a JDK. */
+public class ZipFile {
+    private long jzfile;
+    private final String name;
+    private final int total;
+    private final boolean locsig;
+    private volatile boolean closeRequested = false;
+
+    private static final int STORED = ZipEntry.STORED;
+    private static final int DEFLATED = ZipEntry.DEFLATED;
+
+    public static final int OPEN_READ = 0x1;
+    public static final int OPEN_DELETE = 0x4;
+
+    private static final int JZENTRY_NAME = 0;
+    private static final int JZENTRY_EXTRA = 1;
+    private static final int JZENTRY_COMMENT = 2;
+
+    static {
+        initIDs();
+    }
+
+    private static native void initIDs();
+
+    public ZipFile(String name) throws IOException {
+        this(new File(name), OPEN_READ);
+    }
+
+    public ZipFile(File file, int mode) throws IOException {
+        String name = file.getPath();
+        jzfile = open(name, mode, file.lastModified(), false);
+        this.name = name;
+        this.total = getTotal(jzfile);
+        this.locsig = startsWithLOC(jzfile);
+    }
+
+    public String getComment() {
+        synchronized (this) {
+            ensureOpen();
+            byte[] bcomm = getCommentBytes(jzfile);
+            if (bcomm == null) {
+                return null;
+            }
+            return ZipCoder.toString(bcomm, bcomm.length);
+        }
+    }
+
+    public ZipEntry getEntry(String name) {
+        if (name == null) {
+            throw new NullPointerException("name");
+        }
+        long jzentry = 0;
+        synchronized (this) {
+            ensureOpen();
+            jzentry = getEntry(jzfile, ZipCoder.getBytes(name), true);
+            if (jzentry != 0) {
+                ZipEntry ze = getZipEntry(name, jzentry);
+                freeEntry(jzfile, jzentry);
+                return ze;
+            }
+        }
+        return null;
+    }
+
+    public InputStream getInputStream(ZipEntry entry) throws IOException {
+        if (entry == null) {
+            throw new NullPointerException("entry");
+        }
+        long jzentry = 0;
+        ZipFileInputStream in = null;
+        synchronized (this) {
+            ensureOpen();
+            jzentry = getEntry(jzfile, ZipCoder.getBytes(entry.name), false);
+            if (jzentry == 0) {
+                return null;
+            }
+            in = new ZipFileInputStream(jzentry);
+            switch (getEntryMethod(jzentry)) {
+            case STORED:
+                return in;
+            case DEFLATED:
+                long size = getEntrySize(jzentry) + 2;
+                if (size > 65536) {
+                    size = 8192;
+                }
+                if (size <= 0) {
+                    size = 4096;
+                }
+                return new ZipFileInflaterInputStream(in, new Inflater(true), (int) size);
+            default:
+                throw new ZipException("invalid compression method");
+            }
+        }
+    }
+
+    /** The entry at index `i` of the central directory, null past the last one. */
+    ZipEntry getEntry(int i) {
+        synchronized (this) {
+            ensureOpen();
+            long jzentry = getNextEntry(jzfile, i);
+            if (jzentry == 0) {
+                return null;
+            }
+            ZipEntry ze = getZipEntry(null, jzentry);
+            freeEntry(jzfile, jzentry);
+            return ze;
+        }
+    }
+
+    private ZipEntry getZipEntry(String name, long jzentry) {
+        ZipEntry e = new ZipEntry();
+        e.flag = getEntryFlag(jzentry);
+        if (name != null) {
+            e.name = name;
+        } else {
+            byte[] bname = getEntryBytes(jzentry, JZENTRY_NAME);
+            e.name = ZipCoder.toString(bname, bname.length);
+        }
+        e.xdostime = getEntryTime(jzentry);
+        e.crc = getEntryCrc(jzentry);
+        e.size = getEntrySize(jzentry);
+        e.csize = getEntryCSize(jzentry);
+        e.method = getEntryMethod(jzentry);
+        e.extra = getEntryBytes(jzentry, JZENTRY_EXTRA);
+        byte[] bcomm = getEntryBytes(jzentry, JZENTRY_COMMENT);
+        if (bcomm != null) {
+            e.comment = ZipCoder.toString(bcomm, bcomm.length);
+        }
+        return e;
+    }
+
+    public String getName() {
+        return name;
+    }
+
+    public int size() {
+        ensureOpen();
+        return total;
+    }
+
+    public void close() throws IOException {
+        if (closeRequested) {
+            return;
+        }
+        closeRequested = true;
+        synchronized (this) {
+            if (jzfile != 0) {
+                long zf = this.jzfile;
+                jzfile = 0;
+                close(zf);
+            }
+        }
+    }
+
+    private void ensureOpen() {
+        if (closeRequested) {
+            throw new IllegalStateException("zip file closed");
+        }
+        if (this.jzfile == 0) {
+            throw new IllegalStateException("The object is not initialized.");
+        }
+    }
+
+    private void ensureOpenOrZipException() throws IOException {
+        if (closeRequested) {
+            throw new ZipException("ZipFile closed");
+        }
+    }
+
+    private class ZipFileInflaterInputStream extends InflaterInputStream {
+        private volatile boolean closeRequested = false;
+        private boolean eof = false;
+        private final ZipFileInputStream zfin;
+
+        ZipFileInflaterInputStream(ZipFileInputStream zfin, Inflater inf, int size) {
+            super(zfin, inf, size);
+            this.zfin = zfin;
+        }
+
+        public void close() throws IOException {
+            if (closeRequested) {
+                return;
+            }
+            closeRequested = true;
+            super.close();
+        }
+
+        protected void fill() throws IOException {
+            if (eof) {
+                throw new EOFException("Unexpected end of ZLIB input stream");
+            }
+            len = in.read(buf, 0, buf.length);
+            if (len == -1) {
+                buf[0] = 0;
+                len = 1;
+                eof = true;
+            }
+            inf.setInput(buf, 0, len);
+        }
+
+        public int available() throws IOException {
+            if (closeRequested) {
+                return 0;
+            }
+            long avail = zfin.size() - inf.getBytesWritten();
+            return (avail > 0x7fffffffL ? 0x7fffffff : (int) avail);
+        }
+    }
+
+    private class ZipFileInputStream extends InputStream {
+        private volatile boolean zfisCloseRequested = false;
+        protected long jzentry;
+        private long pos;
+        protected long rem;
+        protected long size;
+
+        ZipFileInputStream(long jzentry) {
+            pos = 0;
+            rem = getEntryCSize(jzentry);
+            size = getEntrySize(jzentry);
+            this.jzentry = jzentry;
+        }
+
+        public int read(byte[] b, int off, int len) throws IOException {
+            synchronized (ZipFile.this) {
+                long rem = this.rem;
+                long pos = this.pos;
+                if (rem == 0) {
+                    return -1;
+                }
+                if (len <= 0) {
+                    return 0;
+                }
+                if (len > rem) {
+                    len = (int) rem;
+                }
+                ensureOpenOrZipException();
+                len = ZipFile.read(ZipFile.this.jzfile, jzentry, pos, b, off, len);
+                if (len > 0) {
+                    this.pos = (pos + len);
+                    this.rem = (rem - len);
+                }
+            }
+            if (rem == 0) {
+                close();
+            }
+            return len;
+        }
+
+        public int read() throws IOException {
+            byte[] b = new byte[1];
+            if (read(b, 0, 1) == 1) {
+                return b[0] & 0xff;
+            } else {
+                return -1;
+            }
+        }
+
+        public int available() {
+            return rem > 0x7fffffff ? 0x7fffffff : (int) rem;
+        }
+
+        public long size() {
+            return size;
+        }
+
+        public void close() {
+            if (zfisCloseRequested) {
+                return;
+            }
+            zfisCloseRequested = true;
+            rem = 0;
+            synchronized (ZipFile.this) {
+                if (jzentry != 0 && ZipFile.this.jzfile != 0) {
+                    freeEntry(ZipFile.this.jzfile, jzentry);
+                    jzentry = 0;
+                }
+            }
+        }
+    }
+
+    private static native long open(String name, int mode, long lastModified,
+                                    boolean usemmap) throws IOException;
+    private static native int getTotal(long jzfile);
+    private static native boolean startsWithLOC(long jzfile);
+    private static native int read(long jzfile, long jzentry,
+                                   long pos, byte[] b, int off, int len);
+
+    private static native long getEntry(long jzfile, byte[] name, boolean addSlash);
+    private static native void freeEntry(long jzfile, long jzentry);
+    private static native long getNextEntry(long jzfile, int i);
+    private static native void close(long jzfile);
+
+    private static native int getEntryTime(long jzentry);
+    private static native long getEntryCrc(long jzentry);
+    private static native long getEntryCSize(long jzentry);
+    private static native long getEntrySize(long jzentry);
+    private static native int getEntryMethod(long jzentry);
+    private static native int getEntryFlag(long jzentry);
+    private static native byte[] getCommentBytes(long jzfile);
+
+    private static native byte[] getEntryBytes(long jzentry, int type);
+
+    private static native String getZipMessage(long jzfile);
+}
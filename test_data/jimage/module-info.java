@@ -0,0 +1,18 @@
+// The java.base of the runtime images in zip/ and compact-cp/, with the
+// classes of test_data/java and test_data/sun. Built with:
+//
+//   javac --release 9 --patch-module java.base=classes -d classes module-info.java
+//   jmod create --class-path classes --module-version 17.0.15 \
+//       --target-platform linux-amd64 mp/java.base.jmod
+//   jlink --module-path mp --add-modules java.base \
+//       --disable-plugin system-modules --disable-plugin generate-jli-classes \
+//       --compress='2:filter=regex:/java.base/java/lang/.*' --output zip
+//   jlink ... --compress=1 --output compact-cp
+//
+// zip/ has java/lang compressed with zip and the other classes stored,
+// compact-cp/ has all of them compressed with string sharing.
+module java.base {
+    exports java.io;
+    exports java.lang;
+    exports java.lang.reflect;
+}
@@ -0,0 +1,108 @@
+package jni;
+
+/** Calls the natives of the library at `args[0]`, see jni_test.c. */
+public class JniTest {
+    static int sum;
+    static double mixed;
+    static String greeting;
+    static int arraySum;
+    static int[] range;
+    static int callBack;
+    static long bumps;
+    static double bumped;
+    static String thrown;
+    static boolean caught;
+    static int created;
+    static String kept;
+    static String[] pair;
+    static int overloadedInt;
+    static long overloadedLong;
+    static int underscore;
+    static int answer;
+    static String libraryName;
+
+    int value;
+
+    JniTest() {
+    }
+
+    JniTest(int value) {
+        this.value = value;
+    }
+
+    public static void main(String[] args) {
+        System.load(args[0]);
+        sum = add(2, 3);
+        mixed = mix(true, (byte) -2, 'a', (short) 300, 40000, 5000000000L, 1.5f, 0.25);
+        greeting = greet("hippo");
+        arraySum = sumArray(new int[] {1, 2, 3, 4});
+        range = range(5);
+        JniTest test = new JniTest(10);
+        callBack = test.callBack(21) + test.value;
+        bump(7, 2.5);
+        bump(3, 0.5);
+        try {
+            throwNew("from C");
+        } catch (IllegalArgumentException e) {
+            thrown = e.getMessage();
+        }
+        caught = catchJava();
+        created = ((JniTest) create(42)).value;
+        keep("kept by a global reference");
+        kept = (String) kept();
+        pair = pair("left", "right");
+        overloadedInt = overloaded(1);
+        overloadedLong = overloaded(1L);
+        underscore = under_score();
+        answer = answer();
+        libraryName = System.mapLibraryName("jni_test");
+    }
+
+    int twice(int x) {
+        return 2 * x;
+    }
+
+    static void record(long by, double amount) {
+        bumps += by;
+        bumped += amount;
+    }
+
+    static void fail() {
+        throw new RuntimeException("from Java");
+    }
+
+    static native int add(int a, int b);
+
+    static native double mix(boolean z, byte b, char c, short s, int i, long j, float f, double d);
+
+    static native String greet(String name);
+
+    static native int sumArray(int[] values);
+
+    static native int[] range(int n);
+
+    native int callBack(int x);
+
+    static native void bump(long by, double amount);
+
+    static native void throwNew(String message);
+
+    static native boolean catchJava();
+
+    static native Object create(int value);
+
+    static native void keep(Object o);
+
+    static native Object kept();
+
+    static native String[] pair(String a, String b);
+
+    static native int overloaded(int x);
+
+    static native long overloaded(long x);
+
+    static native int under_score();
+
+    /** Bound by `RegisterNatives` in `JNI_OnLoad`. */
+    static native int answer();
+}
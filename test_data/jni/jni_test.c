@@ -0,0 +1,135 @@
+/*
+ * The natives of jni.JniTest, built by the test with
+ * `cc -shared -fPIC -I$JAVA_HOME/include -I$JAVA_HOME/include/linux`.
+ */
+#include <jni.h>
+#include <stdio.h>
+
+static jobject kept_ref;
+
+JNIEXPORT jint JNICALL Java_jni_JniTest_add(JNIEnv *env, jclass clazz, jint a, jint b) {
+    return a + b;
+}
+
+JNIEXPORT jdouble JNICALL Java_jni_JniTest_mix(JNIEnv *env, jclass clazz, jboolean z, jbyte b,
+                                               jchar c, jshort s, jint i, jlong j, jfloat f,
+                                               jdouble d) {
+    return z + b + c + s + i + (jdouble) j + f + d;
+}
+
+JNIEXPORT jstring JNICALL Java_jni_JniTest_greet(JNIEnv *env, jclass clazz, jstring name) {
+    char greeting[64];
+    const char *chars = (*env)->GetStringUTFChars(env, name, NULL);
+    snprintf(greeting, sizeof(greeting), "Hello, %s (%d)!", chars,
+             (*env)->GetStringLength(env, name));
+    (*env)->ReleaseStringUTFChars(env, name, chars);
+    return (*env)->NewStringUTF(env, greeting);
+}
+
+JNIEXPORT jint JNICALL Java_jni_JniTest_sumArray(JNIEnv *env, jclass clazz, jintArray values) {
+    jint sum = 0;
+    jsize length = (*env)->GetArrayLength(env, values);
+    jint *elements = (*env)->GetIntArrayElements(env, values, NULL);
+    for (jsize i = 0; i < length; i++) {
+        sum += elements[i];
+    }
+    (*env)->ReleaseIntArrayElements(env, values, elements, JNI_ABORT);
+    return sum;
+}
+
+JNIEXPORT jintArray JNICALL Java_jni_JniTest_range(JNIEnv *env, jclass clazz, jint n) {
+    jint values[16];
+    jintArray array = (*env)->NewIntArray(env, n);
+    for (jint i = 0; i < n; i++) {
+        values[i] = i * i;
+    }
+    (*env)->SetIntArrayRegion(env, array, 0, n, values);
+    return array;
+}
+
+JNIEXPORT jint JNICALL Java_jni_JniTest_callBack(JNIEnv *env, jobject this, jint x) {
+    jclass clazz = (*env)->GetObjectClass(env, this);
+    jmethodID twice = (*env)->GetMethodID(env, clazz, "twice", "(I)I");
+    jfieldID value = (*env)->GetFieldID(env, clazz, "value", "I");
+    jint result = (*env)->CallIntMethod(env, this, twice, x);
+    (*env)->SetIntField(env, this, value, (*env)->GetIntField(env, this, value) + 1);
+    return result;
+}
+
+JNIEXPORT void JNICALL Java_jni_JniTest_bump(JNIEnv *env, jclass clazz, jlong by, jdouble amount) {
+    jmethodID record = (*env)->GetStaticMethodID(env, clazz, "record", "(JD)V");
+    jfieldID bumped = (*env)->GetStaticFieldID(env, clazz, "bumped", "D");
+    (*env)->CallStaticVoidMethod(env, clazz, record, by, amount);
+    (*env)->SetStaticDoubleField(env, clazz, bumped,
+                                 (*env)->GetStaticDoubleField(env, clazz, bumped) * 2);
+}
+
+JNIEXPORT void JNICALL Java_jni_JniTest_throwNew(JNIEnv *env, jclass clazz, jstring message) {
+    jclass exception = (*env)->FindClass(env, "java/lang/IllegalArgumentException");
+    const char *chars = (*env)->GetStringUTFChars(env, message, NULL);
+    (*env)->ThrowNew(env, exception, chars);
+    (*env)->ReleaseStringUTFChars(env, message, chars);
+}
+
+JNIEXPORT jboolean JNICALL Java_jni_JniTest_catchJava(JNIEnv *env, jclass clazz) {
+    jmethodID fail = (*env)->GetStaticMethodID(env, clazz, "fail", "()V");
+    (*env)->CallStaticVoidMethod(env, clazz, fail);
+    if (!(*env)->ExceptionCheck(env) || (*env)->ExceptionOccurred(env) == NULL) {
+        return JNI_FALSE;
+    }
+    (*env)->ExceptionClear(env);
+    return !(*env)->ExceptionCheck(env);
+}
+
+JNIEXPORT jobject JNICALL Java_jni_JniTest_create(JNIEnv *env, jclass clazz, jint value) {
+    jclass test = (*env)->FindClass(env, "jni/JniTest");
+    jmethodID constructor = (*env)->GetMethodID(env, test, "<init>", "(I)V");
+    return (*env)->NewObject(env, test, constructor, value);
+}
+
+JNIEXPORT void JNICALL Java_jni_JniTest_keep(JNIEnv *env, jclass clazz, jobject o) {
+    kept_ref = (*env)->NewGlobalRef(env, o);
+}
+
+JNIEXPORT jobject JNICALL Java_jni_JniTest_kept(JNIEnv *env, jclass clazz) {
+    return kept_ref;
+}
+
+JNIEXPORT jobjectArray JNICALL Java_jni_JniTest_pair(JNIEnv *env, jclass clazz, jstring a,
+                                                     jstring b) {
+    jobjectArray array;
+    (*env)->PushLocalFrame(env, 2);
+    array = (*env)->NewObjectArray(env, 2, (*env)->FindClass(env, "java/lang/String"), NULL);
+    (*env)->SetObjectArrayElement(env, array, 0, a);
+    (*env)->SetObjectArrayElement(env, array, 1, b);
+    return (*env)->PopLocalFrame(env, array);
+}
+
+JNIEXPORT jint JNICALL Java_jni_JniTest_overloaded__I(JNIEnv *env, jclass clazz, jint x) {
+    return x + 1;
+}
+
+JNIEXPORT jlong JNICALL Java_jni_JniTest_overloaded__J(JNIEnv *env, jclass clazz, jlong x) {
+    return x + 2;
+}
+
+JNIEXPORT jint JNICALL Java_jni_JniTest_under_1score(JNIEnv *env, jclass clazz) {
+    return 7;
+}
+
+static jint answer(JNIEnv *env, jclass clazz) {
+    return 42;
+}
+
+JNIEXPORT jint JNICALL JNI_OnLoad(JavaVM *vm, void *reserved) {
+    JNIEnv *env;
+    JNINativeMethod methods[] = {{"answer", "()I", (void *) answer}};
+    if ((*vm)->GetEnv(vm, (void **) &env, JNI_VERSION_1_6) != JNI_OK) {
+        return JNI_ERR;
+    }
+    jclass clazz = (*env)->FindClass(env, "jni/JniTest");
+    if ((*env)->RegisterNatives(env, clazz, methods, 1) != JNI_OK) {
+        return JNI_ERR;
+    }
+    return JNI_VERSION_1_6;
+}
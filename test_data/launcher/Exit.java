@@ -0,0 +1,20 @@
+package launcher;
+
+import java.lang.Shutdown;
+
+/** Keeps its arguments and halts with a status, skipping the finally block. */
+public class Exit {
+    static int count;
+    static String first;
+    static boolean reached;
+
+    public static void main(String[] args) {
+        count = args.length;
+        first = args[0];
+        try {
+            Shutdown.halt0(count + 40);
+        } finally {
+            reached = true;
+        }
+    }
+}
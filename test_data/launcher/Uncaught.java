@@ -0,0 +1,7 @@
+package launcher;
+
+public class Uncaught {
+    public static void main(String[] args) {
+        throw new Error("uncaught");
+    }
+}
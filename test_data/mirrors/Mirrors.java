@@ -0,0 +1,80 @@
+package mirrors;
+
+/** Checks the answers of the `java.lang.Class` natives. */
+public class Mirrors {
+    static boolean sameLiteral;
+    static boolean sameGetClass;
+    static boolean sameArray;
+    static boolean samePrimitive;
+    static boolean superclass;
+    static boolean noSuperclass;
+    static boolean arraySuperclass;
+    static boolean isInterface;
+    static boolean isArray;
+    static boolean isPrimitive;
+    static boolean componentType;
+    static boolean assignable;
+    static boolean notAssignable;
+    static boolean nullNotAssignable;
+    static String className;
+    static String arrayName;
+    static String nestedArrayName;
+    static String primitiveName;
+    static String lastName;
+
+    public static void main(String[] args) {
+        Class<?> literal = Mirrors.class;
+        sameLiteral = literal == Mirrors.class;
+        sameGetClass = new Mirrors().getClass() == literal && "".getClass() == String.class;
+        sameArray = new int[0].getClass() == int[].class && new String[1].getClass() == String[].class;
+        Class<?> intClass = int[].class.getComponentType();
+        samePrimitive = intClass == new int[2].getClass().getComponentType()
+                && intClass != long[].class.getComponentType();
+
+        superclass = Child.class.getSuperclass() == Parent.class;
+        noSuperclass = Object.class.getSuperclass() == null
+                && Shape.class.getSuperclass() == null
+                && intClass.getSuperclass() == null;
+        arraySuperclass = int[].class.getSuperclass() == Object.class
+                && Child[].class.getSuperclass() == Object.class;
+
+        isInterface = Shape.class.isInterface() && !Child.class.isInterface() && !int[].class.isInterface();
+        isArray = int[][].class.isArray() && String[].class.isArray() && !Mirrors.class.isArray() && !intClass.isArray();
+        isPrimitive = intClass.isPrimitive() && !int[].class.isPrimitive() && !String.class.isPrimitive();
+        componentType = String[][].class.getComponentType() == String[].class
+                && String[].class.getComponentType() == String.class
+                && Mirrors.class.getComponentType() == null;
+
+        assignable = Parent.class.isAssignableFrom(Child.class)
+                && Shape.class.isAssignableFrom(Child.class)
+                && Object.class.isAssignableFrom(Shape.class)
+                && Object.class.isAssignableFrom(int[].class)
+                && Parent[].class.isAssignableFrom(Child[].class)
+                && intClass.isAssignableFrom(intClass);
+        notAssignable = !Child.class.isAssignableFrom(Parent.class)
+                && !Object.class.isAssignableFrom(intClass)
+                && !int[].class.isAssignableFrom(long[].class)
+                && !Child[].class.isAssignableFrom(Parent[].class);
+        try {
+            Object.class.isAssignableFrom(null);
+        } catch (NullPointerException e) {
+            nullNotAssignable = true;
+        }
+
+        className = Child.class.getName();
+        arrayName = String[].class.getName();
+        nestedArrayName = int[][].class.getName();
+        primitiveName = intClass.getName();
+        // names are per mirror, not shared by all of them
+        lastName = Parent.class.getName();
+    }
+}
+
+interface Shape {
+}
+
+class Parent {
+}
+
+class Child extends Parent implements Shape {
+}
@@ -0,0 +1,47 @@
+package monitors;
+
+/** Threads incrementing one counter in synchronized blocks and methods. */
+public class Counter {
+    static final Object lock = new Object();
+    static int blockCount;
+    static int methodCount;
+
+    public static void main(String[] args) throws InterruptedException {
+        Counter counter = new Counter();
+        Thread[] threads = new Thread[4];
+        for (int i = 0; i < threads.length; i++) {
+            threads[i] = new Incrementer(counter);
+            threads[i].start();
+        }
+        for (Thread thread : threads) {
+            thread.join();
+        }
+    }
+
+    synchronized void increment() {
+        int count = methodCount;
+        Thread.yield();
+        methodCount = count + 1;
+    }
+}
+
+class Incrementer extends Thread {
+    private final Counter counter;
+
+    Incrementer(Counter counter) {
+        super("incrementer");
+        this.counter = counter;
+    }
+
+    @Override
+    public void run() {
+        for (int i = 0; i < 200; i++) {
+            synchronized (Counter.lock) {
+                int count = Counter.blockCount;
+                Thread.yield();
+                Counter.blockCount = count + 1;
+            }
+            counter.increment();
+        }
+    }
+}
@@ -0,0 +1,94 @@
+package monitors;
+
+/** Hands values from a producer to the main thread with wait and notify. */
+public class WaitNotify {
+    static final Object lock = new Object();
+    /** 0 while empty. */
+    static int slot;
+    static int sum;
+    static boolean illegalNotify;
+    static boolean illegalWait;
+    static boolean timedOut;
+    static boolean releasedAfterThrow;
+
+    public static void main(String[] args) throws InterruptedException {
+        new Producer().start();
+        for (int i = 0; i < 10; i++) {
+            synchronized (lock) {
+                while (slot == 0) {
+                    lock.wait();
+                }
+                sum += slot;
+                slot = 0;
+                lock.notifyAll();
+            }
+        }
+
+        try {
+            lock.notify();
+        } catch (IllegalMonitorStateException e) {
+            illegalNotify = true;
+        }
+        try {
+            lock.wait(1);
+        } catch (IllegalMonitorStateException e) {
+            illegalWait = true;
+        }
+        synchronized (lock) {
+            lock.wait(20);
+            timedOut = true;
+        }
+
+        WaitNotify thrower = new WaitNotify();
+        try {
+            thrower.fail();
+        } catch (Error e) {
+            Thread taker = new Taker(thrower);
+            taker.start();
+            taker.join();
+        }
+    }
+
+    synchronized void fail() {
+        throw new Error("fail");
+    }
+}
+
+class Producer extends Thread {
+    Producer() {
+        super("producer");
+    }
+
+    @Override
+    public void run() {
+        for (int i = 1; i <= 10; i++) {
+            synchronized (WaitNotify.lock) {
+                while (WaitNotify.slot != 0) {
+                    try {
+                        WaitNotify.lock.wait();
+                    } catch (InterruptedException e) {
+                        return;
+                    }
+                }
+                WaitNotify.slot = i;
+                WaitNotify.lock.notifyAll();
+            }
+        }
+    }
+}
+
+class Taker extends Thread {
+    private final Object object;
+
+    Taker(Object object) {
+        super("taker");
+        this.object = object;
+    }
+
+    @Override
+    public void run() {
+        synchronized (object) {
+            WaitNotify.releasedAfterThrow = true;
+        }
+    }
+}
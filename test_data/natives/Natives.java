@@ -0,0 +1,20 @@
+package natives;
+
+/** Calls a native bound by the embedder and one bound to nothing. */
+public class Natives {
+    static int sum;
+    static String unsatisfied;
+
+    public static void main(String[] args) {
+        sum = add(2, 3);
+        try {
+            missing();
+        } catch (UnsatisfiedLinkError e) {
+            unsatisfied = e.getMessage();
+        }
+    }
+
+    static native int add(int a, int b);
+
+    static native void missing();
+}
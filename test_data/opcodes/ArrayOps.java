@@ -0,0 +1,69 @@
+package opcodes;
+
+public class ArrayOps {
+    static int bytes, booleans, chars, shorts, ints;
+    static long longs;
+    static float floats;
+    static double doubles;
+    static int objects, length;
+    static int multi, multiLength, partial;
+    static int dupX2;
+    static long dup2X2;
+
+    static {
+        byte[] b = new byte[2];
+        b[0] = (byte) 200;
+        b[1] = 7;
+        bytes = b[0] + b[1];
+
+        boolean[] z = new boolean[2];
+        z[1] = true;
+        booleans = (z[0] ? 1 : 0) + (z[1] ? 2 : 0);
+
+        char[] c = new char[1];
+        c[0] = (char) 65535;
+        chars = c[0];
+
+        short[] s = new short[1];
+        s[0] = (short) 40000;
+        shorts = s[0];
+
+        int[] i = new int[3];
+        i[2] = 42;
+        ints = i[0] + i[2];
+
+        long[] l = new long[2];
+        l[1] = 1L << 40;
+        l[1] += 1;
+        longs = l[1];
+
+        float[] f = new float[1];
+        f[0] = 1.5f;
+        floats = f[0];
+
+        double[] d = new double[1];
+        d[0] = 2.5;
+        doubles = d[0];
+
+        Object[] o = new Object[2];
+        o[1] = o;
+        objects = o[0] == null && o[1] == o ? 1 : 0;
+        length = o.length + i.length;
+
+        int[][][] m = new int[2][3][4];
+        m[1][2][3] = 9;
+        multi = m[1][2][3];
+        multiLength = m.length * 100 + m[1].length * 10 + m[1][2].length;
+
+        int[][] p = new int[3][];
+        p[1] = new int[5];
+        partial = p.length * 10 + p[1].length + (p[0] == null ? 100 : 0);
+
+        int j = 0;
+        int k = (i[1] = 5) + j;
+        dupX2 = k + i[1];
+
+        long m2 = (l[0] = 7L) + 1;
+        dup2X2 = m2 + l[0];
+    }
+}
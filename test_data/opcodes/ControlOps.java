@@ -0,0 +1,62 @@
+package opcodes;
+
+public class ControlOps {
+    static int table, tableDefault, lookup, lookupDefault;
+    static int branches;
+    static int refs;
+
+    static {
+        table = table(2) * 10 + table(4);
+        tableDefault = table(-1) + table(100);
+        lookup = lookup(-1000) + lookup(5000);
+        lookupDefault = lookup(0);
+        branches = branches(5, 3) * 100 + branches(3, 5) * 10 + branches(4, 4);
+        Object a = new Object();
+        Object b = new Object();
+        refs = (same(a, a) ? 1 : 0) + (same(a, b) ? 10 : 0) + (isNull(null) ? 100 : 0) + (isNull(a) ? 1000 : 0);
+    }
+
+    static int table(int i) {
+        switch (i) {
+            case 1: return 1;
+            case 2: return 2;
+            case 3: return 3;
+            case 4: return 4;
+            default: return -1;
+        }
+    }
+
+    static int lookup(int i) {
+        switch (i) {
+            case -1000: return 1;
+            case 5000: return 2;
+            case 123456: return 3;
+            default: return -1;
+        }
+    }
+
+    static int branches(int a, int b) {
+        int r = 0;
+        if (a > b) r += 1;
+        if (a >= b) r += 2;
+        if (a < b) r += 4;
+        if (a <= b) r += 8;
+        if (a == b) r += 16;
+        if (a != b) r += 32;
+        if (a - b > 0) r += 64;
+        if (a - b >= 0) r += 128;
+        if (a - b < 0) r += 256;
+        if (a - b <= 0) r += 512;
+        if (a - b == 0) r += 1024;
+        if (a - b != 0) r += 2048;
+        return r;
+    }
+
+    static boolean same(Object a, Object b) {
+        return a == b;
+    }
+
+    static boolean isNull(Object a) {
+        return a == null;
+    }
+}
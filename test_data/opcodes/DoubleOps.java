@@ -0,0 +1,61 @@
+package opcodes;
+
+public class DoubleOps {
+    static double add, sub, mul, div, rem, neg;
+    static double const0, const1, ldc2;
+    static int cmpgNaN, cmplNaN, cmpLess, cmpGreater;
+    static int d2iNaN, d2i;
+    static long d2lLarge, d2l;
+    static float d2f;
+    static double storeLoad;
+
+    static {
+        add = add(1.5, 2.25);
+        sub = sub(1.5, 2.25);
+        mul = mul(1.5, -2);
+        div = div(-1, 0);
+        rem = rem(7.5, -2);
+        neg = neg(1.25);
+        const0 = 0.0;
+        const1 = 1.0;
+        ldc2 = 3.25;
+        cmpgNaN = less(Double.NaN, 1) ? 1 : 0;
+        cmplNaN = greater(Double.NaN, 1) ? 1 : 0;
+        cmpLess = less(1, 2) ? 1 : 0;
+        cmpGreater = greater(3, 2) ? 1 : 0;
+        d2iNaN = (int) pass(Double.NaN);
+        d2i = (int) pass(-2.5);
+        d2lLarge = (long) pass(1e300);
+        d2l = (long) pass(9876543210.5);
+        d2f = (float) pass(0.25);
+        storeLoad = storeLoad(1, 2);
+    }
+
+    static double pass(double a) { return a; }
+    static double add(double a, double b) { return a + b; }
+    static double sub(double a, double b) { return a - b; }
+    static double mul(double a, double b) { return a * b; }
+    static double div(double a, double b) { return a / b; }
+    static double rem(double a, double b) { return a % b; }
+    static double neg(double a) { return -a; }
+    static boolean less(double a, double b) { return a < b; }
+    static boolean greater(double a, double b) { return a > b; }
+
+    static double storeLoad(double a, double b) {
+        double c = a + b;
+        double d = c * 2;
+        a = d;
+        b = c;
+        return a * 10 + b;
+    }
+
+    static double storeLoadLow() {
+        double a = 1;
+        double b = 2;
+        return a + b;
+    }
+
+    static {
+        storeLoad += storeLoadLow();
+    }
+}
@@ -0,0 +1,55 @@
+package opcodes;
+
+public class FloatOps {
+    static float add, sub, mul, div, rem, neg;
+    static float const0, const1, const2, ldc;
+    static int cmpgNaN, cmplNaN, cmpLess, cmpEqual;
+    static int f2iNaN, f2iLarge, f2i;
+    static long f2lSmall, f2l;
+    static double f2d;
+    static float storeLoad;
+
+    static {
+        add = add(1.5f, 2.25f);
+        sub = sub(1.5f, 2.25f);
+        mul = mul(1.5f, -2f);
+        div = div(1f, 0f);
+        rem = rem(-7.5f, 2f);
+        neg = neg(0f);
+        const0 = 0f;
+        const1 = 1f;
+        const2 = 2f;
+        ldc = 3.5f;
+        cmpgNaN = less(Float.NaN, 1f) ? 1 : 0;
+        cmplNaN = greater(Float.NaN, 1f) ? 1 : 0;
+        cmpLess = less(1f, 2f) ? 1 : 0;
+        cmpEqual = equal(2f, 2f) ? 1 : 0;
+        f2iNaN = (int) pass(Float.NaN);
+        f2iLarge = (int) pass(1e20f);
+        f2i = (int) pass(-3.9f);
+        f2lSmall = (long) pass(-1e30f);
+        f2l = (long) pass(12345.75f);
+        f2d = pass(0.5f);
+        storeLoad = storeLoad(1f, 2f, 3f, 4f);
+    }
+
+    static float pass(float a) { return a; }
+    static float add(float a, float b) { return a + b; }
+    static float sub(float a, float b) { return a - b; }
+    static float mul(float a, float b) { return a * b; }
+    static float div(float a, float b) { return a / b; }
+    static float rem(float a, float b) { return a % b; }
+    static float neg(float a) { return -a; }
+    static boolean less(float a, float b) { return a < b; }
+    static boolean greater(float a, float b) { return a > b; }
+    static boolean equal(float a, float b) { return a == b; }
+
+    static float storeLoad(float a, float b, float c, float d) {
+        float e = a + d;
+        a = e;
+        b = c;
+        c = e * 2;
+        d = b;
+        return a + b * 10 + c * 100 + d * 1000;
+    }
+}
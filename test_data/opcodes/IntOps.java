@@ -0,0 +1,74 @@
+package opcodes;
+
+public class IntOps {
+    static int add, sub, mul, div, rem, neg, shl, shr, ushr, and, or, xor;
+    static int inc, wideInc, minusOne, bipush, sipush, ldc;
+    static int i2b, i2c, i2s;
+    static long i2l;
+    static float i2f;
+    static double i2d;
+    static int storeLoad;
+
+    static {
+        add = add(Integer.MAX_VALUE, 1);
+        sub = sub(Integer.MIN_VALUE, 1);
+        mul = mul(65536, 65536 + 3);
+        div = div(Integer.MIN_VALUE, -1) + div(-7, 2);
+        rem = rem(-7, 3);
+        neg = neg(Integer.MIN_VALUE) + neg(5);
+        shl = shl(1, 33);
+        shr = shr(-16, 2);
+        ushr = ushr(-16, 28);
+        and = and(0xf0f0, 0xff00);
+        or = or(0xf0f0, 0x0f00);
+        xor = xor(0xf0f0, 0xff00);
+        inc = inc(5);
+        wideInc = wideInc(5);
+        minusOne = -1;
+        bipush = -100;
+        sipush = -30000;
+        ldc = 123456789;
+        i2b = (byte) pass(200);
+        i2c = (char) pass(-1);
+        i2s = (short) pass(40000);
+        i2l = pass(-3);
+        i2f = pass(7);
+        i2d = pass(-9);
+        storeLoad = storeLoad(1, 2, 3, 4);
+    }
+
+    static int pass(int a) { return a; }
+    static int add(int a, int b) { return a + b; }
+    static int sub(int a, int b) { return a - b; }
+    static int mul(int a, int b) { return a * b; }
+    static int div(int a, int b) { return a / b; }
+    static int rem(int a, int b) { return a % b; }
+    static int neg(int a) { return -a; }
+    static int shl(int a, int b) { return a << b; }
+    static int shr(int a, int b) { return a >> b; }
+    static int ushr(int a, int b) { return a >>> b; }
+    static int and(int a, int b) { return a & b; }
+    static int or(int a, int b) { return a | b; }
+    static int xor(int a, int b) { return a ^ b; }
+
+    static int inc(int a) {
+        a += 100;
+        a -= 1;
+        return a;
+    }
+
+    static int wideInc(int a) {
+        a += 1000;
+        return a;
+    }
+
+    static int storeLoad(int a, int b, int c, int d) {
+        int e = a + d;
+        int f = e * b;
+        a = f;
+        b = c;
+        c = e;
+        d = f + 1;
+        return a * 1000 + b * 100 + c * 10 + d;
+    }
+}
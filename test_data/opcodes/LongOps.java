@@ -0,0 +1,77 @@
+package opcodes;
+
+public class LongOps {
+    static long add, sub, mul, div, rem, neg, shl, shr, ushr, and, or, xor;
+    static long const0, const1, ldc2;
+    static int cmpLess, cmpEqual, cmpGreater;
+    static int l2i;
+    static float l2f;
+    static double l2d;
+    static long storeLoad;
+
+    static {
+        add = add(Long.MAX_VALUE, 1L);
+        sub = sub(Long.MIN_VALUE, 1L);
+        mul = mul(4294967296L, 4294967299L);
+        div = div(Long.MIN_VALUE, -1L) + div(-7L, 2L);
+        rem = rem(-7L, 3L);
+        neg = neg(Long.MIN_VALUE) + neg(5L);
+        shl = shl(1L, 65);
+        shr = shr(-16L, 2);
+        ushr = ushr(-16L, 60);
+        and = and(0xf0f0L, 0xff00L);
+        or = or(0xf0f0L, 0x0f00L);
+        xor = xor(0xf0f0L, 0xff00L);
+        const0 = 0L;
+        const1 = 1L;
+        ldc2 = 1234567890123L;
+        cmpLess = cmp(1L, 2L);
+        cmpEqual = cmp(2L, 2L);
+        cmpGreater = cmp(3L, 2L);
+        l2i = (int) pass(0x1_0000_0005L);
+        l2f = pass(1L << 40);
+        l2d = pass(-3L);
+        storeLoad = storeLoad(1L, 2L);
+    }
+
+    static long pass(long a) { return a; }
+    static long add(long a, long b) { return a + b; }
+    static long sub(long a, long b) { return a - b; }
+    static long mul(long a, long b) { return a * b; }
+    static long div(long a, long b) { return a / b; }
+    static long rem(long a, long b) { return a % b; }
+    static long neg(long a) { return -a; }
+    static long shl(long a, int b) { return a << b; }
+    static long shr(long a, int b) { return a >> b; }
+    static long ushr(long a, int b) { return a >>> b; }
+    static long and(long a, long b) { return a & b; }
+    static long or(long a, long b) { return a | b; }
+    static long xor(long a, long b) { return a ^ b; }
+
+    static int cmp(long a, long b) {
+        if (a < b) {
+            return -1;
+        } else if (a == b) {
+            return 0;
+        }
+        return 1;
+    }
+
+    static long storeLoad(long a, long b) {
+        long c = a + b;
+        long d = c * 10;
+        a = d;
+        b = c;
+        return a * 100 + b;
+    }
+
+    static long storeLoadLow() {
+        long a = 1L;
+        long b = 2L;
+        return a + b;
+    }
+
+    static {
+        storeLoad += storeLoadLow();
+    }
+}
@@ -0,0 +1,39 @@
+package opcodes;
+
+public class StackOps {
+    static long counter;
+    static long dup2;
+    static long dup2X1;
+    static int pop;
+    static long pop2;
+    static int dup;
+
+    long value;
+
+    static {
+        counter = 10;
+        dup2 = counter++;
+        dup2 += counter;
+
+        StackOps ops = new StackOps();
+        dup2X1 = ops.value = 5L;
+        dup2X1 += ops.value;
+
+        count();
+        countLong();
+
+        int[] a = new int[1];
+        a[0]++;
+        dup = a[0];
+    }
+
+    static int count() {
+        pop++;
+        return pop;
+    }
+
+    static long countLong() {
+        pop2++;
+        return pop2;
+    }
+}
@@ -0,0 +1,239 @@
+#!/usr/bin/env python3
+"""Generates RawOps.class, covering instructions javac never emits (or only
+emits for unusual sources): jsr/ret, goto_w, wide, swap, ldc_w and the
+remaining dup forms.
+
+Every `static int name()` method gets a `static int name` field which is
+assigned in <clinit>. The class file version is 49 so no StackMapTable is
+needed for the jsr subroutines.
+
+Usage: python3 raw_ops.py > RawOps.class
+"""
+import struct
+import sys
+
+OP = {
+    "aconst_null": 0x01, "iconst_0": 0x03, "iconst_1": 0x04, "iconst_2": 0x05,
+    "iconst_3": 0x06, "iconst_4": 0x07, "iconst_5": 0x08, "lconst_0": 0x09,
+    "lconst_1": 0x0a, "fconst_2": 0x0d, "dconst_1": 0x0f, "bipush": 0x10,
+    "sipush": 0x11, "ldc_w": 0x13, "iload": 0x15, "lload": 0x16, "fload": 0x17,
+    "dload": 0x18, "aload": 0x19, "lload_1": 0x1f, "lload_3": 0x21,
+    "dload_1": 0x27, "dload_3": 0x29, "istore": 0x36, "lstore": 0x37,
+    "fstore": 0x38, "dstore": 0x39, "astore": 0x3a, "lstore_1": 0x40,
+    "lstore_3": 0x42, "dstore_1": 0x48, "dstore_3": 0x4a, "astore_1": 0x4c,
+    "pop": 0x57, "dup_x1": 0x5a, "dup_x2": 0x5b, "dup2": 0x5c, "dup2_x1": 0x5d,
+    "dup2_x2": 0x5e, "swap": 0x5f, "iadd": 0x60, "ladd": 0x61, "dadd": 0x63,
+    "isub": 0x64, "imul": 0x68, "iinc": 0x84, "l2i": 0x88, "f2i": 0x8b,
+    "d2i": 0x8e, "if_acmpeq": 0xa5, "goto": 0xa7, "jsr": 0xa8, "ret": 0xa9,
+    "ireturn": 0xac, "return": 0xb1, "putstatic": 0xb3, "invokespecial": 0xb7,
+    "invokestatic": 0xb8, "wide": 0xc4, "ifnull": 0xc6, "goto_w": 0xc8,
+    "jsr_w": 0xc9,
+}
+
+
+class ConstPool:
+    def __init__(self):
+        self.entries = []
+        self.index = {}
+
+    def _add(self, key, data):
+        if key not in self.index:
+            self.entries.append(data)
+            self.index[key] = len(self.entries)
+        return self.index[key]
+
+    def utf8(self, s):
+        b = s.encode()
+        return self._add(("utf8", s), struct.pack(">BH", 1, len(b)) + b)
+
+    def integer(self, n):
+        return self._add(("int", n), struct.pack(">Bi", 3, n))
+
+    def klass(self, name):
+        return self._add(("class", name), struct.pack(">BH", 7, self.utf8(name)))
+
+    def name_and_type(self, name, desc):
+        return self._add(
+            ("nat", name, desc), struct.pack(">BHH", 12, self.utf8(name), self.utf8(desc))
+        )
+
+    def ref(self, tag, owner, name, desc):
+        return self._add(
+            (tag, owner, name, desc),
+            struct.pack(">BHH", tag, self.klass(owner), self.name_and_type(name, desc)),
+        )
+
+    def field(self, owner, name, desc):
+        return self.ref(9, owner, name, desc)
+
+    def method(self, owner, name, desc):
+        return self.ref(10, owner, name, desc)
+
+    def encode(self):
+        return struct.pack(">H", len(self.entries) + 1) + b"".join(self.entries)
+
+
+class Code:
+    """Assembles instructions, resolving labels for 2 and 4 byte branches."""
+
+    def __init__(self):
+        self.code = bytearray()
+        self.labels = {}
+        self.fixups = []
+
+    def op(self, name, *operands):
+        self.code.append(OP[name])
+        for fmt, value in operands:
+            self.code += struct.pack(">" + fmt, value)
+        return self
+
+    def branch(self, name, label, wide=False):
+        start = len(self.code)
+        self.code.append(OP[name])
+        self.fixups.append((start, len(self.code), label, wide))
+        self.code += b"\0" * (4 if wide else 2)
+        return self
+
+    def label(self, name):
+        self.labels[name] = len(self.code)
+        return self
+
+    def combine(self, types, first_slot=0):
+        """Stores the operand stack (`types` from bottom to top) into locals
+        and returns its values as decimal digits, bottom first."""
+        slots = []
+        slot = first_slot
+        for t in types:
+            slots.append(slot)
+            slot += 2 if t in "JD" else 1
+        for t, s in reversed(list(zip(types, slots))):
+            self.op({"I": "istore", "J": "lstore"}[t], ("B", s))
+        for i, (t, s) in enumerate(zip(types, slots)):
+            self.op({"I": "iload", "J": "lload"}[t], ("B", s))
+            if t == "J":
+                self.op("l2i")
+            if i > 0:
+                self.op("iadd")
+            if i < len(types) - 1:
+                self.op("bipush", ("b", 10)).op("imul")
+        return self.op("ireturn"), slot
+
+    def encode(self):
+        for start, at, label, wide in self.fixups:
+            offset = self.labels[label] - start
+            self.code[at:at + (4 if wide else 2)] = struct.pack(">i" if wide else ">h", offset)
+        return bytes(self.code)
+
+
+def method(cp, access, name, desc, code, max_stack, max_locals):
+    body = code.encode()
+    attr = struct.pack(">HHI", max_stack, max_locals, len(body)) + body + struct.pack(">HH", 0, 0)
+    return struct.pack(">HHHH", access, cp.utf8(name), cp.utf8(desc), 1) + struct.pack(
+        ">HI", cp.utf8("Code"), len(attr)
+    ) + attr
+
+
+def tests():
+    # iconst_0; istore_0; jsr sub; iload_0; ireturn; sub: astore_1; iinc 0 5; ret 1
+    jsr = (Code().op("iconst_0").op("istore", ("B", 0)).branch("jsr", "sub")
+           .op("iload", ("B", 0)).op("ireturn")
+           .label("sub").op("astore_1").op("iinc", ("B", 0), ("b", 5)).op("ret", ("B", 1)))
+    yield "jsr", jsr, 2
+    jsr_w = (Code().op("iconst_0").op("istore", ("B", 0)).branch("jsr_w", "sub", wide=True)
+             .op("iload", ("B", 0)).op("ireturn")
+             .label("sub").op("astore_1").op("iinc", ("B", 0), ("b", 7)).op("ret", ("B", 1)))
+    yield "jsrW", jsr_w, 2
+    goto_w = (Code().branch("goto_w", "forward", wide=True).label("back").op("iconst_2").op("ireturn")
+              .label("forward").branch("goto_w", "back", wide=True))
+    yield "gotoW", goto_w, 0
+
+    wide = Code().op("sipush", ("h", 7))
+    wide.op("wide").op("istore", ("H", 280)).op("wide").op("iinc", ("H", 280), ("h", 1000))
+    wide.op("wide").op("iload", ("H", 280))
+    wide.op("lconst_1").op("wide").op("lstore", ("H", 282)).op("wide").op("lload", ("H", 282))
+    wide.op("l2i").op("iadd")
+    wide.op("fconst_2").op("wide").op("fstore", ("H", 284)).op("wide").op("fload", ("H", 284))
+    wide.op("f2i").op("iadd")
+    wide.op("dconst_1").op("wide").op("dstore", ("H", 286)).op("wide").op("dload", ("H", 286))
+    wide.op("d2i").op("iadd")
+    wide.op("aconst_null").op("wide").op("astore", ("H", 288)).op("wide").op("aload", ("H", 288))
+    wide.op("pop")
+    wide.branch("jsr", "sub").op("ireturn")
+    wide.label("sub").op("wide").op("astore", ("H", 290)).op("wide").op("ret", ("H", 290))
+    yield "wide", wide, 300
+
+    yield "ldcW", Code().op("ldc_w", ("H", CP.integer(100000))).op("ireturn"), 0
+    swap = Code().op("bipush", ("b", 10)).op("iconst_3").op("swap").op("isub").op("ireturn")
+    yield "swap", swap, 0
+
+    longs = (Code().op("iconst_0").op("istore", ("B", 0))
+             .op("lconst_1").op("lstore_1").op("lconst_1").op("lload_1").op("ladd").op("lstore_3")
+             .op("lload_3").op("lload_1").op("ladd").op("l2i").op("ireturn"))
+    yield "longLocals", longs, 5
+    doubles = (Code().op("iconst_0").op("istore", ("B", 0))
+               .op("dconst_1").op("dstore_1").op("dconst_1").op("dload_1").op("dadd").op("dstore_3")
+               .op("dload_3").op("dload_1").op("dadd").op("d2i").op("ireturn"))
+    yield "doubleLocals", doubles, 5
+
+    refs = (Code().op("aconst_null").branch("ifnull", "isnull").op("iconst_0").op("ireturn")
+            .label("isnull").op("aconst_null").op("aconst_null").branch("if_acmpeq", "same")
+            .op("iconst_0").op("ireturn").label("same").op("iconst_1").op("ireturn"))
+    yield "refCompare", refs, 0
+
+    def stack_test(name, setup, op, result_types):
+        code = Code()
+        for instruction in setup:
+            code.op(instruction)
+        code.op(op)
+        _, max_locals = code.combine(result_types)
+        return name, code, max_locals
+
+    # ..., v2, v1 -> ..., v1, v2, v1
+    yield stack_test("dupX1", ["iconst_1", "iconst_2"], "dup_x1", "III")
+    # form 2: ..., v2 (long), v1 -> ..., v1, v2, v1
+    yield stack_test("dupX2Long", ["lconst_1", "iconst_2"], "dup_x2", "IJI")
+    # form 1: ..., v2, v1 -> ..., v2, v1, v2, v1
+    yield stack_test("dup2Ints", ["iconst_1", "iconst_2"], "dup2", "IIII")
+    # form 1: ..., v3, v2, v1 -> ..., v2, v1, v3, v2, v1
+    yield stack_test("dup2X1Ints", ["iconst_1", "iconst_2", "iconst_3"], "dup2_x1", "IIIII")
+    # form 1: ..., v4, v3, v2, v1 -> ..., v2, v1, v4, v3, v2, v1
+    yield stack_test("dup2X2Ints", ["iconst_1", "iconst_2", "iconst_3", "iconst_4"], "dup2_x2",
+                     "IIIIII")
+    # form 3: ..., v3 (long), v2, v1 -> ..., v2, v1, v3, v2, v1
+    yield stack_test("dup2X2Form3", ["lconst_1", "iconst_2", "iconst_3"], "dup2_x2", "IIJII")
+    # form 4: ..., v2 (long), v1 (long) -> ..., v1, v2, v1
+    yield stack_test("dup2X2Longs", ["lconst_0", "lconst_1"], "dup2_x2", "JJJ")
+
+
+CP = ConstPool()
+
+
+def main():
+    this = "opcodes/RawOps"
+    methods = []
+    fields = []
+    clinit = Code()
+    for name, code, max_locals in tests():
+        methods.append(method(CP, 0x0008, name, "()I", code, 8, max_locals))
+        fields.append(struct.pack(">HHHH", 0x0008, CP.utf8(name), CP.utf8("I"), 0))
+        clinit.op("invokestatic", ("H", CP.method(this, name, "()I")))
+        clinit.op("putstatic", ("H", CP.field(this, name, "I")))
+    clinit.op("return")
+    methods.append(method(CP, 0x0008, "<clinit>", "()V", clinit, 1, 0))
+
+    init = Code().op("aload", ("B", 0))
+    init.op("invokespecial", ("H", CP.method("java/lang/Object", "<init>", "()V"))).op("return")
+    methods.append(method(CP, 0x0001, "<init>", "()V", init, 1, 1))
+
+    this_index = CP.klass(this)
+    super_index = CP.klass("java/lang/Object")
+    out = struct.pack(">IHH", 0xCAFEBABE, 0, 49) + CP.encode()
+    out += struct.pack(">HHHH", 0x0021, this_index, super_index, 0)
+    out += struct.pack(">H", len(fields)) + b"".join(fields)
+    out += struct.pack(">H", len(methods)) + b"".join(methods)
+    out += struct.pack(">H", 0)
+    sys.stdout.buffer.write(out)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,185 @@
+package reflection;
+
+import java.lang.reflect.Constructor;
+import java.lang.reflect.Field;
+import java.lang.reflect.InvocationTargetException;
+import java.lang.reflect.Method;
+
+/** Reads and writes fields, invokes methods and constructors reflectively. */
+public class Reflect {
+    static int fieldCount;
+    static int xModifiers;
+    static boolean fieldTypes;
+    static int xValue;
+    static int xAfterSet;
+    static int countAfterSet;
+    static String labelValue;
+    static boolean parameterTypes;
+    static boolean exceptionTypes;
+    static long sum;
+    static String described;
+    static String kind;
+    static boolean returnedNull;
+    static boolean wrapped;
+    static boolean mismatch;
+    static boolean wrongCount;
+    static boolean nullReceiver;
+    static boolean notInstance;
+    static int constructorCount;
+    static int constructedX;
+    static boolean abstractNotInstantiated;
+    static boolean interfaces;
+    static int pointModifiers;
+    static int namedModifiers;
+    static int primitiveModifiers;
+
+    public static void main(String[] args) throws Exception {
+        Class<Point> c = Point.class;
+        fieldCount = c.getDeclaredFields().length;
+        Field x = field(c, "x");
+        Field count = field(c, "count");
+        Field label = field(c, "label");
+        xModifiers = x.getModifiers();
+        fieldTypes = x.getType() == int.class && label.getType() == String.class;
+
+        Point p = new Point(1, 2);
+        xValue = x.getInt(p);
+        x.setInt(p, 5);
+        xAfterSet = p.getX();
+        count.setInt(null, 7);
+        countAfterSet = Point.count;
+        label.set(p, "moved");
+        labelValue = (String) label.get(p);
+
+        Method add = method(c, "add");
+        Class<?>[] params = add.getParameterTypes();
+        parameterTypes = params.length == 2 && params[0] == int.class && params[1] == long.class
+                && add.getReturnType() == long.class;
+        Method fail = method(c, "fail");
+        exceptionTypes = fail.getExceptionTypes().length == 1
+                && fail.getExceptionTypes()[0] == InterruptedException.class;
+        sum = ((Long) add.invoke(p, 3, 4)).longValue();
+        described = (String) method(c, "describe").invoke(null, 42);
+        kind = (String) method(Base.class, "kind").invoke(p);
+        returnedNull = method(c, "reset").invoke(p) == null;
+        try {
+            fail.invoke(p);
+        } catch (InvocationTargetException e) {
+            wrapped = e.getTargetException() instanceof InterruptedException;
+        }
+        try {
+            add.invoke(p, "3", 4);
+        } catch (IllegalArgumentException e) {
+            mismatch = true;
+        }
+        try {
+            add.invoke(p, 3);
+        } catch (IllegalArgumentException e) {
+            wrongCount = true;
+        }
+        try {
+            add.invoke(null, 3, 4);
+        } catch (NullPointerException e) {
+            nullReceiver = true;
+        }
+        try {
+            add.invoke("point", 3, 4);
+        } catch (IllegalArgumentException e) {
+            notInstance = true;
+        }
+
+        Constructor<?>[] constructors = c.getDeclaredConstructors();
+        constructorCount = constructors.length;
+        for (Constructor<?> constructor : constructors) {
+            if (constructor.getParameterTypes().length == 2) {
+                constructedX = ((Point) constructor.newInstance(8, 9)).getX();
+            }
+        }
+        try {
+            Shape.class.getDeclaredConstructors()[0].newInstance();
+        } catch (InstantiationException e) {
+            abstractNotInstantiated = true;
+        }
+
+        Class<?>[] pointInterfaces = c.getInterfaces();
+        interfaces = pointInterfaces.length == 1 && pointInterfaces[0] == Named.class;
+        pointModifiers = c.getModifiers();
+        namedModifiers = Named.class.getModifiers();
+        primitiveModifiers = int.class.getModifiers();
+    }
+
+    static Field field(Class<?> c, String name) {
+        for (Field f : c.getDeclaredFields()) {
+            if (f.getName().equals(name)) {
+                return f;
+            }
+        }
+        return null;
+    }
+
+    static Method method(Class<?> c, String name) {
+        for (Method m : c.getDeclaredMethods()) {
+            if (m.getName().equals(name)) {
+                return m;
+            }
+        }
+        return null;
+    }
+}
+
+interface Named {
+    String name();
+}
+
+abstract class Shape {
+}
+
+class Base {
+    String kind() {
+        return "base";
+    }
+}
+
+final class Point extends Base implements Named {
+    static int count;
+    private int x;
+    int y;
+    String label;
+
+    Point() {
+        this(0, 0);
+    }
+
+    Point(int x, int y) {
+        this.x = x;
+        this.y = y;
+    }
+
+    int getX() {
+        return x;
+    }
+
+    public long add(int dx, long dy) {
+        return x + dx + dy;
+    }
+
+    static String describe(int n) {
+        return n == 42 ? "answer" : "other";
+    }
+
+    String kind() {
+        return "point";
+    }
+
+    public String name() {
+        return "point";
+    }
+
+    void reset() {
+        x = 0;
+    }
+
+    void fail() throws InterruptedException {
+        throw new InterruptedException("failed");
+    }
+}
@@ -0,0 +1,209 @@
+package resolution;
+
+/**
+ * Method resolution and selection, see test_resolution. The classes of
+ * changed/Changed.java are compiled after this file and replace some of its
+ * classes, the way a library changes under code compiled against an older
+ * version of it. Secret.java needs Java 11 for private interface methods.
+ */
+public class Main {
+    static int[] results;
+    static Throwable[] errors = new Throwable[8];
+
+    public static void main(String[] args) {
+        Partial partial = new Full();
+        Top top = new Bottom();
+        results = new int[] {
+            partial.count(),
+            new Bottom().name(),
+            top.name(),
+            new Both().side(),
+            Statics.twice(21),
+            new Leaf().value(),
+            new Keeper().reveal(),
+        };
+
+        try {
+            Evolving.helper();
+        } catch (LinkageError e) {
+            errors[0] = e;
+        }
+        try {
+            Evolving.gone();
+        } catch (LinkageError e) {
+            errors[1] = e;
+        }
+        try {
+            new Evolving().self();
+        } catch (LinkageError e) {
+            errors[2] = e;
+        }
+        try {
+            Shape.sides();
+        } catch (LinkageError e) {
+            errors[3] = e;
+        }
+        try {
+            Job job = new Worker();
+            job.work();
+        } catch (LinkageError e) {
+            errors[4] = e;
+        }
+        try {
+            new Pair().pick();
+        } catch (LinkageError e) {
+            errors[5] = e;
+        }
+        try {
+            Runner.make().run();
+        } catch (LinkageError e) {
+            errors[6] = e;
+        }
+        try {
+            new Child().speak();
+        } catch (LinkageError e) {
+            errors[7] = e;
+        }
+    }
+}
+
+/** An abstract class gets the methods of its interfaces it doesn't declare. */
+interface Counter {
+    int count();
+}
+
+abstract class Partial implements Counter {}
+
+class Full extends Partial {
+    public int count() {
+        return 5;
+    }
+}
+
+/** The default of the most specific superinterface is selected. */
+interface Named {
+    default int name() {
+        return 1;
+    }
+}
+
+interface NamedMore extends Named {
+    default int name() {
+        return 2;
+    }
+}
+
+class Top implements Named {}
+
+class Bottom extends Top implements NamedMore {}
+
+/** A class resolves conflicting defaults by calling them with `super`. */
+interface Left {
+    default int side() {
+        return 1;
+    }
+}
+
+interface Right {
+    default int side() {
+        return 2;
+    }
+}
+
+class Both implements Left, Right {
+    public int side() {
+        return Left.super.side() * 10 + Right.super.side();
+    }
+}
+
+interface Statics {
+    static int twice(int x) {
+        return 2 * x;
+    }
+}
+
+/** `super.value()` calls the method of the direct superclass it finds. */
+class Base {
+    int value() {
+        return 100;
+    }
+}
+
+class Middle extends Base {}
+
+class Leaf extends Middle {
+    int value() {
+        return super.value() + 1;
+    }
+}
+
+/** The private method of the interface is called, not the one of the class. */
+class Keeper implements Secret {
+    public int hidden() {
+        return 0;
+    }
+}
+
+class Evolving {
+    static int helper() {
+        return 1;
+    }
+
+    static int gone() {
+        return 2;
+    }
+
+    int self() {
+        return 3;
+    }
+}
+
+class Shape {
+    static int sides() {
+        return 4;
+    }
+}
+
+interface Job {
+    default int work() {
+        return 1;
+    }
+}
+
+class Worker implements Job {}
+
+interface Alpha {
+    default int pick() {
+        return 1;
+    }
+}
+
+interface Beta {}
+
+class Pair implements Alpha, Beta {}
+
+interface Runner {
+    int run();
+
+    static Runner make() {
+        return new Sprinter();
+    }
+}
+
+class Sprinter implements Runner {
+    public int run() {
+        return 1;
+    }
+}
+
+class Ancestor {
+    int speak() {
+        return 1;
+    }
+}
+
+class Child extends Ancestor {
+    int speak() {
+        return super.speak() + 1;
+    }
+}
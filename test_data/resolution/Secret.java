@@ -0,0 +1,16 @@
+package resolution;
+
+/** Compiled with `--release 11`, see Main. */
+public interface Secret {
+    private int hidden() {
+        return 7;
+    }
+
+    private static int twice(int x) {
+        return 2 * x;
+    }
+
+    default int reveal() {
+        return twice(hidden());
+    }
+}
@@ -0,0 +1,44 @@
+package resolution;
+
+/** The classes of Main.java that change after it was compiled, see Main. */
+class Middle extends Base {
+    int value() {
+        return 200;
+    }
+}
+
+class Evolving {
+    int helper() {
+        return 1;
+    }
+
+    static int self() {
+        return 3;
+    }
+}
+
+interface Shape {
+    static int sides() {
+        return 3;
+    }
+}
+
+interface Job {
+    int work();
+}
+
+interface Beta {
+    default int pick() {
+        return 2;
+    }
+}
+
+class Sprinter {
+    public int run() {
+        return 1;
+    }
+}
+
+abstract class Ancestor {
+    abstract int speak();
+}
@@ -0,0 +1,98 @@
+package resources;
+
+import java.io.FileNotFoundException;
+import java.io.IOException;
+import java.io.InputStream;
+import java.lang.reflect.Method;
+import java.util.jar.JarFile;
+import java.util.zip.ZipEntry;
+import java.util.zip.ZipException;
+import java.util.zip.ZipFile;
+
+/**
+ * Reads the entries of the jar `args[0]` like class loaders do, and tries to
+ * open the missing file `args[1]` and the file `args[2]` that isn't a zip file.
+ */
+public class Lookup {
+    static int total;
+    static byte[] stored;
+    static long storedSize;
+    static int storedMethod;
+    static byte[] deflated;
+    static long deflatedSize;
+    static long deflatedCompressedSize;
+    static int deflatedMethod;
+    static long deflatedCrc;
+    static int atEnd;
+    static boolean directory;
+    static boolean missing;
+    static String[] metaInf;
+    static String closed;
+    static String notFound;
+    static String notZip;
+
+    public static void main(String[] args) throws Exception {
+        JarFile jar = new JarFile(args[0]);
+        total = jar.size();
+
+        ZipEntry entry = jar.getEntry("resources/config.txt");
+        storedSize = entry.getSize();
+        storedMethod = entry.getMethod();
+        stored = read(jar.getInputStream(entry), (int) storedSize);
+
+        entry = jar.getEntry("data/lines.txt");
+        deflatedSize = entry.getSize();
+        deflatedCompressedSize = entry.getCompressedSize();
+        deflatedMethod = entry.getMethod();
+        deflatedCrc = entry.getCrc();
+        InputStream in = jar.getInputStream(entry);
+        deflated = read(in, (int) deflatedSize);
+        atEnd = in.read();
+        in.close();
+
+        directory = jar.getEntry("META-INF") != null;
+        missing = jar.getEntry("resources/missing.txt") == null;
+        metaInf = metaInfEntryNames(jar);
+
+        jar.close();
+        try {
+            jar.getEntry("resources/config.txt");
+        } catch (IllegalStateException e) {
+            closed = e.getMessage();
+        }
+        try {
+            new ZipFile(args[1]);
+        } catch (FileNotFoundException e) {
+            notFound = e.getMessage();
+        }
+        try {
+            new ZipFile(args[2]);
+        } catch (ZipException e) {
+            notZip = e.getMessage();
+        }
+    }
+
+    static byte[] read(InputStream in, int size) throws IOException {
+        byte[] b = new byte[size];
+        int n = 0;
+        while (n < size) {
+            int r = in.read(b, n, size - n);
+            if (r == -1) {
+                break;
+            }
+            n += r;
+        }
+        return b;
+    }
+
+    /** What the JDK looks for manifests and signatures with. */
+    static String[] metaInfEntryNames(JarFile jar) throws Exception {
+        Method[] methods = JarFile.class.getDeclaredMethods();
+        for (int i = 0; i < methods.length; i++) {
+            if (methods[i].getName().equals("getMetaInfEntryNames")) {
+                return (String[]) methods[i].invoke(jar);
+            }
+        }
+        return null;
+    }
+}
@@ -0,0 +1,73 @@
+package stack;
+
+import java.lang.reflect.InvocationTargetException;
+import java.lang.reflect.Method;
+
+/** Calls nesting deeper than the stack allows, see test_stack_overflow. */
+public class Overflow {
+    static int depth;
+    static int overflows;
+    static int sum;
+    static boolean released;
+    static int reflectiveDepth;
+    static boolean reflectiveOverflow;
+    static Method recurseReflectively;
+
+    public static void main(String[] args) {
+        try {
+            recurse();
+        } catch (StackOverflowError e) {
+            overflows++;
+        }
+        // the stack is usable again once the error is caught
+        sum = sum(10000);
+        try {
+            recurseSynchronized();
+        } catch (StackOverflowError e) {
+            overflows++;
+        }
+        try {
+            Overflow.class.notify();
+        } catch (IllegalMonitorStateException e) {
+            // every frame released the monitor of the class while unwinding
+            released = true;
+        }
+        for (Method m : Overflow.class.getDeclaredMethods()) {
+            if (m.getName().equals("recurseReflectively")) {
+                recurseReflectively = m;
+            }
+        }
+        try {
+            recurseReflectively();
+        } catch (InvocationTargetException e) {
+            // every level wraps what the one it called threw
+            Throwable cause = e;
+            while (cause instanceof InvocationTargetException) {
+                cause = ((InvocationTargetException) cause).getTargetException();
+            }
+            reflectiveOverflow = cause instanceof StackOverflowError;
+        }
+        sum = sum(10000);
+    }
+
+    static void recurse() {
+        depth++;
+        recurse();
+    }
+
+    static synchronized void recurseSynchronized() {
+        recurseSynchronized();
+    }
+
+    static void recurseReflectively() throws InvocationTargetException {
+        reflectiveDepth++;
+        recurseReflectively.invoke(null);
+    }
+
+    static int sum(int n) {
+        if (n == 0) {
+            return 0;
+        }
+        return n + sum(n - 1);
+    }
+}
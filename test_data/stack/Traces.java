@@ -0,0 +1,34 @@
+package stack;
+
+/** Stack traces of thrown and of created exceptions, see test_stack_traces. */
+public class Traces {
+    static StackTraceElement[] created;
+    static StackTraceElement[] thrown;
+    static StackTraceElement[] refilled;
+
+    public static void main(String[] args) {
+        created = new Failure("created").getStackTrace();
+        try {
+            length(null);
+        } catch (NullPointerException e) {
+            thrown = e.getStackTrace();
+        }
+        Failure failure = new Failure("refilled");
+        refill(failure);
+        refilled = failure.getStackTrace();
+    }
+
+    static int length(int[] array) {
+        return array.length;
+    }
+
+    static void refill(Failure failure) {
+        failure.fillInStackTrace();
+    }
+}
+
+class Failure extends RuntimeException {
+    Failure(String message) {
+        super(message);
+    }
+}
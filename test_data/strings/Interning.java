@@ -0,0 +1,44 @@
+package strings;
+
+import java.lang.reflect.Field;
+
+/** Compares string literals, constant values and interned strings by identity. */
+public class Interning {
+    static boolean sameLiteral;
+    static boolean sameAcrossClasses;
+    static boolean constantValue;
+    static boolean newNotInterned;
+    static boolean internedLiteral;
+    static boolean firstInternWins;
+
+    public static void main(String[] args) throws Exception {
+        sameLiteral = literal() == literal();
+        sameAcrossClasses = Constants.literal() == "hello";
+        Field greeting = null;
+        for (Field f : Constants.class.getDeclaredFields()) {
+            if (f.getName().equals("GREETING")) {
+                greeting = f;
+            }
+        }
+        constantValue = greeting.get(null) == "hello";
+
+        String hello = new String(new char[] {'h', 'e', 'l', 'l', 'o'});
+        newNotInterned = hello != "hello";
+        internedLiteral = hello.intern() == "hello";
+        // no literal "world" has been loaded yet
+        String world = new String(new char[] {'w', 'o', 'r', 'l', 'd'});
+        firstInternWins = world.intern() == world && world == "world";
+    }
+
+    static String literal() {
+        return "hello";
+    }
+}
+
+class Constants {
+    static final String GREETING = "hello";
+
+    static String literal() {
+        return "hello";
+    }
+}
@@ -0,0 +1,39 @@
+package sun.misc;
+
+import java.lang.reflect.Field;
+
+/** Just enough of sun.misc.Unsafe for hippo tests without a JDK. */
+public final class Unsafe {
+    private static native void registerNatives();
+
+    static {
+        registerNatives();
+    }
+
+    private static final Unsafe theUnsafe = new Unsafe();
+
+    private Unsafe() {
+    }
+
+    public static Unsafe getUnsafe() {
+        return theUnsafe;
+    }
+
+    public native long objectFieldOffset(Field f);
+
+    public native long staticFieldOffset(Field f);
+
+    public native Object staticFieldBase(Field f);
+
+    public native void ensureClassInitialized(Class<?> c);
+
+    public native int getInt(Object o, long offset);
+
+    public native void putInt(Object o, long offset, int x);
+
+    public native Object getObject(Object o, long offset);
+
+    public native void putObject(Object o, long offset, Object x);
+
+    public final native boolean compareAndSwapInt(Object o, long offset, int expected, int x);
+}
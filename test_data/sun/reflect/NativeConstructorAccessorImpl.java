@@ -0,0 +1,20 @@
+package sun.reflect;
+
+import java.lang.reflect.Constructor;
+import java.lang.reflect.InvocationTargetException;
+
+/** Just enough of sun.reflect.NativeConstructorAccessorImpl for hippo tests without a JDK. */
+public class NativeConstructorAccessorImpl {
+    private final Constructor<?> c;
+
+    public NativeConstructorAccessorImpl(Constructor<?> c) {
+        this.c = c;
+    }
+
+    public Object newInstance(Object[] args) throws InstantiationException, InvocationTargetException {
+        return newInstance0(c, args);
+    }
+
+    private static native Object newInstance0(Constructor<?> c, Object[] args)
+            throws InstantiationException, InvocationTargetException;
+}
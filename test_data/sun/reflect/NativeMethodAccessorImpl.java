@@ -0,0 +1,19 @@
+package sun.reflect;
+
+import java.lang.reflect.InvocationTargetException;
+import java.lang.reflect.Method;
+
+/** Just enough of sun.reflect.NativeMethodAccessorImpl for hippo tests without a JDK. */
+public class NativeMethodAccessorImpl {
+    private final Method method;
+
+    public NativeMethodAccessorImpl(Method method) {
+        this.method = method;
+    }
+
+    public Object invoke(Object obj, Object[] args) throws InvocationTargetException {
+        return invoke0(method, obj, args);
+    }
+
+    private static native Object invoke0(Method m, Object obj, Object[] args) throws InvocationTargetException;
+}
@@ -0,0 +1,42 @@
+package threads;
+
+/** Main returns at once, the VM waits for the finisher but not the spinner. */
+public class Daemons {
+    static volatile boolean finished;
+
+    public static void main(String[] args) {
+        Thread spinner = new Spinner();
+        spinner.setDaemon(true);
+        spinner.start();
+        new Finisher().start();
+    }
+}
+
+class Spinner extends Thread {
+    Spinner() {
+        super("spinner");
+    }
+
+    @Override
+    public void run() {
+        while (true) {
+            Thread.yield();
+        }
+    }
+}
+
+class Finisher extends Thread {
+    Finisher() {
+        super("finisher");
+    }
+
+    @Override
+    public void run() {
+        try {
+            Thread.sleep(50);
+        } catch (InterruptedException e) {
+            return;
+        }
+        Daemons.finished = true;
+    }
+}
@@ -0,0 +1,31 @@
+package threads;
+
+/** Interrupts a thread sleeping far longer than the test runs. */
+public class Interrupt {
+    static boolean interrupted;
+    static boolean stillInterrupted;
+
+    public static void main(String[] args) throws InterruptedException {
+        Sleeper sleeper = new Sleeper();
+        sleeper.start();
+        Thread.sleep(20);
+        sleeper.interrupt();
+        sleeper.join();
+    }
+}
+
+class Sleeper extends Thread {
+    Sleeper() {
+        super("sleeper");
+    }
+
+    @Override
+    public void run() {
+        try {
+            Thread.sleep(60000);
+        } catch (InterruptedException e) {
+            Interrupt.interrupted = true;
+            Interrupt.stillInterrupted = isInterrupted();
+        }
+    }
+}
@@ -0,0 +1,40 @@
+package threads;
+
+/** Sums in several threads at once, each into its own slot. */
+public class Workers {
+    static long[] sums = new long[4];
+    static boolean alive;
+
+    public static void main(String[] args) throws InterruptedException {
+        Worker[] workers = new Worker[sums.length];
+        for (int i = 0; i < workers.length; i++) {
+            workers[i] = new Worker(i);
+            workers[i].start();
+        }
+        for (Worker worker : workers) {
+            worker.join();
+            alive |= worker.isAlive();
+        }
+    }
+}
+
+class Worker extends Thread {
+    private final int index;
+
+    Worker(int index) {
+        super("worker");
+        this.index = index;
+    }
+
+    @Override
+    public void run() {
+        long sum = 0;
+        for (int i = 0; i < 100000; i++) {
+            sum += i;
+            if (i % 10000 == 0) {
+                Thread.yield();
+            }
+        }
+        Workers.sums[index] = sum;
+    }
+}